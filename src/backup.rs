@@ -0,0 +1,373 @@
+//! Backup module for the envoke CLI tool.
+//!
+//! Files envoke would otherwise destroy, such as a hand-written `.env`
+//! replaced by `switch --force`, are moved to `.envoke/backups` instead, and a
+//! snapshot of a profile is kept there before it is rewritten or removed. Each
+//! backup is named after what it holds and when it was made, e.g.
+//! `env-20250601T093000Z` or `dev.env-20250601T093000Z`, so its age is known
+//! however the file was moved. `envoke restore` brings a backup back, and old
+//! backups are removed according to the `[backups]` retention settings.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::{debug, info};
+
+use crate::config::BackupsConfig;
+use crate::error::Result;
+use crate::fs::FileSystem;
+use crate::profile::{ENV_FILE, ProfileManager};
+
+/// Name of the directory holding backups within the envoke directory.
+pub const BACKUPS_DIR: &str = "backups";
+
+/// A file kept in the backups directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    /// The path of the backup.
+    pub path: PathBuf,
+
+    /// What the backup holds, e.g. `env`.
+    pub kind: String,
+
+    /// When the backup was made.
+    pub created: SystemTime,
+}
+
+/// Formats a time for a backup name, e.g. `20250601T093000Z`.
+///
+/// Separators are dropped as colons are not allowed in Windows file names.
+pub fn stamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time)
+        .to_string()
+        .replace(['-', ':'], "")
+}
+
+/// Parses a backup name, e.g. `env-20250601T093000Z` or
+/// `env-20250601T093000Z-2` for a second backup made in the same second.
+///
+/// # Returns
+///
+/// What the backup holds and when it was made, or `None` if the name is not
+/// that of a backup.
+pub fn parse_name(name: &str) -> Option<(String, SystemTime)> {
+    let parts: Vec<&str> = name.split('-').collect();
+    let at = parts.iter().rposition(|part| is_stamp(part))?;
+    if at == 0 || parts.len() - at > 2 {
+        return None;
+    }
+
+    let s = parts[at];
+    let rfc3339 = format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &s[0..4],
+        &s[4..6],
+        &s[6..8],
+        &s[9..11],
+        &s[11..13],
+        &s[13..15]
+    );
+    let created = humantime::parse_rfc3339(&rfc3339).ok()?;
+    Some((parts[..at].join("-"), created))
+}
+
+/// Checks whether a part of a name looks like a stamp from [`stamp`].
+fn is_stamp(part: &str) -> bool {
+    part.len() == 16
+        && part.is_ascii()
+        && &part[8..9] == "T"
+        && part.ends_with('Z')
+        && part[..8]
+            .chars()
+            .chain(part[9..15].chars())
+            .all(|c| c.is_ascii_digit())
+}
+
+/// Selects the backups the retention settings no longer keep.
+///
+/// # Arguments
+///
+/// * `backups` - The backups, newest first.
+/// * `config` - The retention settings.
+/// * `now` - The current time.
+///
+/// # Returns
+///
+/// The backups beyond the maximum count or older than the maximum age.
+pub fn expired<'a>(
+    backups: &'a [Backup],
+    config: &BackupsConfig,
+    now: SystemTime,
+) -> Vec<&'a Backup> {
+    backups
+        .iter()
+        .enumerate()
+        .filter(|(i, backup)| {
+            let too_many = config.max_count > 0 && *i >= config.max_count;
+            let too_old = config.max_age.is_some_and(|max_age| {
+                now.duration_since(backup.created)
+                    .is_ok_and(|age| age > max_age)
+            });
+            too_many || too_old
+        })
+        .map(|(_, backup)| backup)
+        .collect()
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the directory backups are kept in.
+    pub fn backups_dir(&self) -> PathBuf {
+        self.config.envoke_dir.join(BACKUPS_DIR)
+    }
+
+    /// Lists the backups, ignoring other files in the backups directory.
+    ///
+    /// # Returns
+    ///
+    /// The backups, newest first, or an `Error` if the directory cannot be read.
+    pub fn backups(&self) -> Result<Vec<Backup>> {
+        let dir = self.backups_dir();
+        if !self.fs.path_exists(&dir) {
+            return Ok(Vec::new());
+        }
+
+        let mut backups: Vec<Backup> = self
+            .fs
+            .read_dir(&dir)?
+            .into_iter()
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                let (kind, created) = parse_name(name)?;
+                Some(Backup {
+                    path,
+                    kind,
+                    created,
+                })
+            })
+            .collect();
+        backups.sort_by(|a, b| b.created.cmp(&a.created).then(b.path.cmp(&a.path)));
+
+        Ok(backups)
+    }
+
+    /// Names what the backups of `.env` or a profile hold.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile, or `None` for `.env`.
+    ///
+    /// # Returns
+    ///
+    /// `env` for `.env`, or the file name of the profile, e.g. `dev.env`.
+    pub fn backup_kind(&self, profile: Option<&str>) -> String {
+        match profile {
+            Some(profile) => format!("{}.env", self.profile_name(profile)),
+            None => ENV_FILE.trim_start_matches('.').to_string(),
+        }
+    }
+
+    /// Finds an unused path in the backups directory for a new backup,
+    /// creating the directory if needed.
+    fn new_backup_path(&self, kind: &str) -> Result<PathBuf> {
+        let dir = self.backups_dir();
+        self.fs.create_dir(&dir)?;
+
+        let stamp = stamp(SystemTime::now());
+        let mut path = dir.join(format!("{}-{}", kind, stamp));
+        let mut n = 1;
+        while self.fs.path_exists(&path) || self.fs.is_symlink(&path) {
+            n += 1;
+            path = dir.join(format!("{}-{}-{}", kind, stamp, n));
+        }
+
+        Ok(path)
+    }
+
+    /// Moves `.env` into the backups directory as `env-<timestamp>`.
+    ///
+    /// # Returns
+    ///
+    /// The path of the backup, or an `Error` if `.env` cannot be moved.
+    pub fn backup_env(&self) -> Result<PathBuf> {
+        let path = self.new_backup_path(&self.backup_kind(None))?;
+        debug!(backup = %path.display(), "backing up .env");
+        self.fs.rename(Path::new(ENV_FILE), &path)?;
+        Ok(path)
+    }
+
+    /// Copies a profile into the backups directory as
+    /// `<profile>.env-<timestamp>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The path of the snapshot, `None` if the profile does not exist, or an
+    /// `Error` if it cannot be copied.
+    pub fn snapshot_profile<S: AsRef<str>>(&self, profile: S) -> Result<Option<PathBuf>> {
+        let source = self.profile_path(&profile);
+        if !self.fs.path_exists(&source) {
+            return Ok(None);
+        }
+
+        let path = self.new_backup_path(&self.backup_kind(Some(profile.as_ref())))?;
+        debug!(snapshot = %path.display(), "snapshotting profile");
+        self.fs.copy_file(&source, &path)?;
+        Ok(Some(path))
+    }
+
+    /// Rewrites a profile, keeping a snapshot of the contents it replaces and
+    /// recording the checksum of the new contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `contents` - The new contents.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the profile cannot be written.
+    pub fn write_profile<S, C>(&self, profile: S, contents: C) -> Result<()>
+    where
+        S: AsRef<str>,
+        C: AsRef<[u8]>,
+    {
+        let path = self.profile_path(&profile);
+        if self.read_bytes(&path).ok().as_deref() != Some(contents.as_ref()) {
+            self.snapshot_profile(&profile)?;
+        }
+
+        self.save_profile(profile, contents)
+    }
+
+    /// Replaces `.env` or a profile with the contents of a backup.
+    ///
+    /// The backup is copied next to the target and renamed over it, so the
+    /// target is never left half written. What it replaces is kept as a
+    /// backup in turn, unless it is a `.env` managed by envoke.
+    ///
+    /// # Arguments
+    ///
+    /// * `backup` - The backup to restore.
+    /// * `profile` - The profile to restore, or `None` for `.env`.
+    ///
+    /// # Returns
+    ///
+    /// Where the replaced contents were kept, or an `Error` if the backup
+    /// cannot be restored.
+    pub fn restore_backup(
+        &self,
+        backup: &Backup,
+        profile: Option<&str>,
+    ) -> Result<Option<PathBuf>> {
+        let target = match profile {
+            Some(profile) => self.profile_path(profile),
+            None => PathBuf::from(ENV_FILE),
+        };
+        let mut staged = target.clone().into_os_string();
+        staged.push(".restore");
+        let staged = PathBuf::from(staged);
+        self.fs.copy_file(&backup.path, &staged)?;
+
+        let kept = match profile {
+            Some(profile) => self.snapshot_profile(profile),
+            None if self.fs.path_exists(&target) && !self.is_managed_env(&target) => {
+                self.backup_env().map(Some)
+            }
+            None => Ok(None),
+        };
+        let kept = match kept {
+            Ok(kept) => kept,
+            Err(e) => {
+                let _ = self.fs.remove_file(&staged);
+                return Err(e);
+            }
+        };
+
+        info!(backup = %backup.path.display(), target = %target.display(), "restoring backup");
+        self.fs.rename(&staged, &target)?;
+        Ok(kept)
+    }
+
+    /// Removes the backups the `[backups]` retention settings no longer keep.
+    ///
+    /// # Returns
+    ///
+    /// The removed backups, or an `Error` if one cannot be removed.
+    pub fn gc(&self) -> Result<Vec<PathBuf>> {
+        let backups = self.backups()?;
+        let mut removed = Vec::new();
+        for backup in expired(&backups, &self.config.backups, SystemTime::now()) {
+            debug!(backup = %backup.path.display(), "removing expired backup");
+            self.fs.remove_file(&backup.path)?;
+            removed.push(backup.path.clone());
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn backup(name: &str, days_ago: u64, now: SystemTime) -> Backup {
+        Backup {
+            path: PathBuf::from(name),
+            kind: "env".to_string(),
+            created: now - Duration::from_secs(days_ago * 86400),
+        }
+    }
+
+    #[test]
+    fn test_parse_name() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_748_770_200);
+        assert_eq!("20250601T093000Z", stamp(time));
+        assert_eq!(
+            Some(("env".to_string(), time)),
+            parse_name("env-20250601T093000Z")
+        );
+        assert_eq!(
+            Some(("dev-profile".to_string(), time)),
+            parse_name("dev-profile-20250601T093000Z-2")
+        );
+        assert_eq!(None, parse_name("20250601T093000Z"));
+        assert_eq!(None, parse_name("env-2025"));
+        assert_eq!(None, parse_name("notes.txt"));
+    }
+
+    #[test]
+    fn test_expired() {
+        let now = SystemTime::now();
+        let backups = vec![
+            backup("a", 1, now),
+            backup("b", 10, now),
+            backup("c", 40, now),
+        ];
+
+        let mut config = BackupsConfig {
+            max_count: 2,
+            max_age: None,
+        };
+        let names = |expired: Vec<&Backup>| -> Vec<PathBuf> {
+            expired.into_iter().map(|b| b.path.clone()).collect()
+        };
+        assert_eq!(
+            vec![PathBuf::from("c")],
+            names(expired(&backups, &config, now))
+        );
+
+        config.max_count = 0;
+        config.max_age = Some(Duration::from_secs(5 * 86400));
+        assert_eq!(
+            vec![PathBuf::from("b"), PathBuf::from("c")],
+            names(expired(&backups, &config, now))
+        );
+
+        config.max_age = None;
+        assert!(expired(&backups, &config, now).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Profile bundle module for the envoke CLI tool.
+//!
+//! This module packs files into a single tar archive, optionally encrypted
+//! with a passphrase using the [age](https://age-encryption.org) format, so
+//! profiles can be moved between machines in one piece.
+
+use std::io::Read;
+
+use age::secrecy::SecretString;
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Directory holding profiles within a bundle.
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Directory holding the files profiles include within a bundle, by their
+/// path relative to the envoke directory.
+pub const INCLUDES_DIR: &str = "includes";
+
+/// Magic bytes at the start of every age-encrypted file.
+const AGE_MAGIC: &[u8] = b"age-encryption.org/";
+
+/// A file stored in a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleFile {
+    /// The path of the file within the bundle.
+    pub path: String,
+
+    /// The contents of the file.
+    pub contents: Vec<u8>,
+}
+
+/// Packs files into a tar archive.
+///
+/// # Arguments
+///
+/// * `files` - The files to pack.
+///
+/// # Returns
+///
+/// The archive, or an `Error` if it cannot be built.
+pub fn pack(files: &[BundleFile]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    for file in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.contents.len() as u64);
+        header.set_mode(0o600);
+        header.set_cksum();
+
+        builder
+            .append_data(&mut header, &file.path, file.contents.as_slice())
+            .map_err(bundle_error)?;
+    }
+
+    builder.into_inner().map_err(bundle_error)
+}
+
+/// Unpacks the files of a tar archive.
+///
+/// # Arguments
+///
+/// * `archive` - The archive to unpack.
+///
+/// # Returns
+///
+/// The files in the archive, or an `Error` if it is malformed.
+pub fn unpack(archive: &[u8]) -> Result<Vec<BundleFile>> {
+    let mut archive = tar::Archive::new(archive);
+    let mut files = Vec::new();
+
+    for entry in archive.entries().map_err(bundle_error)? {
+        let mut entry = entry.map_err(bundle_error)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().map_err(bundle_error)?;
+        let path = path.to_string_lossy().into_owned();
+
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(bundle_error)?;
+
+        files.push(BundleFile { path, contents });
+    }
+
+    Ok(files)
+}
+
+/// Checks whether data is age-encrypted.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(AGE_MAGIC)
+}
+
+/// Encrypts data with a passphrase.
+///
+/// # Arguments
+///
+/// * `data` - The data to encrypt.
+/// * `passphrase` - The passphrase to derive the key from.
+///
+/// # Returns
+///
+/// The encrypted data, or an `Error` if encryption fails.
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let recipient = age::scrypt::Recipient::new(SecretString::from(passphrase.to_string()));
+    age::encrypt(&recipient, data).map_err(bundle_error)
+}
+
+/// Decrypts data encrypted with [`encrypt`].
+///
+/// # Arguments
+///
+/// * `data` - The encrypted data.
+/// * `passphrase` - The passphrase used to encrypt it.
+///
+/// # Returns
+///
+/// The decrypted data, or an `Error` if the passphrase is wrong or the data
+/// is corrupt.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    age::decrypt(&identity, data).map_err(|_| Error::new(ErrorKind::Decrypt))
+}
+
+fn bundle_error<E: ToString>(e: E) -> Error {
+    Error::new(ErrorKind::Bundle {
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<BundleFile> {
+        vec![
+            BundleFile {
+                path: "profiles/dev.env".into(),
+                contents: b"A=1\n".to_vec(),
+            },
+            BundleFile {
+                path: "metadata.json".into(),
+                contents: b"{}\n".to_vec(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_pack_round_trip() {
+        let archive = pack(&files()).unwrap();
+        assert!(!is_encrypted(&archive));
+        assert_eq!(files(), unpack(&archive).unwrap());
+    }
+
+    #[test]
+    fn test_unpack_invalid() {
+        assert!(unpack(b"not a tar archive").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let encrypted = encrypt(b"A=1\n", "correct horse").unwrap();
+        assert!(is_encrypted(&encrypted));
+
+        assert_eq!(
+            b"A=1\n".to_vec(),
+            decrypt(&encrypted, "correct horse").unwrap()
+        );
+        assert!(matches!(
+            decrypt(&encrypted, "wrong").unwrap_err().kind,
+            ErrorKind::Decrypt
+        ));
+    }
+}
@@ -1,33 +1,1569 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
 
+use clap::{ArgAction, ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCompleter, Shell};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::codegen::Language;
+use crate::completion;
+use crate::config::{Color, Precedence, parse_duration};
+use crate::dotenv::{Document, Line, is_valid_key};
+use crate::error::{Error, ErrorKind, Result};
+use crate::glob;
+use crate::json_schema::Subject;
+use crate::operations::ProfileOrder;
+use crate::presenter::{ColorChoice, ErrorFormat};
+
+/// Manages multiple environment profiles.
 #[derive(Parser)]
+#[command(version)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print secret values instead of masking them."
+    )]
+    pub show_secrets: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "When to color output - `auto` respects `NO_COLOR`."
+    )]
+    pub color: ColorChoice,
+
+    #[arg(
+        long,
+        short,
+        global = true,
+        action = ArgAction::Count,
+        help = "Log what envoke is doing - repeat for more detail, e.g. `-vv`."
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        long,
+        short,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only report errors - silences warnings and the progress of commands that change profiles."
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Activate the default profile without asking when no profile is active."
+    )]
+    pub auto: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Report the files that would be created, removed, linked or rewritten without changing them."
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ErrorFormat::Text,
+        help = "How to print errors - `json` includes hints and the exit code."
+    )]
+    pub error_format: ErrorFormat,
 }
 
 #[derive(Subcommand)]
 pub enum Command {
     /// Initializes the directory.
-    Init,
+    Init {
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Create these profiles, e.g. `dev,staging,prod`."
+        )]
+        profiles: Vec<String>,
+
+        #[arg(
+            long,
+            short,
+            requires = "profiles",
+            help = "Fill in each profile from a global template."
+        )]
+        template: Option<String>,
+
+        #[arg(
+            long,
+            short,
+            requires = "profiles",
+            help = "Switch to the first profile once created."
+        )]
+        switch: bool,
+
+        #[arg(
+            long,
+            value_name = "PROFILE",
+            conflicts_with = "switch",
+            help = "Create this profile, make it the default in `config.toml` and switch to it."
+        )]
+        default: Option<String>,
+
+        #[arg(
+            long,
+            help = "Succeed if already initialized, adding only what is missing."
+        )]
+        ok_if_initialized: bool,
+
+        #[arg(
+            long,
+            exclusive = true,
+            help = "Print the Starship configuration that shows the active profile, then exit."
+        )]
+        starship_snippet: bool,
+    },
+
+    /// Removes envoke from the directory, deleting `.envoke` and unlinking `.env`.
+    Deinit {
+        #[arg(
+            long,
+            help = "Replace the linked `.env` with a plain copy of the active profile."
+        )]
+        eject: bool,
+
+        #[arg(long, short, help = "Skip the confirmation prompt.")]
+        yes: bool,
+    },
+
+    /// Creates one or more new profiles.
+    Create {
+        #[arg(required = true)]
+        profiles: Vec<String>,
+
+        #[arg(
+            long,
+            short,
+            help = "Fill in the profile from a template in `.envoke/templates`."
+        )]
+        template: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "template",
+            help = "Read the contents of the profile from stdin."
+        )]
+        stdin: bool,
 
-    /// Creates a new profile.
-    Create { profile: String },
+        #[arg(
+            long,
+            short,
+            conflicts_with_all = ["template", "stdin"],
+            help = "Prompt for every key declared in the schema or `.env.example`."
+        )]
+        interactive: bool,
+
+        #[arg(
+            long,
+            short,
+            help = "Switch to the (first) profile once created - the default with `[create] switch = true`."
+        )]
+        switch: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "switch",
+            help = "Do not switch to the profile."
+        )]
+        no_switch: bool,
+
+        #[arg(
+            long,
+            short,
+            help = "Override the existing env without checks when switching."
+        )]
+        force: bool,
+
+        #[arg(long, help = "Skip profiles that already exist instead of failing.")]
+        if_missing: bool,
+    },
 
     /// Switch to a specified profile.
     Switch {
-        profile: String,
+        /// The profile, or a stack of profiles such as `dev+local` where later
+        /// profiles override earlier ones. Omit to choose interactively.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
 
         #[arg(long, short, help = "Override the existing env without checks.")]
         force: bool,
+
+        #[arg(long, help = "Refuse to switch if the profile violates the schema.")]
+        validate: bool,
+
+        #[arg(
+            long,
+            help = "Refuse to switch if `.env` or profiles are tracked by git."
+        )]
+        strict: bool,
+
+        #[arg(
+            long,
+            requires = "profile",
+            help = "Exit silently with code 4 if the profile does not exist."
+        )]
+        if_exists: bool,
+    },
+
+    /// Deletes a profile, keeping a snapshot for `envoke restore`.
+    Remove {
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        #[arg(
+            long,
+            value_name = "PROFILE",
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "Switch to this profile if the removed one was active - overrides `[remove] switch_to`."
+        )]
+        switch_to: Option<String>,
+    },
+
+    /// Freezes the active profile, refusing to switch or remove profiles.
+    Lock {
+        #[arg(
+            long,
+            short,
+            help = "Why the directory is locked - recorded in the log."
+        )]
+        reason: Option<String>,
+    },
+
+    /// Allows profiles to be switched and removed again.
+    Unlock {
+        #[arg(long, short, help = "Why the lock is lifted - recorded in the log.")]
+        reason: Option<String>,
+    },
+
+    /// Removes the backups the retention settings no longer keep.
+    Gc,
+
+    /// Restores `.env` or a profile from a backup.
+    Restore {
+        #[arg(help = "The profile to restore - `.env` when omitted.")]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            short,
+            help = "The backup to restore, e.g. `env-20250601T093000Z` - chosen from a list when omitted."
+        )]
+        backup: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "backup",
+            help = "List the backups and how each differs from the current contents."
+        )]
+        list: bool,
+
+        #[arg(long, short, help = "Skip the confirmation prompt.")]
+        yes: bool,
     },
 
-    /// Deletes a profile - cannot be undone.
-    Remove { profile: String },
+    /// Activates a profile, bringing it in from the global profiles if needed.
+    Use {
+        profile: String,
+
+        #[arg(
+            long,
+            short,
+            help = "Link the profile from the global profiles directory."
+        )]
+        global: bool,
+
+        #[arg(
+            long,
+            short,
+            requires = "global",
+            help = "Copy the global profile instead of linking it."
+        )]
+        copy: bool,
+
+        #[arg(long, short, help = "Override the existing env without checks.")]
+        force: bool,
+    },
 
     /// Lists available profiles.
-    List,
+    List {
+        #[arg(long, short, help = "List the global profiles instead.")]
+        global: bool,
+
+        #[arg(long, short, help = "Show descriptions and mark the active profile.")]
+        long: bool,
+
+        #[arg(long, conflicts_with = "long", help = "Print the profiles as JSON.")]
+        json: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ProfileOrder::Name,
+            conflicts_with = "global",
+            help = "The order to list the profiles in."
+        )]
+        sort: ProfileOrder,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["sort", "global"],
+            help = "List the most recently used profiles first - the same as `--sort recent`."
+        )]
+        recent: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "global",
+            add = ArgValueCompleter::new(completion::groups),
+            help = "Only list the profiles of a group defined under `[groups]`."
+        )]
+        group: Option<String>,
+    },
+
+    /// Lists profiles first in `list` and the picker, or shows the pinned profiles.
+    Pin {
+        /// The profiles to pin - none to show the pinned profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profiles: Vec<String>,
+    },
+
+    /// Stops listing profiles first.
+    Unpin {
+        /// The profiles to unpin.
+        #[arg(required = true, add = ArgValueCompleter::new(completion::profiles))]
+        profiles: Vec<String>,
+    },
+
+    /// Shows the active profile of every project envoke manages, or switches them.
+    Projects {
+        #[command(subcommand)]
+        command: Option<ProjectsCommand>,
+    },
 
     /// Display the current active profile.
-    Current,
+    Current {
+        #[arg(long, short, help = "Show the layers and how `.env` was created.")]
+        long: bool,
+
+        #[arg(long, conflicts_with = "long", help = "Print the profile as JSON.")]
+        json: bool,
+
+        #[arg(
+            long,
+            value_name = "PROFILE",
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "Exit with an error unless this profile or stack is active - combine with `--quiet` for scripts."
+        )]
+        is: Option<String>,
+    },
+
+    /// Summarizes the active profile and the profiles in the directory.
+    Status {
+        #[arg(long, short, help = "Also list the profiles.")]
+        long: bool,
+
+        #[arg(long, conflicts_with = "long", help = "Print the status as JSON.")]
+        json: bool,
+    },
+
+    /// Watches the active profile, regenerating `.env` and exports when it changes.
+    Watch,
+
+    /// Prints the contents of a profile, masking secrets.
+    Show {
+        /// The profile to show - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[command(flatten)]
+        filter: KeyFilter,
+    },
+
+    /// Prints a profile with all includes resolved.
+    Export {
+        /// The profile or stack (e.g. `dev+local`) to export - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(long, short, value_enum, default_value_t = ExportFormat::Dotenv)]
+        format: ExportFormat,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "The shell to write statements for - implies `--format shell`."
+        )]
+        shell: Option<ActivateShell>,
+
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+
+        #[command(flatten)]
+        filter: KeyFilter,
+    },
+
+    /// Shows how the variables of two profiles, or of `.env` and a profile, differ.
+    Diff {
+        /// The profile to compare from, or with `--env`, the profile to compare `.env` with.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        /// The profile to compare to.
+        #[arg(
+            required_unless_present = "env",
+            conflicts_with = "env",
+            add = ArgValueCompleter::new(completion::profiles)
+        )]
+        other: Option<String>,
+
+        #[arg(
+            long,
+            help = "Compare the `.env` on disk, including any hand edits, with the profile."
+        )]
+        env: bool,
+
+        #[arg(long, short, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+    },
+
+    /// Applies a patch written by `envoke diff --format patch` or `--format json` to a profile.
+    Apply {
+        /// The profile to change.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        /// The patch file, or `-` to read it from stdin.
+        patch: PathBuf,
+    },
+
+    /// Creates or updates a profile from a docker-compose service, terraform variables and outputs, a URL or another project.
+    #[command(group(
+        ArgGroup::new("source")
+            .required(true)
+            .args(["from_compose", "from_tfvars", "from_tf_output", "url", "from_project"])
+    ))]
+    Import {
+        /// The profile to create or update.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            requires = "service",
+            help = "Read the `environment:` block of a docker-compose file, e.g. `docker-compose.yml`."
+        )]
+        from_compose: Option<PathBuf>,
+
+        #[arg(
+            long,
+            requires = "from_compose",
+            help = "The compose service whose environment is imported."
+        )]
+        service: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Read the variables of a `.tfvars` or `.tfvars.json` file."
+        )]
+        from_tfvars: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Read the output of `terraform output -json`, or `-` for stdin."
+        )]
+        from_tf_output: Option<PathBuf>,
+
+        #[arg(
+            long,
+            requires = "sha256",
+            help = "Download an env file over HTTPS, e.g. from an internal config server."
+        )]
+        url: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "HASH",
+            requires = "url",
+            help = "The SHA-256 checksum the downloaded file must match."
+        )]
+        sha256: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Copy the profile of the same name from another project, e.g. `../billing`."
+        )]
+        from_project: Option<PathBuf>,
+
+        #[arg(
+            long,
+            requires = "from_project",
+            conflicts_with_all = ["prefix", "transform"],
+            help = "Link the other project's profile instead of copying it, so both share it."
+        )]
+        link: bool,
+
+        #[arg(long, help = "Prepend a prefix to every imported key, e.g. `TF_`.")]
+        prefix: Option<String>,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = KeyTransform::None,
+            help = "Change the case of imported keys before the prefix is added."
+        )]
+        transform: KeyTransform,
+    },
+
+    /// Prints the fully resolved environment of the active profile, as `run` would use it.
+    Env {
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+
+        #[arg(long, help = "Print the environment as JSON.")]
+        json: bool,
+
+        #[command(flatten)]
+        filter: KeyFilter,
+    },
+
+    /// Masks the values of a profile in text read from stdin, e.g. build logs.
+    Redact {
+        /// The profile whose values to mask - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            help = "Mask every value, not only those of keys matched by `[secrets]` patterns."
+        )]
+        all: bool,
+    },
+
+    /// Prints statements that set the variables of a profile in the current shell.
+    ///
+    /// Use as `eval "$(envoke activate dev)"`.
+    Activate {
+        /// The profile or stack to activate - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(long, short, value_enum, default_value_t = ActivateShell::Posix, help = "The shell to print statements for.")]
+        shell: ActivateShell,
+
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+    },
+
+    /// Prints statements that unset the variables set by `activate`.
+    ///
+    /// Use as `eval "$(envoke deactivate)"`.
+    Deactivate {
+        #[arg(long, short, value_enum, default_value_t = ActivateShell::Posix, help = "The shell to print statements for.")]
+        shell: ActivateShell,
+    },
+
+    /// Starts `$SHELL` with the variables of a profile set and the profile in the prompt.
+    ///
+    /// Exiting the subshell returns to the original environment.
+    Shell {
+        /// The profile or stack to load - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+    },
+
+    /// Runs a command with the variables of a profile set.
+    #[command(visible_alias = "exec")]
+    Run {
+        #[arg(
+            long,
+            short,
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "The profile or stack to load, repeated to layer profiles in order - defaults to the active profile."
+        )]
+        profile: Vec<String>,
+
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Whether the profile or the current environment wins for variables set in both - defaults to `[run] precedence`, or `profile`."
+        )]
+        precedence: Option<Precedence>,
+
+        #[arg(
+            long,
+            help = "Start the command with only the profile's variables and those allowed by `[run] allow`."
+        )]
+        isolated: bool,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            requires = "isolated",
+            help = "Also pass on variables matching this glob from the current environment, e.g. `AWS_*`."
+        )]
+        allow: Vec<String>,
+
+        #[arg(
+            long = "env",
+            short = 'e',
+            value_name = "KEY=VALUE",
+            value_parser = parse_override,
+            help = "Set a variable for this run only, after all profiles are layered. Repeat for more."
+        )]
+        overrides: Vec<(String, String)>,
+
+        /// The command to run, after `--`.
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Prints the value of a key, or copies it to the clipboard.
+    Get {
+        /// The key to look up.
+        #[arg(add = ArgValueCompleter::new(completion::keys))]
+        key: String,
+
+        #[arg(
+            long,
+            short,
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "Read from this profile instead of the active one."
+        )]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            short,
+            help = "Copy the value to the clipboard instead of printing it."
+        )]
+        copy: bool,
+
+        #[arg(long, value_parser = parse_duration, requires = "copy", help = "Clear the clipboard after this long, e.g. `30s` (`0s` disables).")]
+        clear_after: Option<Duration>,
+    },
+
+    /// Shows the final value of a key and every assignment that produced it.
+    Resolve {
+        /// The key to trace.
+        #[arg(add = ArgValueCompleter::new(completion::keys))]
+        key: String,
+
+        #[arg(
+            long,
+            short,
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "Trace the key in this profile or stack instead of the active one."
+        )]
+        profile: Option<String>,
+
+        #[arg(long, help = "Do not overlay `.envoke/local.env`.")]
+        no_local: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Report the value `envoke run --precedence` would pass on - defaults to `[run] precedence`, or `profile`."
+        )]
+        precedence: Option<Precedence>,
+    },
+
+    /// Flags values that look like credentials or were committed to git.
+    Scan {
+        #[arg(
+            long,
+            short,
+            add = ArgValueCompleter::new(completion::profiles),
+            help = "Scan only this profile."
+        )]
+        profile: Option<String>,
+
+        #[arg(long, help = "Do not search the git history for secret values.")]
+        no_history: bool,
+
+        #[arg(long, help = "Print the findings as JSON.")]
+        json: bool,
+    },
+
+    /// Generates the identity used to decrypt encrypted values.
+    Keygen {
+        #[arg(
+            long,
+            short,
+            help = "Replace an existing identity - values encrypted only to it become unreadable."
+        )]
+        force: bool,
+    },
+
+    /// Encrypts values of a profile in place.
+    Encrypt {
+        /// The profile holding the values.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        /// The keys whose values to encrypt.
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// Manages the public keys values are encrypted to.
+    #[command(subcommand)]
+    Recipients(RecipientsCommand),
+
+    /// Generates a `.env.example` with the keys of a profile but no values.
+    Example {
+        /// The profile to read keys from - defaults to the active profile.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            short,
+            default_value = ".env.example",
+            help = "The file to write."
+        )]
+        output: PathBuf,
+
+        #[arg(long, default_value = "", help = "The value written for every key.")]
+        placeholder: String,
+
+        #[arg(
+            long,
+            short,
+            help = "Replace the file if it exists, even if it is managed by envoke."
+        )]
+        force: bool,
+    },
+
+    /// Checks that profiles define every key in `.env.example`.
+    Verify {
+        /// Verify a single profile instead of all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            default_value = ".env.example",
+            help = "The example file to verify against."
+        )]
+        example: PathBuf,
+
+        #[arg(long, help = "Also fail when a profile has keys the example does not.")]
+        strict: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "profile",
+            add = ArgValueCompleter::new(completion::groups),
+            help = "Verify the profiles of a group defined under `[groups]`."
+        )]
+        group: Option<String>,
+    },
+
+    /// Detects profiles changed outside envoke since it last wrote them.
+    VerifyIntegrity {
+        /// Check a single profile instead of all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(
+            long,
+            help = "Trust the current contents, recording them as the expected ones."
+        )]
+        accept: bool,
+    },
+
+    /// Signs reviewed profiles with the `[signing]` key.
+    Sign {
+        /// The profiles to sign.
+        #[arg(required = true, add = ArgValueCompleter::new(completion::profiles))]
+        profiles: Vec<String>,
+    },
+
+    /// Checks that profiles are signed by a trusted key.
+    VerifySignature {
+        /// Check a single profile instead of all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+    },
+
+    /// Checks profiles against `.envoke/schema.toml`.
+    Validate {
+        /// Validate a single profile instead of all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+    },
+
+    /// Generates typed access to the variables of the schema and profiles.
+    Codegen {
+        /// The language to generate.
+        language: Language,
+
+        #[arg(long, short, help = "The file to write instead of printing the code.")]
+        output: Option<PathBuf>,
+    },
+
+    /// Prints the JSON Schema of `config.toml` or of a machine-readable output.
+    Schema {
+        #[command(flatten)]
+        subject: SchemaSubject,
+    },
+
+    /// Manages reusable profile templates.
+    #[command(subcommand)]
+    Template(TemplateCommand),
+
+    /// Packs profiles into a single, optionally encrypted, archive.
+    #[command(subcommand)]
+    Bundle(BundleCommand),
+
+    /// Manages the remotes profiles are synchronised with.
+    #[command(subcommand)]
+    Remote(RemoteCommand),
+
+    /// Uploads all profiles, encrypted, to a remote.
+    Push {
+        /// The remote to push to - defaults to the only remote or `origin`.
+        #[arg(add = ArgValueCompleter::new(completion::remotes))]
+        remote: Option<String>,
+
+        #[arg(long, short, help = "Overwrite changes made on the remote.")]
+        force: bool,
+    },
+
+    /// Replaces the local profiles with those stored in a remote.
+    Pull {
+        /// The remote to pull from - defaults to the only remote or `origin`.
+        #[arg(add = ArgValueCompleter::new(completion::remotes))]
+        remote: Option<String>,
+
+        #[arg(long, short, help = "Discard local changes.")]
+        force: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ConflictAction::Merge,
+            conflicts_with = "force",
+            help = "What to do with profiles changed both locally and remotely."
+        )]
+        on_conflict: ConflictAction,
+
+        #[arg(
+            long,
+            value_enum,
+            conflicts_with = "force",
+            help = "Settle keys changed on both sides without asking."
+        )]
+        strategy: Option<MergeStrategy>,
+    },
+
+    /// Writes a validated `.env` for pipelines, without prompting.
+    Ci {
+        /// The profile or stack (e.g. `dev+ci`) to write.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+    },
+
+    /// Reports how long ago each value last changed.
+    Audit {
+        /// Audit a single profile instead of all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: Option<String>,
+
+        #[arg(long, value_parser = parse_duration, help = "Fail if any value is older than this, e.g. `90d`.")]
+        max_age: Option<Duration>,
+    },
+
+    /// Renames a profile, or every profile matching a pattern.
+    Rename {
+        /// The profile to rename, or with `--pattern` the new name of each matching profile,
+        /// where `{name}` is its current name.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        name: String,
+
+        /// The new name of the profile.
+        #[arg(required_unless_present = "pattern", conflicts_with = "pattern")]
+        renamed: Option<String>,
+
+        #[arg(long, help = "Rename every profile matching a glob, e.g. `legacy-*`.")]
+        pattern: Option<String>,
+    },
+
+    /// Renames a key in every profile, or in the given profiles.
+    MvKey {
+        /// The key to rename.
+        #[arg(add = ArgValueCompleter::new(completion::keys))]
+        old: String,
+
+        /// The new name of the key.
+        new: String,
+
+        /// The profiles to rename the key in - defaults to all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profiles: Vec<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "profiles",
+            add = ArgValueCompleter::new(completion::groups),
+            help = "Rename the key in the profiles of a group defined under `[groups]`."
+        )]
+        group: Option<String>,
+    },
+
+    /// Replaces text in the values of every profile, or of the given profiles.
+    Replace {
+        #[arg(long, value_name = "TEXT", help = "The text to find in values.")]
+        value_matching: String,
+
+        /// The text to replace it with. With `--regex`, `$1` or `${name}` insert capture groups.
+        replacement: String,
+
+        /// The profiles to change - defaults to all profiles.
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profiles: Vec<String>,
+
+        #[arg(long, help = "Treat the text to find as a regular expression.")]
+        regex: bool,
+    },
+
+    /// Reports which profiles are missing keys that other profiles define.
+    AuditKeys {
+        #[arg(long, help = "Print the report as JSON.")]
+        json: bool,
+    },
+
+    /// Shows the most recent operations recorded in the log.
+    Log {
+        #[arg(
+            long,
+            short = 'n',
+            default_value_t = 20,
+            help = "Show at most this many operations."
+        )]
+        limit: usize,
+
+        #[arg(long, help = "Print one JSON object per line.")]
+        json: bool,
+    },
+
+    /// Prints or installs shell completions.
+    Completions {
+        #[arg(
+            value_enum,
+            help = "The shell to generate completions for. Detected from `$SHELL` when omitted."
+        )]
+        shell: Option<Shell>,
+
+        #[arg(
+            long,
+            help = "Write the script to the shell's completion directory instead of printing it."
+        )]
+        install: bool,
+    },
+
+    /// Prints the active profile for use in a shell prompt, or nothing.
+    Prompt {
+        #[arg(
+            long,
+            help = "The text to print, with `{profile}` replaced - overrides `[prompt] format`."
+        )]
+        format: Option<String>,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Color the segment - overrides `[prompt] style`."
+        )]
+        style: Option<Color>,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = PromptShell::Plain,
+            help = "Wrap color codes so the shell measures the prompt correctly."
+        )]
+        shell: PromptShell,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "style"],
+            help = "Print only the profile, failing when none is active, for a Starship custom module."
+        )]
+        starship: bool,
+    },
+
+    /// Replaces envoke with the latest release, after verifying its signature.
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        #[arg(long, help = "Only report whether a newer release is available.")]
+        check: bool,
+    },
+
+    /// Opens an interactive dashboard for browsing, diffing, switching and editing profiles.
+    Ui,
+
+    /// Writes roff manual pages for envoke and each of its commands.
+    #[command(hide = true)]
+    Man {
+        #[arg(
+            long,
+            help = "Write a page per command into this directory instead of printing the main page."
+        )]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Clears the clipboard if it still holds a copied value, whose digest is
+    /// read from stdin.
+    #[command(name = "__clear-clipboard", hide = true)]
+    ClearClipboard { millis: u64 },
+}
+
+impl Command {
+    /// Names the operation recorded in the log for commands that change
+    /// profiles, remotes, the active `.env` or write other files.
+    ///
+    /// # Returns
+    ///
+    /// The operation, e.g. `template add`, or `None` for read-only commands.
+    pub fn operation(&self) -> Option<&'static str> {
+        let operation = match self {
+            Command::Init {
+                starship_snippet: false,
+                ..
+            } => "init",
+            Command::Deinit { .. } => "deinit",
+            Command::Create { .. } => "create",
+            Command::Switch { .. } => "switch",
+            Command::Remove { .. } => "remove",
+            Command::Lock { .. } => "lock",
+            Command::Unlock { .. } => "unlock",
+            Command::Gc => "gc",
+            Command::Restore { list: false, .. } => "restore",
+            Command::Use { .. } => "use",
+            Command::Pin { profiles } if !profiles.is_empty() => "pin",
+            Command::Unpin { .. } => "unpin",
+            Command::Apply { .. } => "apply",
+            Command::Import { .. } => "import",
+            Command::Encrypt { .. } => "encrypt",
+            Command::Rename { .. } => "rename",
+            Command::MvKey { .. } => "mv-key",
+            Command::Replace { .. } => "replace",
+            Command::Recipients(RecipientsCommand::Add { .. }) => "recipients add",
+            Command::Recipients(RecipientsCommand::Remove { .. }) => "recipients remove",
+            Command::Template(TemplateCommand::Add { .. }) => "template add",
+            Command::Template(TemplateCommand::Remove { .. }) => "template remove",
+            Command::Bundle(BundleCommand::Import { .. }) => "bundle import",
+            Command::Remote(RemoteCommand::Add { .. }) => "remote add",
+            Command::Remote(RemoteCommand::Remove { .. }) => "remote remove",
+            Command::Projects {
+                command: Some(ProjectsCommand::Switch { .. }),
+            } => "projects switch",
+            Command::Projects {
+                command: Some(ProjectsCommand::Forget { .. }),
+            } => "projects forget",
+            Command::Push { .. } => "push",
+            Command::Pull { .. } => "pull",
+            Command::Ci { .. } => "ci",
+            Command::VerifyIntegrity { accept: true, .. } => "verify-integrity",
+            Command::Sign { .. } => "sign",
+            Command::Ui => "ui",
+            Command::Watch => "watch",
+            Command::Example { .. } => "example",
+            Command::Keygen { .. } => "keygen",
+            Command::Codegen {
+                output: Some(_), ..
+            } => "codegen",
+            Command::Bundle(BundleCommand::Export { .. }) => "bundle export",
+            _ => return None,
+        };
+
+        Some(operation)
+    }
+
+    /// Checks whether the command must not run while the directory is
+    /// locked, as it changes profiles, `.env` or `.envoke` itself.
+    ///
+    /// # Returns
+    ///
+    /// `true` for every logged operation except `lock`, `unlock`, `pin`,
+    /// `unpin`, `gc`, `push`, `sign`, `verify-integrity`, `projects`, `ui`,
+    /// `watch`, `example`, `keygen`, `codegen` and `bundle export`.
+    pub fn respects_lock(&self) -> bool {
+        match self {
+            Command::Lock { .. }
+            | Command::Unlock { .. }
+            | Command::Pin { .. }
+            | Command::Unpin { .. }
+            | Command::Gc
+            | Command::Push { .. }
+            | Command::Sign { .. }
+            | Command::VerifyIntegrity { .. }
+            | Command::Projects { .. }
+            | Command::Ui
+            | Command::Watch
+            | Command::Example { .. }
+            | Command::Keygen { .. }
+            | Command::Codegen { .. }
+            | Command::Bundle(BundleCommand::Export { .. }) => false,
+            _ => self.operation().is_some(),
+        }
+    }
+
+    /// Checks whether the command can be previewed with `--dry-run`.
+    ///
+    /// Commands that start processes or keep running cannot be, as their
+    /// effects do not go through the filesystem envoke controls.
+    /// Switching other projects is not previewed either, as it does not go
+    /// through the filesystem of the current one.
+    ///
+    /// # Returns
+    ///
+    /// `false` for `run`, `shell`, `watch`, `ui`, `sign`, `projects switch`
+    /// and `self-update`.
+    pub fn supports_dry_run(&self) -> bool {
+        match self {
+            Command::Run { .. }
+            | Command::Shell { .. }
+            | Command::Watch
+            | Command::Ui
+            | Command::Sign { .. }
+            | Command::Projects {
+                command: Some(ProjectsCommand::Switch { .. }),
+            } => false,
+            #[cfg(feature = "self-update")]
+            Command::SelfUpdate { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+/// Output formats supported by `envoke export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A `.env` file, keeping comments.
+    Dotenv,
+
+    /// `export KEY='value'` statements for POSIX shells.
+    Shell,
+
+    /// A JSON object of keys and values.
+    Json,
+}
+
+/// Output formats supported by `envoke diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// A colored summary of added, removed and changed keys.
+    Text,
+
+    /// A unified diff that `envoke apply` can read.
+    Patch,
+
+    /// A JSON patch (RFC 6902) that `envoke apply` can read.
+    Json,
+}
+
+/// What `envoke pull` does with a profile changed both locally and remotely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictAction {
+    /// Merge the two versions key by key, asking about conflicting keys.
+    Merge,
+
+    /// Keep the local profile and write the remote one next to it as
+    /// `<profile>.env.conflict`.
+    Copy,
+
+    /// Fail without changing anything.
+    Refuse,
+}
+
+/// How `envoke pull` settles keys changed both locally and remotely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeStrategy {
+    /// Keep the local value.
+    Ours,
+
+    /// Take the remote value.
+    Theirs,
+}
+
+/// How `envoke import` changes the names of imported keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeyTransform {
+    /// Keep names as they are.
+    None,
+
+    /// Upper-case names and replace dashes with underscores, e.g. `db-host`
+    /// becomes `DB_HOST`.
+    Upper,
+
+    /// Lower-case names and replace dashes with underscores.
+    Lower,
+}
+
+/// What `envoke schema` describes - exactly one is given.
+#[derive(Debug, Default, Clone, Args)]
+#[group(required = true, multiple = false)]
+pub struct SchemaSubject {
+    #[arg(long, help = "Describe `.envoke/config.toml`.")]
+    pub config: bool,
+
+    #[arg(long, help = "Describe the output of `list --json`.")]
+    pub list: bool,
+
+    #[arg(long, help = "Describe the output of `current --json`.")]
+    pub current: bool,
+
+    #[arg(long, help = "Describe the output of `status --json`.")]
+    pub status: bool,
+
+    #[arg(
+        long,
+        help = "Describe the output of `env --json` and `export --format json`."
+    )]
+    pub env: bool,
+
+    #[arg(long, help = "Describe the output of `projects list --json`.")]
+    pub projects: bool,
+
+    #[arg(long, help = "Describe the output of `scan --json`.")]
+    pub scan: bool,
+
+    #[arg(long, help = "Describe the output of `audit-keys --json`.")]
+    pub audit_keys: bool,
+
+    #[arg(long, help = "Describe each line of `log --json`.")]
+    pub log: bool,
+
+    #[arg(
+        long,
+        help = "Describe the output of `diff --format json`, as read by `apply`."
+    )]
+    pub diff: bool,
+
+    #[arg(long, help = "Describe errors written with `--error-format json`.")]
+    pub error: bool,
+}
+
+impl SchemaSubject {
+    /// Gets the subject selected by the flags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::cli::SchemaSubject;
+    /// use envoke::json_schema::Subject;
+    ///
+    /// let flags = SchemaSubject {
+    ///     status: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(Subject::Status, flags.subject());
+    /// ```
+    pub fn subject(&self) -> Subject {
+        [
+            (self.config, Subject::Config),
+            (self.list, Subject::List),
+            (self.current, Subject::Current),
+            (self.status, Subject::Status),
+            (self.env, Subject::Env),
+            (self.projects, Subject::Projects),
+            (self.scan, Subject::Scan),
+            (self.audit_keys, Subject::AuditKeys),
+            (self.log, Subject::Log),
+            (self.diff, Subject::Diff),
+            (self.error, Subject::Error),
+        ]
+        .into_iter()
+        .find_map(|(selected, subject)| selected.then_some(subject))
+        .unwrap_or(Subject::Config)
+    }
+}
+
+/// Options narrowing the variables `show`, `export` and `env` print.
+#[derive(Debug, Default, Clone, Args)]
+pub struct KeyFilter {
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Only print keys matching the glob, e.g. `AWS_*` - may be repeated."
+    )]
+    pub only: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Leave out keys matching the glob, e.g. `*_SECRET` - may be repeated."
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(long, help = "Leave out comments and blank lines.")]
+    pub no_comments: bool,
+}
+
+impl KeyFilter {
+    /// Checks whether a key is printed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::cli::KeyFilter;
+    ///
+    /// let filter = KeyFilter {
+    ///     only: vec!["AWS_*".to_string()],
+    ///     exclude: vec!["*_SECRET*".to_string()],
+    ///     no_comments: false,
+    /// };
+    /// assert!(filter.includes("AWS_REGION"));
+    /// assert!(!filter.includes("AWS_SECRET_ACCESS_KEY"));
+    /// assert!(!filter.includes("PORT"));
+    /// ```
+    pub fn includes(&self, key: &str) -> bool {
+        (self.only.is_empty() || glob::matches_any(&self.only, key))
+            && !glob::matches_any(&self.exclude, key)
+    }
+
+    /// Keeps the lines of a profile that are printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc` - The profile.
+    ///
+    /// # Returns
+    ///
+    /// The entries of included keys, and the comments and blank lines unless
+    /// `no_comments` is set.
+    pub fn apply(&self, doc: &Document) -> Document {
+        let lines = doc
+            .lines()
+            .iter()
+            .filter_map(|line| match line {
+                Line::Entry(entry) if !self.includes(&entry.key) => None,
+                Line::Entry(entry) if self.no_comments => {
+                    Some(Line::Entry(entry.without_comment()))
+                }
+                Line::Blank(_) | Line::Comment(_) if self.no_comments => None,
+                line => Some(line.clone()),
+            })
+            .collect();
+
+        Document::from_lines(lines)
+    }
+}
+
+/// Parses a `KEY=VALUE` override given on the command line.
+///
+/// # Returns
+///
+/// The key and value, or an `Error` if there is no `=` or the key is invalid.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::cli::parse_override;
+///
+/// assert_eq!(
+///     ("LOG_LEVEL".to_string(), "a=b".to_string()),
+///     parse_override("LOG_LEVEL=a=b").unwrap()
+/// );
+/// assert!(parse_override("LOG_LEVEL").is_err());
+/// assert!(parse_override("LOG-LEVEL=trace").is_err());
+/// ```
+pub fn parse_override(input: &str) -> Result<(String, String)> {
+    let (key, value) = input.split_once('=').ok_or_else(|| {
+        Error::new(ErrorKind::InvalidOverride {
+            input: input.to_string(),
+        })
+    })?;
+    if !is_valid_key(key) {
+        return Err(ErrorKind::InvalidKey {
+            key: key.to_string(),
+        }
+        .into());
+    }
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Shells that `envoke activate` and `envoke export` can print statements for.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ActivateShell {
+    /// `export` and `unset`, for sh, bash and zsh.
+    #[value(alias = "sh", alias = "bash", alias = "zsh")]
+    Posix,
+
+    /// `set -gx` and `set -e`.
+    Fish,
+
+    /// `${env:}` assignments and `Remove-Item`.
+    Powershell,
+}
+
+/// Shells whose prompts need color codes marked as zero-width.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PromptShell {
+    /// Print color codes as they are, e.g. for fish or Starship.
+    Plain,
+
+    /// Wrap color codes in readline's `\001` and `\002` markers for `PS1`.
+    Bash,
+
+    /// Wrap color codes in `%{` and `%}` for `PROMPT`.
+    Zsh,
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommand {
+    /// Writes profiles and their metadata to an archive.
+    Export {
+        #[arg(long, short, help = "The archive to write, e.g. `team-envs.tar.age`.")]
+        out: PathBuf,
+
+        #[arg(
+            long,
+            short,
+            value_delimiter = ',',
+            help = "The profiles to include - defaults to all profiles."
+        )]
+        profiles: Vec<String>,
+
+        #[arg(
+            long,
+            short,
+            conflicts_with = "profiles",
+            add = ArgValueCompleter::new(completion::groups),
+            help = "Include the profiles of a group defined under `[groups]`."
+        )]
+        group: Option<String>,
+
+        #[arg(
+            long,
+            short,
+            help = "Encrypt with a passphrase - implied by a `.age` extension."
+        )]
+        encrypt: bool,
+    },
+
+    /// Unpacks an archive into `.envoke`.
+    Import {
+        file: PathBuf,
+
+        #[arg(long, short, help = "Overwrite existing profiles.")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RemoteCommand {
+    /// Lists the configured remotes.
+    List,
+
+    /// Adds a git repository or `s3://bucket/prefix` remote.
+    Add { name: String, url: String },
+
+    /// Removes a remote - the data stored in it is kept.
+    Remove {
+        #[arg(add = ArgValueCompleter::new(completion::remotes))]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectsCommand {
+    /// Lists each project with its active profile and health - the default.
+    List {
+        #[arg(long, help = "Print the projects as JSON.")]
+        json: bool,
+    },
+
+    /// Switches registered projects that have a profile to it.
+    Switch {
+        #[arg(add = ArgValueCompleter::new(completion::profiles))]
+        profile: String,
+
+        /// The projects to switch.
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        projects: Vec<PathBuf>,
+
+        #[arg(long, short, help = "Switch every registered project.")]
+        all: bool,
+    },
+
+    /// Removes a project from the registry - its profiles are kept.
+    Forget { path: PathBuf },
+}
+
+#[derive(Subcommand)]
+pub enum RecipientsCommand {
+    /// Lists the configured recipients.
+    List,
+
+    /// Adds an `age1...` public key and re-encrypts every encrypted value.
+    Add { recipient: String },
+
+    /// Removes a public key and re-encrypts every encrypted value without it.
+    Remove { recipient: String },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommand {
+    /// Lists project and global templates.
+    List,
+
+    /// Creates a template, optionally copying a profile or file.
+    Add {
+        template: String,
+
+        #[arg(
+            long,
+            conflicts_with = "file",
+            help = "Copy the contents of this profile."
+        )]
+        from: Option<String>,
+
+        #[arg(long, help = "Copy the contents of this file.")]
+        file: Option<PathBuf>,
+
+        #[arg(long, short, help = "Store the template in the global directory.")]
+        global: bool,
+    },
+
+    /// Deletes a template - cannot be undone.
+    Remove {
+        template: String,
+
+        #[arg(long, short, help = "Remove the template from the global directory.")]
+        global: bool,
+    },
+
+    /// Prints the contents of a template.
+    Show { template: String },
 }
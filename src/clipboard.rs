@@ -0,0 +1,297 @@
+//! Clipboard module for the envoke CLI tool.
+//!
+//! This module copies values to the system clipboard by delegating to the
+//! platform's clipboard utilities (`pbcopy`, `wl-copy`, `xclip`, `xsel` or
+//! `clip.exe`), keeping envoke itself free of windowing dependencies.
+
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::digest::sha256_hex;
+use crate::error::{Error, ErrorKind, Result};
+use tracing::debug;
+
+/// A clipboard utility and the arguments needed to copy and paste with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clipboard {
+    /// Command and arguments that read the new contents from stdin.
+    copy: &'static [&'static str],
+
+    /// Command and arguments that write the current contents to stdout.
+    paste: &'static [&'static str],
+}
+
+const PBCOPY: Clipboard = Clipboard {
+    copy: &["pbcopy"],
+    paste: &["pbpaste"],
+};
+
+const WL_COPY: Clipboard = Clipboard {
+    copy: &["wl-copy"],
+    paste: &["wl-paste", "--no-newline"],
+};
+
+const XCLIP: Clipboard = Clipboard {
+    copy: &["xclip", "-selection", "clipboard"],
+    paste: &["xclip", "-selection", "clipboard", "-o"],
+};
+
+const XSEL: Clipboard = Clipboard {
+    copy: &["xsel", "--clipboard", "--input"],
+    paste: &["xsel", "--clipboard", "--output"],
+};
+
+const CLIP_EXE: Clipboard = Clipboard {
+    copy: &["clip.exe"],
+    paste: &["powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"],
+};
+
+impl Clipboard {
+    /// Detects the clipboard utility available on this system.
+    ///
+    /// # Returns
+    ///
+    /// The detected `Clipboard`, or an `Error` if no supported utility is installed.
+    pub fn detect() -> Result<Self> {
+        Self::detect_with(|var| env::var_os(var).is_some(), in_path)
+    }
+
+    /// Detects a clipboard utility using the given environment probes.
+    ///
+    /// # Arguments
+    ///
+    /// * `has_var` - Checks whether an environment variable is set.
+    /// * `has_command` - Checks whether a command is installed.
+    ///
+    /// # Returns
+    ///
+    /// The first suitable `Clipboard`, or an `Error` if none are available.
+    pub fn detect_with<V, C>(has_var: V, has_command: C) -> Result<Self>
+    where
+        V: Fn(&str) -> bool,
+        C: Fn(&str) -> bool,
+    {
+        let mut candidates = vec![PBCOPY];
+        if has_var("WAYLAND_DISPLAY") {
+            candidates.push(WL_COPY);
+        }
+        if has_var("DISPLAY") {
+            candidates.extend([XCLIP, XSEL]);
+        }
+        candidates.push(CLIP_EXE);
+
+        candidates
+            .into_iter()
+            .find(|clipboard| has_command(clipboard.copy[0]))
+            .ok_or_else(|| ErrorKind::ClipboardUnavailable.into())
+    }
+
+    /// Replaces the clipboard contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The new clipboard contents.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the clipboard utility fails.
+    pub fn copy(&self, contents: &str) -> Result<()> {
+        let mut child = self
+            .command(self.copy)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| self.error(self.copy, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(contents.as_bytes())
+                .map_err(|e| self.error(self.copy, e))?;
+        }
+
+        let status = child.wait().map_err(|e| self.error(self.copy, e))?;
+        if !status.success() {
+            return Err(self.error(self.copy, std::io::Error::other(status.to_string())));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the clipboard contents.
+    ///
+    /// # Returns
+    ///
+    /// The current clipboard contents, or an `Error` if the clipboard utility fails.
+    pub fn paste(&self) -> Result<String> {
+        let output = self
+            .command(self.paste)
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| self.error(self.paste, e))?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Clears the clipboard after a delay, but only if it still holds the
+    /// contents with the given digest.
+    ///
+    /// This blocks for the duration of the delay, so it is intended to run in
+    /// a detached background process (see [`schedule_clear`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - How long to wait before clearing.
+    /// * `digest` - The SHA-256 digest of the contents to clear.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the clipboard was cleared, or an `Error` if it could not be accessed.
+    pub fn clear_after(&self, after: Duration, digest: &str) -> Result<bool> {
+        thread::sleep(after);
+
+        // Never clobber something the user copied in the meantime. Some
+        // utilities, like `Get-Clipboard`, append a line ending when pasting.
+        let contents = self.paste()?;
+        let trimmed = contents
+            .strip_suffix("\r\n")
+            .or_else(|| contents.strip_suffix('\n'))
+            .unwrap_or(&contents);
+        if sha256_hex(&contents) != digest && sha256_hex(trimmed) != digest {
+            return Ok(false);
+        }
+
+        self.copy("")?;
+        Ok(true)
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        debug!(?args, "running clipboard utility");
+        let mut command = Command::new(args[0]);
+        command.args(&args[1..]);
+        command
+    }
+
+    fn error(&self, args: &[&str], source: std::io::Error) -> Error {
+        Error::new(ErrorKind::Clipboard {
+            command: args[0].to_string(),
+            source,
+        })
+    }
+}
+
+/// Spawns a detached envoke process that clears the clipboard after a delay.
+///
+/// Only a digest of the contents is passed to the background process, over
+/// its stdin, so neither the value nor its digest appears in the process
+/// list.
+///
+/// # Arguments
+///
+/// * `after` - How long to wait before clearing.
+/// * `contents` - The contents that were copied.
+///
+/// # Returns
+///
+/// `Ok(())` once the background process is running, or an `Error` if it
+/// could not be started.
+pub fn schedule_clear(after: Duration, contents: &str) -> Result<()> {
+    let exe = env::current_exe().map_err(|e| {
+        Error::new(ErrorKind::Clipboard {
+            command: "envoke".into(),
+            source: e,
+        })
+    })?;
+
+    let error = |source| {
+        Error::new(ErrorKind::Clipboard {
+            command: "envoke".into(),
+            source,
+        })
+    };
+
+    let mut child = Command::new(exe)
+        .arg("__clear-clipboard")
+        .arg(after.as_millis().to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(error)?;
+
+    // Dropping stdin closes it, so the process is never left waiting.
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", sha256_hex(contents)).map_err(error)?;
+    }
+
+    Ok(())
+}
+
+/// Checks whether a command exists in any directory on `$PATH`.
+fn in_path(command: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(command))))
+        .unwrap_or(false)
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_prefers_platform_tools() {
+        let clipboard = Clipboard::detect_with(|_| true, |_| true).unwrap();
+        assert_eq!(PBCOPY, clipboard);
+    }
+
+    #[test]
+    fn test_detect_wayland_before_x11() {
+        let clipboard = Clipboard::detect_with(|_| true, |cmd| cmd != "pbcopy").unwrap();
+        assert_eq!(WL_COPY, clipboard);
+    }
+
+    #[test]
+    fn test_detect_requires_display() {
+        let has_x11 = |cmd: &str| cmd == "xclip" || cmd == "xsel";
+
+        let clipboard = Clipboard::detect_with(|var| var == "DISPLAY", has_x11).unwrap();
+        assert_eq!(XCLIP, clipboard);
+
+        let result = Clipboard::detect_with(|_| false, has_x11);
+        assert!(matches!(
+            result.unwrap_err().kind,
+            ErrorKind::ClipboardUnavailable
+        ));
+    }
+
+    #[test]
+    fn test_clear_after_ignores_line_ending() {
+        let clipboard = Clipboard {
+            copy: &["true"],
+            paste: &["printf", "secret\r\n"],
+        };
+
+        let cleared = clipboard.clear_after(Duration::ZERO, &sha256_hex("secret"));
+        assert!(cleared.unwrap());
+        let cleared = clipboard.clear_after(Duration::ZERO, &sha256_hex("other"));
+        assert!(!cleared.unwrap());
+    }
+
+    #[test]
+    fn test_detect_wsl() {
+        let clipboard = Clipboard::detect_with(|_| false, |cmd| cmd == "clip.exe").unwrap();
+        assert_eq!(CLIP_EXE, clipboard);
+    }
+}
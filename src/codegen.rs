@@ -0,0 +1,674 @@
+//! Code generation module for the envoke CLI tool.
+//!
+//! `envoke codegen` describes the variables of a project - their names from
+//! the schema and the profiles, their types and whether they are required
+//! from the schema - as source code, so applications read the environment
+//! envoke manages through typed accessors rather than raw strings.
+
+use std::collections::BTreeSet;
+
+use clap::ValueEnum;
+
+use crate::dotenv::Document;
+use crate::schema::{Schema, ValueType};
+
+/// The languages `envoke codegen` can generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    /// A Rust module with an `Env` struct and a `from_env()` loader.
+    Rust,
+
+    /// An `env.d.ts` declaring the variables of `process.env`.
+    Typescript,
+}
+
+/// Whether a variable must be set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Presence {
+    /// The variable must be set.
+    Required,
+
+    /// The variable falls back to the value when unset.
+    Default(String),
+
+    /// The variable may be unset.
+    Optional,
+}
+
+/// A variable of the environment, as code sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    /// The name of the variable.
+    pub key: String,
+
+    /// The type its value parses as.
+    pub value_type: ValueType,
+
+    /// The allowed values when `value_type` is `enum`.
+    pub values: Vec<String>,
+
+    /// Whether the variable must be set.
+    pub presence: Presence,
+}
+
+/// Collects the variables of a project.
+///
+/// Keys come from the schema and from every profile. The schema decides the
+/// type and presence of the keys it describes; other keys are strings,
+/// required only if every profile defines them.
+///
+/// # Arguments
+///
+/// * `schema` - The schema, if one has been defined.
+/// * `profiles` - The resolved profiles.
+///
+/// # Returns
+///
+/// The variables, sorted by key.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::codegen::{Presence, variables};
+/// use envoke::dotenv::Document;
+///
+/// let dev = Document::parse("HOST=localhost\nDEBUG=1\n").unwrap();
+/// let prod = Document::parse("HOST=example.com\n").unwrap();
+/// let vars = variables(None, &[dev, prod]);
+/// assert_eq!("DEBUG", vars[0].key);
+/// assert_eq!(Presence::Optional, vars[0].presence);
+/// assert_eq!(Presence::Required, vars[1].presence);
+/// ```
+pub fn variables(schema: Option<&Schema>, profiles: &[Document]) -> Vec<Variable> {
+    let key_sets: Vec<_> = profiles.iter().map(Document::key_set).collect();
+    let mut keys: BTreeSet<&str> = key_sets.iter().flatten().copied().collect();
+    if let Some(schema) = schema {
+        keys.extend(schema.keys.keys().map(String::as_str));
+    }
+
+    keys.into_iter()
+        .map(|key| match schema.and_then(|schema| schema.keys.get(key)) {
+            Some(rule) => Variable {
+                key: key.to_string(),
+                value_type: rule.value_type,
+                values: rule.values.clone(),
+                presence: match (&rule.default, rule.required) {
+                    (Some(default), _) => Presence::Default(default.clone()),
+                    (None, true) => Presence::Required,
+                    (None, false) => Presence::Optional,
+                },
+            },
+            None => Variable {
+                key: key.to_string(),
+                value_type: ValueType::String,
+                values: Vec::new(),
+                presence: match key_sets.iter().all(|keys| keys.contains(key)) {
+                    true => Presence::Required,
+                    false => Presence::Optional,
+                },
+            },
+        })
+        .collect()
+}
+
+/// Generates a Rust module reading the variables from the environment.
+///
+/// The module only depends on the standard library. It defines an `Env`
+/// struct with a field per variable, an enum per `enum` variable, and an
+/// `EnvError` returned by `Env::from_env()` when a required variable is unset
+/// or a value does not parse.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::codegen::{Presence, Variable, rust};
+/// use envoke::schema::ValueType;
+///
+/// let port = Variable {
+///     key: "PORT".to_string(),
+///     value_type: ValueType::Port,
+///     values: Vec::new(),
+///     presence: Presence::Required,
+/// };
+/// let code = rust(&[port]);
+/// assert!(code.contains("pub port: u16,"));
+/// assert!(code.contains("port: required(\"PORT\", parse_port)?,"));
+/// ```
+pub fn rust(variables: &[Variable]) -> String {
+    let mut code = String::from("// Generated by `envoke codegen rust` - do not edit.\n\n");
+    if !variables.is_empty() {
+        code.push_str("use std::env;\n");
+    }
+    code.push_str("use std::fmt;\n\n");
+
+    let fields = unique_names(variables.iter().map(|var| field_name(&var.key)), &[]);
+    let types = unique_names(
+        variables.iter().map(|var| type_name(&var.key)),
+        RESERVED_TYPES,
+    );
+
+    code.push_str("/// The environment variables of the project.\n");
+    code.push_str("#[derive(Debug, Clone, PartialEq)]\npub struct Env {\n");
+    for ((var, field), name) in variables.iter().zip(&fields).zip(&types) {
+        let field_type = match var.presence {
+            Presence::Optional => format!("Option<{}>", rust_type(var, name)),
+            _ => rust_type(var, name),
+        };
+        code.push_str(&format!("    /// `{}`\n", var.key));
+        code.push_str(&format!(
+            "    pub {}: {},\n",
+            escape_keyword(field),
+            field_type
+        ));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(
+        "/// Why the environment could not be read.\n\
+         #[derive(Debug, Clone, PartialEq, Eq)]\n\
+         pub enum EnvError {\n    \
+             /// A required variable is unset or empty.\n    \
+             Missing(&'static str),\n\n    \
+             /// A variable is set to a value that does not parse.\n    \
+             Invalid { key: &'static str, value: String },\n\
+         }\n\n\
+         impl fmt::Display for EnvError {\n    \
+             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n        \
+                 match self {\n            \
+                     EnvError::Missing(key) => write!(f, \"`{}` is required but unset\", key),\n            \
+                     EnvError::Invalid { key, value } => write!(f, \"`{}` has an invalid value `{}`\", key, value),\n        \
+                 }\n    \
+             }\n\
+         }\n\n\
+         impl std::error::Error for EnvError {}\n\n",
+    );
+
+    code.push_str("impl Env {\n");
+    code.push_str("    /// Reads the variables from the environment of the process.\n");
+    code.push_str("    pub fn from_env() -> Result<Self, EnvError> {\n        Ok(Env {\n");
+    for ((var, field), name) in variables.iter().zip(&fields).zip(&types) {
+        let parser = parser(var, name);
+        let value = match &var.presence {
+            Presence::Required => format!("required({:?}, {})?", var.key, parser),
+            Presence::Default(default) => {
+                format!("with_default({:?}, {:?}, {})?", var.key, default, parser)
+            }
+            Presence::Optional => format!("optional({:?}, {})?", var.key, parser),
+        };
+        code.push_str(&format!(
+            "            {}: {},\n",
+            escape_keyword(field),
+            value
+        ));
+    }
+    code.push_str("        })\n    }\n}\n");
+
+    for (var, name) in variables.iter().zip(&types) {
+        if var.value_type == ValueType::Enum {
+            code.push('\n');
+            code.push_str(&rust_enum(var, name));
+        }
+    }
+
+    code.push_str(&rust_helpers(variables));
+    code
+}
+
+/// Generates TypeScript declarations typing the variables of `process.env`.
+///
+/// Values in `process.env` are always strings, so `enum` variables become
+/// unions of their values and every other variable a `string`. Variables that
+/// are not required are optional, as they may be unset outside `envoke run`.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::codegen::{Presence, Variable, typescript};
+/// use envoke::schema::ValueType;
+///
+/// let level = Variable {
+///     key: "LOG_LEVEL".to_string(),
+///     value_type: ValueType::Enum,
+///     values: vec!["debug".to_string(), "info".to_string()],
+///     presence: Presence::Required,
+/// };
+/// let code = typescript(&[level]);
+/// assert!(code.contains("      LOG_LEVEL: \"debug\" | \"info\";\n"));
+/// ```
+pub fn typescript(variables: &[Variable]) -> String {
+    let mut code = String::from(
+        "// Generated by `envoke codegen typescript` - do not edit.\n\n\
+         declare global {\n  namespace NodeJS {\n    interface ProcessEnv {\n",
+    );
+
+    for (i, var) in variables.iter().enumerate() {
+        if i > 0 {
+            code.push('\n');
+        }
+
+        let mut doc = match var.value_type {
+            ValueType::String | ValueType::Enum => Vec::new(),
+            other => vec![format!("A {}.", other)],
+        };
+        if let Presence::Default(default) = &var.presence {
+            doc.push(format!("Defaults to `{}`.", default));
+        }
+        if !doc.is_empty() {
+            code.push_str(&format!("      /** {} */\n", doc.join(" ")));
+        }
+
+        let value_type = match var.value_type {
+            ValueType::Enum if !var.values.is_empty() => var
+                .values
+                .iter()
+                .map(|value| serde_json::Value::String(value.clone()).to_string())
+                .collect::<Vec<_>>()
+                .join(" | "),
+            _ => "string".to_string(),
+        };
+        let optional = match var.presence {
+            Presence::Required => "",
+            _ => "?",
+        };
+        code.push_str(&format!(
+            "      {}{}: {};\n",
+            property_name(&var.key),
+            optional,
+            value_type
+        ));
+    }
+
+    code.push_str("    }\n  }\n}\n\nexport {};\n");
+    code
+}
+
+/// Quotes a variable name that is not a valid TypeScript identifier.
+fn property_name(key: &str) -> String {
+    let valid = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    match valid {
+        true => key.to_string(),
+        false => serde_json::Value::String(key.to_string()).to_string(),
+    }
+}
+
+/// The Rust type of a variable's value, with `name` naming its enum.
+fn rust_type(var: &Variable, name: &str) -> String {
+    match var.value_type {
+        ValueType::String | ValueType::Url => "String".to_string(),
+        ValueType::Int => "i64".to_string(),
+        ValueType::Bool => "bool".to_string(),
+        ValueType::Port => "u16".to_string(),
+        ValueType::Enum => name.to_string(),
+    }
+}
+
+/// The function parsing a variable's value in the generated module, with
+/// `name` naming its enum.
+fn parser(var: &Variable, name: &str) -> String {
+    match var.value_type {
+        ValueType::String | ValueType::Url => "parse_string".to_string(),
+        ValueType::Int => "parse_int".to_string(),
+        ValueType::Bool => "parse_bool".to_string(),
+        ValueType::Port => "parse_port".to_string(),
+        ValueType::Enum => format!("{}::parse", name),
+    }
+}
+
+/// Generates the enum named `name` of an `enum` variable, with a variant per
+/// distinct value.
+fn rust_enum(var: &Variable, name: &str) -> String {
+    let mut values: Vec<&String> = Vec::new();
+    for value in &var.values {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    let variants = unique_names(values.iter().map(|value| variant_name(value)), &["Self"]);
+
+    let mut code = format!(
+        "/// The values of `{}`.\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum {} {{\n",
+        var.key, name
+    );
+    for (value, variant) in values.iter().zip(&variants) {
+        code.push_str(&format!("    /// `{}`\n    {},\n", value, variant));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(&format!(
+        "impl {} {{\n    fn parse(value: &str) -> Option<Self> {{\n        match value {{\n",
+        name
+    ));
+    for (value, variant) in values.iter().zip(&variants) {
+        code.push_str(&format!(
+            "            {:?} => Some({}::{}),\n",
+            value, name, variant
+        ));
+    }
+    code.push_str("            _ => None,\n        }\n    }\n}\n");
+    code
+}
+
+/// Generates the functions reading variables, leaving out unused ones so the
+/// module compiles without warnings.
+fn rust_helpers(variables: &[Variable]) -> String {
+    let presences: BTreeSet<_> = variables
+        .iter()
+        .map(|var| match var.presence {
+            Presence::Required => "required",
+            Presence::Default(_) => "with_default",
+            Presence::Optional => "optional",
+        })
+        .collect();
+    let types: BTreeSet<_> = variables.iter().map(|var| var.value_type).collect();
+
+    let mut code = String::new();
+    if !variables.is_empty() {
+        code.push_str(
+            "\nfn optional<T>(key: &'static str, parse: fn(&str) -> Option<T>) -> Result<Option<T>, EnvError> {\n    \
+                 match env::var(key) {\n        \
+                     Ok(value) if !value.is_empty() => match parse(&value) {\n            \
+                         Some(parsed) => Ok(Some(parsed)),\n            \
+                         None => Err(EnvError::Invalid { key, value }),\n        \
+                     },\n        \
+                     _ => Ok(None),\n    \
+                 }\n\
+             }\n",
+        );
+    }
+    if presences.contains("required") {
+        code.push_str(
+            "\nfn required<T>(key: &'static str, parse: fn(&str) -> Option<T>) -> Result<T, EnvError> {\n    \
+                 optional(key, parse)?.ok_or(EnvError::Missing(key))\n\
+             }\n",
+        );
+    }
+    if presences.contains("with_default") {
+        code.push_str(
+            "\nfn with_default<T>(key: &'static str, default: &str, parse: fn(&str) -> Option<T>) -> Result<T, EnvError> {\n    \
+                 match optional(key, parse)? {\n        \
+                     Some(value) => Ok(value),\n        \
+                     None => parse(default).ok_or_else(|| EnvError::Invalid { key, value: default.to_string() }),\n    \
+                 }\n\
+             }\n",
+        );
+    }
+    if types.contains(&ValueType::String) || types.contains(&ValueType::Url) {
+        code.push_str(
+            "\nfn parse_string(value: &str) -> Option<String> {\n    Some(value.to_string())\n}\n",
+        );
+    }
+    if types.contains(&ValueType::Int) {
+        code.push_str("\nfn parse_int(value: &str) -> Option<i64> {\n    value.parse().ok()\n}\n");
+    }
+    if types.contains(&ValueType::Bool) {
+        code.push_str(
+            "\nfn parse_bool(value: &str) -> Option<bool> {\n    \
+                 match value.to_ascii_lowercase().as_str() {\n        \
+                     \"true\" | \"1\" | \"yes\" | \"on\" => Some(true),\n        \
+                     \"false\" | \"0\" | \"no\" | \"off\" => Some(false),\n        \
+                     _ => None,\n    \
+                 }\n\
+             }\n",
+        );
+    }
+    if types.contains(&ValueType::Port) {
+        code.push_str(
+            "\nfn parse_port(value: &str) -> Option<u16> {\n    \
+                 value.parse().ok().filter(|port| *port != 0)\n\
+             }\n",
+        );
+    }
+
+    code
+}
+
+/// Rust keywords that cannot be field names without `r#`.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+];
+
+/// Names the generated module defines or uses, which enums must not shadow.
+const RESERVED_TYPES: &[&str] = &[
+    "Env", "EnvError", "String", "Option", "Some", "None", "Result", "Ok", "Err", "Self",
+];
+
+/// Converts a variable name such as `DATABASE_URL` to a field name.
+fn field_name(key: &str) -> String {
+    let name = identifier(key).to_ascii_lowercase();
+    match name.as_str() {
+        "self" | "super" | "crate" => format!("{}_", name),
+        _ => name,
+    }
+}
+
+/// Prefixes a field name that is a keyword with `r#`.
+fn escape_keyword(name: &str) -> String {
+    match KEYWORDS.contains(&name) {
+        true => format!("r#{}", name),
+        false => name.to_string(),
+    }
+}
+
+/// Makes names distinct, e.g. when keys such as `DB.URL` and `DB_URL` map to
+/// the same field, by numbering the later ones.
+///
+/// # Arguments
+///
+/// * `names` - The names, in the order they are defined.
+/// * `reserved` - Names that are already taken.
+///
+/// # Returns
+///
+/// The distinct names, in the same order.
+fn unique_names<I: IntoIterator<Item = String>>(names: I, reserved: &[&str]) -> Vec<String> {
+    let mut taken: BTreeSet<String> = reserved.iter().map(|name| name.to_string()).collect();
+    let separator = |name: &str| match name.ends_with(|c: char| c.is_ascii_digit()) {
+        true => "_",
+        false => match name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            true => "",
+            false => "_",
+        },
+    };
+
+    names
+        .into_iter()
+        .map(|name| {
+            let mut unique = name.clone();
+            let mut n = 2;
+            while !taken.insert(unique.clone()) {
+                unique = format!("{}{}{}", name, separator(&name), n);
+                n += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+/// Converts a variable name such as `LOG_LEVEL` to a type name.
+fn type_name(key: &str) -> String {
+    variant_name(key)
+}
+
+/// Converts a value such as `us-east-1` to a variant name such as `UsEast1`.
+fn variant_name(value: &str) -> String {
+    let name: String = value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    match name.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => name,
+        _ => format!("V{}", name),
+    }
+}
+
+/// Replaces characters that cannot appear in an identifier.
+fn identifier(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    match name.chars().next() {
+        Some(first) if first.is_ascii_digit() => format!("_{}", name),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(input: &str) -> Schema {
+        toml::from_str(input).unwrap()
+    }
+
+    #[test]
+    fn test_variables() {
+        let schema = schema(
+            "[keys.PORT]\ntype = \"port\"\nrequired = true\n\
+             [keys.LOG_LEVEL]\ntype = \"enum\"\nvalues = [\"debug\", \"info\"]\ndefault = \"info\"\n",
+        );
+        let dev = Document::parse("PORT=80\nHOST=localhost\nDEBUG=1\n").unwrap();
+        let prod = Document::parse("HOST=example.com\n").unwrap();
+
+        let vars = variables(Some(&schema), &[dev, prod]);
+        let keys: Vec<_> = vars.iter().map(|var| var.key.as_str()).collect();
+        assert_eq!(vec!["DEBUG", "HOST", "LOG_LEVEL", "PORT"], keys);
+        assert_eq!(Presence::Optional, vars[0].presence);
+        assert_eq!(Presence::Required, vars[1].presence);
+        assert_eq!(Presence::Default("info".to_string()), vars[2].presence);
+        assert_eq!(ValueType::Enum, vars[2].value_type);
+        assert_eq!(Presence::Required, vars[3].presence);
+    }
+
+    #[test]
+    fn test_rust() {
+        let schema = schema(
+            "[keys.DEBUG]\ntype = \"bool\"\n\
+             [keys.LOG_LEVEL]\ntype = \"enum\"\nvalues = [\"debug\", \"us-east-1\"]\ndefault = \"debug\"\n",
+        );
+        let code = rust(&variables(Some(&schema), &[]));
+
+        assert!(code.contains("pub debug: Option<bool>,"));
+        assert!(code.contains("pub log_level: LogLevel,"));
+        assert!(
+            code.contains("log_level: with_default(\"LOG_LEVEL\", \"debug\", LogLevel::parse)?,")
+        );
+        assert!(code.contains("\"us-east-1\" => Some(LogLevel::UsEast1),"));
+        assert!(code.contains("fn parse_bool("));
+        assert!(!code.contains("fn required<T>("));
+        assert!(!code.contains("fn parse_port("));
+    }
+
+    #[test]
+    fn test_typescript() {
+        let schema = schema(
+            "[keys.PORT]\ntype = \"port\"\ndefault = \"8080\"\n\
+             [keys.LEVEL]\ntype = \"enum\"\nvalues = [\"debug\", \"info\"]\n",
+        );
+        let dev = Document::parse("HOST=db\nAPP.NAME=x\n").unwrap();
+        let code = typescript(&variables(Some(&schema), &[dev]));
+
+        assert_eq!(
+            "// Generated by `envoke codegen typescript` - do not edit.\n\n\
+             declare global {\n  namespace NodeJS {\n    interface ProcessEnv {\n      \
+             \"APP.NAME\": string;\n\n      \
+             HOST: string;\n\n      \
+             LEVEL?: \"debug\" | \"info\";\n\n      \
+             /** A port. Defaults to `8080`. */\n      \
+             PORT?: string;\n    \
+             }\n  }\n}\n\nexport {};\n",
+            code
+        );
+    }
+
+    #[test]
+    fn test_rust_name_collisions() {
+        let schema = schema(
+            "[keys.ENV]\ntype = \"enum\"\nvalues = [\"info\", \"INFO\", \"us-east-1\", \"us_east_1\", \"self\"]\n\
+             [keys.ENV_ERROR]\ntype = \"enum\"\nvalues = [\"a\", \"a\"]\n\
+             [keys.STRING]\ntype = \"enum\"\nvalues = [\"x\"]\n\
+             [keys.\"DB.URL\"]\n[keys.DB_URL]\n[keys.type]\n[keys.TYPE]\n",
+        );
+        let code = rust(&variables(Some(&schema), &[]));
+
+        assert!(code.contains("pub db_url: Option<String>,"));
+        assert!(code.contains("pub db_url_2: Option<String>,"));
+        assert!(code.contains("pub r#type: Option<String>,"));
+        assert!(code.contains("pub type_2: Option<String>,"));
+        assert!(code.contains("pub env: Option<Env2>,"));
+        assert!(code.contains("pub env_error: Option<EnvError2>,"));
+        assert!(code.contains("pub string: Option<String2>,"));
+        assert!(code.contains("\"INFO\" => Some(Env2::Info2),"));
+        assert!(code.contains("\"us_east_1\" => Some(Env2::UsEast1_2),"));
+        assert!(code.contains("\"self\" => Some(Env2::Self2),"));
+    }
+
+    #[test]
+    fn test_rust_compiles() {
+        let schema = schema(
+            "[keys.PORT]\ntype = \"port\"\nrequired = true\n\
+             [keys.DEBUG]\ntype = \"bool\"\ndefault = \"false\"\n\
+             [keys.RETRIES]\ntype = \"int\"\n\
+             [keys.API_URL]\ntype = \"url\"\n\
+             [keys.ENV]\ntype = \"enum\"\nvalues = [\"info\", \"INFO\", \"us-east-1\", \"us_east_1\", \"self\", \"1\"]\n\
+             [keys.ENV_ERROR]\ntype = \"enum\"\nvalues = [\"a\", \"a\"]\n\
+             [keys.STRING]\ntype = \"enum\"\nvalues = [\"x\"]\n\
+             [keys.\"DB.URL\"]\n[keys.DB_URL]\n[keys.type]\n[keys.TYPE]\n[keys.SELF]\n[keys.2FA_SECRET]\n",
+        );
+        let code = rust(&variables(Some(&schema), &[]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("env.rs");
+        std::fs::write(&source, code).unwrap();
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = std::process::Command::new(rustc)
+            .args([
+                "--edition",
+                "2024",
+                "--crate-type",
+                "lib",
+                "--emit",
+                "metadata",
+            ])
+            .args(["-D", "warnings", "--out-dir"])
+            .arg(dir.path())
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!("database_url", field_name("DATABASE_URL"));
+        assert_eq!("r#type", escape_keyword(&field_name("TYPE")));
+        assert_eq!("self_", field_name("SELF"));
+        assert_eq!("_2fa_secret", field_name("2FA_SECRET"));
+        assert_eq!("LogLevel", type_name("LOG_LEVEL"));
+        assert_eq!("V1", variant_name("1"));
+    }
+}
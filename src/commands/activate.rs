@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::io::Write;
+
+use crate::cli::ActivateShell;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
+
+/// Environment variable naming the profile activated in the current shell.
+pub const ACTIVATED_VAR: &str = "ENVOKE_ACTIVATED";
+
+/// Environment variable listing the keys set by `activate`, separated by commas,
+/// so a later `activate` or `deactivate` can unset them.
+pub const ACTIVATED_KEYS_VAR: &str = "ENVOKE_ACTIVATED_KEYS";
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    shell: ActivateShell,
+    local: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    let doc = manager.load_env(&profile, local)?;
+    let values: BTreeMap<&str, &str> = doc
+        .entries()
+        .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+        .collect();
+
+    let profile = manager
+        .profile_layers(&profile)
+        .join(&LAYER_SEPARATOR.to_string());
+    activate(out, shell, &profile, &values, &activated_keys())
+}
+
+/// Prints the statements that unset the variables of the activated profile.
+pub fn deactivate(out: &mut impl Write, shell: ActivateShell) -> Result<()> {
+    if env::var_os(ACTIVATED_VAR).is_none() {
+        return Err(ErrorKind::NotActivated.into());
+    }
+
+    for key in activated_keys() {
+        writeln!(out, "{}", shell.unset(&key))?;
+    }
+    writeln!(out, "{}", shell.unset(ACTIVATED_KEYS_VAR))?;
+    writeln!(out, "{}", shell.unset(ACTIVATED_VAR))?;
+
+    Ok(())
+}
+
+/// Prints the statements that set the variables of a profile, unsetting the
+/// variables of a previously activated profile that it does not define.
+///
+/// # Arguments
+///
+/// * `out` - Where to print the statements.
+/// * `shell` - The shell the statements are for.
+/// * `profile` - The name of the profile.
+/// * `values` - The final value of every variable of the profile.
+/// * `previous` - The keys set by the previous activation.
+pub fn activate(
+    out: &mut impl Write,
+    shell: ActivateShell,
+    profile: &str,
+    values: &BTreeMap<&str, &str>,
+    previous: &[String],
+) -> Result<()> {
+    let values = shell.exportable(values);
+    for key in previous {
+        if !values.contains_key(key.as_str()) {
+            writeln!(out, "{}", shell.unset(key))?;
+        }
+    }
+
+    for (key, value) in &values {
+        writeln!(out, "{}", shell.export(key, value))?;
+    }
+
+    let keys = values.keys().copied().collect::<Vec<_>>().join(",");
+    writeln!(out, "{}", shell.export(ACTIVATED_KEYS_VAR, &keys))?;
+    writeln!(out, "{}", shell.export(ACTIVATED_VAR, profile))?;
+
+    Ok(())
+}
+
+impl ActivateShell {
+    /// Checks whether the shell can set a variable with the given name.
+    ///
+    /// Profiles allow dotted keys, which sh and fish reject as variable names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::cli::ActivateShell;
+    ///
+    /// assert!(ActivateShell::Posix.accepts_key("DB_URL"));
+    /// assert!(!ActivateShell::Posix.accepts_key("db.url"));
+    /// assert!(ActivateShell::Powershell.accepts_key("db.url"));
+    /// ```
+    pub fn accepts_key(&self, key: &str) -> bool {
+        match self {
+            ActivateShell::Posix | ActivateShell::Fish => !key.contains('.'),
+            ActivateShell::Powershell => true,
+        }
+    }
+
+    /// Keeps the variables the shell can set, warning about each one skipped.
+    pub fn exportable<'a>(
+        &self,
+        values: &BTreeMap<&'a str, &'a str>,
+    ) -> BTreeMap<&'a str, &'a str> {
+        values
+            .iter()
+            .filter(|(key, _)| {
+                let accepted = self.accepts_key(key);
+                if !accepted {
+                    presenter::warn(format!(
+                        "Skipping `{}`, which is not a valid variable name in this shell",
+                        key
+                    ));
+                }
+                accepted
+            })
+            .map(|(key, value)| (*key, *value))
+            .collect()
+    }
+
+    /// Returns the statement that sets an environment variable, quoting the
+    /// value so spaces, `$` and newlines are kept as they are.
+    pub fn export(&self, key: &str, value: &str) -> String {
+        match self {
+            ActivateShell::Posix => {
+                format!("export {}='{}'", key, value.replace('\'', r"'\''"))
+            }
+            ActivateShell::Fish => format!(
+                "set -gx {} '{}'",
+                key,
+                value.replace('\\', r"\\").replace('\'', r"\'")
+            ),
+            ActivateShell::Powershell => {
+                format!("${{env:{}}} = '{}'", key, value.replace('\'', "''"))
+            }
+        }
+    }
+
+    /// Returns the statement that unsets an environment variable.
+    fn unset(&self, key: &str) -> String {
+        match self {
+            ActivateShell::Posix => format!("unset {}", key),
+            ActivateShell::Fish => format!("set -e {}", key),
+            ActivateShell::Powershell => {
+                format!(
+                    "Remove-Item -LiteralPath 'Env:{}' -ErrorAction SilentlyContinue",
+                    key
+                )
+            }
+        }
+    }
+}
+
+/// Reads the keys set by the previous activation in this shell.
+fn activated_keys() -> Vec<String> {
+    env::var(ACTIVATED_KEYS_VAR)
+        .unwrap_or_default()
+        .split(',')
+        .filter(|key| !key.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statements(shell: ActivateShell, previous: &[&str]) -> String {
+        let values = BTreeMap::from([("PORT", "8080"), ("GREETING", "it's")]);
+        let previous = previous.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+
+        let mut out = Vec::new();
+        activate(&mut out, shell, "dev", &values, &previous).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_activate_posix() {
+        assert_eq!(
+            "unset DEBUG\n\
+             export GREETING='it'\\''s'\n\
+             export PORT='8080'\n\
+             export ENVOKE_ACTIVATED_KEYS='GREETING,PORT'\n\
+             export ENVOKE_ACTIVATED='dev'\n",
+            statements(ActivateShell::Posix, &["DEBUG", "PORT"])
+        );
+    }
+
+    #[test]
+    fn test_activate_fish() {
+        assert_eq!(
+            "set -gx GREETING 'it\\'s'\n\
+             set -gx PORT '8080'\n\
+             set -gx ENVOKE_ACTIVATED_KEYS 'GREETING,PORT'\n\
+             set -gx ENVOKE_ACTIVATED 'dev'\n",
+            statements(ActivateShell::Fish, &[])
+        );
+    }
+
+    #[test]
+    fn test_activate_powershell() {
+        let output = statements(ActivateShell::Powershell, &["DEBUG"]);
+        assert!(
+            output.starts_with(
+                "Remove-Item -LiteralPath 'Env:DEBUG' -ErrorAction SilentlyContinue\n"
+            )
+        );
+        assert!(output.contains("${env:GREETING} = 'it''s'\n"));
+    }
+
+    #[test]
+    fn test_activate_dotted_keys() {
+        let values = BTreeMap::from([("db.url", "postgres://db"), ("PORT", "8080")]);
+
+        let mut out = Vec::new();
+        activate(&mut out, ActivateShell::Posix, "dev", &values, &[]).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(!output.contains("db.url"));
+        assert!(output.contains("export ENVOKE_ACTIVATED_KEYS='PORT'\n"));
+
+        let mut out = Vec::new();
+        activate(&mut out, ActivateShell::Powershell, "dev", &values, &[]).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("${env:db.url} = 'postgres://db'\n"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::dotenv::Entry;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::patch::{self, Change};
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::prompt;
+use crate::redact::MASK;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+    patch: &Path,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let input = if patch == Path::new("-") {
+        prompt::read_stdin()?
+    } else {
+        manager.read_file(patch)?
+    };
+
+    let profile = manager.profile_name(profile);
+    let mut doc = manager.read_profile(&profile)?;
+    let current: BTreeMap<String, String> = manager
+        .resolve_profile(&profile)?
+        .entries()
+        .map(|entry| (entry.key.clone(), entry.value.clone()))
+        .collect();
+
+    let changes = patch::parse(&input, &current)?;
+    if changes.iter().any(|change| match change {
+        Change::Added { value, .. } | Change::Removed { value, .. } => value == MASK,
+        Change::Changed { old, new, .. } => old == MASK || new == MASK,
+    }) {
+        return Err(ErrorKind::InvalidPatch {
+            reason: "it contains masked secrets - write it to a file or pass `--show-secrets`"
+                .into(),
+        }
+        .into());
+    }
+
+    let mut applied = 0;
+    let mut conflicts = Vec::new();
+    for change in &changes {
+        let key = change.key();
+        let value = current.get(key).map(String::as_str);
+
+        // Changes the profile already has are skipped, so a patch can be
+        // applied twice.
+        let result = match change {
+            Change::Added { value: new, .. } | Change::Changed { new, .. }
+                if value == Some(new.as_str()) =>
+            {
+                continue;
+            }
+            Change::Removed { .. } if value.is_none() => continue,
+            Change::Added { .. } if value.is_some() => Err("is already set to another value"),
+            Change::Added { value: new, .. } => {
+                doc.push(Entry::new(key, new));
+                Ok(())
+            }
+            Change::Removed { value: old, .. } | Change::Changed { old, .. }
+                if value != Some(old.as_str()) =>
+            {
+                Err("has changed since the patch was made")
+            }
+            Change::Removed { .. } => {
+                if doc.remove(key) {
+                    Ok(())
+                } else {
+                    Err("is defined in an included file")
+                }
+            }
+            Change::Changed { new, .. } => {
+                // Assigning after an include overrides it.
+                if !doc.set(key, new) {
+                    doc.push(Entry::new(key, new));
+                }
+                Ok(())
+            }
+        };
+
+        match result {
+            Ok(()) => applied += 1,
+            Err(reason) => conflicts.push(format!("`{}` {}", key, reason)),
+        }
+    }
+
+    if !conflicts.is_empty() {
+        for conflict in &conflicts {
+            writeln!(out, "{}", presenter::failure(conflict))?;
+        }
+        return Err(ErrorKind::PatchConflict {
+            count: conflicts.len(),
+        }
+        .into());
+    }
+
+    if applied == 0 {
+        writeln!(out, "`{}` already includes these changes.", profile)?;
+        return Ok(());
+    }
+
+    manager.write_profile(&profile, doc.to_string())?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Applied {} change(s) to `{}`.", applied, profile))
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "# Web\nPORT=8080\nDEBUG=true\n");
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    fn apply(manager: &ProfileManager<MockFileSystem>, patch: &str) -> Result<String> {
+        manager.fs.add_file("changes.diff", patch);
+        let mut out = Vec::new();
+        run(manager, &mut out, "dev".into(), Path::new("changes.diff"))?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_apply() {
+        let manager = manager();
+        let patch =
+            "--- dev\n+++ prod\n@@ -1,2 +1,2 @@\n-DEBUG=true\n-PORT=8080\n+PORT=80\n+TOKEN=abc\n";
+
+        let output = apply(&manager, patch).unwrap();
+        assert!(output.contains("Applied 3 change(s) to `dev`."));
+        assert_eq!(
+            "# Web\nPORT=80\nTOKEN=abc\n",
+            manager.read_file(&manager.profile_path("dev")).unwrap()
+        );
+
+        let output = apply(&manager, patch).unwrap();
+        assert!(output.contains("already includes these changes"));
+    }
+
+    #[test]
+    fn test_apply_conflicts() {
+        let manager = manager();
+        let patch = r#"[
+            { "op": "test", "path": "/PORT", "value": "3000" },
+            { "op": "replace", "path": "/PORT", "value": "80" },
+            { "op": "add", "path": "/HOST", "value": "localhost" }
+        ]"#;
+
+        let err = apply(&manager, patch).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::PatchConflict { count: 1 }));
+        assert_eq!(
+            "# Web\nPORT=8080\nDEBUG=true\n",
+            manager.read_file(&manager.profile_path("dev")).unwrap()
+        );
+
+        let patch = "@@ -0,0 +1,1 @@\n+TOKEN=********\n";
+        let err = apply(&manager, patch).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InvalidPatch { .. }));
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::metadata::format_age;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    max_age: Option<Duration>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut profiles = match profile {
+        Some(profile) => vec![manager.profile_name(profile)],
+        None => manager.profiles()?,
+    };
+    profiles.sort();
+
+    let mut metadata = manager.load_metadata()?;
+    let mut changed = false;
+    for profile in &profiles {
+        changed |= manager.observe_profile(&mut metadata, profile)?;
+    }
+
+    if changed {
+        manager.save_metadata(&metadata)?;
+    }
+
+    let max_age = max_age.or(manager.config.audit.max_age);
+    let now = SystemTime::now();
+
+    let report: Vec<_> = profiles
+        .iter()
+        .flat_map(|profile| {
+            let ages = match max_age {
+                Some(max_age) => metadata.stale_keys(profile, max_age, now),
+                None => metadata.key_ages(profile, now),
+            };
+            ages.into_iter().map(move |age| (profile, age))
+        })
+        .collect();
+
+    let profile_width = report.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    let key_width = report.iter().map(|(_, a)| a.key.len()).max().unwrap_or(0);
+
+    for (profile, age) in &report {
+        writeln!(
+            out,
+            "{:<pw$}  {:<kw$}  {}",
+            profile,
+            age.key,
+            format_age(age.age),
+            pw = profile_width,
+            kw = key_width
+        )?;
+    }
+
+    match max_age {
+        Some(_) if !report.is_empty() => Err(ErrorKind::StaleKeys {
+            count: report.len(),
+        }
+        .into()),
+        Some(max_age) => {
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!(
+                    "All keys have changed within {}.",
+                    format_age(max_age)
+                ))
+            )?;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+/// Which keys each profile is missing from the union of all their keys.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct KeyReport {
+    /// The profiles compared, sorted by name.
+    pub profiles: Vec<String>,
+
+    /// Every key defined by at least one profile, sorted.
+    pub keys: Vec<String>,
+
+    /// The keys each profile is missing, for profiles missing any.
+    pub missing: BTreeMap<String, Vec<String>>,
+}
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    json: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let report = report(manager)?;
+
+    if json {
+        let json = serde_json::to_string_pretty(&report).expect("reports are serializable");
+        writeln!(out, "{}", json)?;
+    } else if report.profiles.is_empty() {
+        writeln!(
+            out,
+            "No profiles found. Run `envoke create <profile>` to get started!"
+        )?;
+    } else {
+        table(out, &report)?;
+    }
+
+    if !report.missing.is_empty() {
+        return Err(ErrorKind::KeysMissing {
+            count: report.missing.len(),
+        }
+        .into());
+    }
+
+    if !json && !report.profiles.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "All {} profile(s) define all {} key(s).",
+                report.profiles.len(),
+                report.keys.len()
+            ))
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Compares the keys of every profile, with includes expanded.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+///
+/// # Returns
+///
+/// The `KeyReport`, or an `Error` if a profile cannot be resolved.
+pub fn report<F: FileSystem>(manager: &ProfileManager<F>) -> Result<KeyReport> {
+    let mut profiles = manager.profiles()?;
+    profiles.sort();
+
+    let docs = profiles
+        .iter()
+        .map(|profile| manager.resolve_profile(profile))
+        .collect::<Result<Vec<_>>>()?;
+    let key_sets: Vec<_> = docs.iter().map(|doc| doc.key_set()).collect();
+    let keys: BTreeSet<&str> = key_sets.iter().flatten().copied().collect();
+
+    let missing = profiles
+        .iter()
+        .zip(&key_sets)
+        .filter_map(|(profile, actual)| {
+            let missing: Vec<_> = keys.difference(actual).map(|k| k.to_string()).collect();
+            (!missing.is_empty()).then(|| (profile.clone(), missing))
+        })
+        .collect();
+
+    Ok(KeyReport {
+        keys: keys.into_iter().map(String::from).collect(),
+        profiles,
+        missing,
+    })
+}
+
+/// Writes a row per key and a column per profile, marking missing keys.
+fn table(out: &mut impl Write, report: &KeyReport) -> Result<()> {
+    const MISSING: &str = "missing";
+
+    let key_width = report.keys.iter().map(String::len).max().unwrap_or(0);
+    let key_width = key_width.max("KEY".len());
+    let widths: Vec<_> = report
+        .profiles
+        .iter()
+        .map(|profile| {
+            let cell = if report.missing.contains_key(profile) {
+                MISSING
+            } else {
+                "ok"
+            };
+            profile.len().max(cell.len())
+        })
+        .collect();
+
+    let mut header = format!("{:<width$}", "KEY", width = key_width);
+    for (profile, width) in report.profiles.iter().zip(&widths) {
+        header += &format!("  {:<width$}", profile, width = width);
+    }
+    writeln!(out, "{}", header.trim_end())?;
+
+    for key in &report.keys {
+        let mut row = format!("{:<width$}", key, width = key_width);
+        for (profile, width) in report.profiles.iter().zip(&widths) {
+            let is_missing = report
+                .missing
+                .get(profile)
+                .is_some_and(|missing| missing.contains(key));
+            let cell = format!(
+                "{:<width$}",
+                if is_missing { MISSING } else { "ok" },
+                width = width
+            );
+            row += "  ";
+            row += &if is_missing {
+                presenter::failure(cell)
+            } else {
+                cell
+            };
+        }
+        writeln!(out, "{}", row.trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_report() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\nDEBUG=true\nNEW_FLAG=1\n");
+        fs.add_file(
+            ".envoke/prod.env",
+            "# envoke:include fragments/shared.env\n",
+        );
+        fs.add_file(".envoke/fragments/shared.env", "PORT=80\nDEBUG=false\n");
+        fs.add_file(".envoke/staging.env", "PORT=8081\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+
+        let report = report(&manager).unwrap();
+        assert_eq!(vec!["DEBUG", "NEW_FLAG", "PORT"], report.keys);
+        assert_eq!(
+            BTreeMap::from([
+                ("prod".to_string(), vec!["NEW_FLAG".to_string()]),
+                (
+                    "staging".to_string(),
+                    vec!["DEBUG".to_string(), "NEW_FLAG".to_string()]
+                ),
+            ]),
+            report.missing
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use std::env;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use crate::bundle::{self, BundleFile, INCLUDES_DIR, PROFILES_DIR};
+use crate::digest::sha256_hex;
+use crate::dotenv::Document;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::{FileSystem, normalize};
+use crate::metadata::{METADATA_FILE, Metadata};
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::prompt;
+
+/// Environment variable supplying the passphrase non-interactively.
+const PASSPHRASE_VAR: &str = "ENVOKE_PASSPHRASE";
+
+pub fn export<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    archive: PathBuf,
+    profiles: Vec<String>,
+    encrypt: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut profiles: Vec<String> = match profiles.is_empty() {
+        true => manager.profiles()?,
+        false => profiles.iter().map(|p| manager.profile_name(p)).collect(),
+    };
+    profiles.sort();
+
+    let mut data = bundle::pack(&collect(manager, &profiles)?)?;
+
+    // A `.age` extension implies encryption.
+    let encrypt = encrypt || archive.extension().is_some_and(|ext| ext == "age");
+    if encrypt {
+        data = bundle::encrypt(&data, &passphrase()?)?;
+    }
+
+    manager.write_file(&archive, &data)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Bundled {} profile(s) into {}{}",
+            profiles.len(),
+            archive.to_string_lossy(),
+            if encrypt { " (encrypted)" } else { "" }
+        ))
+    )?;
+
+    Ok(())
+}
+
+pub fn import<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    file: PathBuf,
+    force: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut data = manager.read_bytes(&file)?;
+    if bundle::is_encrypted(&data) {
+        data = bundle::decrypt(&data, &passphrase()?)?;
+    }
+
+    let files = bundle::unpack(&data)?;
+    if !force {
+        // Check everything up front so a conflict leaves the project untouched.
+        for (profile, _) in profiles(manager, &files)? {
+            let path = manager.profile_path(profile);
+            if manager.fs.path_exists(&path) {
+                return Err(ErrorKind::FileExists { file: path }.into());
+            }
+        }
+        for (path, file) in includes(manager, &files)? {
+            if manager.fs.path_exists(&path) && manager.read_bytes(&path)? != file.contents {
+                return Err(ErrorKind::FileExists { file: path }.into());
+            }
+        }
+    }
+
+    for profile in restore(manager, &files)? {
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!("Imported profile {}", profile))
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Collects profiles, the files they include and their metadata as bundle
+/// files.
+pub fn collect<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profiles: &[String],
+) -> Result<Vec<BundleFile>> {
+    let metadata = manager.load_metadata()?;
+    let mut bundled = Metadata::default();
+    let mut files = Vec::new();
+    let mut included = Vec::new();
+
+    for profile in profiles {
+        let path = manager.profile_path(profile);
+        if !manager.fs.path_exists(&path) {
+            return Err(ErrorKind::ProfileNotFound {
+                profile: profile.clone(),
+            }
+            .into());
+        }
+
+        files.push(BundleFile {
+            path: format!("{}/{}.env", PROFILES_DIR, profile),
+            contents: manager.read_bytes(&path)?,
+        });
+
+        if let Some(meta) = metadata.profiles.get(profile) {
+            bundled.profiles.insert(profile.clone(), meta.clone());
+        }
+
+        for file in manager.included_files(&path)? {
+            let relative = normalize(&file)
+                .strip_prefix(normalize(&manager.config.envoke_dir))
+                .map(Path::to_path_buf)
+                .map_err(|_| {
+                    Error::new(ErrorKind::Bundle {
+                        reason: format!(
+                            "`{}` includes `{}`, which is outside the envoke directory",
+                            profile,
+                            file.display()
+                        ),
+                    })
+                })?;
+            if !included.contains(&relative) {
+                files.push(BundleFile {
+                    path: format!("{}/{}", INCLUDES_DIR, relative.to_string_lossy()),
+                    contents: manager.read_bytes(&file)?,
+                });
+                included.push(relative);
+            }
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&bundled).expect("metadata is serializable");
+    files.push(BundleFile {
+        path: METADATA_FILE.to_string(),
+        contents: (json + "\n").into_bytes(),
+    });
+
+    Ok(files)
+}
+
+/// Writes the profiles of a bundle and the files they include into the
+/// project, replacing existing ones.
+///
+/// Returns the names of the restored profiles.
+pub fn restore<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    files: &[BundleFile],
+) -> Result<Vec<String>> {
+    let mut bundled = Metadata::default();
+    if let Some(file) = files.iter().find(|file| file.path == METADATA_FILE) {
+        bundled = serde_json::from_slice(&file.contents).map_err(|e| {
+            Error::new(ErrorKind::Bundle {
+                reason: format!("invalid metadata: {}", e),
+            })
+        })?;
+    }
+
+    let profiles = profiles(manager, files)?;
+    let mut metadata = manager.load_metadata()?;
+    let key = manager.metadata_key()?;
+
+    for (path, file) in includes(manager, files)? {
+        write_include(manager, &path, &file.contents)?;
+    }
+
+    for (profile, contents) in &profiles {
+        manager.write_profile(profile, contents)?;
+
+        match bundled.profiles.remove(profile) {
+            Some(meta) => metadata.profiles.insert(profile.clone(), meta),
+            None => metadata.profiles.remove(profile),
+        };
+        // The bundled digests were made with the other project's key.
+        if let Ok(doc) = Document::parse(&String::from_utf8_lossy(contents)) {
+            metadata.rehash(profile, &doc, &key);
+        }
+        // Saving this metadata replaces what writing the profile recorded.
+        metadata
+            .profiles
+            .entry(profile.clone())
+            .or_default()
+            .checksum = Some(sha256_hex(contents));
+    }
+    manager.save_metadata(&metadata)?;
+
+    Ok(profiles.into_iter().map(|(profile, _)| profile).collect())
+}
+
+/// Reads the bundle passphrase from the environment or asks for it.
+pub fn passphrase() -> Result<String> {
+    match env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(passphrase),
+        _ => prompt::ask("Passphrase: ", true),
+    }
+}
+
+/// Extracts the profiles from bundle files, ignoring the metadata and
+/// included files.
+pub fn profiles<'a, F: FileSystem>(
+    manager: &ProfileManager<F>,
+    files: &'a [BundleFile],
+) -> Result<Vec<(String, &'a [u8])>> {
+    files
+        .iter()
+        .filter(|file| file.path != METADATA_FILE && !is_include(file))
+        .map(|file| {
+            let name = Path::new(&file.path)
+                .strip_prefix(PROFILES_DIR)
+                .ok()
+                .and_then(|name| name.to_str())
+                .filter(|name| name.ends_with(".env") && !name.contains('/'))
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::Bundle {
+                        reason: format!("unexpected file `{}`", file.path),
+                    })
+                })?;
+
+            Ok((manager.profile_name(name), file.contents.as_slice()))
+        })
+        .collect()
+}
+
+/// Extracts the included files from bundle files, with the path each is
+/// restored to.
+pub fn includes<'a, F: FileSystem>(
+    manager: &ProfileManager<F>,
+    files: &'a [BundleFile],
+) -> Result<Vec<(PathBuf, &'a BundleFile)>> {
+    files
+        .iter()
+        .filter(|file| is_include(file))
+        .map(|file| {
+            // Only plain relative paths, so a bundle cannot write outside the
+            // envoke directory.
+            let relative = Path::new(&file.path)
+                .strip_prefix(INCLUDES_DIR)
+                .ok()
+                .filter(|path| {
+                    path.components().next().is_some()
+                        && path.components().all(|c| matches!(c, Component::Normal(_)))
+                })
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::Bundle {
+                        reason: format!("unexpected file `{}`", file.path),
+                    })
+                })?;
+
+            Ok((manager.config.envoke_dir.join(relative), file))
+        })
+        .collect()
+}
+
+/// Writes an included file, creating its directory if needed.
+pub fn write_include<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    path: &Path,
+    contents: &[u8],
+) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        manager.fs.create_dir(dir)?;
+    }
+    manager.write_file(path, contents)
+}
+
+/// Checks whether a bundle file is a file included by a profile.
+fn is_include(file: &BundleFile) -> bool {
+    Path::new(&file.path).starts_with(INCLUDES_DIR)
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::{ENV_FILE, LAYER_SEPARATOR, ProfileManager};
+use crate::template::resolve_references;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = manager
+        .profile_layers(&profile)
+        .join(&LAYER_SEPARATOR.to_string());
+    let doc = manager.load_env(&profile, true)?;
+
+    // Secrets are injected by the pipeline through `{{env:NAME}}` references.
+    let mut missing = BTreeSet::new();
+    let doc = doc.map_values(|entry| {
+        resolve_references(&entry.value, |name| env::var(name).ok()).unwrap_or_else(|names| {
+            missing.extend(names);
+            entry.value.clone()
+        })
+    });
+
+    if !missing.is_empty() {
+        return Err(ErrorKind::UnresolvedReferences {
+            names: missing.into_iter().collect(),
+        }
+        .into());
+    }
+
+    if let Some(schema) = manager.load_schema()? {
+        let mut violations = schema.validate(&doc, &manager.config.secrets.redactor(true));
+        super::validate::locate(manager, &profile, &mut violations);
+        if !super::validate::report(out, &profile, &violations)? {
+            return Err(ErrorKind::ValidationFailed { count: 1 }.into());
+        }
+    }
+
+    // The checkout is disposable, so whatever `.env` exists is replaced.
+    let env_path = Path::new(ENV_FILE);
+    if manager.fs.path_exists(env_path) || manager.fs.is_symlink(env_path) {
+        manager.fs.remove_file(env_path)?;
+    }
+
+    manager.write_generated_env(&profile, &doc)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Profile `{}` written to .env", profile))
+    )?;
+
+    Ok(())
+}
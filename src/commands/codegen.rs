@@ -0,0 +1,50 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::codegen::{self, Language};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    language: Language,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let schema = manager.load_schema()?;
+    let profiles = manager
+        .profiles()?
+        .iter()
+        .map(|profile| manager.resolve_profile(profile))
+        .collect::<Result<Vec<_>>>()?;
+    let variables = codegen::variables(schema.as_ref(), &profiles);
+
+    let code = match language {
+        Language::Rust => codegen::rust(&variables),
+        Language::Typescript => codegen::typescript(&variables),
+    };
+
+    let Some(output) = output else {
+        write!(out, "{}", code)?;
+        return Ok(());
+    };
+
+    manager.write_file(&output, code)?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Wrote {} variable(s) to {}",
+            variables.len(),
+            output.display()
+        ))
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap_complete::Shell;
+use clap_complete::env::Shells;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+/// Name of the binary that completions are generated for.
+const BIN_NAME: &str = "envoke";
+
+/// Environment variable through which the shell requests completions.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    shell: Option<Shell>,
+    install: bool,
+) -> Result<()> {
+    let shell = shell
+        .or_else(Shell::from_env)
+        .ok_or(ErrorKind::UnknownShell)?;
+
+    // The script calls back into envoke so profiles and keys are completed
+    // from the `.envoke` directory at the time TAB is pressed.
+    let mut script = Vec::new();
+    Shells::builtins()
+        .completer(&shell.to_string())
+        .ok_or(ErrorKind::UnknownShell)?
+        .write_registration(COMPLETE_VAR, BIN_NAME, BIN_NAME, BIN_NAME, &mut script)
+        .map_err(|e| ErrorKind::WriteFile {
+            file: PathBuf::from(BIN_NAME),
+            source: e,
+        })?;
+
+    if !install {
+        write!(out, "{}", String::from_utf8_lossy(&script))?;
+        return Ok(());
+    }
+
+    let path = install_path(shell)?;
+    if let Some(dir) = path.parent() {
+        manager.fs.create_dir(dir)?;
+    }
+    manager.write_file(&path, &script)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Completions for {} installed to {}",
+            shell,
+            path.display()
+        ))
+    )?;
+    if shell == Shell::Zsh {
+        writeln!(
+            out,
+            "Add `fpath=(~/.zfunc $fpath)` before `compinit` in your .zshrc to load them."
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Locates the conventional completion script path for a shell.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to install completions for.
+///
+/// # Returns
+///
+/// The path to write the script to, or an `Error` if the shell has no
+/// per-user completion directory or the home directory is unknown.
+fn install_path(shell: Shell) -> Result<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let xdg = |var: &str, fallback: &str| {
+        env::var_os(var)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|home| home.join(fallback)))
+            .ok_or(ErrorKind::UnknownHome)
+    };
+
+    match shell {
+        Shell::Bash => Ok(xdg("XDG_DATA_HOME", ".local/share")?
+            .join("bash-completion/completions")
+            .join(BIN_NAME)),
+        Shell::Fish => Ok(xdg("XDG_CONFIG_HOME", ".config")?
+            .join("fish/completions")
+            .join(format!("{}.fish", BIN_NAME))),
+        Shell::Zsh => Ok(home
+            .ok_or(ErrorKind::UnknownHome)?
+            .join(".zfunc")
+            .join(format!("_{}", BIN_NAME))),
+        shell => Err(ErrorKind::CompletionsInstall {
+            shell: shell.to_string(),
+        }
+        .into()),
+    }
+}
@@ -1,39 +1,429 @@
+use std::collections::HashMap;
 use std::io::Write;
+use std::path::Path;
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::dotenv::{Document, Entry};
+use crate::error::{ErrorKind, Result};
 use crate::fs::FileSystem;
-use crate::profile::ProfileManager;
+use crate::hooks::{self, Hook, HookContext};
+use crate::presenter;
+use crate::profile::{ENV_FILE, ProfileManager};
+use crate::redact::MASK;
+use crate::schema::KeyRule;
+use crate::{prompt, template};
 
-const PROFILE_HEADER: &str = "\
-# ------------------------------------------------------------------------------
-# Profile: ";
+/// Where the contents of a new profile come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Seed {
+    /// The profile starts out empty.
+    Empty,
 
-pub fn run<F, S>(manager: &ProfileManager<F>, profile: S) -> Result<()>
+    /// A template, filled in by prompting for each placeholder.
+    Template(String),
+
+    /// Given contents, e.g. read from stdin.
+    Contents(String),
+
+    /// Values prompted for each key in the schema and `.env.example`.
+    Interactive,
+}
+
+/// The example file whose keys the interactive wizard prompts for.
+const EXAMPLE_FILE: &str = ".env.example";
+
+pub fn run<F, S>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: S,
+    seed: &Seed,
+    switch: bool,
+    force: bool,
+) -> Result<()>
 where
     F: FileSystem,
     S: AsRef<str>,
 {
-    let profile = profile.as_ref();
+    // Fail before prompting for template or interactive values.
+    let path = manager.check_new_profile(profile.as_ref())?;
 
-    if !manager.is_initialized() {
-        return Err(ErrorKind::Uninitialized.into());
+    // Fail before creating the profile if it could not be switched to.
+    let env_path = Path::new(ENV_FILE);
+    if switch && !force && manager.fs.path_exists(env_path) && !manager.is_managed_env(env_path) {
+        return Err(ErrorKind::NonLinkedEnv {
+            command: format!("envoke create {} --switch", profile.as_ref()),
+        }
+        .into());
     }
 
-    let path = manager.profile_path(&profile);
+    let context = HookContext {
+        old_profile: None,
+        new_profile: Some(manager.profile_name(profile.as_ref())),
+        target: Some(path),
+    };
+    hooks::run(manager, Hook::PreCreate, &context)?;
 
-    if path.exists() {
-        return Err(ErrorKind::FileExists { file: path }.into());
+    let body = match seed {
+        Seed::Empty => String::new(),
+        Seed::Template(template) => render_template(manager, template)?,
+        Seed::Contents(contents) => contents.clone(),
+        Seed::Interactive => prompt_keys(manager, prompt::ask)?,
+    };
+
+    let info = manager.create_profile(profile, &body)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Profile {} created at {}",
+            info.name,
+            info.path.to_string_lossy()
+        ))
+    )?;
+
+    if let Err(e) = hooks::run(manager, Hook::PostCreate, &context) {
+        presenter::warn_error(e);
     }
 
-    let mut file = manager.fs.create_file(&path)?;
-    writeln!(file, "{}{}", PROFILE_HEADER, profile).map_err(|e| {
-        Error::new(ErrorKind::WriteFile {
-            file: path.to_path_buf(),
-            source: e,
-        })
-    })?;
+    if switch {
+        super::switch::run(manager, out, &info.name, force, false, false)?;
+    }
+
+    Ok(())
+}
 
-    println!("Profile {} created at {}", profile, path.to_string_lossy());
+/// Creates several profiles, carrying on past profiles that cannot be created.
+///
+/// Failures are collected and returned together. With `switch`, the first
+/// profile that is created is switched to. With `if_missing`, profiles that
+/// already exist are skipped rather than reported as failures.
+///
+/// # Returns
+///
+/// `Ok(())` if every profile was created, the error of a single profile, or
+/// an `Error` holding the error of each profile that could not be created.
+pub fn run_many<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profiles: Vec<String>,
+    seed: &Seed,
+    switch: bool,
+    force: bool,
+    if_missing: bool,
+) -> Result<()> {
+    let mut pending = Vec::new();
+    for profile in profiles {
+        if if_missing && manager.fs.path_exists(&manager.profile_path(&profile)) {
+            writeln!(
+                out,
+                "Profile {} already exists, skipped",
+                manager.profile_name(&profile)
+            )?;
+            continue;
+        }
+        pending.push(profile);
+    }
+
+    if let [profile] = pending.as_slice() {
+        return run(manager, out, profile, seed, switch, force);
+    }
+
+    let mut switched = false;
+    let mut failures = Vec::new();
+    for profile in &pending {
+        let switch = switch && !switched;
+        match run(manager, out, profile, seed, switch, force) {
+            Ok(()) => switched |= switch,
+            Err(e) => failures.push((profile.clone(), e)),
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(ErrorKind::ProfilesNotCreated { failures }.into());
+    }
 
     Ok(())
 }
+
+/// Reads a template and prompts for the value of each placeholder.
+fn render_template<F: FileSystem>(manager: &ProfileManager<F>, name: &str) -> Result<String> {
+    let path = manager.find_template(name)?;
+    let contents = manager.read_file(&path)?;
+    let secrets = manager.config.secrets.redactor(true);
+
+    let mut values = HashMap::new();
+    for placeholder in template::placeholders(&contents) {
+        let question = format!("{}: ", placeholder);
+        let answer = prompt::ask(&question, secrets.is_secret(&placeholder))?;
+        values.insert(placeholder, answer);
+    }
+
+    Ok(template::render(&contents, &values))
+}
+
+/// A key the interactive wizard prompts for.
+struct Question<'a> {
+    key: String,
+    default: Option<String>,
+    rule: Option<&'a KeyRule>,
+}
+
+/// Prompts for the value of every key declared in the schema or the example
+/// file, asking again until each value satisfies the schema.
+///
+/// Keys come in the order of the example file, followed by keys only the
+/// schema declares. Schema defaults take precedence over example values.
+///
+/// # Arguments
+///
+/// * `ask` - Asks a question, hiding the answer when told to.
+///
+/// # Returns
+///
+/// The contents of the profile, or an `Error` if no keys are declared or an
+/// answer cannot be read.
+fn prompt_keys<F, A>(manager: &ProfileManager<F>, mut ask: A) -> Result<String>
+where
+    F: FileSystem,
+    A: FnMut(&str, bool) -> Result<String>,
+{
+    let schema = manager.load_schema()?.unwrap_or_default();
+    let example = Path::new(EXAMPLE_FILE);
+    let example = match manager.fs.path_exists(example) {
+        true => manager.read_document(example)?,
+        false => Document::default(),
+    };
+
+    let mut questions: Vec<Question> = example
+        .entries()
+        .map(|entry| Question {
+            key: entry.key.clone(),
+            default: Some(entry.value.clone()).filter(|value| !value.is_empty()),
+            rule: None,
+        })
+        .collect();
+    for key in schema.keys.keys() {
+        if example.get(key).is_none() {
+            questions.push(Question {
+                key: key.clone(),
+                default: None,
+                rule: None,
+            });
+        }
+    }
+    if questions.is_empty() {
+        return Err(ErrorKind::NoDeclaredKeys.into());
+    }
+
+    let secrets = manager.config.secrets.redactor(true);
+    let mut doc = Document::default();
+    for mut question in questions {
+        if let Some(rule) = schema.keys.get(&question.key) {
+            question.default = rule.default.clone().or(question.default);
+            question.rule = Some(rule);
+        }
+
+        let hidden = secrets.is_secret(&question.key);
+        let value = loop {
+            let answer = ask(&question.text(hidden), hidden)?;
+            let value = match answer.is_empty() {
+                true => question.default.clone().unwrap_or_default(),
+                false => answer,
+            };
+            match question.check(&value, hidden) {
+                Some(message) => presenter::warn(format!("{}: {}", question.key, message)),
+                None => break value,
+            }
+        };
+        doc.push(Entry::new(question.key, value));
+    }
+
+    Ok(doc.to_string())
+}
+
+impl Question<'_> {
+    /// Gets the text shown when asking for the value, e.g.
+    /// `LOG_LEVEL (debug|info) [info]: `. Defaults of secrets are masked.
+    fn text(&self, hidden: bool) -> String {
+        let mut text = self.key.clone();
+        if let Some(rule) = self.rule.filter(|rule| !rule.values.is_empty()) {
+            text.push_str(&format!(" ({})", rule.values.join("|")));
+        }
+        match &self.default {
+            Some(_) if hidden => text.push_str(&format!(" [{}]", MASK)),
+            Some(default) => text.push_str(&format!(" [{}]", default)),
+            None => {}
+        }
+        text + ": "
+    }
+
+    /// Checks a value against the schema. Values of secrets are masked in
+    /// the description.
+    ///
+    /// # Returns
+    ///
+    /// A description of the problem, or `None` if the value is valid.
+    fn check(&self, value: &str, hidden: bool) -> Option<String> {
+        let rule = self.rule?;
+        match value.is_empty() {
+            true if rule.required => Some("is required".into()),
+            true => None,
+            false => rule.check(value, if hidden { MASK } else { value }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_create_and_switch() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(ENV_FILE, "HANDWRITTEN=1\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+
+        // An unmanaged `.env` stops the profile from being created at all.
+        let err = run(&manager, &mut Vec::new(), "dev", &Seed::Empty, true, false).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::NonLinkedEnv { .. }));
+        assert!(!manager.fs.path_exists(&manager.profile_path("dev")));
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, "dev", &Seed::Empty, true, true).unwrap();
+        assert_eq!("dev", manager.active_profile().unwrap());
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("Profile `dev` linked to .env")
+        );
+
+        run(
+            &manager,
+            &mut Vec::new(),
+            "prod",
+            &Seed::Empty,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!("dev", manager.active_profile().unwrap());
+    }
+
+    #[test]
+    fn test_create_from_contents() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+
+        let seed = Seed::Contents("TOKEN=abc\n".to_string());
+        run(&manager, &mut Vec::new(), "staging", &seed, false, false).unwrap();
+        let contents = manager.read_file(&manager.profile_path("staging")).unwrap();
+        assert!(contents.ends_with("\nTOKEN=abc\n"));
+
+        // Contents that do not parse are rejected before anything is written.
+        let seed = Seed::Contents("not a variable\n".to_string());
+        let err = run(&manager, &mut Vec::new(), "prod", &seed, false, false).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseProfile { .. }));
+        assert!(!manager.fs.path_exists(&manager.profile_path("prod")));
+    }
+
+    #[test]
+    fn test_create_many() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/staging.env", "");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        let profiles = ["staging", "dev", "bad+name", "prod"]
+            .map(String::from)
+            .to_vec();
+
+        let mut out = Vec::new();
+        let err = run_many(
+            &manager,
+            &mut out,
+            profiles,
+            &Seed::Empty,
+            true,
+            false,
+            false,
+        )
+        .unwrap_err();
+        let ErrorKind::ProfilesNotCreated { failures } = &err.kind else {
+            panic!("unexpected error: {}", err);
+        };
+        let failed: Vec<&str> = failures.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(vec!["staging", "bad+name"], failed);
+
+        // The failures do not stop later profiles, and the first profile
+        // created is the one switched to.
+        assert!(manager.fs.path_exists(&manager.profile_path("prod")));
+        assert_eq!("dev", manager.active_profile().unwrap());
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Profile dev created"));
+        assert!(output.contains("Profile prod created"));
+
+        // Existing profiles are skipped, so running again is not an error.
+        let profiles = ["staging", "dev", "ci"].map(String::from).to_vec();
+        let mut out = Vec::new();
+        run_many(
+            &manager,
+            &mut out,
+            profiles,
+            &Seed::Empty,
+            false,
+            false,
+            true,
+        )
+        .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Profile staging already exists, skipped"));
+        assert!(output.contains("Profile dev already exists, skipped"));
+        assert!(output.contains("Profile ci created"));
+    }
+
+    #[test]
+    fn test_prompt_keys() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        let err = prompt_keys(&manager, |_, _| Ok(String::new())).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::NoDeclaredKeys));
+
+        manager
+            .fs
+            .add_file(EXAMPLE_FILE, "HOST=localhost\nAPI_TOKEN=\nDEBUG=\n");
+        manager.fs.add_file(
+            ".envoke/schema.toml",
+            "[keys.PORT]\nrequired = true\ntype = \"port\"\n\n\
+             [keys.DEBUG]\ntype = \"enum\"\nvalues = [\"yes\", \"no\"]\ndefault = \"no\"\n",
+        );
+
+        // Answers that violate the schema are asked for again.
+        let mut answers = vec!["", "tok_123", "", "0", "", "8080"].into_iter();
+        let mut asked = Vec::new();
+        let contents = prompt_keys(&manager, |question, hidden| {
+            asked.push((question.to_string(), hidden));
+            Ok(answers.next().unwrap().to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            "HOST=localhost\nAPI_TOKEN=tok_123\nDEBUG=no\nPORT=8080\n",
+            contents
+        );
+        assert_eq!(
+            vec![
+                ("HOST [localhost]: ".to_string(), false),
+                ("API_TOKEN: ".to_string(), true),
+                ("DEBUG (yes|no) [no]: ".to_string(), false),
+                ("PORT: ".to_string(), false),
+                ("PORT: ".to_string(), false),
+                ("PORT: ".to_string(), false),
+            ],
+            asked
+        );
+    }
+}
@@ -1,28 +1,88 @@
-use std::path::Path;
+use std::io::Write;
 
 use crate::error::{ErrorKind, Result};
 use crate::fs::FileSystem;
-use crate::profile::ProfileManager;
+use crate::presenter::{self, Format};
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
 
-pub fn run<F: FileSystem>(manager: &ProfileManager<F>) -> Result<()> {
-    if !manager.is_initialized() {
-        return Err(ErrorKind::Uninitialized.into());
+/// Prints the active profile.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+/// * `out` - Where to print the profile.
+/// * `format` - How to print it.
+/// * `is` - A profile or stack that must be the active one.
+/// * `quiet` - Print nothing, only reporting the result through the return value.
+///
+/// # Returns
+///
+/// `true` if a profile is active and, with `is`, it is the given profile;
+/// `false` if not and `quiet` or `is` was given. Otherwise, a missing active
+/// profile is an `Error`.
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    format: Format,
+    is: Option<String>,
+    quiet: bool,
+) -> Result<bool> {
+    let current = match manager.current_profile() {
+        Ok(current) => current,
+        Err(e)
+            if (quiet || is.is_some())
+                && matches!(
+                    e.kind,
+                    ErrorKind::NoActiveProfile | ErrorKind::NonLinkedEnv { .. }
+                ) =>
+        {
+            return Ok(false);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if !quiet {
+        presenter::current(out, &current, format)?;
     }
 
-    let env_path = Path::new(".env");
+    Ok(match is {
+        Some(profile) => {
+            manager
+                .profile_layers(profile)
+                .join(&LAYER_SEPARATOR.to_string())
+                == current.profile
+        }
+        None => true,
+    })
+}
 
-    if !env_path.exists() {
-        return Err(ErrorKind::NoActiveProfile.into());
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
 
-    if !manager.fs.is_symlink(env_path) {
-        return Err(ErrorKind::NonLinkedEnv.into());
-    }
+    #[test]
+    fn test_current_checks() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "");
+        fs.add_file(".envoke/ci.env", "");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        let is = |profile: &str| Some(profile.to_string());
 
-    let target = manager.fs.read_link(env_path)?;
-    let target = target.file_stem().unwrap();
+        let mut out = Vec::new();
+        assert!(!run(&manager, &mut out, Format::Plain, None, true).unwrap());
+        assert!(run(&manager, &mut out, Format::Plain, None, false).is_err());
 
-    print!("{}\n", target.to_string_lossy());
+        manager.switch_profile("dev+ci", false).unwrap();
+        assert!(run(&manager, &mut out, Format::Plain, None, true).unwrap());
+        assert!(run(&manager, &mut out, Format::Plain, is("dev.env+ci"), true).unwrap());
+        assert!(!run(&manager, &mut out, Format::Plain, is("dev"), true).unwrap());
+        assert!(out.is_empty());
 
-    Ok(())
+        assert!(!run(&manager, &mut out, Format::Plain, is("dev"), false).unwrap());
+        assert_eq!("dev+ci\n", String::from_utf8(out).unwrap());
+    }
 }
@@ -0,0 +1,132 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::{ENV_FILE, ProfileManager};
+use crate::prompt;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    eject: bool,
+    yes: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let env_path = Path::new(ENV_FILE);
+    let managed = manager.fs.path_exists(env_path) && manager.is_managed_env(env_path);
+
+    // Resolved before anything is removed, so a broken profile leaves the
+    // project as it was.
+    let ejected = if eject && managed {
+        let profile = manager.active_profile()?;
+        Some((manager.resolve_env(&profile, true)?, profile))
+    } else {
+        None
+    };
+
+    if !yes {
+        if !prompt::is_interactive() {
+            return Err(ErrorKind::ConfirmationRequired.into());
+        }
+
+        let question = format!(
+            "Remove `{}` and all of its profiles?",
+            manager.config.envoke_dir.display()
+        );
+        if !prompt::confirm(&question)? {
+            writeln!(out, "Nothing was removed.")?;
+            return Ok(());
+        }
+    }
+
+    if managed {
+        manager.fs.remove_file(env_path)?;
+    }
+
+    match ejected {
+        Some((doc, profile)) => {
+            manager.write_file(env_path, doc.to_string())?;
+            writeln!(out, "Ejected `{}` to .env", profile)?;
+        }
+        None if managed => writeln!(out, "Unlinking .env")?,
+        None => (),
+    }
+
+    // The project is located through its envoke directory, so it is
+    // unregistered while that still exists.
+    manager.unregister_project();
+    manager.fs.remove_dir_all(&manager.config.envoke_dir)?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Removed {} - envoke no longer manages this directory.",
+            manager.config.envoke_dir.display()
+        ))
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\n");
+        fs.add_file(".envoke/ci.env", "CI=true\n");
+
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    #[test]
+    fn test_deinit() {
+        let manager = manager();
+        manager.switch_profile("dev", false).unwrap();
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, false, true).unwrap();
+
+        assert!(!manager.is_initialized());
+        assert!(!manager.fs.path_exists(Path::new(ENV_FILE)));
+        assert!(String::from_utf8(out).unwrap().contains("Unlinking .env"));
+    }
+
+    #[test]
+    fn test_deinit_eject() {
+        let manager = manager();
+        manager.switch_profile("dev+ci", false).unwrap();
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, true, true).unwrap();
+
+        assert!(!manager.is_initialized());
+        let env = manager.read_file(Path::new(ENV_FILE)).unwrap();
+        assert!(env.contains("PORT=8080"));
+        assert!(env.contains("CI=true"));
+        assert!(!manager.is_managed_env(Path::new(ENV_FILE)));
+    }
+
+    #[test]
+    fn test_deinit_keeps_unmanaged_env() {
+        let manager = manager();
+        manager.fs.add_file(ENV_FILE, "OWN=1\n");
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, true, true).unwrap();
+
+        assert!(!manager.is_initialized());
+        assert_eq!("OWN=1\n", manager.read_file(Path::new(ENV_FILE)).unwrap());
+    }
+}
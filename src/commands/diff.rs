@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::cli::DiffFormat;
+use crate::config::Color;
+use crate::dotenv::Document;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::patch::{self, Change};
+use crate::presenter::{self, Stream};
+use crate::profile::{ENV_FILE, ProfileManager};
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+    other: Option<String>,
+    env: bool,
+    format: DiffFormat,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let (old_name, old, new_name, new) = if env {
+        let env_path = Path::new(ENV_FILE);
+        if !manager.fs.path_exists(env_path) {
+            return Err(ErrorKind::NoActiveProfile.into());
+        }
+
+        // Compared with what switching to the profile would write.
+        let new = manager.resolve_env(&profile, true)?;
+        (
+            ENV_FILE.to_string(),
+            manager.read_document(env_path)?,
+            profile,
+            new,
+        )
+    } else {
+        let other = other.ok_or(ErrorKind::ProfileRequired)?;
+        (
+            profile.clone(),
+            manager.resolve_stack(&profile)?,
+            other.clone(),
+            manager.resolve_stack(&other)?,
+        )
+    };
+
+    let (old, new) = (values(&old), values(&new));
+    let changes = patch::changes(&old, &new);
+
+    match format {
+        DiffFormat::Patch => {
+            write!(
+                out,
+                "{}",
+                patch::unified(&old_name, &new_name, &old, &new, redactor)
+            )?;
+            return Ok(());
+        }
+        DiffFormat::Json => {
+            writeln!(out, "{}", patch::json(&changes, redactor))?;
+            return Ok(());
+        }
+        DiffFormat::Text => {}
+    }
+
+    writeln!(out, "--- {}", old_name)?;
+    writeln!(out, "+++ {}", new_name)?;
+    if changes.is_empty() {
+        writeln!(out, "{}", presenter::success("No differences."))?;
+        return Ok(());
+    }
+
+    write_changes(out, &changes, redactor)
+}
+
+/// Writes changes one per line, `+` for added, `-` for removed and `~` for
+/// changed variables.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `changes` - The changes to write.
+/// * `redactor` - Masks secret values.
+pub fn write_changes(out: &mut impl Write, changes: &[Change], redactor: &Redactor) -> Result<()> {
+    for change in changes {
+        let line = match change {
+            Change::Added { key, value } => {
+                presenter::success(format!("+ {}={}", key, redactor.redact(key, value)))
+            }
+            Change::Removed { key, value } => {
+                presenter::failure(format!("- {}={}", key, redactor.redact(key, value)))
+            }
+            Change::Changed { key, old, new } => presenter::paint(
+                format!(
+                    "~ {}={} -> {}",
+                    key,
+                    redactor.redact(key, old),
+                    redactor.redact(key, new)
+                ),
+                Color::Yellow,
+                Stream::Stdout,
+            ),
+        };
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Collects the final value of every variable, as later assignments win.
+pub fn values(doc: &Document) -> BTreeMap<String, String> {
+    doc.entries()
+        .map(|entry| (entry.key.clone(), entry.value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\nDEBUG=true\n");
+        fs.add_file(".envoke/staging.env", "PORT=80\nAPI_URL=https://staging\n");
+
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    fn diff(manager: &ProfileManager<MockFileSystem>, other: Option<&str>, env: bool) -> String {
+        let mut out = Vec::new();
+        let redactor = Redactor::new(Vec::new(), false);
+        run(
+            manager,
+            &mut out,
+            "staging".to_string(),
+            other.map(String::from),
+            env,
+            DiffFormat::Text,
+            &redactor,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_diff_profiles() {
+        let manager = manager();
+        assert_eq!(
+            "--- staging\n+++ dev\n\
+             - API_URL=https://staging\n\
+             + DEBUG=true\n\
+             ~ PORT=80 -> 8080\n",
+            diff(&manager, Some("dev"), false)
+        );
+    }
+
+    #[test]
+    fn test_diff_env() {
+        let manager = manager();
+        manager
+            .fs
+            .add_file(ENV_FILE, "PORT=80\nAPI_URL=https://staging\nEDITED=1\n");
+        assert_eq!(
+            "--- .env\n+++ staging\n- EDITED=1\n",
+            diff(&manager, None, true)
+        );
+
+        manager
+            .fs
+            .add_file(ENV_FILE, "PORT=80\nAPI_URL=https://staging\n");
+        assert!(diff(&manager, None, true).ends_with("No differences.\n"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::Write;
+
+use crate::encryption::{self, is_encrypted};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+    keys: Vec<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = manager.profile_name(profile);
+    let mut doc = manager.read_profile(&profile)?;
+    let recipients = manager.recipients()?;
+
+    // Only needed to re-encrypt values that are already encrypted.
+    let mut identity = None;
+    for key in &keys {
+        let Some(value) = doc.get(key) else {
+            return Err(ErrorKind::KeyNotFound {
+                key: key.clone(),
+                profile,
+            }
+            .into());
+        };
+
+        let plaintext = if is_encrypted(value) {
+            if identity.is_none() {
+                identity = Some(manager.identity()?);
+            }
+            let identity = identity.as_ref().expect("the identity is loaded");
+            encryption::decrypt(value, identity)
+                .ok_or_else(|| ErrorKind::DecryptValue { key: key.clone() })?
+        } else {
+            value.to_string()
+        };
+
+        doc.set(key, encryption::encrypt(&plaintext, &recipients)?);
+    }
+
+    // No snapshot is kept, as it would leave the plaintext values on disk.
+    manager.save_profile(&profile, doc.to_string())?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Encrypted {} value(s) in `{}`.",
+            keys.len(),
+            profile
+        ))
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use age::x25519::Identity;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_encrypt() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(
+            ".envoke/dev.env",
+            "# Database\nDB_PASSWORD=hunter2\nPORT=8080\n",
+        );
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.encryption.recipients = vec![alice.to_public().to_string()];
+        let manager = ProfileManager::new(config, fs);
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, "dev".into(), vec!["DB_PASSWORD".into()]).unwrap();
+
+        let doc = manager.read_profile("dev").unwrap();
+        let value = doc.get("DB_PASSWORD").unwrap();
+        assert!(is_encrypted(value));
+        assert_eq!(
+            Some("hunter2".to_string()),
+            encryption::decrypt(value, &alice)
+        );
+        assert!(encryption::decrypt(value, &bob).is_none());
+        assert_eq!(Some("8080"), doc.get("PORT"));
+
+        let err = run(&manager, &mut out, "dev".into(), vec!["MISSING".into()]).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::KeyNotFound { .. }));
+    }
+}
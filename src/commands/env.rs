@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::cli::KeyFilter;
+use crate::dotenv::Entry;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    local: bool,
+    json: bool,
+    filter: &KeyFilter,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = manager.selected_profile()?;
+    let doc = manager.load_env(&profile, local)?;
+
+    // Later assignments win, as they do for `envoke run`.
+    let values: BTreeMap<&str, &str> = doc
+        .entries()
+        .filter(|entry| filter.includes(&entry.key))
+        .map(|entry| {
+            (
+                entry.key.as_str(),
+                redactor.redact(&entry.key, &entry.value),
+            )
+        })
+        .collect();
+
+    if json {
+        let json = serde_json::to_string_pretty(&values).unwrap_or_default();
+        writeln!(out, "{}", json)?;
+    } else {
+        // Values are quoted as they would be in a profile, so the output can
+        // be read back as one.
+        for (key, value) in values {
+            writeln!(out, "{}", Entry::new(key, value).raw())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/base.env", "PORT=80\nAPI_TOKEN=abc\n");
+        fs.add_file(".envoke/dev.env", "# envoke:include base.env\nPORT=8080\n");
+        fs.add_file(".envoke/local.env", "DEBUG=true\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        manager.switch_profile("dev", false).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_env() {
+        let manager = manager();
+        let redactor = manager.config.secrets.redactor(false);
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            true,
+            false,
+            &KeyFilter::default(),
+            &redactor,
+        )
+        .unwrap();
+        assert_eq!(
+            "API_TOKEN=abc\nDEBUG=true\nPORT=8080\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            false,
+            true,
+            &KeyFilter::default(),
+            &redactor,
+        )
+        .unwrap();
+        let values: BTreeMap<String, String> = serde_json::from_slice(&out).unwrap();
+        assert_eq!("8080", values["PORT"]);
+        assert!(!values.contains_key("DEBUG"));
+
+        let filter = KeyFilter {
+            exclude: vec!["*_TOKEN".to_string()],
+            ..KeyFilter::default()
+        };
+        let mut out = Vec::new();
+        run(&manager, &mut out, true, false, &filter, &redactor).unwrap();
+        assert_eq!("DEBUG=true\nPORT=8080\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_env_quotes_values() {
+        let manager = manager();
+        manager
+            .fs
+            .add_file(".envoke/local.env", "NAME=\"a #b\"\nMULTI=\"one\ntwo\"\n");
+        let redactor = manager.config.secrets.redactor(false);
+
+        let mut out = Vec::new();
+        let filter = KeyFilter {
+            exclude: vec!["API_TOKEN".to_string(), "PORT".to_string()],
+            ..KeyFilter::default()
+        };
+        run(&manager, &mut out, true, false, &filter, &redactor).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert_eq!("MULTI=\"one\\ntwo\"\nNAME=\"a #b\"\n", output);
+
+        let doc = crate::dotenv::Document::parse(&output).unwrap();
+        assert_eq!(Some("one\ntwo"), doc.get("MULTI"));
+    }
+
+    #[test]
+    fn test_env_masks_secrets() {
+        let manager = manager();
+        let redactor = manager.config.secrets.redactor(true);
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            true,
+            false,
+            &KeyFilter::default(),
+            &redactor,
+        )
+        .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(!output.contains("abc"));
+        assert!(output.contains("PORT=8080"));
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::dotenv::{Document, Line};
+use crate::error::{ErrorKind, Result};
+use crate::fs::{FileSystem, normalize};
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    output: PathBuf,
+    placeholder: String,
+    force: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    // Writing through a linked `.env` would replace the profile behind it.
+    let managed = manager.is_managed_env(&output)
+        || absolute(&output).starts_with(absolute(&manager.config.envoke_dir));
+    let exists = manager.fs.path_exists(&output) || manager.fs.is_symlink(&output);
+    if exists && !force {
+        let kind = if managed {
+            ErrorKind::ManagedFile { file: output }
+        } else {
+            ErrorKind::FileExists { file: output }
+        };
+        return Err(kind.into());
+    }
+
+    // Directives such as the description describe the profile, not the
+    // example.
+    let doc = manager.read_profile(&profile)?;
+    let lines = doc
+        .into_lines()
+        .into_iter()
+        .filter(|line| line.directive().is_none())
+        .collect::<Vec<Line>>();
+    let example = Document::from_lines(lines).map_values(|_| placeholder.clone());
+
+    // The file is replaced rather than written through, so a link is never
+    // followed.
+    if exists {
+        manager.fs.remove_file(&output)?;
+    }
+    manager.write_file(&output, example.to_string())?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Wrote {} keys from profile `{}` to {}",
+            example.entries().count(),
+            manager.profile_name(&profile),
+            output.to_string_lossy()
+        ))
+    )?;
+
+    Ok(())
+}
+
+/// Makes a path absolute and removes `.` and `..` components, so paths to the
+/// same file compare equal.
+fn absolute(path: &Path) -> PathBuf {
+    normalize(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::cli::{ActivateShell, ExportFormat, KeyFilter};
+use crate::dotenv::{Document, Line};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::redact::{MASK, Redactor};
+
+#[allow(clippy::too_many_arguments)]
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    format: ExportFormat,
+    shell: ActivateShell,
+    local: bool,
+    filter: &KeyFilter,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    let doc = filter.apply(&manager.load_env(&profile, local)?);
+    render(out, &mask(doc, redactor), format, shell)
+}
+
+/// Replaces the values of secret keys with the mask, leaving every other line
+/// as it was written.
+fn mask(doc: Document, redactor: &Redactor) -> Document {
+    if !redactor.is_enabled() {
+        return doc;
+    }
+
+    let lines = doc.into_lines().into_iter().map(|line| match line {
+        Line::Entry(entry) if redactor.is_secret(&entry.key) => Line::Entry(entry.with_value(MASK)),
+        line => line,
+    });
+    Document::from_lines(lines.collect())
+}
+
+/// Writes a resolved profile in an export format, with `shell` deciding the
+/// statements and quoting of the shell format.
+pub fn render(
+    out: &mut impl Write,
+    doc: &Document,
+    format: ExportFormat,
+    shell: ActivateShell,
+) -> Result<()> {
+    match format {
+        ExportFormat::Dotenv => write!(out, "{}", doc)?,
+        ExportFormat::Shell => {
+            for (key, value) in shell.exportable(&values(doc)) {
+                writeln!(out, "{}", shell.export(key, value))?;
+            }
+        }
+        ExportFormat::Json => {
+            let json = serde_json::to_string_pretty(&values(doc)).unwrap_or_default();
+            writeln!(out, "{}", json)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the final value of every key.
+fn values(doc: &Document) -> BTreeMap<&str, &str> {
+    doc.entries()
+        .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_export() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+        manager
+            .create_profile("dev", "PORT=8080\nGREETING=it's me\n")
+            .unwrap();
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            Some("dev".into()),
+            ExportFormat::Shell,
+            ActivateShell::Posix,
+            false,
+            &KeyFilter::default(),
+            &manager.config.secrets.redactor(false),
+        )
+        .unwrap();
+        assert_eq!(
+            "export GREETING='it'\\''s me'\nexport PORT='8080'\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        let dev = Some("dev".into());
+        run(
+            &manager,
+            &mut out,
+            dev,
+            ExportFormat::Shell,
+            ActivateShell::Fish,
+            false,
+            &KeyFilter::default(),
+            &manager.config.secrets.redactor(false),
+        )
+        .unwrap();
+        assert_eq!(
+            "set -gx GREETING 'it\\'s me'\nset -gx PORT '8080'\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        let dev = Some("dev".into());
+        let shell = ActivateShell::Powershell;
+        let filter = KeyFilter::default();
+        run(
+            &manager,
+            &mut out,
+            dev,
+            ExportFormat::Shell,
+            shell,
+            false,
+            &filter,
+            &manager.config.secrets.redactor(false),
+        )
+        .unwrap();
+        assert_eq!(
+            "${env:GREETING} = 'it''s me'\n${env:PORT} = '8080'\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            Some("dev".into()),
+            ExportFormat::Json,
+            ActivateShell::Posix,
+            false,
+            &KeyFilter::default(),
+            &manager.config.secrets.redactor(false),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!("8080", json["PORT"]);
+
+        manager
+            .write_file(
+                &manager.profile_path("dev"),
+                "# Web\nPORT=8080 # http\n\nAWS_REGION=eu-west-1\n",
+            )
+            .unwrap();
+        let mut out = Vec::new();
+        let filter = KeyFilter {
+            only: vec!["port".to_string()],
+            no_comments: true,
+            ..KeyFilter::default()
+        };
+        let dev = Some("dev".into());
+        run(
+            &manager,
+            &mut out,
+            dev,
+            ExportFormat::Dotenv,
+            ActivateShell::Posix,
+            false,
+            &filter,
+            &manager.config.secrets.redactor(false),
+        )
+        .unwrap();
+        assert_eq!("PORT=8080\n", String::from_utf8(out).unwrap());
+
+        // Secrets are masked unless they were asked for.
+        manager
+            .write_file(&manager.profile_path("dev"), "PORT=8080\nAPI_KEY=abc\n")
+            .unwrap();
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            Some("dev".into()),
+            ExportFormat::Shell,
+            ActivateShell::Posix,
+            false,
+            &KeyFilter::default(),
+            &manager.config.secrets.redactor(true),
+        )
+        .unwrap();
+        assert_eq!(
+            "export API_KEY='********'\nexport PORT='8080'\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(manager: &ProfileManager<F>, out: &mut impl Write) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let removed = manager.gc()?;
+    if removed.is_empty() {
+        writeln!(out, "No backups to remove.")?;
+        return Ok(());
+    }
+
+    for path in &removed {
+        writeln!(out, "Removed {}", path.display())?;
+    }
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Removed {} backup(s).", removed.len()))
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::time::Duration;
+
+use crate::clipboard::{self, Clipboard};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::prompt;
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    key: String,
+    profile: Option<String>,
+    copy: bool,
+    clear_after: Option<Duration>,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    let doc = manager.read_profile(&profile)?;
+    let value = doc.get(&key).ok_or_else(|| ErrorKind::KeyNotFound {
+        key: key.clone(),
+        profile: manager.profile_name(&profile),
+    })?;
+
+    if !copy {
+        writeln!(out, "{}", redactor.redact(&key, value))?;
+        return Ok(());
+    }
+
+    Clipboard::detect()?.copy(value)?;
+
+    match clear_after.or(manager.config.clipboard.clear_after) {
+        Some(after) if !after.is_zero() => {
+            clipboard::schedule_clear(after, value)?;
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!(
+                    "Copied `{}` to the clipboard - it will be cleared in {}.",
+                    key,
+                    humantime::format_duration(after)
+                ))
+            )?;
+        }
+        _ => writeln!(
+            out,
+            "{}",
+            presenter::success(format!("Copied `{}` to the clipboard.", key))
+        )?,
+    }
+
+    Ok(())
+}
+
+pub fn clear_clipboard(after: Duration) -> Result<()> {
+    let digest = prompt::read_stdin()?;
+    Clipboard::detect()?.clear_after(after, digest.trim())?;
+
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::create::{self, Seed};
+use crate::cli::KeyTransform;
+use crate::digest::sha256_hex;
+use crate::dotenv::{Document, Entry};
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::{compose, http, presenter, prompt, terraform};
+
+/// Where `envoke import` reads variables from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The `environment:` block of a docker-compose service.
+    Compose { file: PathBuf, service: String },
+
+    /// A `.tfvars` or `.tfvars.json` file.
+    Tfvars(PathBuf),
+
+    /// The output of `terraform output -json`, or `-` for stdin.
+    TfOutput(PathBuf),
+
+    /// An env file downloaded over HTTPS, which must match a SHA-256 checksum.
+    Url { url: String, sha256: String },
+
+    /// The profile of the same name in another project, copied or linked.
+    Project { dir: PathBuf, link: bool },
+}
+
+/// The names and values of imported variables.
+type Vars = Vec<(String, String)>;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+    source: &Source,
+    prefix: Option<&str>,
+    transform: KeyTransform,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = manager.profile_name(profile);
+    if let Source::Project { dir, link: true } = source {
+        return link(manager, out, &profile, dir);
+    }
+
+    let (vars, passthrough) = read(manager, source, &profile)?;
+    let vars: Vec<_> = vars
+        .into_iter()
+        .map(|(name, value)| (key(&name, prefix, transform), value))
+        .collect();
+
+    // Compose copies these from the host, so there is no value to import.
+    if !passthrough.is_empty() {
+        presenter::warn(format!(
+            "Skipped {} - compose passes these through from the host environment.",
+            passthrough.join(", ")
+        ));
+    }
+
+    if !manager.fs.path_exists(&manager.profile_path(&profile)) {
+        let mut doc = Document::default();
+        for (key, value) in vars {
+            doc.push(Entry::new(key, value));
+        }
+        return create::run(
+            manager,
+            out,
+            &profile,
+            &Seed::Contents(doc.to_string()),
+            false,
+            false,
+        );
+    }
+
+    let mut doc = manager.read_profile(&profile)?;
+    let mut updated = 0;
+    for (key, value) in &vars {
+        if doc.get(key) == Some(value.as_str()) {
+            continue;
+        }
+        if !doc.set(key, value) {
+            doc.push(Entry::new(key, value));
+        }
+        updated += 1;
+    }
+
+    if updated == 0 {
+        writeln!(out, "`{}` already includes these values.", profile)?;
+        return Ok(());
+    }
+
+    manager.write_profile(&profile, doc.to_string())?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Updated {} key(s) in `{}`.", updated, profile))
+    )?;
+
+    Ok(())
+}
+
+/// Reads the variables of a source.
+///
+/// # Returns
+///
+/// The names and values of the variables, and the names of compose variables
+/// that have no value.
+fn read<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    source: &Source,
+    profile: &str,
+) -> Result<(Vars, Vec<String>)> {
+    match source {
+        Source::Compose { file, service } => {
+            let input = manager.read_file(file)?;
+            let mut vars = Vec::new();
+            let mut passthrough = Vec::new();
+            for var in compose::environment(file, &input, service)? {
+                match var.value {
+                    Some(value) => vars.push((var.key, value)),
+                    None => passthrough.push(var.key),
+                }
+            }
+            Ok((vars, passthrough))
+        }
+        Source::Tfvars(file) => {
+            let input = manager.read_file(file)?;
+            Ok((terraform::tfvars(file, &input)?, Vec::new()))
+        }
+        Source::TfOutput(file) => {
+            let input = if file == Path::new("-") {
+                prompt::read_stdin()?
+            } else {
+                manager.read_file(file)?
+            };
+            Ok((terraform::outputs(file, &input)?, Vec::new()))
+        }
+        Source::Url { url, sha256 } => {
+            let data = http::download_https(url)?;
+            let input = verify_pinned(url, &data, sha256)?;
+            let doc = Document::parse(input).map_err(|e| {
+                Error::new(ErrorKind::ParseProfile {
+                    file: PathBuf::from(url),
+                    line: e.line,
+                    reason: e.reason,
+                })
+            })?;
+            let vars = doc
+                .entries()
+                .map(|entry| (entry.key.clone(), entry.value.clone()))
+                .collect();
+            Ok((vars, Vec::new()))
+        }
+        Source::Project { dir, .. } => {
+            let (_, doc) = project_profile(dir, profile)?;
+            let vars = doc
+                .entries()
+                .map(|entry| (entry.key.clone(), entry.value.clone()))
+                .collect();
+            Ok((vars, Vec::new()))
+        }
+    }
+}
+
+/// Links a profile of another project into this one, so both share it.
+fn link<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: &str,
+    dir: &Path,
+) -> Result<()> {
+    let (source, _) = project_profile(dir, profile)?;
+    let path = manager.check_new_profile(profile)?;
+    if manager.fs.is_symlink(&path) {
+        return Err(ErrorKind::FileExists { file: path }.into());
+    }
+
+    manager.fs.create_symlink(&source, &path)?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Profile `{}` of {} linked to {}",
+            profile,
+            dir.display(),
+            path.to_string_lossy()
+        ))
+    )?;
+
+    Ok(())
+}
+
+/// Reads a profile of another project, from the project's own envoke
+/// directory.
+///
+/// # Returns
+///
+/// The absolute path and contents of the profile, or an `Error` if the
+/// project is not managed by envoke or lacks the profile.
+fn project_profile(dir: &Path, profile: &str) -> Result<(PathBuf, Document)> {
+    let dir = std::fs::canonicalize(dir).map_err(|e| {
+        Error::new(ErrorKind::ReadDir {
+            file: dir.to_path_buf(),
+            source: e,
+        })
+    })?;
+
+    let project = super::projects::project_manager(&dir)?;
+    if !project.is_initialized() {
+        return Err(ErrorKind::ProjectUninitialized { path: dir }.into());
+    }
+    let path = project.profile_path(profile);
+    if !project.fs.path_exists(&path) {
+        return Err(ErrorKind::ProjectProfileNotFound {
+            profile: profile.to_string(),
+            path: dir,
+        }
+        .into());
+    }
+    Ok((path, project.read_profile(profile)?))
+}
+
+/// Checks downloaded data against the checksum it was pinned to.
+///
+/// # Returns
+///
+/// The data as text, or an `Error` if it does not match the checksum or is not
+/// UTF-8.
+fn verify_pinned<'a>(url: &str, data: &'a [u8], sha256: &str) -> Result<&'a str> {
+    let actual = sha256_hex(data);
+    if !actual.eq_ignore_ascii_case(sha256.trim()) {
+        return Err(ErrorKind::ChecksumPinMismatch {
+            url: url.to_string(),
+            expected: sha256.trim().to_lowercase(),
+            actual,
+        }
+        .into());
+    }
+
+    std::str::from_utf8(data).map_err(|_| {
+        ErrorKind::ParseProfile {
+            file: PathBuf::from(url),
+            line: 1,
+            reason: "not valid UTF-8".to_string(),
+        }
+        .into()
+    })
+}
+
+/// Maps the name of an imported variable to a key.
+fn key(name: &str, prefix: Option<&str>, transform: KeyTransform) -> String {
+    let name = match transform {
+        KeyTransform::None => name.to_string(),
+        KeyTransform::Upper => name.replace('-', "_").to_uppercase(),
+        KeyTransform::Lower => name.replace('-', "_").to_lowercase(),
+    };
+    format!("{}{}", prefix.unwrap_or_default(), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    fn import(
+        manager: &ProfileManager<MockFileSystem>,
+        source: &Source,
+        prefix: Option<&str>,
+        transform: KeyTransform,
+    ) -> String {
+        let mut out = Vec::new();
+        run(manager, &mut out, "dev".into(), source, prefix, transform).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_import_compose() {
+        let manager = manager();
+        manager.fs.add_file(
+            "docker-compose.yml",
+            "services:\n  api:\n    environment:\n      - PORT=8080\n      - GREETING=hello world\n      - HOME\n",
+        );
+        let source = Source::Compose {
+            file: PathBuf::from("docker-compose.yml"),
+            service: "api".into(),
+        };
+
+        import(&manager, &source, None, KeyTransform::None);
+        let contents = manager.read_file(&manager.profile_path("dev")).unwrap();
+        assert!(contents.ends_with("\nPORT=8080\nGREETING=\"hello world\"\n"));
+    }
+
+    #[test]
+    fn test_import_terraform() {
+        let manager = manager();
+        manager
+            .fs
+            .add_file(".envoke/dev.env", "# App\nTF_REGION=us-east-1\n");
+        manager.fs.add_file(
+            "prod.tfvars",
+            "region = \"eu-west-1\"\ndb-host = \"db.internal\"\n",
+        );
+        let source = Source::Tfvars(PathBuf::from("prod.tfvars"));
+
+        let output = import(&manager, &source, Some("TF_"), KeyTransform::Upper);
+        assert!(output.contains("Updated 2 key(s) in `dev`."));
+        assert_eq!(
+            "# App\nTF_REGION=eu-west-1\nTF_DB_HOST=db.internal\n",
+            manager.read_file(&manager.profile_path("dev")).unwrap()
+        );
+
+        let output = import(&manager, &source, Some("TF_"), KeyTransform::Upper);
+        assert!(output.contains("already includes these values"));
+    }
+
+    #[test]
+    fn test_verify_pinned() {
+        let url = "https://internal/configs/staging.env";
+        let data = b"PORT=8080\n";
+        let digest = sha256_hex(data);
+
+        assert_eq!(
+            Ok("PORT=8080\n"),
+            verify_pinned(url, data, &digest).map_err(|_| ())
+        );
+        assert!(verify_pinned(url, data, &digest.to_uppercase()).is_ok());
+
+        let err = verify_pinned(url, b"PORT=9090\n", &digest).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ChecksumPinMismatch { .. }));
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!("db-host", key("db-host", None, KeyTransform::None));
+        assert_eq!(
+            "APP_DB_HOST",
+            key("db-host", Some("APP_"), KeyTransform::Upper)
+        );
+        assert_eq!("db_host", key("DB-Host", None, KeyTransform::Lower));
+    }
+}
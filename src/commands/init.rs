@@ -1,15 +1,185 @@
-use crate::error::{ErrorKind, Result};
+use std::io::Write;
+
+use toml_edit::{DocumentMut, TomlError};
+
+use super::create::Seed;
+use super::remote::CONFLICT_SUFFIX;
+use crate::backup::BACKUPS_DIR;
+use crate::config::CONFIG_FILE;
+use crate::error::{Error, ErrorKind, Result};
 use crate::fs::FileSystem;
-use crate::profile::ProfileManager;
+use crate::index::INDEX_FILE;
+use crate::lock::LOCK_FILE;
+use crate::metadata::METADATA_KEY_FILE;
+use crate::oplog::LOG_FILE;
+use crate::presenter;
+use crate::profile::{LOCAL_PROFILE, ProfileManager, USER_SUFFIX};
+use crate::usage::USAGE_FILE;
 
-pub fn run<F: FileSystem>(manager: &ProfileManager<F>) -> Result<()> {
-    if manager.is_initialized() {
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profiles: Vec<String>,
+    template: Option<String>,
+    switch: bool,
+    default: Option<String>,
+    ok_if_initialized: bool,
+) -> Result<()> {
+    let initialized = manager.is_initialized();
+    if initialized && !ok_if_initialized {
         return Err(ErrorKind::Initialized.into());
     }
 
-    manager.fs.create_dir(&manager.config.envoke_dir)?;
+    // Fail before touching the filesystem if the template is missing.
+    if let Some(template) = &template {
+        manager.find_template(template)?;
+    }
+
+    if initialized {
+        writeln!(out, "Already initialized, adding anything missing")?;
+    } else {
+        manager.fs.create_dir(&manager.config.envoke_dir)?;
+    }
+    let added = ensure_gitignore(manager)?;
+    if initialized && added > 0 {
+        writeln!(out, "Added {} entries to .gitignore", added)?;
+    }
+
+    if !initialized {
+        writeln!(out, "{}", presenter::success("Successfully initialized!"))?;
+    }
+    manager.register_project();
+
+    // Profiles that already exist are kept as they are when re-running.
+    let missing =
+        |profile: &str| !initialized || !manager.fs.path_exists(&manager.profile_path(profile));
+    let seed = template.map_or(Seed::Empty, Seed::Template);
+    for profile in profiles.iter().filter(|profile| missing(profile)) {
+        super::create::run(manager, out, profile, &seed, false, false)?;
+    }
+
+    // Re-running never replaces the active profile or an existing default.
+    let switch_to = match default {
+        Some(default) => {
+            let default = manager.profile_name(default);
+            if !profiles.iter().any(|p| manager.profile_name(p) == default) && missing(&default) {
+                super::create::run(manager, out, &default, &seed, false, false)?;
+            }
+
+            record_default(manager, &default)?;
+            Some(default)
+        }
+        None if switch => profiles.first().cloned(),
+        None => None,
+    };
+    if let Some(profile) = switch_to
+        && (!initialized || manager.active_profile().is_err())
+    {
+        super::switch::run(manager, out, &profile, false, false, false)?;
+    }
+
+    Ok(())
+}
+
+/// Records the default profile in `config.toml`.
+///
+/// An existing config is edited in place, keeping its comments and layout. A
+/// default profile it already names is kept, with a warning.
+///
+/// # Returns
+///
+/// An `Error` if the config cannot be parsed or written.
+fn record_default<F: FileSystem>(manager: &ProfileManager<F>, default: &str) -> Result<()> {
+    let path = manager.config.envoke_dir.join(CONFIG_FILE);
+    if !manager.fs.path_exists(&path) {
+        let contents = format!(
+            "default_profile = {}\n",
+            toml::Value::String(default.into())
+        );
+        return manager.write_file(&path, contents);
+    }
+
+    let mut doc: DocumentMut = manager.read_file(&path)?.parse().map_err(|e: TomlError| {
+        Error::new(ErrorKind::ParseConfig {
+            file: path.clone(),
+            reason: e.to_string(),
+        })
+    })?;
+    match doc.get("default_profile").and_then(|item| item.as_str()) {
+        Some(existing) if existing == default => Ok(()),
+        Some(existing) => {
+            presenter::warn(format!(
+                "{} already sets the default profile to `{}`, keeping it",
+                CONFIG_FILE, existing
+            ));
+            Ok(())
+        }
+        None => {
+            doc.insert("default_profile", toml_edit::value(default));
+            manager.write_file(&path, doc.to_string())
+        }
+    }
+}
+
+/// Adds the files envoke keeps out of version control to `.gitignore`.
+///
+/// Entries already listed are left alone, so this can be run on a directory
+/// that was initialized by an older version of envoke.
+///
+/// # Returns
+///
+/// The number of entries added, or an `Error` if the file cannot be read or
+/// written.
+fn ensure_gitignore<F: FileSystem>(manager: &ProfileManager<F>) -> Result<usize> {
+    let entries = [
+        format!("{}.env", LOCAL_PROFILE),
+        format!("*{}.env", USER_SUFFIX),
+        LOG_FILE.to_string(),
+        INDEX_FILE.to_string(),
+        LOCK_FILE.to_string(),
+        USAGE_FILE.to_string(),
+        METADATA_KEY_FILE.to_string(),
+        format!("{}/", BACKUPS_DIR),
+        format!("*.env{}", CONFLICT_SUFFIX),
+    ];
+
+    let path = manager.config.envoke_dir.join(".gitignore");
+    let mut contents = match manager.fs.path_exists(&path) {
+        true => manager.read_file(&path)?,
+        false => String::new(),
+    };
+    let missing: Vec<_> = entries
+        .iter()
+        .filter(|entry| !contents.lines().any(|line| line.trim() == *entry))
+        .collect();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    for entry in &missing {
+        contents.push_str(entry);
+        contents.push('\n');
+    }
+    manager.write_file(&path, contents)?;
+
+    Ok(missing.len())
+}
 
-    println!("Successfully initialized!");
+/// Starship configuration for a custom module showing the active profile.
+const STARSHIP_SNIPPET: &str = r#"# Show the active envoke profile - add to ~/.config/starship.toml.
+[custom.envoke]
+command = "envoke prompt --starship"
+when = "envoke prompt --starship"
+symbol = "env "
+style = "bold yellow"
+format = "[$symbol($output )]($style)"
+"#;
 
+/// Prints the Starship configuration for showing the active profile.
+pub fn starship_snippet(out: &mut impl Write) -> Result<()> {
+    write!(out, "{}", STARSHIP_SNIPPET)?;
     Ok(())
 }
@@ -0,0 +1,74 @@
+use std::io::Write;
+
+use age::secrecy::ExposeSecret;
+use age::x25519::Identity;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    force: bool,
+) -> Result<()> {
+    let path = manager.identity_path()?;
+    if manager.fs.path_exists(&path) && !force {
+        return Err(ErrorKind::FileExists { file: path }.into());
+    }
+
+    let dir = manager.global_dir()?;
+    if !manager.fs.path_exists(dir) {
+        manager.fs.create_dir(dir)?;
+    }
+
+    let identity = Identity::generate();
+    let public = identity.to_public();
+    manager.write_file(
+        &path,
+        format!(
+            "# Envoke identity - keep this file private.\n# public key: {}\n{}\n",
+            public,
+            identity.to_string().expose_secret()
+        ),
+    )?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Identity written to {}", path.display()))
+    )?;
+    writeln!(out, "Public key: {}", public)?;
+    writeln!(
+        out,
+        "Add the public key to `[encryption] recipients` so values can be encrypted to you."
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_keygen() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.global_dir = Some(PathBuf::from("global"));
+        let manager = ProfileManager::new(config, MockFileSystem::new());
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, false).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        let public = manager.identity().unwrap().to_public().to_string();
+        assert!(output.contains(&format!("Public key: {}", public)));
+
+        let err = run(&manager, &mut Vec::new(), false).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::FileExists { .. }));
+    }
+}
@@ -1,20 +1,197 @@
+use std::io::Write;
+
 use crate::error::{ErrorKind, Result};
 use crate::fs::FileSystem;
-use crate::profile::ProfileManager;
+use crate::index::Index;
+use crate::operations::ProfileOrder;
+use crate::presenter::{self, Format};
+use crate::profile::{PROFILES_DIR, ProfileManager};
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    global: bool,
+    format: Format,
+    order: ProfileOrder,
+    group: Option<String>,
+) -> Result<()> {
+    if global {
+        let dir = manager.global_dir()?.join(PROFILES_DIR);
+        return presenter::global_profiles(out, &dir, &manager.global_profiles()?, format);
+    }
 
-pub fn run<F: FileSystem>(manager: &ProfileManager<F>) -> Result<()> {
     if !manager.is_initialized() {
         return Err(ErrorKind::Uninitialized.into());
     }
 
-    let list = manager.profiles()?;
-    if list.is_empty() {
-        println!("No profiles found. Run `envoke create <profile>` to get started!")
-    } else {
-        for profile in list {
-            println!("{}", profile);
+    // Plain listings only need names, so neither profiles nor the index are read.
+    let mut index = match format {
+        Format::Plain => Index::default(),
+        _ => manager.load_index(),
+    };
+    let mut names = Vec::new();
+
+    let members = group
+        .map(|group| manager.group_profiles(group))
+        .transpose()?;
+    let profiles = manager.iter_profiles()?.sorted(order);
+    let width = profiles.name_width();
+    let profiles = profiles.filter_map(|entry| {
+        let name = entry.name().to_string();
+        let listed = members
+            .as_ref()
+            .is_none_or(|members| members.contains(&name));
+        names.push(name);
+        if !listed {
+            return None;
         }
+
+        Some(match format {
+            Format::Plain => Ok(entry.summary()),
+            _ => entry.info(&mut index),
+        })
+    });
+    presenter::profiles(out, profiles, width, format)?;
+
+    if format != Format::Plain {
+        index.retain(&names);
+        manager.save_index(&index);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_list() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+
+        let mut out = Vec::new();
+        assert!(
+            run(
+                &manager,
+                &mut out,
+                false,
+                Format::Plain,
+                ProfileOrder::Name,
+                None
+            )
+            .is_err()
+        );
+
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Plain,
+            ProfileOrder::Name,
+            None,
+        )
+        .unwrap();
+        assert!(String::from_utf8_lossy(&out).starts_with("No profiles found."));
+
+        manager
+            .create_profile("prod", "# envoke:description Production\n")
+            .unwrap();
+        manager.create_profile("dev", "").unwrap();
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Plain,
+            ProfileOrder::Name,
+            None,
+        )
+        .unwrap();
+        assert_eq!("dev\nprod\n", String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Verbose,
+            ProfileOrder::Name,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            "  dev\n  prod  Production\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Json,
+            ProfileOrder::Name,
+            None,
+        )
+        .unwrap();
+        let expected = serde_json::to_string_pretty(&manager.list_profiles().unwrap()).unwrap();
+        assert_eq!(expected + "\n", String::from_utf8(out.clone()).unwrap());
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!("prod", json[1]["name"]);
+        assert_eq!("Production", json[1]["description"]);
+        assert_eq!(false, json[1]["active"]);
+
+        // Recently used profiles come first, then the others by name.
+        manager.create_profile("ci", "").unwrap();
+        manager.record_use("prod");
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Plain,
+            ProfileOrder::Recent,
+            None,
+        )
+        .unwrap();
+        assert_eq!("prod\nci\ndev\n", String::from_utf8(out).unwrap());
+
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config
+            .groups
+            .insert("ci".to_string(), vec!["ci".to_string(), "prod".to_string()]);
+        let manager = ProfileManager::new(config, manager.fs);
+        let mut out = Vec::new();
+        let group = Some("ci".to_string());
+        run(
+            &manager,
+            &mut out,
+            false,
+            Format::Plain,
+            ProfileOrder::Name,
+            group,
+        )
+        .unwrap();
+        assert_eq!("ci\nprod\n", String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        let group = Some("missing".to_string());
+        assert!(
+            run(
+                &manager,
+                &mut out,
+                false,
+                Format::Plain,
+                ProfileOrder::Name,
+                group
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::lock::Lock;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn lock<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    reason: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let reason = require_reason(manager, reason)?;
+    manager.ensure_unlocked()?;
+    manager.save_lock(&Lock::new(reason))?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(
+            "Locked - profiles cannot be changed, switched or removed until `envoke unlock`."
+        )
+    )?;
+
+    Ok(())
+}
+
+pub fn unlock<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    reason: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    require_reason(manager, reason)?;
+    match manager.remove_lock()? {
+        Some(lock) => writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Unlocked - locked by {} at {}.",
+                lock.user, lock.time
+            ))
+        )?,
+        None => writeln!(out, "Not locked.")?,
+    }
+
+    Ok(())
+}
+
+/// Checks that a reason was given if the configuration requires one.
+///
+/// # Returns
+///
+/// The trimmed reason, `None` if none was given, or an `Error` if one is
+/// required.
+fn require_reason<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    reason: Option<String>,
+) -> Result<Option<String>> {
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if reason.is_none() && manager.config.lock.require_reason {
+        return Err(ErrorKind::ReasonRequired.into());
+    }
+
+    Ok(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_lock_and_unlock() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.lock.require_reason = true;
+        let manager = ProfileManager::new(config, fs);
+
+        let err = lock(&manager, &mut Vec::new(), Some("  ".into())).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ReasonRequired));
+
+        lock(&manager, &mut Vec::new(), Some("release".into())).unwrap();
+        let err = lock(&manager, &mut Vec::new(), Some("again".into())).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Locked { .. }));
+
+        let mut out = Vec::new();
+        unlock(&manager, &mut out, Some("done".into())).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("Unlocked"));
+
+        let mut out = Vec::new();
+        unlock(&manager, &mut out, Some("done".into())).unwrap();
+        assert_eq!("Not locked.\n", String::from_utf8(out).unwrap());
+    }
+}
@@ -0,0 +1,40 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let entries = manager.load_log()?;
+    if entries.is_empty() && !json {
+        writeln!(out, "No operations recorded.")?;
+        return Ok(());
+    }
+
+    let start = entries.len().saturating_sub(limit);
+    for entry in &entries[start..] {
+        if json {
+            writeln!(
+                out,
+                "{}",
+                serde_json::to_string(entry).expect("log entries are serializable")
+            )?;
+        } else if entry.error.is_some() {
+            writeln!(out, "{}", presenter::failure(entry))?;
+        } else {
+            writeln!(out, "{}", entry)?;
+        }
+    }
+
+    Ok(())
+}
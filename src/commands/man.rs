@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::CommandFactory;
+use clap_mangen::Man;
+
+use crate::cli::Cli;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    out_dir: Option<PathBuf>,
+) -> Result<()> {
+    let mut cmd = Cli::command().disable_help_subcommand(true);
+    cmd.build();
+
+    let Some(out_dir) = out_dir else {
+        write!(out, "{}", String::from_utf8_lossy(&render(&Man::new(cmd))?))?;
+        return Ok(());
+    };
+
+    manager.fs.create_dir(&out_dir)?;
+
+    let mut pages = VecDeque::from([cmd]);
+    while let Some(cmd) = pages.pop_front() {
+        pages.extend(cmd.get_subcommands().filter(|s| !s.is_hide_set()).cloned());
+
+        let man = Man::new(cmd);
+        let path = out_dir.join(man.get_filename());
+        manager.write_file(&path, render(&man)?)?;
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!("Wrote {}", path.display()))
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders a manual page to roff.
+fn render(man: &Man) -> Result<Vec<u8>> {
+    let mut page = Vec::new();
+    man.render(&mut page).map_err(|e| ErrorKind::WriteFile {
+        file: PathBuf::from(man.get_filename()),
+        source: e,
+    })?;
+
+    Ok(page)
+}
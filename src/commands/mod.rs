@@ -1,6 +1,55 @@
+pub mod activate;
+pub mod apply;
+pub mod audit;
+pub mod audit_keys;
+pub mod bundle;
+pub mod ci;
+pub mod codegen;
+pub mod completions;
 pub mod create;
 pub mod current;
+pub mod deinit;
+pub mod diff;
+pub mod encrypt;
+pub mod env;
+pub mod example;
+pub mod export;
+pub mod gc;
+pub mod get;
+pub mod import;
 pub mod init;
+pub mod keygen;
 pub mod list;
+pub mod lock;
+pub mod log;
+pub mod man;
+pub mod mv_key;
+pub mod pin;
+pub mod projects;
+pub mod prompt;
+pub mod recipients;
+pub mod redact;
+pub mod remote;
 pub mod remove;
+pub mod rename;
+pub mod replace;
+pub mod resolve;
+pub mod restore;
+pub mod run;
+pub mod scan;
+pub mod schema;
+#[cfg(feature = "self-update")]
+pub mod self_update;
+pub mod shell;
+pub mod show;
+pub mod sign;
+pub mod status;
 pub mod switch;
+pub mod template;
+pub mod ui;
+pub mod use_profile;
+pub mod validate;
+pub mod verify;
+pub mod verify_integrity;
+pub mod verify_signature;
+pub mod watch;
@@ -0,0 +1,185 @@
+use std::io::Write;
+
+use crate::dotenv::is_valid_key;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    old: String,
+    new: String,
+    profiles: Vec<String>,
+    group: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    if !is_valid_key(&new) {
+        return Err(ErrorKind::InvalidKey { key: new }.into());
+    }
+
+    let mut profiles = match (profiles.is_empty(), group) {
+        (false, _) => profiles.iter().map(|p| manager.profile_name(p)).collect(),
+        (true, Some(group)) => manager.group_profiles(group)?,
+        (true, None) => manager.profiles()?,
+    };
+    profiles.sort();
+
+    // Every profile is checked before any is written, so a conflict leaves
+    // all of them untouched.
+    let mut renamed = Vec::new();
+    let mut conflicts = 0;
+    for profile in profiles {
+        let mut doc = manager.read_profile(&profile)?;
+        if doc.get(&old).is_none() {
+            continue;
+        }
+        if doc.get(&new).is_some() {
+            writeln!(
+                out,
+                "{}",
+                presenter::failure(format!("{}: already defines `{}`", profile, new))
+            )?;
+            conflicts += 1;
+            continue;
+        }
+
+        doc.rename(&old, &new);
+        renamed.push((profile, doc));
+    }
+
+    if conflicts > 0 {
+        return Err(ErrorKind::KeyRenameConflict {
+            key: new,
+            count: conflicts,
+        }
+        .into());
+    }
+
+    if renamed.is_empty() {
+        writeln!(out, "No profile defines `{}`.", old)?;
+        return Ok(());
+    }
+
+    for (profile, doc) in &renamed {
+        manager.write_profile(profile, doc.to_string())?;
+        writeln!(out, "  {}", profile)?;
+    }
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Renamed `{}` to `{}` in {} profile(s).",
+            old,
+            new,
+            renamed.len()
+        ))
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(
+            ".envoke/dev.env",
+            "# Database\nDB_URL=postgres://dev # local\n",
+        );
+        fs.add_file(".envoke/prod.env", "DB_URL=postgres://prod\nPORT=80\n");
+        fs.add_file(".envoke/ci.env", "PORT=80\n");
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    #[test]
+    fn test_mv_key() {
+        let manager = manager();
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            "DB_URL".into(),
+            "DATABASE_URL".into(),
+            Vec::new(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "  dev\n  prod\nRenamed `DB_URL` to `DATABASE_URL` in 2 profile(s).\n",
+            String::from_utf8(out).unwrap()
+        );
+        assert_eq!(
+            "# Database\nDATABASE_URL=postgres://dev # local\n",
+            manager.read_file(&manager.profile_path("dev")).unwrap()
+        );
+        assert_eq!(
+            "PORT=80\n",
+            manager.read_file(&manager.profile_path("ci")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mv_key_selected_profiles() {
+        let manager = manager();
+        let profiles = vec!["prod".to_string()];
+        run(
+            &manager,
+            &mut Vec::new(),
+            "DB_URL".into(),
+            "DATABASE_URL".into(),
+            profiles,
+            None,
+        )
+        .unwrap();
+
+        let dev = manager.read_file(&manager.profile_path("dev")).unwrap();
+        assert!(dev.contains("\nDB_URL="));
+        let prod = manager.read_file(&manager.profile_path("prod")).unwrap();
+        assert!(prod.starts_with("DATABASE_URL="));
+    }
+
+    #[test]
+    fn test_mv_key_conflict() {
+        let manager = manager();
+        let err = run(
+            &manager,
+            &mut Vec::new(),
+            "DB_URL".into(),
+            "PORT".into(),
+            Vec::new(),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::KeyRenameConflict { count: 1, .. }
+        ));
+
+        // Profiles without a conflict are left untouched too.
+        let dev = manager.read_file(&manager.profile_path("dev")).unwrap();
+        assert!(dev.contains("\nDB_URL="));
+
+        let err = run(
+            &manager,
+            &mut Vec::new(),
+            "DB_URL".into(),
+            "DB-URL".into(),
+            Vec::new(),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InvalidKey { .. }));
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn pin<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profiles: Vec<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    if profiles.is_empty() {
+        let pinned = manager.pinned_profiles();
+        if pinned.is_empty() {
+            writeln!(
+                out,
+                "No profiles are pinned. Run `envoke pin <profile>` to list one first."
+            )?;
+        }
+        for profile in pinned {
+            writeln!(out, "{}", profile)?;
+        }
+        return Ok(());
+    }
+
+    for profile in &profiles {
+        if !manager.fs.path_exists(&manager.profile_path(profile)) {
+            return Err(ErrorKind::ProfileNotFound {
+                profile: manager.profile_name(profile),
+            }
+            .into());
+        }
+    }
+
+    for profile in profiles {
+        let name = manager.profile_name(&profile);
+        if manager.set_pinned(&name, true)? {
+            writeln!(out, "{}", presenter::success(format!("Pinned `{}`", name)))?;
+        } else {
+            writeln!(out, "`{}` is already pinned", name)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn unpin<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profiles: Vec<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    // Profiles that no longer exist can still be unpinned.
+    for profile in profiles {
+        let name = manager.profile_name(&profile);
+        if manager.set_pinned(&name, false)? {
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!("Unpinned `{}`", name))
+            )?;
+        } else {
+            writeln!(out, "`{}` is not pinned", name)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.state_dir = Some(PathBuf::from("/state/envoke"));
+        let manager = ProfileManager::new(config, MockFileSystem::new());
+        manager.fs.add_dir(".envoke");
+        manager.create_profile("dev", "").unwrap();
+
+        let mut out = Vec::new();
+        assert!(pin(&manager, &mut out, vec!["prod".to_string()]).is_err());
+        pin(&manager, &mut out, vec!["dev".to_string()]).unwrap();
+        pin(&manager, &mut out, vec!["dev".to_string()]).unwrap();
+        assert_eq!(
+            "Pinned `dev`\n`dev` is already pinned\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        pin(&manager, &mut out, Vec::new()).unwrap();
+        assert_eq!("dev\n", String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        unpin(&manager, &mut out, vec!["dev".to_string()]).unwrap();
+        assert!(manager.pinned_profiles().is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::config::{Config, ENVOKE_DIR};
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::{EnvokeFileSystem, FileSystem};
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+/// The active profile and health of a registered project.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProjectStatus {
+    path: PathBuf,
+    profile: Option<String>,
+    health: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn list<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    json: bool,
+) -> Result<()> {
+    let registry = manager.load_registry()?;
+    let statuses: Vec<ProjectStatus> = registry.projects.into_iter().map(status).collect();
+
+    if json {
+        let json = serde_json::to_string_pretty(&statuses).expect("statuses are serializable");
+        writeln!(out, "{}", json)?;
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        writeln!(
+            out,
+            "No projects found. Projects are registered by `envoke init` and `envoke switch`."
+        )?;
+        return Ok(());
+    }
+
+    let width = statuses
+        .iter()
+        .map(|s| s.path.to_string_lossy().len())
+        .max()
+        .unwrap_or(0);
+    let profile_width = statuses
+        .iter()
+        .map(|s| s.profile.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0);
+    for status in statuses {
+        let health = match (status.health, &status.error) {
+            ("ok", _) => presenter::success(status.health),
+            (health, Some(error)) => presenter::failure(format!("{}: {}", health, error)),
+            (health, None) => presenter::failure(health),
+        };
+        writeln!(
+            out,
+            "{:width$}  {:profile_width$}  {}",
+            status.path.to_string_lossy(),
+            status.profile.as_deref().unwrap_or("-"),
+            health,
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn switch<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: String,
+    projects: Vec<PathBuf>,
+    all: bool,
+) -> Result<()> {
+    let registry = manager.load_registry()?;
+    let projects = if all {
+        registry.projects.into_iter().collect()
+    } else {
+        projects
+            .into_iter()
+            .map(|path| {
+                let path = std::fs::canonicalize(&path).unwrap_or(path);
+                match registry.projects.contains(&path) {
+                    true => Ok(path),
+                    false => Err(Error::new(ErrorKind::ProjectNotRegistered { path })),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut failed = 0;
+    for path in projects {
+        writeln!(out, "{}", path.display())?;
+        let result = in_project(&path, |project| {
+            if !project.is_initialized() {
+                return Err(ErrorKind::Uninitialized.into());
+            }
+            if !project.fs.path_exists(&project.profile_path(&profile)) {
+                writeln!(out, "  Skipped - `{}` does not exist", profile)?;
+                return Ok(());
+            }
+            super::switch::run(project, out, &profile, false, false, false)
+        });
+        if let Err(e) = result {
+            writeln!(out, "  {}", presenter::failure(&e))?;
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::ProjectSwitchFailed { count: failed }.into());
+    }
+
+    Ok(())
+}
+
+pub fn forget<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    path: PathBuf,
+) -> Result<()> {
+    // Projects that were deleted can no longer be canonicalized.
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    let mut registry = manager.load_registry()?;
+    if !registry.projects.remove(&path) {
+        return Err(ErrorKind::ProjectNotRegistered { path }.into());
+    }
+    manager.save_registry(&registry)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Forgot `{}`", path.display()))
+    )?;
+    Ok(())
+}
+
+/// Gets the active profile and health of a registered project.
+fn status(path: PathBuf) -> ProjectStatus {
+    let result = in_project(&path, |project| {
+        if !project.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+        project.active_profile()
+    });
+
+    let (profile, health, error) = match result {
+        Ok(profile) => (Some(profile), "ok", None),
+        Err(e) => match e.kind {
+            ErrorKind::NoActiveProfile => (None, "inactive", None),
+            ErrorKind::NonLinkedEnv { .. } => (None, "unmanaged", None),
+            ErrorKind::Uninitialized | ErrorKind::ReadDir { .. } => (None, "missing", None),
+            _ => (None, "broken", Some(e.to_string())),
+        },
+    };
+
+    ProjectStatus {
+        path,
+        profile,
+        health,
+        error,
+    }
+}
+
+/// Creates a manager for the profiles of another project, without changing
+/// the working directory.
+///
+/// The project's own envoke directory and config are used, whatever the
+/// current project's are. `.env` is still resolved relative to the working
+/// directory, so commands touching it go through [`in_project`].
+pub(crate) fn project_manager(path: &Path) -> Result<ProfileManager<EnvokeFileSystem>> {
+    let config = Config::load(path.join(ENVOKE_DIR))?;
+    Ok(ProfileManager::new(config, EnvokeFileSystem::new()))
+}
+
+/// Runs `f` with a manager for another project.
+///
+/// `.env` is resolved relative to the working directory, so it is changed to
+/// the project for the duration of `f`.
+pub(crate) fn in_project<T>(
+    path: &Path,
+    f: impl FnOnce(&ProfileManager<EnvokeFileSystem>) -> Result<T>,
+) -> Result<T> {
+    let cwd = env::current_dir().map_err(|e| {
+        Error::new(ErrorKind::ReadDir {
+            file: PathBuf::from("."),
+            source: e,
+        })
+    })?;
+    env::set_current_dir(path).map_err(|e| {
+        Error::new(ErrorKind::ReadDir {
+            file: path.to_path_buf(),
+            source: e,
+        })
+    })?;
+
+    let result = project_manager(path).and_then(|project| f(&project));
+
+    env::set_current_dir(&cwd).map_err(|e| {
+        Error::new(ErrorKind::ReadDir {
+            file: cwd.clone(),
+            source: e,
+        })
+    })?;
+    result
+}
@@ -0,0 +1,65 @@
+use std::io::Write;
+
+use crate::cli::PromptShell;
+use crate::config::Color;
+use crate::error::Result;
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+/// Placeholder replaced by the active profile in the prompt format.
+const PROFILE_PLACEHOLDER: &str = "{profile}";
+
+/// Prints the prompt segment. This runs on every prompt, so it never fails:
+/// when there is no active profile, nothing is printed.
+///
+/// Returns whether a segment was printed. In Starship mode only the profile
+/// is printed - Starship applies its own format and style.
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    format: Option<String>,
+    style: Option<Color>,
+    shell: PromptShell,
+    starship: bool,
+) -> Result<bool> {
+    if !manager.is_initialized() {
+        return Ok(false);
+    }
+
+    let Ok(profile) = manager.active_profile() else {
+        return Ok(false);
+    };
+
+    if starship {
+        write!(out, "{}", profile)?;
+        return Ok(true);
+    }
+
+    let config = &manager.config.prompt;
+    let format = format.as_deref().unwrap_or(&config.format);
+    let segment = format.replace(PROFILE_PLACEHOLDER, &profile);
+
+    // The segment is captured by the shell, so stdout is never a terminal.
+    match style.or(config.style) {
+        Some(color) if presenter::color_allowed() => write!(
+            out,
+            "{}{}{}",
+            escape(color.ansi(), shell),
+            segment,
+            escape(presenter::RESET, shell)
+        )?,
+        _ => write!(out, "{}", segment)?,
+    }
+
+    Ok(true)
+}
+
+/// Marks an escape sequence as zero-width so the shell can measure the prompt.
+fn escape(sequence: &str, shell: PromptShell) -> String {
+    match shell {
+        PromptShell::Plain => sequence.to_string(),
+        PromptShell::Bash => format!("\x01{}\x02", sequence),
+        PromptShell::Zsh => format!("%{{{}%}}", sequence),
+    }
+}
@@ -0,0 +1,224 @@
+use std::io::Write;
+
+use crate::encryption::{self, is_encrypted};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn list<F: FileSystem>(manager: &ProfileManager<F>, out: &mut impl Write) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let recipients = &manager.config.encryption.recipients;
+    if recipients.is_empty() {
+        writeln!(
+            out,
+            "No recipients configured - values are encrypted to your own key. Run `envoke recipients add <KEY>` to add one."
+        )?;
+        return Ok(());
+    }
+
+    let own = manager
+        .identity()
+        .ok()
+        .map(|identity| identity.to_public().to_string());
+    for recipient in recipients {
+        if own.as_ref() == Some(recipient) {
+            writeln!(out, "{} (you)", recipient)?;
+        } else {
+            writeln!(out, "{}", recipient)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn add<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    recipient: String,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let recipient = recipient.trim().to_string();
+    encryption::parse_recipients(std::slice::from_ref(&recipient))?;
+
+    let mut recipients = manager.config.encryption.recipients.clone();
+    if recipients.contains(&recipient) {
+        return Err(ErrorKind::RecipientExists { recipient }.into());
+    }
+
+    // Values were encrypted to the local identity until now, so it stays a
+    // recipient rather than locking its owner out.
+    if recipients.is_empty()
+        && let Ok(identity) = manager.identity()
+    {
+        let own = identity.to_public().to_string();
+        if own != recipient {
+            recipients.push(own);
+        }
+    }
+    recipients.push(recipient.clone());
+
+    update(manager, out, &recipients)?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Recipient {} added.", recipient))
+    )?;
+
+    Ok(())
+}
+
+pub fn remove<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    recipient: String,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut recipients = manager.config.encryption.recipients.clone();
+    let Some(index) = recipients.iter().position(|r| *r == recipient) else {
+        return Err(ErrorKind::RecipientNotFound { recipient }.into());
+    };
+    recipients.remove(index);
+
+    update(manager, out, &recipients)?;
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Recipient {} removed.", recipient))
+    )?;
+
+    Ok(())
+}
+
+/// Stores the recipients and re-encrypts every encrypted value to them.
+///
+/// All values are re-encrypted before anything is written, so a value that
+/// cannot be decrypted leaves the configuration and profiles untouched.
+fn update<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    recipients: &[String],
+) -> Result<()> {
+    let keys = if recipients.is_empty() {
+        vec![manager.identity()?.to_public()]
+    } else {
+        encryption::parse_recipients(recipients)?
+    };
+
+    let dir = &manager.config.envoke_dir;
+    let mut identity = None;
+    let mut updates = Vec::new();
+    let mut values = 0;
+    for name in manager.env_files(dir)? {
+        let path = dir.join(format!("{}.env", name));
+        let doc = manager.read_document(&path)?;
+        let count = doc
+            .entries()
+            .filter(|entry| is_encrypted(&entry.value))
+            .count();
+        if count == 0 {
+            continue;
+        }
+
+        if identity.is_none() {
+            identity = Some(manager.identity()?);
+        }
+        let identity = identity.as_ref().expect("the identity is loaded");
+        updates.push((name, encryption::reencrypt(&doc, identity, &keys)?));
+        values += count;
+    }
+
+    manager.save_recipients(recipients)?;
+    for (name, doc) in &updates {
+        manager.save_profile(name, doc.to_string())?;
+    }
+
+    if values > 0 {
+        writeln!(
+            out,
+            "Re-encrypted {} value(s) in {} file(s).",
+            values,
+            updates.len()
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    use age::x25519::Identity;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn manager(owner: &Identity) -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/config.toml", "# Team settings\n[encryption]\n");
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.global_dir = Some(PathBuf::from("global"));
+        config.encryption.recipients = vec![owner.to_public().to_string()];
+        ProfileManager::new(config, fs)
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let alice = Identity::generate();
+        let bob = Identity::generate().to_public().to_string();
+        let mut manager = manager(&alice);
+
+        let mut out = Vec::new();
+        add(&manager, &mut out, bob.clone()).unwrap();
+        assert_eq!(
+            format!(
+                "# Team settings\n[encryption]\nrecipients = [\n    \"{}\",\n    \"{}\",\n]\n",
+                alice.to_public(),
+                bob
+            ),
+            manager.read_file(Path::new(".envoke/config.toml")).unwrap()
+        );
+
+        let err = add(&manager, &mut out, "age1invalid".into()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InvalidRecipient { .. }));
+
+        manager.config.encryption.recipients = vec![alice.to_public().to_string(), bob.clone()];
+        let err = add(&manager, &mut out, bob.clone()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::RecipientExists { .. }));
+
+        remove(&manager, &mut out, bob.clone()).unwrap();
+        let err = remove(&manager, &mut out, "age1unknown".into()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::RecipientNotFound { .. }));
+    }
+
+    #[test]
+    fn test_update_requires_identity() {
+        let alice = Identity::generate();
+        let manager = manager(&alice);
+        let encrypted = encryption::encrypt("hunter2", &[alice.to_public()]).unwrap();
+        manager
+            .fs
+            .add_file(".envoke/dev.env", format!("DB_PASSWORD={}\n", encrypted));
+
+        // Nothing changes when the values cannot be re-encrypted.
+        let bob = Identity::generate().to_public().to_string();
+        let err = add(&manager, &mut Vec::new(), bob).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::IdentityNotFound));
+        assert_eq!(
+            "# Team settings\n[encryption]\n",
+            manager.read_file(Path::new(".envoke/config.toml")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::{BufRead, Write};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::redact::Scrubber;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    mut input: impl BufRead,
+    out: &mut impl Write,
+    profile: Option<String>,
+    all: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.selected_profile()?,
+    };
+
+    let doc = manager.load_env(&profile, true)?;
+    let redactor = manager.config.secrets.redactor(true);
+    let scrubber = Scrubber::new(
+        doc.entries()
+            .filter(|entry| all || redactor.is_secret(&entry.key))
+            .map(|entry| entry.value.as_str()),
+    );
+
+    // Lines are written as they arrive, so piped logs are not held back.
+    // Build logs are not always UTF-8, so invalid bytes are replaced rather
+    // than ending the pipe.
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = input
+            .read_until(b'\n', &mut line)
+            .map_err(|e| Error::new(ErrorKind::Input { source: e }))?;
+        if read == 0 {
+            break;
+        }
+
+        out.write_all(scrubber.scrub(&String::from_utf8_lossy(&line)).as_bytes())?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_redact() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        manager.fs.add_dir(".envoke");
+        manager
+            .create_profile("ci", "API_TOKEN=tok_123\nREGION=eu-west-1\n")
+            .unwrap();
+
+        let input = "using tok_123 in eu-west-1\n".as_bytes();
+        let mut out = Vec::new();
+        run(&manager, input, &mut out, Some("ci".into()), false).unwrap();
+        assert_eq!(
+            "using ******** in eu-west-1\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let input = "using tok_123 in eu-west-1\n".as_bytes();
+        let mut out = Vec::new();
+        run(&manager, input, &mut out, Some("ci".into()), true).unwrap();
+        assert_eq!(
+            "using ******** in ********\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_redact_invalid_utf8() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        manager.fs.add_dir(".envoke");
+        manager.create_profile("ci", "API_TOKEN=tok_123\n").unwrap();
+
+        let input: &[u8] = b"bad \xff byte\r\nusing tok_123";
+        let mut out = Vec::new();
+        run(&manager, input, &mut out, Some("ci".into()), false).unwrap();
+        assert_eq!(
+            "bad \u{fffd} byte\r\nusing ********",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
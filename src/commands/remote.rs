@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::bundle::{self, BundleFile, PROFILES_DIR};
+use crate::cli::{ConflictAction, MergeStrategy};
+use crate::digest::sha256_hex;
+use crate::dotenv::Document;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::merge::{self, Conflict};
+use crate::metadata::METADATA_FILE;
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::prompt;
+use crate::remote::{self, Divergence, Remote};
+
+/// Suffix of the file a conflicting remote profile is written to by
+/// `pull --on-conflict copy`.
+pub const CONFLICT_SUFFIX: &str = ".conflict";
+
+pub fn add<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: String,
+    url: String,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut remotes = manager.load_remotes()?;
+    if remotes.remotes.contains_key(&name) {
+        return Err(ErrorKind::RemoteExists { remote: name }.into());
+    }
+
+    remotes.remotes.insert(
+        name.clone(),
+        Remote {
+            url,
+            synced: None,
+            bases: BTreeMap::new(),
+        },
+    );
+    manager.save_remotes(&remotes)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Remote {} added.", name))
+    )?;
+
+    Ok(())
+}
+
+pub fn remove<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: String,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut remotes = manager.load_remotes()?;
+    if remotes.remotes.remove(&name).is_none() {
+        return Err(ErrorKind::RemoteNotFound { remote: name }.into());
+    }
+    manager.save_remotes(&remotes)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Remote {} removed.", name))
+    )?;
+
+    Ok(())
+}
+
+pub fn list<F: FileSystem>(manager: &ProfileManager<F>, out: &mut impl Write) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let remotes = manager.load_remotes()?;
+    if remotes.remotes.is_empty() {
+        writeln!(
+            out,
+            "No remotes found. Run `envoke remote add <name> <url>` to add one."
+        )?;
+        return Ok(());
+    }
+
+    let width = remotes
+        .remotes
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, remote) in &remotes.remotes {
+        writeln!(out, "{:<width$}  {}", name, remote.url, width = width)?;
+    }
+
+    Ok(())
+}
+
+pub fn push<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: Option<String>,
+    force: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut remotes = manager.load_remotes()?;
+    let name = remotes.select(name.as_deref())?;
+    let url = remotes.remotes[&name].url.clone();
+    let synced = remotes.remotes[&name].synced.clone();
+    let passphrase = super::bundle::passphrase()?;
+
+    let mut profiles = manager.profiles()?;
+    profiles.sort();
+    let files = super::bundle::collect(manager, &profiles)?;
+    let local = remote::digest(&files);
+
+    // Refuse to overwrite changes pushed by someone else since the last sync.
+    if let Some(data) = remote::fetch(&url)? {
+        let theirs = remote::digest(&bundle::unpack(&bundle::decrypt(&data, &passphrase)?)?);
+        if theirs == local {
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!("Remote {} is up to date.", name))
+            )?;
+            return record_sync(manager, name, local, bases(manager, &files)?);
+        }
+
+        if synced.as_deref() != Some(theirs.as_str()) && !force {
+            return Err(ErrorKind::RemoteConflict {
+                remote: name,
+                pull: false,
+            }
+            .into());
+        }
+    }
+
+    if manager.config.dry_run {
+        writeln!(out, "Would push {} profile(s) to {}.", profiles.len(), name)?;
+        return Ok(());
+    }
+
+    let data = bundle::encrypt(&bundle::pack(&files)?, &passphrase)?;
+    remote::store(&url, &data)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Pushed {} profile(s) to {}.", profiles.len(), name))
+    )?;
+
+    let remote = remotes.remotes.get_mut(&name).unwrap();
+    remote.synced = Some(local);
+    remote.bases = bases(manager, &files)?;
+    manager.save_remotes(&remotes)
+}
+
+pub fn pull<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: Option<String>,
+    force: bool,
+    on_conflict: ConflictAction,
+    strategy: Option<MergeStrategy>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let remotes = manager.load_remotes()?;
+    let name = remotes.select(name.as_deref())?;
+    let remote = &remotes.remotes[&name];
+
+    let data = remote::fetch(&remote.url)?.ok_or_else(|| ErrorKind::RemoteEmpty {
+        remote: name.clone(),
+    })?;
+    let files = bundle::unpack(&bundle::decrypt(&data, &super::bundle::passphrase()?)?)?;
+    let theirs = remote::digest(&files);
+    let new_bases = bases(manager, &files)?;
+
+    let profiles = manager.profiles()?;
+    let local = remote::digest(&super::bundle::collect(manager, &profiles)?);
+
+    // Nothing changed remotely since the last sync, so local edits are newer.
+    if theirs == local || (!force && remote.synced.as_deref() == Some(theirs.as_str())) {
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!("Already up to date with {}.", name))
+        )?;
+        return record_sync(manager, name, theirs, new_bases);
+    }
+
+    // Local edits since the last sync would be lost, so both sides are merged.
+    let unchanged = profiles.is_empty() || remote.synced.as_deref() == Some(local.as_str());
+    if !unchanged && !force {
+        let conflicts = merge(manager, out, &name, remote, &files, on_conflict, strategy)?;
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Merged {} with the local profiles, settling {} conflict(s).",
+                name, conflicts
+            ))
+        )?;
+        return record_sync(manager, name, theirs, new_bases);
+    }
+
+    let pulled = super::bundle::restore(manager, &files)?;
+
+    // Mirror the remote, dropping profiles that were removed there.
+    for profile in profiles.iter().filter(|p| !pulled.contains(p)) {
+        if manager.remove_profile(profile)?.unlinked {
+            presenter::warn(format!(
+                "`{}` was removed from {} while active, so .env was unlinked.",
+                profile, name
+            ));
+        }
+    }
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Pulled {} profile(s) from {}.", pulled.len(), name))
+    )?;
+
+    record_sync(manager, name, theirs, new_bases)
+}
+
+/// Merges pulled profiles, and the files they include, into the local ones.
+///
+/// Profiles only on one side are kept, and a profile changed on one side
+/// since the last sync takes that side's version. A profile changed on both
+/// sides is handled by `on_conflict`: merged key by key, with conflicting keys
+/// settled by `strategy` or else by asking the user; kept with the remote
+/// version written next to it; or refused. Nothing is written until every
+/// conflict is settled.
+///
+/// # Returns
+///
+/// The number of conflicts, or an `Error` if they cannot be settled.
+fn merge<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: &str,
+    remote: &Remote,
+    files: &[BundleFile],
+    on_conflict: ConflictAction,
+    strategy: Option<MergeStrategy>,
+) -> Result<usize> {
+    let conflict_error = || ErrorKind::RemoteConflict {
+        remote: name.to_string(),
+        pull: true,
+    };
+    let redactor = manager.config.secrets.redactor(true);
+    let mut resolve = |conflict: &Conflict| -> Result<String> {
+        if let Some(strategy) = strategy {
+            return Ok(strategy.resolve(conflict).to_string());
+        }
+        if !prompt::is_interactive() {
+            return Err(conflict_error().into());
+        }
+
+        let items = [
+            format!(
+                "Keep local   {}",
+                redactor.redact(conflict.key, conflict.ours)
+            ),
+            format!(
+                "Take remote  {}",
+                redactor.redact(conflict.key, conflict.theirs)
+            ),
+            "Edit".to_string(),
+        ];
+        let question = format!(
+            "`{}` changed on both sides in `{}`",
+            conflict.key, conflict.profile
+        );
+        match prompt::select(&question, &items, Some(0))? {
+            Some(0) => Ok(conflict.ours.to_string()),
+            Some(1) => Ok(conflict.theirs.to_string()),
+            Some(_) => prompt::ask(
+                &format!("{}=", conflict.key),
+                redactor.is_secret(conflict.key),
+            ),
+            None => Err(conflict_error().into()),
+        }
+    };
+
+    // Profiles and the files they include are reconciled alike, each against
+    // its digest at the last sync.
+    let profiles = manager.profiles()?;
+    let mut pulled = Vec::new();
+    let mut added = Vec::new();
+    for (profile, contents) in super::bundle::profiles(manager, files)? {
+        if !profiles.contains(&profile) {
+            added.push(profile);
+            continue;
+        }
+        pulled.push(Pulled {
+            path: manager.profile_path(&profile),
+            base: profile.clone(),
+            name: profile,
+            profile: true,
+            contents,
+        });
+    }
+    let mut added_includes = Vec::new();
+    for (path, file) in super::bundle::includes(manager, files)? {
+        let name = path
+            .strip_prefix(&manager.config.envoke_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        if !manager.fs.path_exists(&path) {
+            added_includes.push((name, path, file.contents.as_slice()));
+            continue;
+        }
+        pulled.push(Pulled {
+            name,
+            path,
+            profile: false,
+            base: file.path.clone(),
+            contents: &file.contents,
+        });
+    }
+
+    let mut merged = Vec::new();
+    let mut taken = Vec::new();
+    let mut copied = Vec::new();
+    let mut refused = Vec::new();
+    let mut conflicts = 0;
+    for file in pulled {
+        let ours = sha256_hex(manager.read_bytes(&file.path)?);
+        let base = remote.bases.get(&file.base).map(String::as_str);
+        match (
+            remote::divergence(base, &ours, &sha256_hex(file.contents)),
+            on_conflict,
+        ) {
+            (Divergence::Same | Divergence::Local, _) => continue,
+            (Divergence::Remote, _) => {
+                taken.push(file);
+                continue;
+            }
+            (Divergence::Both, ConflictAction::Copy) => {
+                conflicts += 1;
+                copied.push(file);
+                continue;
+            }
+            (Divergence::Both, ConflictAction::Refuse) => {
+                refused.push(file.name);
+                continue;
+            }
+            (Divergence::Both, ConflictAction::Merge) => {}
+        }
+
+        let theirs = std::str::from_utf8(file.contents)
+            .ok()
+            .and_then(|contents| Document::parse(contents).ok())
+            .ok_or_else(|| ErrorKind::Bundle {
+                reason: format!("`{}` is not a valid profile", file.name),
+            })?;
+        let ours = manager.read_document(&file.path)?;
+        let (doc, count) = merge::merge(&file.name, &ours, &theirs, &mut resolve)?;
+        conflicts += count;
+        if doc != ours {
+            merged.push((file, doc));
+        }
+    }
+
+    if !refused.is_empty() {
+        return Err(ErrorKind::ProfilesConflict {
+            remote: name.to_string(),
+            profiles: refused,
+        }
+        .into());
+    }
+
+    for file in &taken {
+        file.write(manager, file.contents)?;
+        writeln!(out, "Updated `{}`", file.name)?;
+    }
+    for (file, doc) in &merged {
+        file.write(manager, doc.to_string().as_bytes())?;
+        writeln!(out, "Merged `{}`", file.name)?;
+    }
+    for file in &copied {
+        let mut path = file.path.clone().into_os_string();
+        path.push(CONFLICT_SUFFIX);
+        let path = PathBuf::from(path);
+        manager.write_file(&path, file.contents)?;
+        writeln!(
+            out,
+            "Kept the local `{}` - the remote version is in {}",
+            file.name,
+            path.display()
+        )?;
+    }
+    for (name, path, contents) in &added_includes {
+        super::bundle::write_include(manager, path, contents)?;
+        writeln!(out, "Added `{}`", name)?;
+    }
+
+    // Profiles new on the remote are restored with their metadata.
+    let new_files: Vec<_> = files
+        .iter()
+        .filter(|file| {
+            file.path == METADATA_FILE
+                || added
+                    .iter()
+                    .any(|profile| file.path == format!("{}/{}.env", PROFILES_DIR, profile))
+        })
+        .cloned()
+        .collect();
+    for profile in super::bundle::restore(manager, &new_files)? {
+        writeln!(out, "Added `{}`", profile)?;
+    }
+
+    Ok(conflicts)
+}
+
+/// Computes the digest of each profile and included file in a bundle, to
+/// record as the bases of the next sync.
+///
+/// Included files are recorded under their path within the bundle.
+fn bases<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    files: &[BundleFile],
+) -> Result<BTreeMap<String, String>> {
+    let profiles = super::bundle::profiles(manager, files)?
+        .into_iter()
+        .map(|(profile, contents)| (profile, sha256_hex(contents)));
+    let includes = super::bundle::includes(manager, files)?
+        .into_iter()
+        .map(|(_, file)| (file.path.clone(), sha256_hex(&file.contents)));
+
+    Ok(profiles.chain(includes).collect())
+}
+
+/// A pulled profile or included file that also exists locally.
+struct Pulled<'a> {
+    /// The profile name, or the path of the file within the envoke directory.
+    name: String,
+
+    /// The local file.
+    path: PathBuf,
+
+    /// Whether the file is a profile.
+    profile: bool,
+
+    /// The key of its digest in the bases of the remote.
+    base: String,
+
+    /// The remote contents.
+    contents: &'a [u8],
+}
+
+impl Pulled<'_> {
+    /// Replaces the local file.
+    fn write<F: FileSystem>(&self, manager: &ProfileManager<F>, contents: &[u8]) -> Result<()> {
+        match self.profile {
+            true => manager.write_profile(&self.name, contents),
+            false => manager.write_file(&self.path, contents),
+        }
+    }
+}
+
+/// Records the digests of the profiles at the last sync with a remote.
+fn record_sync<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    name: String,
+    digest: String,
+    bases: BTreeMap<String, String>,
+) -> Result<()> {
+    let mut remotes = manager.load_remotes()?;
+    if let Some(remote) = remotes.remotes.get_mut(&name) {
+        remote.synced = Some(digest);
+        remote.bases = bases;
+    }
+
+    manager.save_remotes(&remotes)
+}
@@ -1,44 +1,143 @@
-use std::path::Path;
+use std::io::Write;
 
 use crate::error::{ErrorKind, Result};
 use crate::fs::FileSystem;
+use crate::hooks::{self, Hook, HookContext};
+use crate::presenter;
 use crate::profile::ProfileManager;
 
-pub fn run<F, S>(manager: &ProfileManager<F>, profile: S) -> Result<()>
+pub fn run<F, S>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: S,
+    switch_to: Option<String>,
+) -> Result<()>
 where
     F: FileSystem,
     S: AsRef<str>,
 {
-    let profile = profile.as_ref();
+    manager.ensure_unlocked()?;
 
-    if !manager.is_initialized() {
-        return Err(ErrorKind::Uninitialized.into());
+    let name = manager.profile_name(profile.as_ref());
+    let fallback = fallback(manager, &name, switch_to)?;
+
+    let context = HookContext {
+        old_profile: Some(name),
+        new_profile: None,
+        target: Some(manager.profile_path(profile.as_ref())),
+    };
+    hooks::run(manager, Hook::PreRemove, &context)?;
+
+    let outcome = manager.remove_profile(profile)?;
+
+    if outcome.unlinked {
+        writeln!(out, "Unlinking .env")?;
     }
 
-    let profile_path = manager.profile_path(&profile);
-    let env_path = Path::new(".env");
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Profile {} removed.", outcome.profile))
+    )?;
 
-    if !profile_path.exists() {
-        return Err(ErrorKind::ProfileNotFound {
-            profile: profile.to_string(),
-        }
-        .into());
+    if let Err(e) = hooks::run(manager, Hook::PostRemove, &context) {
+        presenter::warn_error(e);
+    }
+
+    if let Some(fallback) = fallback.filter(|_| outcome.unlinked) {
+        writeln!(
+            out,
+            "`{}` was active, switching to `{}`.",
+            outcome.profile, fallback
+        )?;
+        super::switch::run(manager, out, fallback, false, false, false)?;
     }
 
-    if env_path.exists() && env_path.is_symlink() {
-        let target = manager.fs.read_link(env_path)?;
-        let target = target.file_stem().unwrap();
-        let profile = profile_path.file_stem().unwrap();
+    Ok(())
+}
+
+/// Chooses the profile to switch to if the removed profile is active.
+///
+/// A fallback given on the command line must exist and must not include the
+/// removed profile. The configured fallback is skipped when it includes the
+/// removed profile.
+fn fallback<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profile: &str,
+    switch_to: Option<String>,
+) -> Result<Option<String>> {
+    let explicit = switch_to.is_some();
+    let Some(fallback) = switch_to.or_else(|| manager.config.remove.switch_to.clone()) else {
+        return Ok(None);
+    };
 
-        if target == profile {
-            println!("Unlinking .env");
-            manager.fs.remove_file(&env_path)?;
+    let layers = manager.profile_layers(&fallback);
+    if layers.iter().any(|layer| layer == profile) {
+        if explicit {
+            return Err(ErrorKind::InvalidFallback { profile: fallback }.into());
         }
+        return Ok(None);
     }
 
-    manager.fs.remove_file(&profile_path)?;
+    // Checked before anything is removed, so a typo leaves everything as it was.
+    for layer in layers {
+        if !manager.fs.path_exists(&manager.profile_path(&layer)) {
+            return Err(ErrorKind::ProfileNotFound { profile: layer }.into());
+        }
+    }
 
-    println!("Profile {} removed.", profile);
+    Ok(Some(fallback))
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "");
+        fs.add_file(".envoke/staging.env", "");
+        fs.add_file(".envoke/prod.env", "");
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    #[test]
+    fn test_remove_switches_to_fallback() {
+        let manager = manager();
+        manager.switch_profile("staging", false).unwrap();
+
+        let mut out = Vec::new();
+        run(&manager, &mut out, "staging", Some("dev".to_string())).unwrap();
+        assert_eq!("dev", manager.active_profile().unwrap());
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("`staging` was active, switching to `dev`.")
+        );
+
+        // Removing an inactive profile leaves `.env` alone.
+        run(&manager, &mut Vec::new(), "prod", None).unwrap();
+        assert_eq!("dev", manager.active_profile().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_fallback() {
+        let mut manager = manager();
+        manager.switch_profile("staging", false).unwrap();
+
+        let err = run(&manager, &mut Vec::new(), "staging", Some("staging".into())).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InvalidFallback { .. }));
+        let err = run(&manager, &mut Vec::new(), "staging", Some("missing".into())).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ProfileNotFound { .. }));
+        assert!(manager.fs.path_exists(&manager.profile_path("staging")));
+
+        // A configured fallback that is being removed is ignored.
+        manager.config.remove.switch_to = Some("staging".to_string());
+        run(&manager, &mut Vec::new(), "staging", None).unwrap();
+        assert!(manager.active_profile().is_err());
+    }
 }
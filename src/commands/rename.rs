@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::{glob, presenter};
+
+/// Placeholder for the current name of a profile in the new names given with
+/// `--pattern`.
+const NAME_PLACEHOLDER: &str = "{name}";
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    name: String,
+    renamed: Option<String>,
+    pattern: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+    manager.ensure_unlocked()?;
+
+    let renames: Vec<(String, String)> = match (pattern, renamed) {
+        (Some(pattern), _) => {
+            let profiles: Vec<String> = manager
+                .profiles()?
+                .into_iter()
+                .filter(|profile| glob::matches_exact(&pattern, profile))
+                .collect();
+            if profiles.is_empty() {
+                writeln!(out, "No profiles match `{}`.", pattern)?;
+                return Ok(());
+            }
+            profiles
+                .into_iter()
+                .map(|profile| {
+                    let renamed = name.replace(NAME_PLACEHOLDER, &profile);
+                    (profile, renamed)
+                })
+                .collect()
+        }
+        (None, Some(renamed)) => vec![(name, renamed)],
+        (None, None) => unreachable!("clap requires a new name without a pattern"),
+    };
+
+    // Every new name is checked before any profile is renamed, so a clash
+    // leaves all of them as they were.
+    let mut taken = BTreeSet::new();
+    for (_, renamed) in &renames {
+        let path = manager.check_new_profile(renamed)?;
+        if !taken.insert(manager.profile_name(renamed)) {
+            return Err(ErrorKind::FileExists { file: path }.into());
+        }
+    }
+
+    let pinned = manager.pinned_profiles();
+    for (profile, renamed) in renames {
+        let outcome = manager.rename_profile(profile, renamed)?;
+        if pinned.contains(&outcome.profile) {
+            manager.set_pinned(&outcome.profile, false)?;
+            manager.set_pinned(&outcome.renamed, true)?;
+        }
+
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Profile {} renamed to {}",
+                outcome.profile, outcome.renamed
+            ))
+        )?;
+        if let Some(stack) = outcome.relinked {
+            writeln!(out, "Profile `{}` linked to .env", stack)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        for profile in ["legacy-api", "legacy-web", "dev"] {
+            manager.create_profile(profile, "").unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn test_rename_pattern() {
+        let manager = manager();
+        let mut out = Vec::new();
+        let pattern = Some("legacy-*".to_string());
+        run(&manager, &mut out, "archive-{name}".into(), None, pattern).unwrap();
+
+        assert_eq!(
+            vec!["archive-legacy-api", "archive-legacy-web", "dev"],
+            manager.profiles().unwrap()
+        );
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Profile legacy-web renamed to archive-legacy-web\n"));
+
+        let mut out = Vec::new();
+        let pattern = Some("legacy-*".to_string());
+        run(&manager, &mut out, "{name}".into(), None, pattern).unwrap();
+        assert_eq!(
+            "No profiles match `legacy-*`.\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rename_clash() {
+        let manager = manager();
+
+        // Both profiles would get the same name, so neither is renamed.
+        let pattern = Some("legacy-*".to_string());
+        let err = run(&manager, &mut Vec::new(), "old".into(), None, pattern).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::FileExists { .. }));
+        assert_eq!(
+            vec!["dev", "legacy-api", "legacy-web"],
+            manager.profiles().unwrap()
+        );
+
+        let renamed = Some("dev".to_string());
+        let err = run(
+            &manager,
+            &mut Vec::new(),
+            "legacy-api".into(),
+            renamed,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::FileExists { .. }));
+    }
+}
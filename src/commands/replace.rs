@@ -0,0 +1,178 @@
+use std::io::Write;
+
+use regex::{NoExpand, Regex};
+
+use super::diff;
+use crate::encryption;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::patch::Change;
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    pattern: String,
+    replacement: String,
+    regex: bool,
+    profiles: Vec<String>,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let source = match regex {
+        true => pattern.clone(),
+        false => regex::escape(&pattern),
+    };
+    let matcher = Regex::new(&source).map_err(|e| {
+        Error::new(ErrorKind::InvalidPattern {
+            pattern: pattern.clone(),
+            reason: e.to_string(),
+        })
+    })?;
+
+    let mut profiles = match profiles.is_empty() {
+        true => manager.profiles()?,
+        false => profiles.iter().map(|p| manager.profile_name(p)).collect(),
+    };
+    profiles.sort();
+
+    let mut keys = 0;
+    let mut changed = 0;
+    for profile in profiles {
+        let mut doc = manager.read_profile(&profile)?;
+
+        // Encrypted values cannot match, so they are left alone.
+        let changes: Vec<Change> = diff::values(&doc)
+            .into_iter()
+            .filter(|(_, value)| !encryption::is_encrypted(value))
+            .filter_map(|(key, old)| {
+                let new = match regex {
+                    true => matcher.replace_all(&old, replacement.as_str()),
+                    false => matcher.replace_all(&old, NoExpand(&replacement)),
+                }
+                .into_owned();
+                (new != old).then_some(Change::Changed { key, old, new })
+            })
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+
+        for change in &changes {
+            if let Change::Changed { key, new, .. } = change {
+                doc.set(key, new.as_str());
+            }
+        }
+        manager.write_profile(&profile, doc.to_string())?;
+
+        writeln!(out, "{}", profile)?;
+        diff::write_changes(out, &changes, redactor)?;
+        keys += changes.len();
+        changed += 1;
+    }
+
+    if changed == 0 {
+        writeln!(out, "No values match `{}`.", pattern)?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Replaced {} value(s) in {} profile(s).",
+            keys, changed
+        ))
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(
+            ".envoke/dev.env",
+            "# Database\nDB_HOST=old-host # primary\nDB_URL=postgres://old-host:5432/app\n",
+        );
+        fs.add_file(".envoke/prod.env", "DB_HOST=db.internal\nPORT=80\n");
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    fn replace(
+        manager: &ProfileManager<MockFileSystem>,
+        pattern: &str,
+        replacement: &str,
+        regex: bool,
+    ) -> String {
+        let mut out = Vec::new();
+        let redactor = Redactor::new(Vec::new(), false);
+        run(
+            manager,
+            &mut out,
+            pattern.into(),
+            replacement.into(),
+            regex,
+            Vec::new(),
+            &redactor,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_replace() {
+        let manager = manager();
+        assert_eq!(
+            "dev\n\
+             ~ DB_HOST=old-host -> new-host\n\
+             ~ DB_URL=postgres://old-host:5432/app -> postgres://new-host:5432/app\n\
+             Replaced 2 value(s) in 1 profile(s).\n",
+            replace(&manager, "old-host", "new-host", false)
+        );
+        assert_eq!(
+            "# Database\nDB_HOST=new-host # primary\nDB_URL=postgres://new-host:5432/app\n",
+            manager.read_file(&manager.profile_path("dev")).unwrap()
+        );
+
+        // Without `--regex`, the pattern and replacement are taken literally.
+        assert_eq!(
+            "No values match `.*`.\n",
+            replace(&manager, ".*", "$0", false)
+        );
+    }
+
+    #[test]
+    fn test_replace_regex() {
+        let manager = manager();
+        replace(&manager, r"^(\w+)\.internal$", "$1.example.com", true);
+        assert_eq!(
+            "DB_HOST=db.example.com\nPORT=80\n",
+            manager.read_file(&manager.profile_path("prod")).unwrap()
+        );
+
+        let err = run(
+            &manager,
+            &mut Vec::new(),
+            "(".into(),
+            "".into(),
+            true,
+            Vec::new(),
+            &Redactor::new(Vec::new(), false),
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InvalidPattern { .. }));
+    }
+}
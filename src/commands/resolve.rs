@@ -0,0 +1,89 @@
+use std::env;
+use std::io::Write;
+
+use crate::config::Precedence;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
+use crate::redact::Redactor;
+use crate::template::resolve_references;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    key: String,
+    profile: Option<String>,
+    local: bool,
+    precedence: Precedence,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let selected = profile.is_none() && manager.config.profile_override.is_some();
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.selected_profile()?,
+    };
+    let profile = manager
+        .profile_layers(&profile)
+        .join(&LAYER_SEPARATOR.to_string());
+
+    let origins = manager.key_origins(&profile, local, &key)?;
+    let Some(last) = origins.last() else {
+        return Err(ErrorKind::KeyNotFound { key, profile }.into());
+    };
+
+    writeln!(out, "{}={}", key, redactor.redact(&key, &last.value))?;
+    if selected {
+        writeln!(out, "  profile `{}` selected by ENVOKE_PROFILE", profile)?;
+    }
+
+    let locations: Vec<_> = origins
+        .iter()
+        .map(|origin| format!("{}:{}", origin.file.display(), origin.line))
+        .collect();
+    let width = locations.iter().map(String::len).max().unwrap_or(0);
+    for (i, (origin, location)) in origins.iter().zip(&locations).enumerate() {
+        let line = format!(
+            "  {:<width$}  {}",
+            location,
+            redactor.redact(&key, &origin.value),
+            width = width
+        );
+        if i + 1 < origins.len() {
+            writeln!(out, "{}  (overridden)", line)?;
+        } else {
+            writeln!(out, "{}", line)?;
+        }
+    }
+
+    match resolve_references(&last.value, |name| env::var(name).ok()) {
+        Ok(resolved) if resolved != last.value => writeln!(
+            out,
+            "  references the environment, resolving to {}",
+            redactor.redact(&key, &resolved)
+        )?,
+        Ok(_) => (),
+        Err(names) => writeln!(
+            out,
+            "  references unset environment variable(s): {}",
+            names.join(", ")
+        )?,
+    }
+
+    if env::var_os(&key).is_some() {
+        let outcome = match precedence {
+            Precedence::Profile => "replaces it with the profile value",
+            Precedence::Os => "keeps the environment value",
+        };
+        writeln!(
+            out,
+            "  also set in the environment - `envoke run` {}",
+            outcome
+        )?;
+    }
+
+    Ok(())
+}
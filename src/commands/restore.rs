@@ -0,0 +1,238 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::backup::Backup;
+use crate::dotenv::Document;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::metadata::format_age;
+use crate::patch;
+use crate::presenter;
+use crate::profile::{ENV_FILE, ProfileManager};
+use crate::prompt;
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    backup: Option<String>,
+    list: bool,
+    yes: bool,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = profile.map(|profile| manager.profile_name(profile));
+    let target = match &profile {
+        Some(profile) => format!("`{}`", profile),
+        None => ENV_FILE.to_string(),
+    };
+
+    let kind = manager.backup_kind(profile.as_deref());
+    let backups: Vec<Backup> = manager
+        .backups()?
+        .into_iter()
+        .filter(|backup| backup.kind == kind)
+        .collect();
+    if backups.is_empty() {
+        return Err(ErrorKind::NoBackups { target }.into());
+    }
+
+    let current = current(manager, profile.as_deref());
+    if list {
+        for backup in &backups {
+            describe(manager, out, backup, &current, redactor)?;
+        }
+        return Ok(());
+    }
+
+    let backup = match backup {
+        Some(name) => backups
+            .iter()
+            .find(|backup| backup_name(backup) == name)
+            .ok_or(ErrorKind::BackupNotFound { backup: name })?,
+        None => pick(&backups)?,
+    };
+    describe(manager, out, backup, &current, redactor)?;
+
+    if !yes {
+        if !prompt::is_interactive() {
+            return Err(ErrorKind::ConfirmationRequired.into());
+        }
+
+        let question = format!("Restore {} from {}?", target, backup_name(backup));
+        if !prompt::confirm(&question)? {
+            writeln!(out, "Nothing was restored.")?;
+            return Ok(());
+        }
+    }
+
+    // Restoring `.env` changes the active environment, as switching does.
+    if profile.is_none() {
+        manager.ensure_unlocked()?;
+    }
+
+    if let Some(kept) = manager.restore_backup(backup, profile.as_deref())? {
+        writeln!(out, "Kept the replaced contents in {}", kept.display())?;
+    }
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!("Restored {} from {}.", target, backup_name(backup)))
+    )?;
+
+    Ok(())
+}
+
+/// Reads the current contents of `.env` or a profile, `None` if they are
+/// missing or cannot be parsed.
+fn current<F: FileSystem>(manager: &ProfileManager<F>, profile: Option<&str>) -> Option<Document> {
+    match profile {
+        Some(profile) => manager.read_profile(profile).ok(),
+        None => manager.read_document(Path::new(ENV_FILE)).ok(),
+    }
+}
+
+/// Writes the name and age of a backup, and how restoring it would change the
+/// current contents.
+fn describe<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    backup: &Backup,
+    current: &Option<Document>,
+    redactor: &Redactor,
+) -> Result<()> {
+    let age = SystemTime::now()
+        .duration_since(backup.created)
+        .unwrap_or_default();
+    writeln!(out, "{} ({} ago)", backup_name(backup), format_age(age))?;
+
+    let Ok(restored) = manager.read_document(&backup.path) else {
+        writeln!(out, "  Not a valid env file - it is restored unchanged.")?;
+        return Ok(());
+    };
+
+    let old = current
+        .as_ref()
+        .map(super::diff::values)
+        .unwrap_or_default();
+    let changes = patch::changes(&old, &super::diff::values(&restored));
+    if changes.is_empty() {
+        writeln!(out, "  Same as the current contents.")?;
+    } else {
+        let mut lines = Vec::new();
+        super::diff::write_changes(&mut lines, &changes, redactor)?;
+        for line in String::from_utf8_lossy(&lines).lines() {
+            writeln!(out, "  {}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Lets the user choose a backup, newest first.
+fn pick(backups: &[Backup]) -> Result<&Backup> {
+    if !prompt::is_interactive() {
+        return Err(ErrorKind::BackupRequired.into());
+    }
+
+    let now = SystemTime::now();
+    let items: Vec<String> = backups
+        .iter()
+        .map(|backup| {
+            let age = now.duration_since(backup.created).unwrap_or_default();
+            format!("{} ({} ago)", backup_name(backup), format_age(age))
+        })
+        .collect();
+
+    match prompt::select("Restore backup", &items, Some(0))? {
+        Some(index) => Ok(&backups[index]),
+        None => Err(ErrorKind::BackupRequired.into()),
+    }
+}
+
+/// Gets the file name of a backup, by which it is chosen.
+fn backup_name(backup: &Backup) -> String {
+    backup
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_restore_profile() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        let redactor = Redactor::new(Vec::new(), false);
+
+        manager.write_profile("dev", "PORT=9090\n").unwrap();
+        let backups = manager.backups().unwrap();
+        assert_eq!(1, backups.len());
+        assert_eq!("dev.env", backups[0].kind);
+
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            Some("dev".into()),
+            None,
+            true,
+            false,
+            &redactor,
+        )
+        .unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("~ PORT=9090 -> 8080")
+        );
+
+        let name = backup_name(&backups[0]);
+        let mut out = Vec::new();
+        run(
+            &manager,
+            &mut out,
+            Some("dev".into()),
+            Some(name),
+            false,
+            true,
+            &redactor,
+        )
+        .unwrap();
+        assert_eq!(
+            Some("PORT=8080\n".to_string()),
+            manager.fs.contents(".envoke/dev.env")
+        );
+
+        // The contents it replaced are kept, so the restore can be undone.
+        assert_eq!(2, manager.backups().unwrap().len());
+
+        let err = run(
+            &manager,
+            &mut Vec::new(),
+            None,
+            None,
+            true,
+            false,
+            &redactor,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::NoBackups { .. }));
+    }
+}
@@ -0,0 +1,96 @@
+use std::env;
+use std::os::unix::process::CommandExt;
+use std::process;
+
+use crate::config::Precedence;
+use crate::dotenv::Entry;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::glob;
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
+use crate::schema::missing_required;
+use tracing::debug;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profiles: Vec<String>,
+    local: bool,
+    precedence: Precedence,
+    isolated: Option<Vec<String>>,
+    overrides: Vec<(String, String)>,
+    command: Vec<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    // Each `-p` is a layer, exactly as if the profiles were stacked with `+`.
+    let profile = if profiles.is_empty() {
+        manager.selected_profile()?
+    } else {
+        profiles.join(&LAYER_SEPARATOR.to_string())
+    };
+
+    manager.ensure_signed(&profile, local)?;
+    let schema = manager.load_schema()?;
+    let mut doc = manager.load_env(&profile, local)?;
+    for (key, value) in &overrides {
+        if !doc.set(key, value) {
+            doc.push(Entry::new(key, value));
+        }
+    }
+
+    // Refuse to start a command that would fail later on a missing variable.
+    let missing = missing_required(schema.as_ref(), &doc);
+    if !missing.is_empty() {
+        return Err(ErrorKind::RequiredKeysMissing { keys: missing }.into());
+    }
+
+    let (program, args) = command.split_first().ok_or(ErrorKind::Spawn {
+        command: String::new(),
+        source: std::io::ErrorKind::InvalidInput.into(),
+    })?;
+
+    // An isolated command only inherits the allowed variables.
+    let inherits = |key: &str| {
+        env::var_os(key).is_some()
+            && isolated
+                .as_ref()
+                .is_none_or(|allow| glob::matches_any(allow, key))
+    };
+
+    // Later assignments win, matching how the profile is read elsewhere.
+    let mut child = process::Command::new(program);
+    child.args(args);
+    if let Some(allow) = &isolated {
+        child.env_clear();
+        for (key, value) in env::vars_os() {
+            if key
+                .to_str()
+                .is_some_and(|key| glob::matches_any(allow, key))
+            {
+                child.env(key, value);
+            }
+        }
+    }
+    for entry in doc.entries() {
+        // Overrides given on the command line win over the environment too.
+        let overridden = overrides.iter().any(|(key, _)| *key == entry.key);
+        if precedence == Precedence::Os && !overridden && inherits(&entry.key) {
+            debug!(key = %entry.key, "keeping value from the environment");
+            continue;
+        }
+        child.env(&entry.key, &entry.value);
+    }
+
+    debug!(program, %profile, "executing command");
+    manager.record_use(&profile);
+
+    // `exec` only returns if the command could not be started.
+    let source = child.exec();
+
+    Err(Error::new(ErrorKind::Spawn {
+        command: program.clone(),
+        source,
+    }))
+}
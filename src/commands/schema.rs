@@ -0,0 +1,10 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::json_schema::Subject;
+
+pub fn run(out: &mut impl Write, subject: Subject) -> Result<()> {
+    let json = serde_json::to_string_pretty(&subject.schema()).expect("schemas are serializable");
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
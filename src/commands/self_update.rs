@@ -0,0 +1,47 @@
+use std::env;
+use std::io::Write;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::presenter;
+use crate::update::{self, CURRENT_VERSION};
+
+pub fn run(out: &mut impl Write, check: bool) -> Result<()> {
+    let release = update::latest_release()?;
+    let version = release.version();
+
+    if !update::is_newer(version, CURRENT_VERSION) {
+        writeln!(out, "envoke {} is up to date.", CURRENT_VERSION)?;
+        return Ok(());
+    }
+
+    if check {
+        writeln!(
+            out,
+            "envoke {} is available (installed: {}). Run `envoke self-update` to install it.",
+            version, CURRENT_VERSION
+        )?;
+        return Ok(());
+    }
+
+    // Everything is downloaded and verified before the binary is touched.
+    let binary = update::download_verified(&release, &update::asset_name())?;
+
+    let exe = env::current_exe().map_err(|e| {
+        Error::new(ErrorKind::ReadFile {
+            file: "envoke".into(),
+            source: e,
+        })
+    })?;
+    update::replace_executable(&exe, &binary)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Updated envoke from {} to {}.",
+            CURRENT_VERSION, version
+        ))
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use tempfile::TempDir;
+use tracing::debug;
+
+use crate::commands::activate::{ACTIVATED_KEYS_VAR, ACTIVATED_VAR};
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
+
+/// The shell used when `$SHELL` is not set.
+const DEFAULT_SHELL: &str = "/bin/sh";
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profile: Option<String>,
+    local: bool,
+) -> Result<i32> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    let doc = manager.load_env(&profile, local)?;
+    let profile = manager
+        .profile_layers(&profile)
+        .join(&LAYER_SEPARATOR.to_string());
+
+    let shell = env::var_os("SHELL")
+        .filter(|shell| !shell.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SHELL));
+    let launcher =
+        Launcher::new(&shell, &format!("({}) ", profile)).map_err(|e| spawn_error(&shell, e))?;
+
+    // Later assignments win, matching how the profile is read elsewhere.
+    let mut command = process::Command::new(&shell);
+    command
+        .args(&launcher.args)
+        .envs(launcher.envs.iter().map(|(k, v)| (k, v)));
+    let mut keys = Vec::new();
+    for entry in doc.entries() {
+        command.env(&entry.key, &entry.value);
+        keys.push(entry.key.as_str());
+    }
+    keys.sort_unstable();
+    keys.dedup();
+    command
+        .env(ACTIVATED_VAR, &profile)
+        .env(ACTIVATED_KEYS_VAR, keys.join(","));
+
+    debug!(shell = %shell.display(), %profile, "starting subshell");
+    let status = command.status().map_err(|e| spawn_error(&shell, e))?;
+
+    Ok(status.code().unwrap_or(1))
+}
+
+/// The arguments and environment that make a shell show a prompt label.
+///
+/// Interactive shells set their prompt from their startup files, so a prompt
+/// set in the environment would be overwritten. Instead, bash is given a
+/// startup file that sources `~/.bashrc` and then prefixes `PS1`, zsh a
+/// `ZDOTDIR` that does the same for `~/.zshrc`, and fish a command that wraps
+/// `fish_prompt`. Other shells get `PS1` in the environment.
+#[derive(Debug)]
+struct Launcher {
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+
+    /// Holds the generated startup files until the shell exits.
+    _dir: Option<TempDir>,
+}
+
+impl Launcher {
+    fn new(shell: &Path, label: &str) -> std::io::Result<Self> {
+        let name = shell.file_name().unwrap_or_default().to_string_lossy();
+        let quoted = format!("'{}'", label.replace('\'', r"'\''"));
+        let mut launcher = Launcher {
+            args: Vec::new(),
+            envs: Vec::new(),
+            _dir: None,
+        };
+
+        match name.as_ref() {
+            "bash" => {
+                let dir = tempfile::tempdir()?;
+                let rcfile = dir.path().join("bashrc");
+                std::fs::write(
+                    &rcfile,
+                    format!("[ -f ~/.bashrc ] && . ~/.bashrc\nPS1={}\"$PS1\"\n", quoted),
+                )?;
+                launcher.args = vec!["--rcfile".into(), rcfile.into()];
+                launcher._dir = Some(dir);
+            }
+            "zsh" => {
+                let dir = tempfile::tempdir()?;
+                let home = env::var_os("ZDOTDIR")
+                    .or_else(|| env::var_os("HOME"))
+                    .unwrap_or_default();
+                std::fs::write(
+                    dir.path().join(".zshrc"),
+                    format!(
+                        "ZDOTDIR=\"$ENVOKE_ZDOTDIR\"\nunset ENVOKE_ZDOTDIR\n\
+                         [ -f \"$ZDOTDIR/.zshrc\" ] && . \"$ZDOTDIR/.zshrc\"\nPROMPT={}\"$PROMPT\"\n",
+                        quoted
+                    ),
+                )?;
+                launcher.envs = vec![
+                    ("ENVOKE_ZDOTDIR".into(), home),
+                    ("ZDOTDIR".into(), dir.path().into()),
+                ];
+                launcher._dir = Some(dir);
+            }
+            "fish" => {
+                launcher.args = vec![
+                    "--init-command".into(),
+                    format!(
+                        "functions -c fish_prompt __envoke_fish_prompt; \
+                         function fish_prompt; echo -n {}; __envoke_fish_prompt; end",
+                        quoted
+                    )
+                    .into(),
+                ];
+            }
+            _ => {
+                let prompt = env::var("PS1").unwrap_or_else(|_| "$ ".to_string());
+                launcher.envs = vec![("PS1".into(), format!("{}{}", label, prompt).into())];
+            }
+        }
+
+        Ok(launcher)
+    }
+}
+
+fn spawn_error(shell: &Path, source: std::io::Error) -> Error {
+    ErrorKind::Spawn {
+        command: shell.display().to_string(),
+        source,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bash_launcher() {
+        let launcher = Launcher::new(Path::new("/bin/bash"), "(dev) ").unwrap();
+        assert_eq!("--rcfile", launcher.args[0]);
+
+        let rcfile = std::fs::read_to_string(&launcher.args[1]).unwrap();
+        assert!(rcfile.contains(". ~/.bashrc"));
+        assert!(rcfile.ends_with("PS1='(dev) '\"$PS1\"\n"));
+    }
+
+    #[test]
+    fn test_zsh_launcher() {
+        let launcher = Launcher::new(Path::new("/usr/bin/zsh"), "(dev) ").unwrap();
+        let (_, zdotdir) = launcher.envs.iter().find(|(k, _)| k == "ZDOTDIR").unwrap();
+
+        let zshrc = std::fs::read_to_string(Path::new(zdotdir).join(".zshrc")).unwrap();
+        assert!(zshrc.ends_with("PROMPT='(dev) '\"$PROMPT\"\n"));
+    }
+
+    #[test]
+    fn test_other_launchers() {
+        let launcher = Launcher::new(Path::new("fish"), "(dev) ").unwrap();
+        assert!(
+            launcher.args[1]
+                .to_string_lossy()
+                .contains("echo -n '(dev) '")
+        );
+
+        let launcher = Launcher::new(Path::new("/bin/sh"), "(dev) ").unwrap();
+        assert!(launcher.args.is_empty());
+        assert!(launcher.envs[0].1.to_string_lossy().starts_with("(dev) "));
+    }
+}
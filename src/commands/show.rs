@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use crate::cli::KeyFilter;
+use crate::dotenv::Line;
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::redact::Redactor;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    filter: &KeyFilter,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let profile = match profile {
+        Some(profile) => profile,
+        None => manager.active_profile()?,
+    };
+
+    let doc = filter.apply(&manager.read_profile(&profile)?);
+
+    for line in doc.lines() {
+        match line {
+            Line::Entry(entry) if redactor.is_enabled() && redactor.is_secret(&entry.key) => {
+                writeln!(
+                    out,
+                    "{}={}",
+                    entry.key,
+                    redactor.redact(&entry.key, &entry.value)
+                )?;
+            }
+            line => writeln!(out, "{}", line.raw())?,
+        }
+    }
+
+    Ok(())
+}
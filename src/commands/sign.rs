@@ -0,0 +1,46 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profiles: Vec<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    for profile in profiles {
+        let profile = manager.profile_name(profile);
+        let signature = manager.sign_profile(&profile)?;
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Signed profile `{}` in {}",
+                profile,
+                signature.display()
+            ))
+        )?;
+
+        for file in manager.included_files(&manager.profile_path(&profile))? {
+            let signature = manager.sign_file(&file)?;
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!(
+                    "Signed `{}`, included by `{}`, in {}",
+                    file.display(),
+                    profile,
+                    signature.display()
+                ))
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::fs::FileSystem;
+use crate::presenter::{self, Format};
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    format: Format,
+) -> Result<()> {
+    presenter::status(out, &manager.status()?, format)
+}
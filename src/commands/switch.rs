@@ -1,41 +1,290 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use crate::error::{ErrorKind, Result};
+use super::remote::CONFLICT_SUFFIX;
+use crate::backup::BACKUPS_DIR;
+use crate::config::Color;
+use crate::error::{Error, ErrorKind, Result};
 use crate::fs::FileSystem;
-use crate::profile::ProfileManager;
+use crate::git;
+use crate::hooks::{self, Hook, HookContext};
+use crate::integrity::Integrity;
+use crate::metadata::format_age;
+use crate::operations::{Activation, ProfileOrder};
+use crate::patch::{self, Change};
+use crate::presenter::{self, Stream};
+use crate::profile::{ENV_FILE, LAYER_SEPARATOR, ProfileManager};
+use crate::prompt;
+use crate::template::TEMPLATES_DIR;
 
-pub fn run<F, S>(manager: &ProfileManager<F>, profile: S, force: bool) -> Result<()>
+pub fn run<F, S>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: S,
+    force: bool,
+    validate: bool,
+    strict: bool,
+) -> Result<()>
 where
     F: FileSystem,
     S: AsRef<str>,
 {
-    let profile = profile.as_ref();
-
     if !manager.is_initialized() {
         return Err(ErrorKind::Uninitialized.into());
     }
+    manager.ensure_unlocked()?;
+
+    if validate {
+        let schema = manager.load_schema()?.ok_or(ErrorKind::SchemaNotFound)?;
+        if !super::validate::check(manager, out, &schema, profile.as_ref())? {
+            return Err(ErrorKind::ValidationFailed { count: 1 }.into());
+        }
+    }
+
+    let tracked = tracked_files(manager);
+    if !tracked.is_empty() {
+        if strict {
+            return Err(ErrorKind::TrackedByGit { files: tracked }.into());
+        }
+        presenter::warn_error(ErrorKind::TrackedByGit { files: tracked });
+    }
 
-    let profile_path = manager.profile_path(&profile);
-    let env_path = Path::new(".env");
+    manager.ensure_signed(&profile, true)?;
+    for layer in manager.profile_layers(&profile) {
+        if manager.fs.path_exists(&manager.profile_path(&layer))
+            && manager.check_integrity(&layer)? == Integrity::Modified
+        {
+            presenter::warn_error(ErrorKind::ProfileModified { profile: layer });
+        }
+    }
+
+    let mut context = HookContext {
+        old_profile: manager.active_profile().ok(),
+        new_profile: Some(
+            manager
+                .profile_layers(&profile)
+                .join(&LAYER_SEPARATOR.to_string()),
+        ),
+        target: Some(PathBuf::from(ENV_FILE)),
+    };
+    hooks::run(manager, Hook::PreSwitch, &context)?;
+
+    let previous = env_values(manager);
+    let outcome = manager.switch_profile(profile, force)?;
+    if let Some(backup) = &outcome.backup {
+        writeln!(out, "Moved the previous .env to {}", backup.display())?;
+    }
+    if let Some(fs_type) = &outcome.unreliable_fs {
+        writeln!(
+            out,
+            "Symbolic links are unreliable on {} filesystems, so .env was copied instead",
+            fs_type
+        )?;
+    }
+    let message = match outcome.activation {
+        Activation::Linked => format!("Profile `{}` linked to .env", outcome.profile),
+        Activation::Hardlinked => format!("Profile `{}` hard linked to .env", outcome.profile),
+        Activation::Written | Activation::Environment => {
+            format!("Profile `{}` written to .env", outcome.profile)
+        }
+    };
+    writeln!(out, "{}", presenter::success(message))?;
+    if let (Some(old), Some(new)) = (previous, env_values(manager)) {
+        write_summary(out, &patch::changes(&old, &new))?;
+    }
 
-    if !profile_path.exists() {
-        return Err(ErrorKind::ProfileNotFound {
-            profile: profile.to_string(),
+    if let Some(max_age) = manager.config.audit.max_age {
+        for (_, stale) in &outcome.stale_keys {
+            presenter::warn(format!(
+                "`{}` has not changed in {} (maximum age is {}).",
+                stale.key,
+                format_age(stale.age),
+                format_age(max_age)
+            ));
         }
-        .into());
     }
 
-    if env_path.exists() {
-        if force || manager.fs.is_symlink(env_path) {
-            manager.fs.remove_file(&env_path)?;
-        } else {
-            return Err(ErrorKind::NonLinkedEnv.into());
+    manager.record_use(&outcome.profile);
+    manager.register_project();
+    context.new_profile = Some(outcome.profile);
+    if let Err(e) = hooks::run(manager, Hook::PostSwitch, &context) {
+        presenter::warn_error(e);
+    }
+    for layer in manager.profile_layers(context.new_profile.as_deref().unwrap_or_default()) {
+        if let Err(e) = hooks::run_profile_hooks(manager, &layer, &context) {
+            presenter::warn_error(e);
         }
     }
 
-    manager.fs.create_symlink(&profile_path, env_path)?;
+    Ok(())
+}
+
+/// Checks whether a profile, or any layer of a stack, does not exist.
+///
+/// An uninitialized directory is not reported as missing, so switching still
+/// fails with the underlying error.
+pub fn is_missing<F: FileSystem>(manager: &ProfileManager<F>, profile: &str) -> bool {
+    manager.is_initialized()
+        && manager
+            .profile_layers(profile)
+            .iter()
+            .any(|layer| !manager.fs.path_exists(&manager.profile_path(layer)))
+}
+
+/// Reads the variables of `.env`, if there is one.
+fn env_values<F: FileSystem>(manager: &ProfileManager<F>) -> Option<BTreeMap<String, String>> {
+    let path = Path::new(ENV_FILE);
+    if !manager.fs.path_exists(path) {
+        return None;
+    }
+
+    manager
+        .read_document(path)
+        .ok()
+        .map(|doc| super::diff::values(&doc))
+}
 
-    println!("Profile `{}` linked to .env", profile);
+/// Summarises which variables a switch added, removed and changed, naming
+/// the keys only so no value is shown.
+fn write_summary(out: &mut impl Write, changes: &[Change]) -> Result<()> {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for change in changes {
+        match change {
+            Change::Added { key, .. } => added.push(key.as_str()),
+            Change::Removed { key, .. } => removed.push(key.as_str()),
+            Change::Changed { key, .. } => changed.push(key.as_str()),
+        }
+    }
+
+    if !added.is_empty() {
+        let line = format!("  + {} added: {}", added.len(), added.join(", "));
+        writeln!(out, "{}", presenter::success(line))?;
+    }
+    if !removed.is_empty() {
+        let line = format!("  - {} removed: {}", removed.len(), removed.join(", "));
+        writeln!(out, "{}", presenter::failure(line))?;
+    }
+    if !changed.is_empty() {
+        let line = format!("  ~ {} changed: {}", changed.len(), changed.join(", "));
+        writeln!(
+            out,
+            "{}",
+            presenter::paint(line, Color::Yellow, Stream::Stdout)
+        )?;
+    }
 
     Ok(())
 }
+
+/// Lists `.env` and the profiles in the envoke directory that are tracked by
+/// git, and so shared with anyone who can read the repository.
+///
+/// Templates contain placeholders rather than values, so they are not listed.
+/// Backups and conflict copies hold values too, so they are.
+fn tracked_files<F: FileSystem>(manager: &ProfileManager<F>) -> Vec<PathBuf> {
+    let envoke_dir = &manager.config.envoke_dir;
+    let templates = envoke_dir.join(TEMPLATES_DIR);
+    let backups = envoke_dir.join(BACKUPS_DIR);
+    let conflict = CONFLICT_SUFFIX.trim_start_matches('.');
+
+    git::tracked(Path::new("."), &[Path::new(ENV_FILE), envoke_dir])
+        .into_iter()
+        .filter(|file| {
+            file == Path::new(ENV_FILE)
+                || file.starts_with(&backups)
+                || file.extension().is_some_and(|ext| ext == conflict)
+                || (file.extension().is_some_and(|ext| ext == "env")
+                    && !file.starts_with(&templates))
+        })
+        .collect()
+}
+
+/// Activates the default profile after a command failed because no profile
+/// is active.
+///
+/// The user is asked first unless `auto` is set. Nothing happens when no
+/// default profile is configured, the error has another cause, or the user
+/// cannot be asked.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+/// * `out` - Where to report the switch.
+/// * `error` - The error the command failed with.
+/// * `auto` - Activate without asking.
+///
+/// # Returns
+///
+/// `true` if the default profile was activated and the command can be retried.
+pub fn activate_default<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    error: &Error,
+    auto: bool,
+) -> Result<bool> {
+    let Some(default) = &manager.config.default_profile else {
+        return Ok(false);
+    };
+
+    if !matches!(error.kind, ErrorKind::NoActiveProfile) {
+        return Ok(false);
+    }
+
+    if !auto {
+        if !prompt::is_interactive() {
+            return Ok(false);
+        }
+
+        let question = format!(
+            "No profile is active. Switch to the default profile `{}`?",
+            default
+        );
+        if !prompt::confirm(&question)? {
+            return Ok(false);
+        }
+    }
+
+    run(manager, out, default, false, false, false)?;
+    Ok(true)
+}
+
+/// Lets the user choose a profile with a fuzzy-searchable picker, listing the
+/// most recently used profiles first.
+pub fn pick<F: FileSystem>(manager: &ProfileManager<F>) -> Result<String> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    if !prompt::is_interactive() {
+        return Err(ErrorKind::ProfileRequired.into());
+    }
+
+    let mut profiles: Vec<String> = manager
+        .iter_profiles()?
+        .sorted(ProfileOrder::Recent)
+        .map(|entry| entry.name().to_string())
+        .collect();
+    if profiles.is_empty() {
+        return Err(ErrorKind::NoProfiles.into());
+    }
+
+    let width = profiles.iter().map(|p| p.len()).max().unwrap_or(0);
+    let items = profiles
+        .iter()
+        .map(|profile| match manager.profile_description(profile) {
+            Ok(Some(description)) => format!("{:<width$}  {}", profile, description),
+            _ => profile.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let active = manager.active_profile().ok();
+    let default = profiles.iter().position(|p| Some(p) == active.as_ref());
+
+    match prompt::select("Switch to profile", &items, default)? {
+        Some(index) => Ok(profiles.swap_remove(index)),
+        None => Err(ErrorKind::ProfileRequired.into()),
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::dotenv::{Document, Line};
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::{ProfileManager, Scope};
+use crate::redact::{MASK, Redactor};
+
+fn scope(global: bool) -> Scope {
+    if global {
+        Scope::Global
+    } else {
+        Scope::Project
+    }
+}
+
+pub fn list<F: FileSystem>(manager: &ProfileManager<F>, out: &mut impl Write) -> Result<()> {
+    let templates = manager.templates()?;
+    if templates.is_empty() {
+        writeln!(
+            out,
+            "No templates found. Run `envoke template add <template>` to create one."
+        )?;
+        return Ok(());
+    }
+
+    let width = templates
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, scope) in templates {
+        writeln!(out, "{:<width$}  ({})", name, scope.as_str(), width = width)?;
+    }
+
+    Ok(())
+}
+
+pub fn add<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    template: String,
+    from: Option<String>,
+    file: Option<PathBuf>,
+    global: bool,
+) -> Result<()> {
+    let scope = scope(global);
+    if scope == Scope::Project && !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let path = manager.template_path(&template, scope)?;
+    if manager.fs.path_exists(&path) {
+        return Err(ErrorKind::TemplateExists {
+            template: manager.profile_name(&template),
+        }
+        .into());
+    }
+
+    // Templates are meant to be shared, so secrets are left out of copies.
+    let contents = match (from, file) {
+        (Some(profile), _) => {
+            let redactor = manager.config.secrets.redactor(true);
+            blank_secrets(manager.read_profile(&profile)?, &redactor).to_string()
+        }
+        (None, Some(file)) => manager.read_file(&file)?,
+        (None, None) => String::new(),
+    };
+
+    manager.fs.create_dir(&manager.template_dir(scope)?)?;
+    manager.write_file(&path, &contents)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Template {} created at {}",
+            manager.profile_name(&template),
+            path.to_string_lossy()
+        ))
+    )?;
+
+    Ok(())
+}
+
+pub fn remove<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    template: String,
+    global: bool,
+) -> Result<()> {
+    let path = manager.template_path(&template, scope(global))?;
+    if !manager.fs.path_exists(&path) {
+        return Err(ErrorKind::TemplateNotFound {
+            template: manager.profile_name(&template),
+        }
+        .into());
+    }
+
+    manager.fs.remove_file(&path)?;
+
+    writeln!(
+        out,
+        "{}",
+        presenter::success(format!(
+            "Template {} removed.",
+            manager.profile_name(&template)
+        ))
+    )?;
+
+    Ok(())
+}
+
+pub fn show<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    template: String,
+    redactor: &Redactor,
+) -> Result<()> {
+    let path = manager.find_template(&template)?;
+    let contents = manager.read_file(&path)?;
+
+    // A template that is not a valid profile has no values to mask.
+    let doc = match Document::parse(&contents) {
+        Ok(doc) if redactor.is_enabled() => doc,
+        _ => {
+            write!(out, "{}", contents)?;
+            return Ok(());
+        }
+    };
+
+    for line in doc.lines() {
+        match line {
+            Line::Entry(entry) if redactor.is_secret(&entry.key) => {
+                writeln!(out, "{}={}", entry.key, MASK)?;
+            }
+            line => writeln!(out, "{}", line.raw())?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Empties the values of secret keys, leaving every other line as written.
+fn blank_secrets(doc: Document, redactor: &Redactor) -> Document {
+    let lines = doc
+        .into_lines()
+        .into_iter()
+        .map(|line| match line {
+            Line::Entry(entry) if redactor.is_secret(&entry.key) => {
+                Line::Entry(entry.with_value(""))
+            }
+            line => line,
+        })
+        .collect();
+
+    Document::from_lines(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_show_masks_secrets() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+        manager
+            .fs
+            .create_dir(&manager.template_dir(Scope::Project).unwrap())
+            .unwrap();
+        let path = manager.template_path("web", Scope::Project).unwrap();
+        manager
+            .write_file(&path, "# Web\nPORT=8080\nAPI_KEY=abc\n")
+            .unwrap();
+
+        let mut out = Vec::new();
+        let redactor = manager.config.secrets.redactor(true);
+        show(&manager, &mut out, "web".into(), &redactor).unwrap();
+        assert_eq!(
+            "# Web\nPORT=8080\nAPI_KEY=********\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = Vec::new();
+        let redactor = manager.config.secrets.redactor(false);
+        show(&manager, &mut out, "web".into(), &redactor).unwrap();
+        assert_eq!(
+            "# Web\nPORT=8080\nAPI_KEY=abc\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
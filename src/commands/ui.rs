@@ -0,0 +1,561 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line as TextLine;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::oplog;
+use crate::patch::{Change, changes};
+use crate::profile::ProfileManager;
+use crate::prompt;
+use crate::redact::Redactor;
+
+const HELP: &str = "↑↓ move  tab focus  enter switch  d diff  r reveal  e edit  q quit";
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    redactor: &Redactor,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    if !prompt::is_interactive() {
+        return Err(ErrorKind::TerminalRequired.into());
+    }
+
+    let mut app = App::new(manager, redactor)?;
+
+    let mut terminal = ratatui::try_init().map_err(terminal_error)?;
+    let result = app.run(&mut terminal);
+    ratatui::try_restore().map_err(terminal_error)?;
+    result?;
+
+    // Switching prints a summary, so it happens once the screen is restored.
+    match app.switch_to {
+        Some(profile) => super::switch::run(manager, out, profile, false, false, false),
+        None => Ok(()),
+    }
+}
+
+/// Which pane receives movement keys.
+#[derive(PartialEq, Eq)]
+enum Focus {
+    Profiles,
+    Variables,
+}
+
+/// What the variables pane shows.
+#[derive(PartialEq, Eq)]
+enum View {
+    Variables,
+    Diff,
+}
+
+/// A value being edited in the footer.
+struct Edit {
+    key: String,
+    input: String,
+}
+
+struct App<'a, F: FileSystem> {
+    manager: &'a ProfileManager<F>,
+    redactor: &'a Redactor,
+    profiles: Vec<String>,
+    active: Option<String>,
+    profile_state: ListState,
+    variables: Vec<(String, String)>,
+    variable_state: TableState,
+    focus: Focus,
+    view: View,
+    reveal: bool,
+    edit: Option<Edit>,
+    status: Option<String>,
+    switch_to: Option<String>,
+    quit: bool,
+}
+
+impl<'a, F: FileSystem> App<'a, F> {
+    fn new(manager: &'a ProfileManager<F>, redactor: &'a Redactor) -> Result<Self> {
+        let mut profiles = manager.profiles()?;
+        profiles.sort();
+
+        let active = manager.active_profile().ok();
+        let selected = profiles
+            .iter()
+            .position(|p| Some(p) == active.as_ref())
+            .or((!profiles.is_empty()).then_some(0));
+
+        let mut app = App {
+            manager,
+            redactor,
+            profiles,
+            active,
+            profile_state: ListState::default().with_selected(selected),
+            variables: Vec::new(),
+            variable_state: TableState::default(),
+            focus: Focus::Profiles,
+            view: View::Variables,
+            reveal: false,
+            edit: None,
+            status: None,
+            switch_to: None,
+            quit: false,
+        };
+        app.load()?;
+
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(terminal_error)?;
+
+            if let Event::Key(key) = event::read().map_err(terminal_error)?
+                && key.kind == KeyEventKind::Press
+            {
+                // Problems are reported in the footer rather than closing the interface.
+                self.status = None;
+                if let Err(e) = self.handle(key) {
+                    self.status = Some(e.to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the highlighted profile.
+    fn selected(&self) -> Option<&str> {
+        self.profile_state
+            .selected()
+            .and_then(|i| self.profiles.get(i))
+            .map(String::as_str)
+    }
+
+    /// Loads the variables of the highlighted profile, or its diff against the
+    /// active profile.
+    fn load(&mut self) -> Result<()> {
+        self.variables.clear();
+        self.variable_state.select(None);
+
+        let Some(profile) = self.selected().map(str::to_string) else {
+            return Ok(());
+        };
+
+        let doc = self.manager.resolve_profile(&profile)?;
+        let variables = doc.entries().map(|e| (e.key.clone(), e.value.clone()));
+
+        self.variables = match self.view {
+            View::Variables => variables.collect(),
+            View::Diff => {
+                let active = self.active.as_ref().ok_or(ErrorKind::NoActiveProfile)?;
+                let base = self
+                    .manager
+                    .resolve_stack(active)?
+                    .entries()
+                    .map(|e| (e.key.clone(), e.value.clone()))
+                    .collect();
+                diff(&base, &variables.collect())
+            }
+        };
+
+        if !self.variables.is_empty() {
+            self.variable_state.select(Some(0));
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Result<()> {
+        if self.edit.is_some() {
+            return self.handle_edit(key);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::Profiles => Focus::Variables,
+                    Focus::Variables => Focus::Profiles,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.step(1)?,
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1)?,
+            KeyCode::Enter => {
+                if let Some(profile) = self.selected() {
+                    self.switch_to = Some(profile.to_string());
+                    self.quit = true;
+                }
+            }
+            KeyCode::Char('d') => {
+                self.view = match self.view {
+                    View::Variables => View::Diff,
+                    View::Diff => View::Variables,
+                };
+                if let Err(e) = self.load() {
+                    self.view = View::Variables;
+                    self.load()?;
+                    return Err(e);
+                }
+            }
+            KeyCode::Char('r') => self.reveal = !self.reveal,
+            KeyCode::Char('e') => self.start_edit(),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Moves the highlight in the focused pane.
+    fn step(&mut self, delta: isize) -> Result<()> {
+        let (selected, len) = match self.focus {
+            Focus::Profiles => (self.profile_state.selected(), self.profiles.len()),
+            Focus::Variables => (self.variable_state.selected(), self.variables.len()),
+        };
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        let current = selected.unwrap_or(0) as isize;
+        let next = Some((current + delta).clamp(0, len as isize - 1) as usize);
+
+        match self.focus {
+            Focus::Profiles => {
+                if next != selected {
+                    self.profile_state.select(next);
+                    self.load()?;
+                }
+            }
+            Focus::Variables => self.variable_state.select(next),
+        }
+
+        Ok(())
+    }
+
+    fn start_edit(&mut self) {
+        if self.focus != Focus::Variables || self.view != View::Variables {
+            self.status = Some("Select a variable to edit it.".into());
+            return;
+        }
+
+        let Some((key, value)) = self
+            .variable_state
+            .selected()
+            .and_then(|i| self.variables.get(i))
+        else {
+            return;
+        };
+
+        // A masked value starts empty rather than appearing on screen.
+        let input = if self.is_masked(key) {
+            String::new()
+        } else {
+            value.clone()
+        };
+
+        self.edit = Some(Edit {
+            key: key.clone(),
+            input,
+        });
+    }
+
+    fn handle_edit(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(edit) = self.edit.as_mut() else {
+            return Ok(());
+        };
+
+        match key.code {
+            KeyCode::Esc => self.edit = None,
+            KeyCode::Backspace => {
+                edit.input.pop();
+            }
+            KeyCode::Char(c) => edit.input.push(c),
+            KeyCode::Enter => {
+                let edit = self.edit.take().expect("an edit is in progress");
+                self.save(&edit.key, edit.input)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Writes a new value into the highlighted profile.
+    fn save(&mut self, key: &str, value: String) -> Result<()> {
+        let Some(profile) = self.selected().map(str::to_string) else {
+            return Ok(());
+        };
+
+        self.manager.ensure_unlocked()?;
+        let mut doc = self.manager.read_profile(&profile)?;
+        if !doc.set(key, value) {
+            self.status = Some(format!("`{}` is defined in an included file.", key));
+            return Ok(());
+        }
+
+        self.manager.save_profile(&profile, doc.to_string())?;
+        oplog::record(self.manager, "ui edit", None);
+
+        let selected = self.variable_state.selected();
+        self.load()?;
+        self.variable_state.select(selected);
+        self.status = Some(format!("Updated `{}` in `{}`.", key, profile));
+
+        Ok(())
+    }
+
+    fn is_masked(&self, key: &str) -> bool {
+        !self.reveal && self.redactor.is_enabled() && self.redactor.is_secret(key)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Fill(1)]).areas(main);
+
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+        let border = |focused: bool| match focused {
+            true => Style::default().fg(Color::Cyan),
+            false => Style::default(),
+        };
+
+        let active_layers = self
+            .active
+            .as_ref()
+            .map(|active| self.manager.profile_layers(active))
+            .unwrap_or_default();
+        let items = self.profiles.iter().map(|profile| {
+            let marker = if active_layers.contains(profile) {
+                "* "
+            } else {
+                "  "
+            };
+            ListItem::new(format!("{}{}", marker, profile))
+        });
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(" Profiles ")
+                    .border_style(border(self.focus == Focus::Profiles)),
+            )
+            .highlight_style(highlight);
+        frame.render_stateful_widget(list, left, &mut self.profile_state);
+
+        let title = match (&self.view, &self.active) {
+            (View::Diff, Some(active)) => format!(" Diff against {} ", active),
+            _ => " Variables ".to_string(),
+        };
+        let rows = self.variables.iter().map(|(key, value)| {
+            let key = key.as_str();
+            let value = match self.view {
+                View::Variables if self.is_masked(key) => self.redactor.redact(key, value),
+                _ => value.as_str(),
+            };
+            Row::new([key, value])
+        });
+        let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Fill(1)])
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .border_style(border(self.focus == Focus::Variables)),
+            )
+            .row_highlight_style(highlight);
+        frame.render_stateful_widget(table, right, &mut self.variable_state);
+
+        let text = match (&self.edit, &self.status) {
+            // Secrets being typed are hidden like a password prompt.
+            (Some(edit), _) if self.is_masked(&edit.key) => {
+                format!("{} = {}▏", edit.key, "*".repeat(edit.input.chars().count()))
+            }
+            (Some(edit), _) => format!("{} = {}▏", edit.key, edit.input),
+            (None, Some(status)) => status.clone(),
+            (None, None) => HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(TextLine::from(text)), footer);
+    }
+}
+
+/// Describes how the variables of `other` differ from those of `base`.
+///
+/// Keys only in `other` are marked `+`, keys only in `base` are marked `-`,
+/// and keys with different values are marked `~`. Values are not shown, so
+/// secrets never appear in a diff.
+fn diff(
+    base: &BTreeMap<String, String>,
+    other: &BTreeMap<String, String>,
+) -> Vec<(String, String)> {
+    changes(base, other)
+        .into_iter()
+        .map(|change| {
+            let label = match change {
+                Change::Added { .. } => "+ added",
+                Change::Removed { .. } => "- removed",
+                Change::Changed { .. } => "~ changed",
+            };
+            (change.key().to_string(), label.to_string())
+        })
+        .collect()
+}
+
+fn terminal_error(source: io::Error) -> Error {
+    Error::new(ErrorKind::Terminal { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use std::path::PathBuf;
+
+    fn manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "API_TOKEN=abc\nPORT=8080\n");
+        fs.add_file(".envoke/prod.env", "PORT=80\nHOST=prod\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        manager.switch_profile("dev", false).unwrap();
+        manager
+    }
+
+    fn press<F: FileSystem>(app: &mut App<F>, codes: &[KeyCode]) {
+        for code in codes {
+            app.handle(KeyEvent::from(*code)).unwrap();
+        }
+    }
+
+    fn screen<F: FileSystem>(app: &mut App<F>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 6)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_diff() {
+        let base = BTreeMap::from([
+            ("KEPT".to_string(), "1".to_string()),
+            ("GONE".to_string(), "1".to_string()),
+            ("TOKEN".to_string(), "old".to_string()),
+        ]);
+        let other = BTreeMap::from([
+            ("KEPT".to_string(), "1".to_string()),
+            ("NEW".to_string(), "1".to_string()),
+            ("TOKEN".to_string(), "new".to_string()),
+        ]);
+
+        let rows = diff(&base, &other);
+        assert_eq!(
+            vec![
+                ("GONE".to_string(), "- removed".to_string()),
+                ("NEW".to_string(), "+ added".to_string()),
+                ("TOKEN".to_string(), "~ changed".to_string()),
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn test_navigate_and_switch() {
+        let manager = manager();
+        let redactor = manager.config.secrets.redactor(true);
+        let mut app = App::new(&manager, &redactor).unwrap();
+        assert_eq!(Some("dev"), app.selected());
+
+        press(&mut app, &[KeyCode::Down]);
+        assert_eq!(Some("prod"), app.selected());
+        assert_eq!("HOST", app.variables[1].0);
+
+        press(&mut app, &[KeyCode::Char('d')]);
+        assert_eq!(
+            vec![
+                ("API_TOKEN".to_string(), "- removed".to_string()),
+                ("HOST".to_string(), "+ added".to_string()),
+                ("PORT".to_string(), "~ changed".to_string()),
+            ],
+            app.variables
+        );
+
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(Some("prod".to_string()), app.switch_to);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_secrets_masked() {
+        let manager = manager();
+        let redactor = manager.config.secrets.redactor(true);
+        let mut app = App::new(&manager, &redactor).unwrap();
+
+        let output = screen(&mut app);
+        assert!(!output.contains("abc"));
+        assert!(output.contains("8080"));
+
+        press(&mut app, &[KeyCode::Char('r')]);
+        assert!(screen(&mut app).contains("abc"));
+        press(&mut app, &[KeyCode::Char('r')]);
+
+        // A secret being typed never appears in the footer.
+        press(
+            &mut app,
+            &[
+                KeyCode::Tab,
+                KeyCode::Char('e'),
+                KeyCode::Char('x'),
+                KeyCode::Char('y'),
+            ],
+        );
+        let output = screen(&mut app);
+        assert!(output.contains("API_TOKEN = **▏"));
+        assert!(!output.contains("xy"));
+    }
+
+    #[test]
+    fn test_edit() {
+        let manager = manager();
+        let redactor = manager.config.secrets.redactor(true);
+        let mut app = App::new(&manager, &redactor).unwrap();
+
+        press(&mut app, &[KeyCode::Char('e')]);
+        assert!(app.edit.is_none());
+        assert!(app.status.is_some());
+
+        press(
+            &mut app,
+            &[
+                KeyCode::Tab,
+                KeyCode::Down,
+                KeyCode::Char('e'),
+                KeyCode::Backspace,
+                KeyCode::Char('1'),
+            ],
+        );
+        assert!(screen(&mut app).contains("PORT = 8081▏"));
+
+        press(&mut app, &[KeyCode::Enter]);
+        assert!(app.edit.is_none());
+        let doc = manager.read_profile("dev").unwrap();
+        assert_eq!(Some("8081"), doc.get("PORT"));
+        assert_eq!(("PORT".to_string(), "8081".to_string()), app.variables[1]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F, S>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: S,
+    global: bool,
+    copy: bool,
+    force: bool,
+) -> Result<()>
+where
+    F: FileSystem,
+    S: AsRef<str>,
+{
+    let profile = manager.profile_name(profile);
+
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+    manager.ensure_unlocked()?;
+
+    if global {
+        import(manager, out, &profile, copy)?;
+    }
+
+    super::switch::run(manager, out, &profile, force, false, false)
+}
+
+/// Links or copies a global profile into the project.
+fn import<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: &str,
+    copy: bool,
+) -> Result<()> {
+    let source = manager.global_profile_path(profile)?;
+    if !manager.fs.path_exists(&source) {
+        return Err(ErrorKind::GlobalProfileNotFound {
+            profile: profile.to_string(),
+            dir: source.parent().unwrap_or(&source).to_path_buf(),
+        }
+        .into());
+    }
+
+    let path = manager.profile_path(profile);
+    let already_linked = manager.fs.is_symlink(&path) && manager.fs.read_link(&path)? == source;
+    if already_linked && !copy {
+        return Ok(());
+    }
+
+    if manager.fs.path_exists(&path) || manager.fs.is_symlink(&path) {
+        return Err(ErrorKind::FileExists { file: path }.into());
+    }
+
+    if copy {
+        manager.fs.copy_file(&source, &path)?;
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Global profile `{}` copied to {}",
+                profile,
+                path.to_string_lossy()
+            ))
+        )?;
+    } else {
+        manager.fs.create_symlink(&source, &path)?;
+        writeln!(
+            out,
+            "{}",
+            presenter::success(format!(
+                "Global profile `{}` linked to {}",
+                profile,
+                path.to_string_lossy()
+            ))
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+use crate::schema::{Schema, Violation};
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let schema = manager.load_schema()?.ok_or(ErrorKind::SchemaNotFound)?;
+
+    let mut profiles = match profile {
+        Some(profile) => vec![manager.profile_name(profile)],
+        None => manager.profiles()?,
+    };
+    profiles.sort();
+
+    let mut failed = 0;
+    for profile in &profiles {
+        if check(manager, out, &schema, profile)? {
+            writeln!(out, "{}", presenter::success(format!("{}: OK", profile)))?;
+        } else {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::ValidationFailed { count: failed }.into());
+    }
+
+    Ok(())
+}
+
+/// Validates a profile or layered stack of profiles, printing any violations.
+///
+/// Returns `true` if the profile satisfies the schema.
+pub fn check<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    schema: &Schema,
+    profile: &str,
+) -> Result<bool> {
+    let doc = manager.resolve_stack(profile)?;
+    let mut violations = schema.validate(&doc, &manager.config.secrets.redactor(true));
+    locate(manager, profile, &mut violations);
+
+    report(out, &manager.profile_name(profile), &violations)
+}
+
+/// Points each violation at the assignment that sets the final value of its
+/// key, which may be in another layer or an included file.
+pub fn locate<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profile: &str,
+    violations: &mut [Violation],
+) {
+    for violation in violations {
+        violation.location = manager
+            .key_origins(profile, false, &violation.key)
+            .ok()
+            .and_then(|mut origins| origins.pop())
+            .map(|origin| (origin.file, origin.line));
+    }
+}
+
+/// Prints the violations of a profile, if any.
+///
+/// Returns `true` if there are no violations.
+pub fn report(out: &mut impl Write, profile: &str, violations: &[Violation]) -> Result<bool> {
+    if violations.is_empty() {
+        return Ok(true);
+    }
+
+    writeln!(out, "{}", presenter::failure(format!("{}:", profile)))?;
+    for violation in violations {
+        writeln!(out, "  {}", violation)?;
+    }
+
+    Ok(false)
+}
@@ -0,0 +1,69 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    group: Option<String>,
+    example: &Path,
+    strict: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    if !manager.fs.path_exists(example) {
+        return Err(ErrorKind::ExampleNotFound {
+            file: example.to_path_buf(),
+        }
+        .into());
+    }
+
+    let expected = manager.read_document(example)?;
+    let expected = expected.key_set();
+
+    let mut profiles = match (profile, group) {
+        (Some(profile), _) => vec![manager.profile_name(profile)],
+        (None, Some(group)) => manager.group_profiles(group)?,
+        (None, None) => manager.profiles()?,
+    };
+    profiles.sort();
+
+    let mut failed = 0;
+    for profile in &profiles {
+        let doc = manager.read_profile(profile)?;
+        let actual = doc.key_set();
+
+        let missing: Vec<_> = expected.difference(&actual).copied().collect();
+        let extra: Vec<_> = actual.difference(&expected).copied().collect();
+
+        if missing.is_empty() && extra.is_empty() {
+            writeln!(out, "{}", presenter::success(format!("{}: OK", profile)))?;
+            continue;
+        }
+
+        writeln!(out, "{}", presenter::failure(format!("{}:", profile)))?;
+        if !missing.is_empty() {
+            writeln!(out, "  missing: {}", missing.join(", "))?;
+        }
+        if !extra.is_empty() {
+            writeln!(out, "  extra:   {}", extra.join(", "))?;
+        }
+
+        if !missing.is_empty() || (strict && !extra.is_empty()) {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::VerificationFailed { count: failed }.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::integrity::Integrity;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+    accept: bool,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut profiles = match profile {
+        Some(profile) => {
+            let profile = manager.profile_name(profile);
+            if !manager.fs.path_exists(&manager.profile_path(&profile)) {
+                return Err(ErrorKind::ProfileNotFound { profile }.into());
+            }
+            vec![profile]
+        }
+        None => manager.profiles()?,
+    };
+    profiles.sort();
+
+    let mut failed = 0;
+    for profile in &profiles {
+        let integrity = manager.check_integrity(profile)?;
+        if integrity == Integrity::Intact {
+            writeln!(out, "{}", presenter::success(format!("{}: OK", profile)))?;
+            continue;
+        }
+
+        if accept {
+            let contents = manager.read_bytes(&manager.profile_path(profile))?;
+            manager.record_checksum(profile, contents)?;
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!("{}: accepted", profile))
+            )?;
+            continue;
+        }
+
+        match integrity {
+            Integrity::Modified => {
+                writeln!(
+                    out,
+                    "{}",
+                    presenter::failure(format!("{}: changed outside envoke", profile))
+                )?;
+                failed += 1;
+            }
+            _ => writeln!(out, "{}: no checksum recorded", profile)?,
+        }
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::IntegrityFailed { count: failed }.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::io::Write;
+
+use crate::error::{ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::presenter;
+use crate::profile::ProfileManager;
+
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    profile: Option<String>,
+) -> Result<()> {
+    if !manager.is_initialized() {
+        return Err(ErrorKind::Uninitialized.into());
+    }
+
+    let mut profiles = match profile {
+        Some(profile) => {
+            let profile = manager.profile_name(profile);
+            if !manager.fs.path_exists(&manager.profile_path(&profile)) {
+                return Err(ErrorKind::ProfileNotFound { profile }.into());
+            }
+            vec![profile]
+        }
+        None => manager.profiles()?,
+    };
+    profiles.sort();
+
+    let mut failed = 0;
+    for profile in &profiles {
+        let verified = manager
+            .verify_signature(profile)
+            .and_then(|signer| manager.verify_includes(profile).map(|_| signer));
+        match verified {
+            Ok(signer) if signer.is_empty() => {
+                writeln!(out, "{}", presenter::success(format!("{}: OK", profile)))?;
+            }
+            Ok(signer) => writeln!(
+                out,
+                "{}",
+                presenter::success(format!("{}: signed by {}", profile, signer))
+            )?,
+            Err(e) => match e.kind {
+                ErrorKind::SignatureMissing { .. } => {
+                    writeln!(
+                        out,
+                        "{}",
+                        presenter::failure(format!("{}: not signed", profile))
+                    )?;
+                    failed += 1;
+                }
+                ErrorKind::IncludeUnsigned { file, .. } => {
+                    writeln!(
+                        out,
+                        "{}",
+                        presenter::failure(format!(
+                            "{}: included `{}` not signed",
+                            profile,
+                            file.display()
+                        ))
+                    )?;
+                    failed += 1;
+                }
+                ErrorKind::SignatureInvalid {
+                    profile: file,
+                    reason,
+                } if &file != profile => {
+                    writeln!(
+                        out,
+                        "{}",
+                        presenter::failure(format!(
+                            "{}: invalid signature of included `{}` ({})",
+                            profile, file, reason
+                        ))
+                    )?;
+                    failed += 1;
+                }
+                ErrorKind::SignatureInvalid { reason, .. } => {
+                    writeln!(
+                        out,
+                        "{}",
+                        presenter::failure(format!("{}: invalid signature ({})", profile, reason))
+                    )?;
+                    failed += 1;
+                }
+                _ => return Err(e),
+            },
+        }
+    }
+
+    if failed > 0 {
+        return Err(ErrorKind::SignaturesFailed { count: failed }.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::cli::ActivateShell;
+use crate::commands::export;
+use crate::dotenv::Document;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::operations::{Activation, ActiveProfile};
+use crate::presenter;
+use crate::profile::{ENV_FILE, ProfileManager};
+
+/// How long to wait for further events before acting on a change, so that a
+/// burst of events from a single save is handled once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub fn run<F: FileSystem>(manager: &ProfileManager<F>, out: &mut impl Write) -> Result<()> {
+    let mut active = manager.current_profile()?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(watch_error)?;
+    watcher
+        .watch(&manager.config.envoke_dir, RecursiveMode::Recursive)
+        .map_err(watch_error)?;
+    watcher
+        .watch(Path::new("."), RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+    if let Ok(global_dir) = manager.global_dir()
+        && manager.fs.path_exists(global_dir)
+    {
+        watcher
+            .watch(global_dir, RecursiveMode::Recursive)
+            .map_err(watch_error)?;
+    }
+
+    writeln!(
+        out,
+        "Watching `{}` for changes - press Ctrl+C to stop.",
+        active.profile
+    )?;
+    if let Err(e) = sync(manager, out, &mut active) {
+        presenter::warn_error(e);
+    }
+
+    while let Ok(event) = rx.recv() {
+        event.map_err(watch_error)?;
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+        if let Err(e) = sync(manager, out, &mut active) {
+            presenter::warn_error(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Brings `.env` and the configured exports up to date with the active profile.
+///
+/// A `.env` that was deleted is restored, a generated `.env` is regenerated
+/// and every export is rewritten. Files are only written when their contents
+/// change, so the watcher does not react to its own writes indefinitely.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+/// * `out` - Where to report what was updated.
+/// * `active` - The profile being watched, updated if another was activated.
+///
+/// # Returns
+///
+/// An `Error` if the profile cannot be resolved or a file cannot be written.
+pub fn sync<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    out: &mut impl Write,
+    active: &mut ActiveProfile,
+) -> Result<()> {
+    match manager.current_profile() {
+        Ok(current) => *active = current,
+        Err(e) if matches!(e.kind, ErrorKind::NoActiveProfile) => {
+            let outcome = manager.switch_profile(&active.profile, false)?;
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!("Restored .env from `{}`", outcome.profile))
+            )?;
+            active.activation = outcome.activation;
+            active.layers = outcome.layers;
+        }
+        Err(e) => return Err(e),
+    }
+
+    let doc = manager.resolve_env(&active.profile, true)?;
+
+    if active.activation == Activation::Written {
+        let contents = manager.generated_env(&active.profile, &doc);
+        if update(manager, Path::new(ENV_FILE), contents.as_bytes())? {
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!("Regenerated .env from `{}`", active.profile))
+            )?;
+        }
+    }
+
+    // Exports hold the same plaintext values as `envoke export`.
+    let exported = match manager.config.watch.exports.is_empty() {
+        true => Document::default(),
+        false => manager.load_env(&active.profile, true)?,
+    };
+    for target in &manager.config.watch.exports {
+        let mut contents = Vec::new();
+        export::render(
+            &mut contents,
+            &exported,
+            target.format,
+            ActivateShell::Posix,
+        )?;
+        if update(manager, &target.path, &contents)? {
+            writeln!(
+                out,
+                "{}",
+                presenter::success(format!(
+                    "Exported `{}` to {}",
+                    active.profile,
+                    target.path.display()
+                ))
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a file unless it already holds the given contents.
+///
+/// # Returns
+///
+/// `true` if the file was written.
+fn update<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    path: &Path,
+    contents: &[u8],
+) -> Result<bool> {
+    match manager.read_file(path) {
+        Ok(existing) if existing.as_bytes() == contents => Ok(false),
+        _ => manager.write_file(path, contents).map(|_| true),
+    }
+}
+
+fn watch_error(e: notify::Error) -> Error {
+    ErrorKind::Watch {
+        reason: e.to_string(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use age::secrecy::ExposeSecret;
+
+    use crate::cli::ExportFormat;
+    use crate::config::{Config, WatchExport};
+    use crate::fs::MockFileSystem;
+
+    fn manager(fs: MockFileSystem) -> ProfileManager<MockFileSystem> {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.watch.exports.push(WatchExport {
+            format: ExportFormat::Shell,
+            path: PathBuf::from("env.sh"),
+        });
+        ProfileManager::new(config, fs)
+    }
+
+    #[test]
+    fn test_sync_restores_env_and_exports() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\n");
+
+        let manager = manager(fs);
+        manager.switch_profile("dev", false).unwrap();
+        let mut active = manager.current_profile().unwrap();
+
+        manager.fs.remove_file(Path::new(ENV_FILE)).unwrap();
+        let mut out = Vec::new();
+        sync(&manager, &mut out, &mut active).unwrap();
+
+        assert_eq!("dev", manager.active_profile().unwrap());
+        assert_eq!(
+            "export PORT='8080'\n",
+            manager.read_file(Path::new("env.sh")).unwrap()
+        );
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Restored .env from `dev`"));
+        assert!(output.contains("Exported `dev` to env.sh"));
+
+        // Nothing changed, so nothing is written.
+        let mut out = Vec::new();
+        sync(&manager, &mut out, &mut active).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_sync_regenerates_written_env() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/dev.env", "PORT=8080\n");
+        fs.add_file(".envoke/ci.env", "CI=true\n");
+
+        let manager = manager(fs);
+        manager.switch_profile("dev+ci", false).unwrap();
+        let mut active = manager.current_profile().unwrap();
+        assert_eq!(Activation::Written, active.activation);
+
+        manager
+            .write_file(&manager.profile_path("ci"), "CI=false\n")
+            .unwrap();
+        let mut out = Vec::new();
+        sync(&manager, &mut out, &mut active).unwrap();
+
+        let env = manager.read_file(Path::new(ENV_FILE)).unwrap();
+        assert!(env.contains("CI=false"));
+        assert!(env.contains("PORT=8080"));
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Regenerated .env from `dev+ci`"));
+    }
+
+    #[test]
+    fn test_sync_exports_plaintext() {
+        let identity = age::x25519::Identity::generate();
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_dir("global");
+        fs.add_file(
+            "global/identity.txt",
+            format!("{}\n", identity.to_string().expose_secret()),
+        );
+        fs.add_file(
+            ".envoke/schema.toml",
+            "[keys.HOST]\ndefault = \"localhost\"\n",
+        );
+
+        let mut manager = manager(fs);
+        manager.config.global_dir = Some(PathBuf::from("global"));
+        let encrypted = crate::encryption::encrypt("hunter2", &[identity.to_public()]).unwrap();
+        manager
+            .fs
+            .add_file(".envoke/dev.env", format!("DB_PASSWORD={}\n", encrypted));
+        manager.switch_profile("dev", false).unwrap();
+        let mut active = manager.current_profile().unwrap();
+
+        let mut out = Vec::new();
+        sync(&manager, &mut out, &mut active).unwrap();
+        assert_eq!(
+            "export DB_PASSWORD='hunter2'\nexport HOST='localhost'\n",
+            manager.read_file(Path::new("env.sh")).unwrap()
+        );
+    }
+}
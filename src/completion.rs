@@ -0,0 +1,197 @@
+//! Dynamic shell completion module for the envoke CLI tool.
+//!
+//! The completion scripts printed by `envoke completions` call back into
+//! envoke whenever TAB is pressed. The completers in this module then read the
+//! `.envoke` directory of the current working directory, so suggestions always
+//! reflect the profiles and keys that exist at that moment. Any failure simply
+//! produces no suggestions.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+use clap_complete::CompletionCandidate;
+
+use crate::config::Config;
+use crate::fs::EnvokeFileSystem;
+use crate::profile::{LAYER_SEPARATOR, ProfileManager};
+
+/// Completes a profile name, or the last layer of a stack such as `dev+`.
+///
+/// # Arguments
+///
+/// * `current` - The text typed so far.
+///
+/// # Returns
+///
+/// A candidate for each matching profile, described by its
+/// `# envoke:description` directive where present.
+pub fn profiles(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(manager) = manager() else {
+        return Vec::new();
+    };
+    let Ok(mut profiles) = manager.profiles() else {
+        return Vec::new();
+    };
+    profiles.sort();
+
+    let current = current.to_string_lossy();
+    let (stack, partial) = match current.rfind(LAYER_SEPARATOR) {
+        Some(i) => current.split_at(i + 1),
+        None => ("", current.as_ref()),
+    };
+
+    profiles
+        .into_iter()
+        .filter(|profile| profile.starts_with(partial))
+        .map(|profile| {
+            let description = manager.profile_description(&profile).ok().flatten();
+            CompletionCandidate::new(format!("{}{}", stack, profile))
+                .help(description.map(Into::into))
+        })
+        .collect()
+}
+
+/// Completes a key of the profile named by `--profile`, or of the active
+/// profile when none is given.
+///
+/// # Arguments
+///
+/// * `current` - The text typed so far.
+///
+/// # Returns
+///
+/// A candidate for each matching key.
+pub fn keys(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(manager) = manager() else {
+        return Vec::new();
+    };
+
+    let profile = match profile_arg(env::args_os()) {
+        Some(profile) => profile,
+        None => match manager.active_profile() {
+            Ok(profile) => profile,
+            Err(_) => return Vec::new(),
+        },
+    };
+    let Ok(doc) = manager.resolve_stack(&profile) else {
+        return Vec::new();
+    };
+
+    let current = current.to_string_lossy();
+    let mut keys = doc.keys();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| key.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Completes the name of a configured remote.
+///
+/// # Arguments
+///
+/// * `current` - The text typed so far.
+///
+/// # Returns
+///
+/// A candidate for each matching remote.
+pub fn remotes(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(remotes) = manager().and_then(|manager| manager.load_remotes().ok()) else {
+        return Vec::new();
+    };
+
+    let current = current.to_string_lossy();
+    remotes
+        .remotes
+        .keys()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Completes the name of a group defined under `[groups]`.
+///
+/// # Arguments
+///
+/// * `current` - The text typed so far.
+///
+/// # Returns
+///
+/// A candidate for each matching group.
+pub fn groups(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(manager) = manager() else {
+        return Vec::new();
+    };
+
+    let current = current.to_string_lossy();
+    manager
+        .config
+        .groups
+        .keys()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Loads the profile manager of the current directory, if it is initialized.
+fn manager() -> Option<ProfileManager<EnvokeFileSystem>> {
+    let config = Config::load(PathBuf::from(".envoke")).ok()?;
+    let manager = ProfileManager::new(config, EnvokeFileSystem::new());
+
+    manager.is_initialized().then_some(manager)
+}
+
+/// Finds the value of `--profile` or `-p` in the command line being completed.
+fn profile_arg<I: IntoIterator<Item = OsString>>(args: I) -> Option<String> {
+    let mut args = args
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned());
+
+    while let Some(arg) = args.next() {
+        if arg == "--profile" || arg == "-p" {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix("--profile=") {
+            return Some(value.to_string());
+        }
+        if let Some(value) = arg.strip_prefix("-p")
+            && !value.is_empty()
+        {
+            return Some(value.trim_start_matches('=').to_string());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_profile_arg() {
+        assert_eq!(
+            None,
+            profile_arg(args(&["envoke", "--", "envoke", "get", ""]))
+        );
+        assert_eq!(
+            Some("dev".to_string()),
+            profile_arg(args(&["envoke", "get", "-p", "dev", ""]))
+        );
+        assert_eq!(
+            Some("dev+ci".to_string()),
+            profile_arg(args(&["envoke", "get", "--profile=dev+ci", ""]))
+        );
+        assert_eq!(
+            Some("prod".to_string()),
+            profile_arg(args(&["envoke", "get", "-pprod", ""]))
+        );
+    }
+}
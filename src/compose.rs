@@ -0,0 +1,210 @@
+//! Compose module for the envoke CLI tool.
+//!
+//! This module reads the `environment:` block of a service in a
+//! docker-compose file, so projects that embed their configuration in compose
+//! files can move it into profiles. Both the mapping form (`KEY: value`) and
+//! the list form (`- KEY=value`) are supported.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::error::{ErrorKind, Result};
+
+#[derive(Debug, Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    services: BTreeMap<String, Service>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Service {
+    #[serde(default)]
+    environment: Value,
+}
+
+/// A variable of a service's environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    /// The name of the variable.
+    pub key: String,
+
+    /// The value, or `None` when compose passes it through from the host.
+    pub value: Option<String>,
+}
+
+/// Reads the environment of a service from a docker-compose file.
+///
+/// # Arguments
+///
+/// * `file` - The path of the compose file, used in errors.
+/// * `input` - The contents of the compose file.
+/// * `service` - The name of the service.
+///
+/// # Returns
+///
+/// The variables in the order they are listed, or an `Error` if the file
+/// cannot be parsed or has no such service.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use envoke::compose::environment;
+///
+/// let input = "services:\n  api:\n    environment:\n      PORT: 8080\n      DEBUG: \"true\"\n";
+/// let vars = environment(Path::new("docker-compose.yml"), input, "api").unwrap();
+/// assert_eq!("PORT", vars[0].key);
+/// assert_eq!(Some("8080".to_string()), vars[0].value);
+/// ```
+pub fn environment(file: &Path, input: &str, service: &str) -> Result<Vec<Variable>> {
+    let invalid = |reason: String| ErrorKind::ParseCompose {
+        file: file.to_path_buf(),
+        reason,
+    };
+
+    let compose: ComposeFile = serde_yaml::from_str(input).map_err(|e| invalid(e.to_string()))?;
+    let Some(found) = compose.services.get(service) else {
+        return Err(ErrorKind::ServiceNotFound {
+            service: service.to_string(),
+            services: compose.services.into_keys().collect(),
+        }
+        .into());
+    };
+
+    let mut vars = Vec::new();
+    match &found.environment {
+        Value::Null => {}
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let key = scalar(key).flatten().ok_or_else(|| {
+                    invalid(format!("`{}` has a key that is not a string", service))
+                })?;
+                let value = scalar(value)
+                    .ok_or_else(|| invalid(format!("the value of `{}` is not a scalar", key)))?;
+                vars.push(Variable { key, value });
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                let Some(Some(item)) = scalar(item) else {
+                    return Err(invalid(format!(
+                        "the environment of `{}` lists an entry that is not a string",
+                        service
+                    ))
+                    .into());
+                };
+                let var = match item.split_once('=') {
+                    Some((key, value)) => Variable {
+                        key: key.to_string(),
+                        value: Some(value.to_string()),
+                    },
+                    None => Variable {
+                        key: item,
+                        value: None,
+                    },
+                };
+                vars.push(var);
+            }
+        }
+        _ => {
+            return Err(invalid(format!(
+                "the environment of `{}` must be a mapping or a list",
+                service
+            ))
+            .into());
+        }
+    }
+
+    Ok(vars)
+}
+
+/// Converts a YAML scalar to a string, `Some(None)` standing for `null`.
+fn scalar(value: &Value) -> Option<Option<String>> {
+    match value {
+        Value::Null => Some(None),
+        Value::Bool(b) => Some(Some(b.to_string())),
+        Value::Number(n) => Some(Some(n.to_string())),
+        Value::String(s) => Some(Some(s.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSE: &str = r#"
+services:
+  api:
+    image: api
+    environment:
+      PORT: 8080
+      DEBUG: true
+      DATABASE_URL: "postgres://db/app"
+      API_TOKEN:
+  worker:
+    environment:
+      - QUEUE=jobs
+      - RETRIES=3
+      - GREETING=a=b
+      - HOME
+  db:
+    image: postgres
+"#;
+
+    fn vars(service: &str) -> Vec<(String, Option<String>)> {
+        environment(Path::new("docker-compose.yml"), COMPOSE, service)
+            .unwrap()
+            .into_iter()
+            .map(|var| (var.key, var.value))
+            .collect()
+    }
+
+    fn var(key: &str, value: Option<&str>) -> (String, Option<String>) {
+        (key.to_string(), value.map(String::from))
+    }
+
+    #[test]
+    fn test_environment() {
+        assert_eq!(
+            vec![
+                var("PORT", Some("8080")),
+                var("DEBUG", Some("true")),
+                var("DATABASE_URL", Some("postgres://db/app")),
+                var("API_TOKEN", None),
+            ],
+            vars("api")
+        );
+        assert_eq!(
+            vec![
+                var("QUEUE", Some("jobs")),
+                var("RETRIES", Some("3")),
+                var("GREETING", Some("a=b")),
+                var("HOME", None),
+            ],
+            vars("worker")
+        );
+        assert!(vars("db").is_empty());
+    }
+
+    #[test]
+    fn test_environment_errors() {
+        let file = Path::new("docker-compose.yml");
+
+        let err = environment(file, COMPOSE, "web").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::ServiceNotFound { ref services, .. } if services == &["api", "db", "worker"]
+        ));
+
+        let err = environment(file, "services: [", "api").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseCompose { .. }));
+
+        let input = "services:\n  api:\n    environment:\n      NESTED:\n        A: 1\n";
+        let err = environment(file, input, "api").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseCompose { .. }));
+    }
+}
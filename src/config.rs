@@ -1,9 +1,35 @@
 //! Configuration module for the envoke CLI tool.
 //!
 //! This module provides the `Config` struct which stores all essential paths
-//! and settings used throughout the application.
+//! and settings used throughout the application. Settings are read from an
+//! optional `config.toml` file inside the envoke directory.
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer};
+use tracing::debug;
+
+use crate::cli::ExportFormat;
+use crate::error::{Error, ErrorKind, Result};
+use crate::hooks::Hook;
+use crate::redact::{DEFAULT_SECRET_PATTERNS, Redactor};
+use crate::signing::SignatureFormat;
+
+/// Name of the envoke directory within a project.
+pub const ENVOKE_DIR: &str = ".envoke";
+
+/// Name of the configuration file within the envoke directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Environment variable selecting a profile in place of the one linked to
+/// `.env`, for `run`, `env` and `current`.
+pub const PROFILE_VAR: &str = "ENVOKE_PROFILE";
 
 /// Stores configuration settings and paths for the envoke CLI tool.
 ///
@@ -26,10 +52,671 @@ use std::path::PathBuf;
 ///     PathBuf::from("/custom/path/.envoke"),
 /// );
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+#[schemars(description = "Settings read from `.envoke/config.toml`.")]
 pub struct Config {
     /// Root directory for storing environment profiles and metadata.
+    #[serde(skip)]
     pub envoke_dir: PathBuf,
+
+    /// User-wide directory for templates and profiles shared between projects.
+    #[serde(skip)]
+    pub global_dir: Option<PathBuf>,
+
+    /// User-wide directory for state such as the operation log.
+    #[serde(skip)]
+    pub state_dir: Option<PathBuf>,
+
+    /// A profile selected with `ENVOKE_PROFILE`, overriding the active profile.
+    #[serde(skip)]
+    pub profile_override: Option<String>,
+
+    /// Whether changes are only reported, as set by `--dry-run`. Hooks are
+    /// not run and nothing is pushed to remotes.
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    /// The profile `init --default` activates, and which commands offer to
+    /// activate when no profile is active.
+    pub default_profile: Option<String>,
+
+    /// Settings for key age auditing.
+    pub audit: AuditConfig,
+
+    /// Settings for identifying secret values.
+    pub secrets: SecretsConfig,
+
+    /// Settings for `envoke scan`.
+    pub scan: ScanConfig,
+
+    /// Settings for encrypting individual values.
+    pub encryption: EncryptionConfig,
+
+    /// Settings for copying values to the clipboard.
+    pub clipboard: ClipboardConfig,
+
+    /// Settings for the shell prompt segment.
+    pub prompt: PromptConfig,
+
+    /// Settings for the operation log.
+    pub log: LogConfig,
+
+    /// Settings for `envoke watch`.
+    pub watch: WatchConfig,
+
+    /// Shell commands run before and after profiles change.
+    pub hooks: HooksConfig,
+
+    /// Settings for switching profiles.
+    pub switch: SwitchConfig,
+
+    /// Settings for how long backups are kept.
+    pub backups: BackupsConfig,
+
+    /// Settings for creating profiles.
+    pub create: CreateConfig,
+
+    /// Settings for removing profiles.
+    pub remove: RemoveConfig,
+
+    /// Settings for locking the directory.
+    pub lock: LockConfig,
+
+    /// Settings for running commands with a profile loaded.
+    pub run: RunConfig,
+
+    /// Settings for signing profiles.
+    pub signing: SigningConfig,
+
+    /// Named groups of profiles that commands such as `verify --group` work
+    /// on together.
+    ///
+    /// ```toml
+    /// [groups]
+    /// ci = ["test", "e2e"]
+    /// ```
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+/// Settings controlling how key ages are audited.
+///
+/// ```toml
+/// [audit]
+/// max_age = "90d"
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    /// The maximum age of a value before `switch` warns about it.
+    #[serde(deserialize_with = "deserialize_duration")]
+    #[schemars(with = "Option<String>")]
+    pub max_age: Option<Duration>,
+}
+
+/// Settings controlling which keys hold secrets.
+///
+/// ```toml
+/// [secrets]
+/// patterns = ["*TOKEN*", "*PASSWORD*", "STRIPE_*"]
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Glob patterns matching the names of keys whose values are secret.
+    pub patterns: Vec<String>,
+}
+
+impl SecretsConfig {
+    /// Creates a `Redactor` for the configured secret patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether secret values should be masked.
+    ///
+    /// # Returns
+    ///
+    /// A `Redactor` matching the configured patterns.
+    pub fn redactor(&self, enabled: bool) -> Redactor {
+        Redactor::new(self.patterns.clone(), enabled)
+    }
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        SecretsConfig {
+            patterns: DEFAULT_SECRET_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Settings controlling `envoke scan`.
+///
+/// ```toml
+/// [scan]
+/// allow = ["PUBLIC_*", "SENTRY_DSN"]
+/// entropy = 4.5
+/// history = false
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Glob patterns matching the names of keys that are never reported.
+    pub allow: Vec<String>,
+
+    /// The entropy, in bits per character, above which a value is considered
+    /// random.
+    pub entropy: f64,
+
+    /// Whether to search the git history for secret values.
+    pub history: bool,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            allow: Vec::new(),
+            entropy: 4.0,
+            history: true,
+        }
+    }
+}
+
+/// Settings controlling how individual values are encrypted.
+///
+/// ```toml
+/// [encryption]
+/// recipients = ["age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"]
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// The age public keys values are encrypted to, usually one per team
+    /// member. When empty, values are encrypted to the local identity.
+    pub recipients: Vec<String>,
+}
+
+/// Settings controlling clipboard integration.
+///
+/// ```toml
+/// [clipboard]
+/// clear_after = "45s"
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    /// How long a copied value stays on the clipboard. Zero disables clearing.
+    #[serde(deserialize_with = "deserialize_duration")]
+    #[schemars(with = "Option<String>", extend("default" = "45s"))]
+    pub clear_after: Option<Duration>,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        ClipboardConfig {
+            clear_after: Some(Duration::from_secs(45)),
+        }
+    }
+}
+
+/// Settings controlling the operation log.
+///
+/// ```toml
+/// [log]
+/// enabled = true
+/// location = "state"
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Whether commands that change anything are recorded.
+    pub enabled: bool,
+
+    /// Where the log is stored.
+    pub location: LogLocation,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            enabled: true,
+            location: LogLocation::Project,
+        }
+    }
+}
+
+/// Settings controlling what `envoke watch` keeps up to date.
+///
+/// ```toml
+/// [watch]
+/// exports = [{ format = "json", path = "config/env.json" }]
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Files rewritten from the active profile whenever it changes.
+    pub exports: Vec<WatchExport>,
+}
+
+/// A file `envoke watch` exports the active profile to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WatchExport {
+    /// The format to export, as for `envoke export`.
+    pub format: ExportFormat,
+
+    /// The file to write, relative to the project directory.
+    pub path: PathBuf,
+}
+
+/// Settings controlling `envoke switch`.
+///
+/// ```toml
+/// [switch]
+/// backup = false
+/// link_mode = "copy"
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SwitchConfig {
+    /// Whether `--force` moves a `.env` envoke does not manage into the
+    /// backups directory, rather than deleting it.
+    pub backup: bool,
+
+    /// How `.env` is made to hold a single profile.
+    pub link_mode: LinkMode,
+}
+
+impl Default for SwitchConfig {
+    fn default() -> Self {
+        SwitchConfig {
+            backup: true,
+            link_mode: LinkMode::default(),
+        }
+    }
+}
+
+/// How `envoke switch` makes `.env` hold a single profile.
+///
+/// Stacks and profiles with includes or overlays are always copied, as there
+/// is no single file to link to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// Link `.env` to the profile with a symbolic link, copying it instead on
+    /// filesystems where symbolic links are unreliable.
+    #[default]
+    Symlink,
+
+    /// Write the contents of the profile to `.env`, marked as generated.
+    Copy,
+
+    /// Make `.env` a hard link to the profile, for when symbolic links are
+    /// blocked but `.env` and the profile share a filesystem.
+    Hardlink,
+}
+
+/// Settings controlling how long backups are kept.
+///
+/// Backups beyond either limit are removed after each command that changes
+/// anything, and by `envoke gc`.
+///
+/// ```toml
+/// [backups]
+/// max_count = 20
+/// max_age = "30d"
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupsConfig {
+    /// The most backups kept. Zero keeps every backup.
+    pub max_count: usize,
+
+    /// The age after which a backup is removed.
+    #[serde(deserialize_with = "deserialize_duration")]
+    #[schemars(with = "Option<String>")]
+    pub max_age: Option<Duration>,
+}
+
+impl Default for BackupsConfig {
+    fn default() -> Self {
+        BackupsConfig {
+            max_count: 20,
+            max_age: None,
+        }
+    }
+}
+
+/// Settings controlling `envoke create`.
+///
+/// ```toml
+/// [create]
+/// switch = true
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct CreateConfig {
+    /// Whether to switch to a profile once it is created.
+    pub switch: bool,
+}
+
+/// Settings controlling `envoke remove`.
+///
+/// ```toml
+/// [remove]
+/// switch_to = "dev"
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoveConfig {
+    /// The profile to switch to after removing the active profile.
+    pub switch_to: Option<String>,
+}
+
+/// Settings controlling `envoke lock` and `envoke unlock`.
+///
+/// ```toml
+/// [lock]
+/// require_reason = true
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockConfig {
+    /// Whether locking and unlocking require a `--reason`, which is recorded
+    /// in the operation log.
+    pub require_reason: bool,
+}
+
+/// Variables `envoke run --isolated` passes on from the environment it is
+/// started from, unless configured otherwise.
+pub const DEFAULT_ISOLATED_ALLOW: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TERM", "LANG", "LC_*", "TZ", "TMPDIR",
+];
+
+/// Settings controlling `envoke run`.
+///
+/// ```toml
+/// [run]
+/// precedence = "os"
+/// allow = ["PATH", "HOME", "TERM", "SSH_AUTH_SOCK"]
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConfig {
+    /// Which value wins for variables set both by the profile and in the
+    /// environment `envoke run` is started from.
+    pub precedence: Precedence,
+
+    /// Glob patterns matching the variables `--isolated` passes on from the
+    /// environment `envoke run` is started from.
+    pub allow: Vec<String>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            precedence: Precedence::default(),
+            allow: DEFAULT_ISOLATED_ALLOW
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Which value `envoke run` passes on for a variable that is set both by the
+/// profile and in the environment it is started from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Precedence {
+    /// The profile overrides the environment.
+    #[default]
+    Profile,
+
+    /// The environment overrides the profile, e.g. for secrets a CI system
+    /// provides.
+    Os,
+}
+
+/// Settings controlling how profiles are signed and verified.
+///
+/// A leading `~` in a path is the home directory. The keys that are trusted
+/// can only be set in the user configuration, see [`UserConfig`], and a
+/// project can require signatures but not turn off a user's requirement.
+///
+/// ```toml
+/// [signing]
+/// format = "ssh"
+/// key = "~/.ssh/id_ed25519"
+/// required = true
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningConfig {
+    /// The tool profiles are signed with.
+    pub format: SignatureFormat,
+
+    /// The private key `envoke sign` signs with.
+    pub key: Option<PathBuf>,
+
+    /// The keys whose signatures are trusted, read from the user
+    /// configuration.
+    #[schemars(skip)]
+    pub trusted: Option<PathBuf>,
+
+    /// Whether `switch` and `run` refuse profiles without a trusted
+    /// signature.
+    pub required: bool,
+}
+
+/// Settings read from the user-wide `config.toml` in the global directory.
+///
+/// A project's `config.toml` can be edited by anyone who can edit its
+/// profiles, so the settings that decide whether those profiles are trusted
+/// live here instead. For SSH signatures `trusted` is an allowed signers
+/// file, as used by `ssh-keygen -Y verify`; for minisign it is a public key.
+/// A relative path is relative to the global directory.
+///
+/// ```toml
+/// [signing]
+/// trusted = "allowed_signers"
+/// required = true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    /// Settings for verifying signed profiles.
+    pub signing: UserSigningConfig,
+}
+
+/// The signing settings of the user configuration.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserSigningConfig {
+    /// The keys whose signatures are trusted.
+    pub trusted: Option<PathBuf>,
+
+    /// Whether signatures are required in every project.
+    pub required: bool,
+}
+
+impl UserConfig {
+    /// Loads the user configuration stored in the given global directory.
+    ///
+    /// A missing configuration file is not an error - default settings are
+    /// used instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `global_dir` - The user-wide envoke directory.
+    ///
+    /// # Returns
+    ///
+    /// The loaded `UserConfig`, or an `Error` if the file cannot be read or
+    /// parsed.
+    pub fn load(global_dir: &Path) -> Result<Self> {
+        let file = global_dir.join(CONFIG_FILE);
+        if !file.exists() {
+            return Ok(UserConfig::default());
+        }
+
+        debug!(file = %file.display(), "loading user configuration");
+        let contents = fs::read_to_string(&file).map_err(|e| {
+            Error::new(ErrorKind::ReadFile {
+                file: file.clone(),
+                source: e,
+            })
+        })?;
+
+        let mut config: UserConfig = toml::from_str(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseConfig {
+                file: file.clone(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        if let Some(trusted) = &config.signing.trusted
+            && trusted.is_relative()
+            && !trusted.starts_with("~")
+        {
+            config.signing.trusted = Some(global_dir.join(trusted));
+        }
+
+        Ok(config)
+    }
+}
+
+/// Shell commands run before and after profiles change.
+///
+/// Each command runs with `sh -c` in the project directory, with the details
+/// of the change in `ENVOKE_*` environment variables. A failing `pre_` hook
+/// aborts the operation.
+///
+/// ```toml
+/// [hooks]
+/// post_switch = "docker compose restart api"
+/// ```
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Runs before `.env` is switched to another profile.
+    pub pre_switch: Option<String>,
+
+    /// Runs after `.env` is switched to another profile.
+    pub post_switch: Option<String>,
+
+    /// Runs before a profile is created.
+    pub pre_create: Option<String>,
+
+    /// Runs after a profile is created.
+    pub post_create: Option<String>,
+
+    /// Runs before a profile is removed.
+    pub pre_remove: Option<String>,
+
+    /// Runs after a profile is removed.
+    pub post_remove: Option<String>,
+}
+
+impl HooksConfig {
+    /// Gets the command configured for a hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The hook.
+    ///
+    /// # Returns
+    ///
+    /// The shell command, or `None` if the hook is not configured.
+    pub fn command(&self, hook: Hook) -> Option<&str> {
+        let command = match hook {
+            Hook::PreSwitch => &self.pre_switch,
+            Hook::PostSwitch => &self.post_switch,
+            Hook::PreCreate => &self.pre_create,
+            Hook::PostCreate => &self.post_create,
+            Hook::PreRemove => &self.pre_remove,
+            Hook::PostRemove => &self.post_remove,
+        };
+        command.as_deref()
+    }
+}
+
+/// Where the operation log is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLocation {
+    /// In the envoke directory of the project.
+    Project,
+
+    /// In the user's state directory, shared by all projects.
+    State,
+}
+
+/// Settings controlling the segment printed by `envoke prompt`.
+///
+/// ```toml
+/// [prompt]
+/// format = "({profile}) "
+/// style = "yellow"
+/// ```
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptConfig {
+    /// The text printed, with `{profile}` replaced by the active profile.
+    pub format: String,
+
+    /// The color of the segment.
+    pub style: Option<Color>,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        PromptConfig {
+            format: "{profile}".to_string(),
+            style: None,
+        }
+    }
+}
+
+/// A standard terminal color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    /// Returns the ANSI escape sequence that switches to this color.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::config::Color;
+    ///
+    /// assert_eq!("\x1b[32m", Color::Green.ansi());
+    /// ```
+    pub fn ansi(&self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Blue => "\x1b[34m",
+            Color::Magenta => "\x1b[35m",
+            Color::Cyan => "\x1b[36m",
+            Color::White => "\x1b[37m",
+        }
+    }
 }
 
 impl Config {
@@ -38,13 +725,103 @@ impl Config {
     /// # Arguments
     ///
     /// * `envoke_dir` - Directory path for storing environment profiles and metadata.
-    /// * `current_file` - File path for tracking the currently active profile.
     ///
     /// # Returns
     ///
-    /// A new `Config` instance with the specified paths.
+    /// A new `Config` instance with the specified paths and default settings.
     pub fn new(envoke_dir: PathBuf) -> Self {
-        Config { envoke_dir }
+        Config {
+            envoke_dir,
+            global_dir: None,
+            state_dir: None,
+            profile_override: None,
+            dry_run: false,
+            default_profile: None,
+            audit: AuditConfig::default(),
+            secrets: SecretsConfig::default(),
+            scan: ScanConfig::default(),
+            encryption: EncryptionConfig::default(),
+            clipboard: ClipboardConfig::default(),
+            prompt: PromptConfig::default(),
+            log: LogConfig::default(),
+            watch: WatchConfig::default(),
+            hooks: HooksConfig::default(),
+            switch: SwitchConfig::default(),
+            backups: BackupsConfig::default(),
+            create: CreateConfig::default(),
+            remove: RemoveConfig::default(),
+            lock: LockConfig::default(),
+            run: RunConfig::default(),
+            signing: SigningConfig::default(),
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// Loads the configuration stored in the given envoke directory.
+    ///
+    /// A missing configuration file is not an error - default settings are
+    /// used instead. The global and state directories are located using
+    /// [`default_global_dir`] and [`default_state_dir`], and a profile
+    /// selected with `ENVOKE_PROFILE` is read into `profile_override`.
+    ///
+    /// # Arguments
+    ///
+    /// * `envoke_dir` - Directory path for storing environment profiles and metadata.
+    ///
+    /// # Returns
+    ///
+    /// The loaded `Config`, or an `Error` if the file cannot be read or parsed.
+    pub fn load(envoke_dir: PathBuf) -> Result<Self> {
+        let file = envoke_dir.join(CONFIG_FILE);
+        let mut config = if file.exists() {
+            debug!(file = %file.display(), "loading configuration");
+            let contents = fs::read_to_string(&file).map_err(|e| {
+                Error::new(ErrorKind::ReadFile {
+                    file: file.clone(),
+                    source: e,
+                })
+            })?;
+
+            let mut config: Config = toml::from_str(&contents).map_err(|e| {
+                Error::new(ErrorKind::ParseConfig {
+                    file: file.clone(),
+                    reason: e.to_string(),
+                })
+            })?;
+
+            if config.signing.trusted.is_some() {
+                return Err(ErrorKind::UserOnlySetting {
+                    setting: "signing.trusted",
+                    file,
+                }
+                .into());
+            }
+
+            config.envoke_dir = envoke_dir;
+            config
+        } else {
+            debug!(file = %file.display(), "no configuration file, using defaults");
+            Config::new(envoke_dir)
+        };
+
+        config.global_dir = default_global_dir();
+        config.state_dir = default_state_dir();
+        config.profile_override = profile_override();
+        if let Some(global_dir) = &config.global_dir {
+            let user = UserConfig::load(global_dir)?;
+            config.apply_user(user);
+        }
+
+        Ok(config)
+    }
+
+    /// Applies the settings of the user configuration.
+    ///
+    /// The user's trusted keys are the only ones used, and signatures are
+    /// required if either the user or the project requires them.
+    pub fn apply_user(&mut self, user: UserConfig) {
+        self.signing.trusted = user.signing.trusted;
+        self.signing.required |= user.signing.required;
     }
 }
 
@@ -53,25 +830,105 @@ impl Default for Config {
     ///
     /// The default configuration uses:
     /// - `.envoke` for the root directory.
-    /// - `.envoke/current` for the current profile file.
     ///
     /// # Returns
     ///
     /// A `Config` instance with default paths.
     fn default() -> Self {
-        let envoke_dir = PathBuf::from(".envoke");
-        Config { envoke_dir }
+        Config::new(PathBuf::from(".envoke"))
     }
 }
 
+/// Locates the user-wide envoke directory.
+///
+/// This is `$XDG_CONFIG_HOME/envoke` when `XDG_CONFIG_HOME` is set, and
+/// `$HOME/.config/envoke` otherwise.
+///
+/// # Returns
+///
+/// The global directory, or `None` if neither variable is set.
+pub fn default_global_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("envoke"))
+}
+
+/// Locates the user-wide envoke state directory.
+///
+/// This is `$XDG_STATE_HOME/envoke` when `XDG_STATE_HOME` is set, and
+/// `$HOME/.local/state/envoke` otherwise.
+///
+/// # Returns
+///
+/// The state directory, or `None` if neither variable is set.
+pub fn default_state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    Some(base.join("envoke"))
+}
+
+/// Reads the profile selected with `ENVOKE_PROFILE`, if it is set and not empty.
+fn profile_override() -> Option<String> {
+    env::var(PROFILE_VAR)
+        .ok()
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Parses a human-readable duration such as `90d`, `12h` or `2w`.
+///
+/// # Arguments
+///
+/// * `input` - The duration to parse.
+///
+/// # Returns
+///
+/// The parsed `Duration`, or an `Error` if the input is invalid.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::config::parse_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(Duration::from_secs(90 * 86400), parse_duration("90d").unwrap());
+/// assert!(parse_duration("soon").is_err());
+/// ```
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    humantime::parse_duration(input.trim()).map_err(|_| {
+        Error::new(ErrorKind::InvalidDuration {
+            input: input.to_string(),
+        })
+    })
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let input = String::deserialize(deserializer)?;
+    parse_duration(&input)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
 
     #[test]
     fn test_default_config() {
         let config = Config::default();
         assert_eq!(config.envoke_dir, PathBuf::from(".envoke"));
+        assert!(config.audit.max_age.is_none());
+        assert!(config.secrets.redactor(true).is_secret("API_TOKEN"));
+        assert_eq!(config.clipboard.clear_after, Some(Duration::from_secs(45)));
     }
 
     #[test]
@@ -79,4 +936,130 @@ mod tests {
         let config = Config::new(PathBuf::from("/custom/.envoke"));
         assert_eq!(config.envoke_dir, PathBuf::from("/custom/.envoke"));
     }
+
+    #[test]
+    fn test_load_missing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(config.envoke_dir, temp_dir.path());
+        assert!(config.audit.max_age.is_none());
+    }
+
+    #[test]
+    fn test_load_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+        fs::write(&file, "[audit]\nmax_age = \"90d\"\n").unwrap();
+
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.envoke_dir, temp_dir.path());
+        assert_eq!(config.audit.max_age, Some(Duration::from_secs(90 * 86400)));
+    }
+
+    #[test]
+    fn test_load_secret_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+        fs::write(&file, "[secrets]\npatterns = [\"STRIPE_*\"]\n").unwrap();
+
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+        let redactor = config.secrets.redactor(true);
+        assert!(redactor.is_secret("STRIPE_KEY"));
+        assert!(!redactor.is_secret("API_TOKEN"));
+    }
+
+    #[test]
+    fn test_load_user_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let user = UserConfig::load(temp_dir.path()).unwrap();
+        assert!(user.signing.trusted.is_none());
+
+        let file = temp_dir.path().join(CONFIG_FILE);
+        fs::write(
+            &file,
+            "[signing]\ntrusted = \"allowed_signers\"\nrequired = true\n",
+        )
+        .unwrap();
+        let user = UserConfig::load(temp_dir.path()).unwrap();
+        assert_eq!(
+            user.signing.trusted,
+            Some(temp_dir.path().join("allowed_signers"))
+        );
+
+        // A project can require signatures, but not turn off the user's requirement.
+        let mut config = Config::default();
+        config.apply_user(user);
+        assert!(config.signing.required);
+    }
+
+    #[test]
+    fn test_load_project_trusted() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+        fs::write(&file, "[signing]\ntrusted = \"allowed_signers\"\n").unwrap();
+
+        let err = Config::load(temp_dir.path().to_path_buf()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::UserOnlySetting { .. }));
+    }
+
+    #[test]
+    fn test_load_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.run.precedence, Precedence::Profile);
+
+        assert!(config.run.allow.contains(&"PATH".to_string()));
+
+        fs::write(&file, "[run]\nprecedence = \"os\"\nallow = [\"PATH\"]\n").unwrap();
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.run.precedence, Precedence::Os);
+        assert_eq!(config.run.allow, vec!["PATH"]);
+    }
+
+    #[test]
+    fn test_load_prompt() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+        fs::write(
+            &file,
+            "[prompt]\nformat = \"({profile}) \"\nstyle = \"cyan\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.prompt.format, "({profile}) ");
+        assert_eq!(config.prompt.style, Some(Color::Cyan));
+
+        fs::write(&file, "[prompt]\nstyle = \"mauve\"\n").unwrap();
+        let err = Config::load(temp_dir.path().to_path_buf()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseConfig { .. }));
+    }
+
+    #[test]
+    fn test_load_invalid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join(CONFIG_FILE);
+
+        fs::write(&file, "[audit]\nmax_age = \"whenever\"\n").unwrap();
+        let err = Config::load(temp_dir.path().to_path_buf()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseConfig { .. }));
+
+        fs::write(&file, "unknown = true\n").unwrap();
+        let err = Config::load(temp_dir.path().to_path_buf()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseConfig { .. }));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(86400), parse_duration("1d").unwrap());
+        assert_eq!(
+            Duration::from_secs(14 * 86400),
+            parse_duration("2w").unwrap()
+        );
+        assert_eq!(Duration::from_secs(3600), parse_duration(" 1h ").unwrap());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("ninety days").is_err());
+    }
 }
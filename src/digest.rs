@@ -0,0 +1,87 @@
+//! Content digest module for the envoke CLI tool.
+//!
+//! This module provides helpers for hashing content, used wherever envoke
+//! needs to detect changes without storing the content itself.
+
+use sha2::{Digest, Sha256};
+
+/// Computes the SHA-256 digest of some data as a lowercase hex string.
+///
+/// # Arguments
+///
+/// * `data` - The data to hash.
+///
+/// # Returns
+///
+/// The 64-character hex digest.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::digest::sha256_hex;
+///
+/// assert_eq!(
+///     "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+///     sha256_hex(b"hello"),
+/// );
+/// ```
+pub fn sha256_hex<D: AsRef<[u8]>>(data: D) -> String {
+    hex(&Sha256::digest(data.as_ref()))
+}
+
+/// Computes the HMAC-SHA-256 of some data as a lowercase hex string.
+///
+/// Unlike a plain digest, the result cannot be recomputed without the key, so
+/// it does not allow guessing the data offline.
+///
+/// # Arguments
+///
+/// * `key` - The secret key.
+/// * `data` - The data to authenticate.
+///
+/// # Returns
+///
+/// The 64-character hex digest.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::digest::hmac_sha256_hex;
+///
+/// assert_eq!(
+///     "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+///     hmac_sha256_hex(b"Jefe", b"what do ya want for nothing?"),
+/// );
+/// ```
+pub fn hmac_sha256_hex<K, D>(key: K, data: D) -> String
+where
+    K: AsRef<[u8]>,
+    D: AsRef<[u8]>,
+{
+    const BLOCK_SIZE: usize = 64;
+
+    // Keys longer than a block are hashed first, and all keys are zero padded.
+    let mut block = [0u8; BLOCK_SIZE];
+    let key = key.as_ref();
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(data.as_ref())
+        .finalize();
+    let outer = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize();
+
+    hex(&outer)
+}
+
+/// Formats bytes as a lowercase hex string.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,1030 @@
+//! Dotenv parsing module for the envoke CLI tool.
+//!
+//! This module provides a lossless representation of `.env` files. Every line
+//! of the original input is retained, so a parsed `Document` can be written
+//! back out without disturbing comments, blank lines, or key order.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Prefix of comments that carry instructions for envoke, e.g.
+/// `# envoke:include fragments/database.env`.
+pub const DIRECTIVE_PREFIX: &str = "envoke:";
+
+/// An error encountered while parsing a dotenv document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The 1-based line number on which the error occurred.
+    pub line: usize,
+
+    /// A description of the problem.
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// A single `KEY=VALUE` assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The variable name.
+    pub key: String,
+
+    /// The unquoted, unescaped value.
+    pub value: String,
+
+    /// Whether the assignment is prefixed with `export`.
+    export: bool,
+
+    /// The inline comment following the value, including the leading `#`.
+    comment: Option<String>,
+
+    /// The original text of the assignment, possibly spanning several lines.
+    raw: String,
+}
+
+impl Entry {
+    /// Creates a new `Entry`, quoting the value where necessary.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    /// * `value` - The unquoted value.
+    ///
+    /// # Returns
+    ///
+    /// A new `Entry` whose raw text is a canonical `KEY=VALUE` line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Entry;
+    ///
+    /// assert_eq!("PORT=8080", Entry::new("PORT", "8080").raw());
+    /// assert_eq!("NAME=\"a b\"", Entry::new("NAME", "a b").raw());
+    /// ```
+    pub fn new<K: Into<String>, V: Into<String>>(key: K, value: V) -> Self {
+        let key = key.into();
+        let value = value.into();
+        let raw = format!("{}={}", key, quote(&value));
+
+        Entry {
+            key,
+            value,
+            export: false,
+            comment: None,
+            raw,
+        }
+    }
+
+    /// Creates a copy of this entry with a different value.
+    ///
+    /// The `export` prefix and any inline comment are preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new unquoted value.
+    ///
+    /// # Returns
+    ///
+    /// A new `Entry` with the same key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let doc = Document::parse("export PORT=80 # http\n").unwrap();
+    /// let entry = doc.entries().next().unwrap().with_value("8080");
+    /// assert_eq!("export PORT=8080 # http", entry.raw());
+    /// ```
+    pub fn with_value<V: Into<String>>(&self, value: V) -> Self {
+        let value = value.into();
+        let mut raw = String::new();
+
+        if self.export {
+            raw.push_str("export ");
+        }
+        raw.push_str(&self.key);
+        raw.push('=');
+        raw.push_str(&quote(&value));
+        if let Some(comment) = &self.comment {
+            raw.push(' ');
+            raw.push_str(comment);
+        }
+
+        Entry {
+            key: self.key.clone(),
+            value,
+            export: self.export,
+            comment: self.comment.clone(),
+            raw,
+        }
+    }
+
+    /// Creates a copy of this entry with a different key.
+    ///
+    /// The value is kept exactly as written, along with the `export` prefix
+    /// and any inline comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The new variable name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let doc = Document::parse("export port='80' # http\n").unwrap();
+    /// let entry = doc.entries().next().unwrap().with_key("PORT");
+    /// assert_eq!("export PORT='80' # http", entry.raw());
+    /// ```
+    pub fn with_key<K: Into<String>>(&self, key: K) -> Self {
+        let key = key.into();
+        let unprefixed = self.raw.strip_prefix("export ").unwrap_or(&self.raw);
+        let start = self.raw.len() - unprefixed.trim_start().len();
+        let raw = format!(
+            "{}{}{}",
+            &self.raw[..start],
+            key,
+            &self.raw[start + self.key.len()..]
+        );
+
+        Entry {
+            key,
+            raw,
+            ..self.clone()
+        }
+    }
+
+    /// Creates a copy of the entry without its inline comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let doc = Document::parse("export PORT=80 # http\n").unwrap();
+    /// let entry = doc.entries().next().unwrap().without_comment();
+    /// assert_eq!("export PORT=80", entry.raw());
+    /// ```
+    pub fn without_comment(&self) -> Self {
+        let entry = Entry {
+            comment: None,
+            ..self.clone()
+        };
+        entry.with_value(self.value.clone())
+    }
+
+    /// Returns the inline comment following the value, if any.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Returns the original text of the assignment.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+}
+
+/// A single logical line of a dotenv document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// An empty or whitespace-only line.
+    Blank(String),
+
+    /// A comment line, including the leading `#`.
+    Comment(String),
+
+    /// A `KEY=VALUE` assignment.
+    Entry(Entry),
+}
+
+impl Line {
+    /// Returns the original text of the line.
+    pub fn raw(&self) -> &str {
+        match self {
+            Line::Blank(raw) | Line::Comment(raw) => raw,
+            Line::Entry(entry) => entry.raw(),
+        }
+    }
+
+    /// Parses a `# envoke:<name> <argument>` directive comment.
+    ///
+    /// # Returns
+    ///
+    /// The directive name and its (trimmed, possibly empty) argument, or
+    /// `None` if the line is not a directive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let doc = Document::parse("# envoke:include fragments/db.env\n").unwrap();
+    /// assert_eq!(Some(("include", "fragments/db.env")), doc.lines()[0].directive());
+    /// ```
+    pub fn directive(&self) -> Option<(&str, &str)> {
+        let Line::Comment(raw) = self else {
+            return None;
+        };
+
+        let rest = raw.trim().strip_prefix('#')?.trim_start();
+        let rest = rest.strip_prefix(DIRECTIVE_PREFIX)?;
+        let (name, argument) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some((name, argument.trim()))
+    }
+}
+
+/// A parsed dotenv document.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::Document;
+///
+/// let input = "# Database\nDB_HOST=localhost\nDB_PORT=5432\n";
+/// let doc = Document::parse(input).unwrap();
+///
+/// assert_eq!(Some("localhost"), doc.get("DB_HOST"));
+/// assert_eq!(input, doc.to_string());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Document {
+    lines: Vec<Line>,
+    trailing_newline: bool,
+}
+
+impl Document {
+    /// Parses a dotenv document.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The contents of a dotenv file.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `Document`, or a `ParseError` describing the first invalid line.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut lines = Vec::new();
+        let mut raw_lines = input.split('\n').enumerate().peekable();
+        let trailing_newline = input.ends_with('\n');
+
+        while let Some((index, line)) = raw_lines.next() {
+            // `split` yields an empty final element for a trailing newline.
+            if trailing_newline && raw_lines.peek().is_none() {
+                break;
+            }
+
+            let number = index + 1;
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() {
+                lines.push(Line::Blank(line.to_string()));
+                continue;
+            }
+
+            if trimmed.starts_with('#') {
+                lines.push(Line::Comment(line.to_string()));
+                continue;
+            }
+
+            let mut raw = line.to_string();
+            let export = trimmed.starts_with("export ");
+            let (key, rest) = split_assignment(trimmed).ok_or_else(|| ParseError {
+                line: number,
+                reason: "expected `KEY=VALUE`".into(),
+            })?;
+
+            // Quoted values may span several physical lines.
+            let mut rest = rest.to_string();
+            while is_unterminated(&rest) {
+                match raw_lines.next() {
+                    Some((_, next)) if !(trailing_newline && raw_lines.peek().is_none()) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                        rest.push('\n');
+                        rest.push_str(next);
+                    }
+                    _ => {
+                        return Err(ParseError {
+                            line: number,
+                            reason: "unterminated quoted value".into(),
+                        });
+                    }
+                }
+            }
+
+            let (value, comment) = parse_value(&rest).map_err(|reason| ParseError {
+                line: number,
+                reason,
+            })?;
+
+            lines.push(Line::Entry(Entry {
+                key: key.to_string(),
+                value,
+                export,
+                comment,
+                raw,
+            }));
+        }
+
+        Ok(Document {
+            lines,
+            trailing_newline,
+        })
+    }
+
+    /// Creates a document from a sequence of lines, ending with a newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The lines of the document.
+    ///
+    /// # Returns
+    ///
+    /// The new `Document`.
+    pub fn from_lines(lines: Vec<Line>) -> Self {
+        Document {
+            lines,
+            trailing_newline: true,
+        }
+    }
+
+    /// Returns all lines of the document in order.
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Consumes the document, returning its lines.
+    pub fn into_lines(self) -> Vec<Line> {
+        self.lines
+    }
+
+    /// Returns an iterator over the assignments in the document.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Returns the names of all keys in the document, in order of appearance.
+    pub fn keys(&self) -> Vec<&str> {
+        self.entries().map(|entry| entry.key.as_str()).collect()
+    }
+
+    /// Returns the distinct keys of the document, sorted by name.
+    pub fn key_set(&self) -> BTreeSet<&str> {
+        self.entries().map(|entry| entry.key.as_str()).collect()
+    }
+
+    /// Returns the value of a key.
+    ///
+    /// When a key is assigned more than once, the last assignment wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    ///
+    /// # Returns
+    ///
+    /// The value if the key exists, `None` otherwise.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|entry| entry.key == key)
+            .last()
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// Changes the value of an existing key.
+    ///
+    /// Only the last assignment of the key is changed, and the rest of the
+    /// document is left exactly as it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    /// * `value` - The new unquoted value.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key exists, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let mut doc = Document::parse("# Web\nPORT='80'\nHOST=localhost\n").unwrap();
+    /// assert!(doc.set("HOST", "example.com"));
+    /// assert!(!doc.set("MISSING", "1"));
+    /// assert_eq!("# Web\nPORT='80'\nHOST=example.com\n", doc.to_string());
+    /// ```
+    pub fn set<V: Into<String>>(&mut self, key: &str, value: V) -> bool {
+        let entry = self.lines.iter_mut().rev().find_map(|line| match line {
+            Line::Entry(entry) if entry.key == key => Some(entry),
+            _ => None,
+        });
+
+        match entry {
+            Some(entry) => {
+                *entry = entry.with_value(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Appends an assignment to the end of the document.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The assignment to append.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::{Document, Entry};
+    ///
+    /// let mut doc = Document::parse("PORT=80\n").unwrap();
+    /// doc.push(Entry::new("HOST", "localhost"));
+    /// assert_eq!("PORT=80\nHOST=localhost\n", doc.to_string());
+    /// ```
+    pub fn push(&mut self, entry: Entry) {
+        // A missing final newline is kept, unless the document was empty.
+        if self.lines.is_empty() {
+            self.trailing_newline = true;
+        }
+        self.lines.push(Line::Entry(entry));
+    }
+
+    /// Removes every assignment of a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key existed, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let mut doc = Document::parse("# Web\nPORT=80\nHOST=localhost\n").unwrap();
+    /// assert!(doc.remove("PORT"));
+    /// assert!(!doc.remove("PORT"));
+    /// assert_eq!("# Web\nHOST=localhost\n", doc.to_string());
+    /// ```
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, Line::Entry(entry) if entry.key == key));
+
+        self.lines.len() != before
+    }
+
+    /// Renames every assignment of a key, keeping its position, value and
+    /// comments.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The current variable name.
+    /// * `new_key` - The new variable name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key existed, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let mut doc = Document::parse("# Web\nPORT=80 # http\n").unwrap();
+    /// assert!(doc.rename("PORT", "HTTP_PORT"));
+    /// assert!(!doc.rename("PORT", "HTTP_PORT"));
+    /// assert_eq!("# Web\nHTTP_PORT=80 # http\n", doc.to_string());
+    /// ```
+    pub fn rename(&mut self, key: &str, new_key: &str) -> bool {
+        let mut renamed = false;
+        for line in &mut self.lines {
+            if let Line::Entry(entry) = line
+                && entry.key == key
+            {
+                *entry = entry.with_key(new_key);
+                renamed = true;
+            }
+        }
+
+        renamed
+    }
+}
+
+impl Document {
+    /// Merges documents so that keys in later documents override earlier ones.
+    ///
+    /// Assignments overridden by a later document are dropped, so each key is
+    /// assigned in only one layer. Comments and blank lines are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `layers` - The documents to merge, lowest precedence first.
+    ///
+    /// # Returns
+    ///
+    /// The merged `Document`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    ///
+    /// let base = Document::parse("A=1\nB=2\n").unwrap();
+    /// let local = Document::parse("B=3\n").unwrap();
+    ///
+    /// assert_eq!("A=1\nB=3\n", Document::merge(vec![base, local]).to_string());
+    /// ```
+    pub fn merge(layers: Vec<Document>) -> Document {
+        let mut lines = Vec::new();
+
+        for (i, layer) in layers.iter().enumerate() {
+            let overridden: BTreeSet<&str> =
+                layers[i + 1..].iter().flat_map(|doc| doc.keys()).collect();
+
+            lines.extend(
+                layer
+                    .lines
+                    .iter()
+                    .filter(|line| {
+                        !matches!(line, Line::Entry(entry) if overridden.contains(entry.key.as_str()))
+                    })
+                    .cloned(),
+            );
+        }
+
+        Document::from_lines(lines)
+    }
+
+    /// Creates a copy of the document with every value replaced.
+    ///
+    /// Comments, blank lines, key order, `export` prefixes and inline comments
+    /// are all preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Computes the new value of each entry.
+    ///
+    /// # Returns
+    ///
+    /// The transformed `Document`.
+    pub fn map_values<F: FnMut(&Entry) -> String>(&self, mut f: F) -> Document {
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                Line::Entry(entry) => Line::Entry(entry.with_value(f(entry))),
+                other => other.clone(),
+            })
+            .collect();
+
+        Document {
+            lines,
+            trailing_newline: self.trailing_newline,
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    /// Writes the document back out exactly as it was parsed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(line.raw())?;
+        }
+
+        if self.trailing_newline && !self.lines.is_empty() {
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits an assignment into its key and the raw text following the `=`.
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+    let (key, rest) = line.split_once('=')?;
+    let key = key.trim_end();
+
+    is_valid_key(key).then_some((key, rest))
+}
+
+/// Checks whether a name can be used as a key, i.e. starts with a letter or
+/// `_` followed by letters, digits, `_` or `.`.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::is_valid_key;
+///
+/// assert!(is_valid_key("DATABASE_URL"));
+/// assert!(!is_valid_key("1PASSWORD"));
+/// assert!(!is_valid_key("API-KEY"));
+/// ```
+pub fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+
+    valid_start && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Checks whether a raw value opens a quote that is not closed.
+fn is_unterminated(rest: &str) -> bool {
+    let mut chars = rest.trim_start().chars();
+    let Some(quote) = chars.next().filter(|c| *c == '"' || *c == '\'') else {
+        return false;
+    };
+
+    let mut escaped = false;
+    for c in chars {
+        if quote == '"' && escaped {
+            escaped = false;
+        } else if quote == '"' && c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Parses the raw text following the `=` of an assignment into the value and
+/// any inline comment.
+fn parse_value(rest: &str) -> Result<(String, Option<String>), String> {
+    let trimmed = rest.trim_start();
+
+    // Whitespace followed by `#` starts a comment, leaving the value empty.
+    if trimmed.starts_with('#') && trimmed.len() != rest.len() {
+        return Ok((String::new(), Some(trimmed.trim_end().to_string())));
+    }
+
+    let rest = trimmed;
+    let mut chars = rest.chars();
+
+    match chars.next() {
+        Some('"') => {
+            let mut value = String::new();
+            let mut escaped = false;
+
+            for (i, c) in rest.char_indices().skip(1) {
+                if escaped {
+                    value.push(match c {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        other => other,
+                    });
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    let comment = parse_trailing(&rest[i + 1..])?;
+                    return Ok((value, comment));
+                } else {
+                    value.push(c);
+                }
+            }
+
+            Err("unterminated quoted value".into())
+        }
+        Some('\'') => {
+            let end = rest[1..]
+                .find('\'')
+                .ok_or_else(|| "unterminated quoted value".to_string())?;
+            let comment = parse_trailing(&rest[end + 2..])?;
+            Ok((rest[1..end + 1].to_string(), comment))
+        }
+        _ => {
+            // An unquoted value ends at an inline comment.
+            match rest.find(" #") {
+                Some(index) => Ok((
+                    rest[..index].trim().to_string(),
+                    Some(rest[index + 1..].trim_end().to_string()),
+                )),
+                None => Ok((rest.trim().to_string(), None)),
+            }
+        }
+    }
+}
+
+/// Ensures only whitespace or a comment follows a closing quote, returning
+/// the comment if there is one.
+fn parse_trailing(trailing: &str) -> Result<Option<String>, String> {
+    let trailing = trailing.trim();
+    if trailing.is_empty() {
+        Ok(None)
+    } else if trailing.starts_with('#') {
+        Ok(Some(trailing.to_string()))
+    } else {
+        Err("unexpected characters after closing quote".into())
+    }
+}
+
+/// Quotes a value if it contains characters that would not survive unquoted.
+fn quote(value: &str) -> String {
+    let safe = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-./:@,+%=".contains(c));
+
+    if safe {
+        return value.to_string();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            other => quoted.push(other),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trip() {
+        let input = "\
+# ------------------------------------------------------------------------------
+# Profile: dev
+
+DB_HOST=localhost
+export DB_PORT = 5432
+NAME=\"hello world\" # greeting
+MULTI=\"line one
+line two\"
+SINGLE='raw $value'
+";
+        let doc = Document::parse(input).unwrap();
+        assert_eq!(input, doc.to_string());
+    }
+
+    #[test]
+    fn test_parse_without_trailing_newline() {
+        let input = "A=1\nB=2";
+        let doc = Document::parse(input).unwrap();
+        assert_eq!(input, doc.to_string());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let input = "\
+PLAIN=value
+SPACED =  padded value
+COMMENTED=value # comment
+HASH=value#not-a-comment
+DOUBLE=\"a \\\"quoted\\\" \\n value\"
+SINGLE='it s'
+EMPTY=
+MULTI=\"one
+two\"
+";
+        let doc = Document::parse(input).unwrap();
+
+        assert_eq!(Some("value"), doc.get("PLAIN"));
+        assert_eq!(Some("padded value"), doc.get("SPACED"));
+        assert_eq!(Some("value"), doc.get("COMMENTED"));
+        assert_eq!(Some("value#not-a-comment"), doc.get("HASH"));
+        assert_eq!(Some("a \"quoted\" \n value"), doc.get("DOUBLE"));
+        assert_eq!(Some("it s"), doc.get("SINGLE"));
+        assert_eq!(Some(""), doc.get("EMPTY"));
+        assert_eq!(Some("one\ntwo"), doc.get("MULTI"));
+        assert_eq!(None, doc.get("MISSING"));
+    }
+
+    #[test]
+    fn test_keys_and_last_assignment_wins() {
+        let doc = Document::parse("A=1\nB=2\nA=3\n").unwrap();
+        assert_eq!(vec!["A", "B", "A"], doc.keys());
+        assert_eq!(Some("3"), doc.get("A"));
+    }
+
+    #[test]
+    fn test_key_set() {
+        let doc = Document::parse("B=1\nA=2\nB=3\n").unwrap();
+        assert_eq!(
+            vec!["A", "B"],
+            doc.key_set().into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Document::parse("A=1\nnot an assignment\n").unwrap_err();
+        assert_eq!(2, err.line);
+
+        let err = Document::parse("A=\"unterminated\n").unwrap_err();
+        assert_eq!(1, err.line);
+
+        let err = Document::parse("1KEY=value\n").unwrap_err();
+        assert_eq!(1, err.line);
+
+        let err = Document::parse("A='closed' trailing\n").unwrap_err();
+        assert_eq!(1, err.line);
+    }
+
+    #[test]
+    fn test_inline_comments() {
+        let doc = Document::parse("A=1 # one\nB=\"2\"  # two\nC=3\n").unwrap();
+        let comments: Vec<_> = doc.entries().map(|e| e.comment()).collect();
+        assert_eq!(vec![Some("# one"), Some("# two"), None], comments);
+    }
+
+    #[test]
+    fn test_map_values_preserves_layout() {
+        let input = "# Header\n\nexport A=1 # one\nB=\"two words\"\n";
+        let doc = Document::parse(input).unwrap();
+
+        let blanked = doc.map_values(|_| String::new());
+        assert_eq!("# Header\n\nexport A= # one\nB=\n", blanked.to_string());
+
+        let reparsed = Document::parse(&blanked.to_string()).unwrap();
+        assert_eq!(Some(""), reparsed.get("A"));
+        assert_eq!(Some("# one"), reparsed.entries().next().unwrap().comment());
+
+        let upper = doc.map_values(|e| e.value.to_uppercase());
+        assert_eq!(Some("TWO WORDS"), upper.get("B"));
+    }
+
+    #[test]
+    fn test_entry_new_quotes_when_needed() {
+        assert_eq!("A=plain", Entry::new("A", "plain").raw());
+        assert_eq!("A=\"two words\"", Entry::new("A", "two words").raw());
+        assert_eq!("A=\"line\\nbreak\"", Entry::new("A", "line\nbreak").raw());
+        assert_eq!("A=", Entry::new("A", "").raw());
+
+        let doc = Document::parse(&format!("{}\n", Entry::new("A", "x \"y\" \\ z").raw())).unwrap();
+        assert_eq!(Some("x \"y\" \\ z"), doc.get("A"));
+    }
+
+    #[test]
+    fn test_directives() {
+        let input = "# envoke:include a.env\n#envoke:generated  dev \n# envoke:\n# plain\nA=1\n";
+        let doc = Document::parse(input).unwrap();
+        let directives: Vec<_> = doc.lines().iter().map(Line::directive).collect();
+
+        assert_eq!(
+            vec![
+                Some(("include", "a.env")),
+                Some(("generated", "dev")),
+                None,
+                None,
+                None
+            ],
+            directives
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = Document::parse("# Base\nA=1\nB=2\nC=3\n").unwrap();
+        let shared = Document::parse("B=shared\n").unwrap();
+        let local = Document::parse("# Local\nC=local\nD=4\n").unwrap();
+
+        let merged = Document::merge(vec![base, shared, local]);
+        assert_eq!(
+            "# Base\nA=1\nB=shared\n# Local\nC=local\nD=4\n",
+            merged.to_string()
+        );
+        assert!(Document::merge(Vec::new()).lines().is_empty());
+    }
+
+    #[test]
+    fn test_set() {
+        let mut doc = Document::parse("A=1\nexport B=2 # two\nA=3").unwrap();
+
+        assert!(doc.set("A", "new value"));
+        assert!(doc.set("B", "x"));
+        assert!(!doc.set("C", "x"));
+        assert_eq!("A=1\nexport B=x # two\nA=\"new value\"", doc.to_string());
+    }
+
+    /// Fragments that generated documents are assembled from, covering every
+    /// kind of line the parser distinguishes.
+    const FRAGMENTS: &[&str] = &[
+        "",
+        "   ",
+        "# comment",
+        "  # indented comment",
+        "# envoke:description Generated",
+        "KEY_{}=plain",
+        "KEY_{}=",
+        "export KEY_{}=exported",
+        "KEY_{} = spaced",
+        "KEY_{}=\"double quoted # not a comment\"",
+        "KEY_{}='single $quoted'",
+        "KEY_{}=value # inline comment",
+        "KEY_{}=\"multi\nline\"",
+        "KEY_{}=\"escaped \\\" quote\"",
+    ];
+
+    /// Generates pseudo-random documents from a fixed seed, so failures are
+    /// reproducible without a property testing dependency.
+    fn documents() -> Vec<String> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        (0..500)
+            .map(|_| {
+                let lines: Vec<_> = (0..next() % 12)
+                    .map(|_| {
+                        FRAGMENTS[next() % FRAGMENTS.len()].replace("{}", &(next() % 4).to_string())
+                    })
+                    .collect();
+                let mut input = lines.join("\n");
+                if next() % 4 != 0 {
+                    input.push('\n');
+                }
+                input
+            })
+            .collect()
+    }
+
+    /// Returns the raw text of every line not assigning `key`.
+    fn other_lines<'a>(doc: &'a Document, key: &str) -> Vec<&'a str> {
+        doc.lines()
+            .iter()
+            .filter(|line| !matches!(line, Line::Entry(entry) if entry.key == key))
+            .map(Line::raw)
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_property() {
+        for input in documents() {
+            let doc = Document::parse(&input).unwrap();
+            assert_eq!(input, doc.to_string(), "round trip of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_edits_preserve_unrelated_lines() {
+        for input in documents() {
+            let original = Document::parse(&input).unwrap();
+
+            for key in ["KEY_0", "KEY_1"] {
+                let mut doc = original.clone();
+                let existed = doc.set(key, "new value");
+                assert_eq!(other_lines(&original, key), other_lines(&doc, key));
+                assert_eq!(original.lines().len(), doc.lines().len());
+                if existed {
+                    assert_eq!(Some("new value"), doc.get(key));
+                }
+                assert_eq!(doc, Document::parse(&doc.to_string()).unwrap());
+
+                let mut doc = original.clone();
+                doc.remove(key);
+                assert_eq!(other_lines(&original, key), other_lines(&doc, key));
+                assert_eq!(None, doc.get(key));
+
+                let mut doc = original.clone();
+                doc.push(Entry::new(key, "pushed"));
+                assert_eq!(original.lines(), &doc.lines()[..original.lines().len()]);
+                assert_eq!(Some("pushed"), doc.get(key));
+                assert_eq!(doc, Document::parse(&doc.to_string()).unwrap());
+            }
+        }
+    }
+}
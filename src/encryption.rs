@@ -0,0 +1,372 @@
+//! Encryption module for the envoke CLI tool.
+//!
+//! This module encrypts individual values of a profile with
+//! [age](https://age-encryption.org) X25519 keys. An encrypted value is stored
+//! as `enc:` followed by the base64 encoded ciphertext, so the rest of the
+//! profile stays readable and diffs stay meaningful. Values are encrypted to
+//! the recipients listed in `[encryption] recipients`, and decrypted with the
+//! identity in `ENVOKE_IDENTITY` or the global `identity.txt`.
+
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use age::x25519::{Identity, Recipient};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use toml_edit::{Array, DocumentMut, TomlError};
+
+use crate::config::CONFIG_FILE;
+use crate::dotenv::Document;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+
+/// The prefix marking an encrypted value.
+pub const PREFIX: &str = "enc:";
+
+/// The environment variable holding an identity, e.g. in CI.
+pub const IDENTITY_VAR: &str = "ENVOKE_IDENTITY";
+
+/// The name of the identity file in the global envoke directory.
+pub const IDENTITY_FILE: &str = "identity.txt";
+
+/// Checks whether a value is encrypted.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::encryption::is_encrypted;
+///
+/// assert!(is_encrypted("enc:YWdlLWVu..."));
+/// assert!(!is_encrypted("hunter2"));
+/// ```
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Encrypts a value to one or more recipients.
+///
+/// # Arguments
+///
+/// * `value` - The plaintext value.
+/// * `recipients` - The public keys that can decrypt the value.
+///
+/// # Returns
+///
+/// The encrypted value, including the `enc:` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use age::x25519::Identity;
+/// use envoke::encryption::{decrypt, encrypt};
+///
+/// let identity = Identity::generate();
+/// let encrypted = encrypt("hunter2", &[identity.to_public()]).unwrap();
+/// assert!(encrypted.starts_with("enc:"));
+/// assert_eq!("hunter2", decrypt(&encrypted, &identity).unwrap());
+/// ```
+pub fn encrypt(value: &str, recipients: &[Recipient]) -> Result<String> {
+    let encryptor =
+        age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+            .map_err(|_| ErrorKind::NoRecipients)?;
+
+    let mut ciphertext = Vec::new();
+    let written = encryptor
+        .wrap_output(&mut ciphertext)
+        .and_then(|mut writer| {
+            std::io::Write::write_all(&mut writer, value.as_bytes())?;
+            writer.finish()
+        });
+    written.map_err(|e| ErrorKind::Encrypt {
+        reason: e.to_string(),
+    })?;
+
+    Ok(format!("{}{}", PREFIX, STANDARD.encode(ciphertext)))
+}
+
+/// Decrypts an encrypted value.
+///
+/// # Arguments
+///
+/// * `value` - The encrypted value, including the `enc:` prefix.
+/// * `identity` - The private key to decrypt it with.
+///
+/// # Returns
+///
+/// The plaintext value, or `None` if the value is malformed or was not
+/// encrypted to the identity.
+pub fn decrypt(value: &str, identity: &Identity) -> Option<String> {
+    let ciphertext = STANDARD.decode(value.strip_prefix(PREFIX)?.trim()).ok()?;
+    let plaintext = age::decrypt(identity, &ciphertext).ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+/// Re-encrypts every encrypted value of a profile to new recipients.
+///
+/// # Arguments
+///
+/// * `doc` - The profile.
+/// * `identity` - The private key the values are currently encrypted to.
+/// * `recipients` - The public keys to encrypt the values to.
+///
+/// # Returns
+///
+/// The profile with re-encrypted values, or an `Error` if a value cannot be
+/// decrypted with `identity`.
+pub fn reencrypt(
+    doc: &Document,
+    identity: &Identity,
+    recipients: &[Recipient],
+) -> Result<Document> {
+    let mut error = None;
+    let doc = doc.map_values(|entry| {
+        if !is_encrypted(&entry.value) || error.is_some() {
+            return entry.value.clone();
+        }
+        let result = decrypt(&entry.value, identity)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::DecryptValue {
+                    key: entry.key.clone(),
+                })
+            })
+            .and_then(|value| encrypt(&value, recipients));
+        result.unwrap_or_else(|e| {
+            error = Some(e);
+            entry.value.clone()
+        })
+    });
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(doc),
+    }
+}
+
+/// Parses age public keys.
+///
+/// # Returns
+///
+/// The recipients, or an `Error` naming the first key that is invalid.
+pub fn parse_recipients(recipients: &[String]) -> Result<Vec<Recipient>> {
+    recipients
+        .iter()
+        .map(|recipient| {
+            Recipient::from_str(recipient).map_err(|_| {
+                Error::new(ErrorKind::InvalidRecipient {
+                    recipient: recipient.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
+/// Parses an identity in the format written by `age-keygen`.
+///
+/// Lines starting with `#` are comments.
+fn parse_identity(contents: &str) -> Option<Identity> {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .and_then(|line| Identity::from_str(line).ok())
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the path of the identity file.
+    ///
+    /// # Returns
+    ///
+    /// The path of `identity.txt` in the global envoke directory, or an
+    /// `Error` if there is no global directory.
+    pub fn identity_path(&self) -> Result<PathBuf> {
+        Ok(self.global_dir()?.join(IDENTITY_FILE))
+    }
+
+    /// Loads the identity used to decrypt values.
+    ///
+    /// `ENVOKE_IDENTITY` takes precedence over the identity file.
+    ///
+    /// # Returns
+    ///
+    /// The identity, or an `Error` if none is available or it is invalid.
+    pub fn identity(&self) -> Result<Identity> {
+        if let Ok(key) = env::var(IDENTITY_VAR)
+            && !key.is_empty()
+        {
+            return parse_identity(&key).ok_or_else(|| {
+                ErrorKind::InvalidIdentity {
+                    origin: IDENTITY_VAR.to_string(),
+                }
+                .into()
+            });
+        }
+
+        let path = self.identity_path()?;
+        if !self.fs.path_exists(&path) {
+            return Err(ErrorKind::IdentityNotFound.into());
+        }
+        parse_identity(&self.read_file(&path)?).ok_or_else(|| {
+            ErrorKind::InvalidIdentity {
+                origin: path.to_string_lossy().into_owned(),
+            }
+            .into()
+        })
+    }
+
+    /// Gets the recipients new values are encrypted to.
+    ///
+    /// # Returns
+    ///
+    /// The recipients in `[encryption] recipients`, or the public key of the
+    /// local identity if none are configured.
+    pub fn recipients(&self) -> Result<Vec<Recipient>> {
+        let configured = &self.config.encryption.recipients;
+        if configured.is_empty() {
+            return Ok(vec![self.identity()?.to_public()]);
+        }
+
+        parse_recipients(configured)
+    }
+
+    /// Stores the recipients in `[encryption] recipients`.
+    ///
+    /// The rest of `config.toml`, including comments, is left as it is.
+    ///
+    /// # Arguments
+    ///
+    /// * `recipients` - The public keys, one per team member.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the configuration cannot be read,
+    /// parsed or written.
+    pub fn save_recipients(&self, recipients: &[String]) -> Result<()> {
+        let path = self.config.envoke_dir.join(CONFIG_FILE);
+        let contents = if self.fs.path_exists(&path) {
+            self.read_file(&path)?
+        } else {
+            String::new()
+        };
+
+        let parse_error = |reason: String| {
+            Error::new(ErrorKind::ParseConfig {
+                file: path.clone(),
+                reason,
+            })
+        };
+        let mut doc: DocumentMut = contents
+            .parse()
+            .map_err(|e: TomlError| parse_error(e.to_string()))?;
+        let table = doc
+            .entry("encryption")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| parse_error("`encryption` is not a table".into()))?;
+
+        // One key per line keeps changes to the team readable in diffs.
+        let mut array: Array = recipients.iter().map(String::as_str).collect();
+        for value in array.iter_mut() {
+            value.decor_mut().set_prefix("\n    ");
+        }
+        if !array.is_empty() {
+            array.set_trailing("\n");
+            array.set_trailing_comma(true);
+        }
+        table.insert("recipients", toml_edit::value(array));
+
+        self.write_file(&path, doc.to_string())
+    }
+
+    /// Decrypts every encrypted value of a resolved profile.
+    ///
+    /// The identity is only loaded if the profile has encrypted values.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc` - The resolved profile.
+    ///
+    /// # Returns
+    ///
+    /// The profile with plaintext values, or an `Error` if a value cannot be
+    /// decrypted.
+    pub fn decrypt_values(&self, doc: Document) -> Result<Document> {
+        if !doc.entries().any(|entry| is_encrypted(&entry.value)) {
+            return Ok(doc);
+        }
+
+        let identity = self.identity()?;
+        let mut error = None;
+        let doc = doc.map_values(|entry| {
+            if !is_encrypted(&entry.value) {
+                return entry.value.clone();
+            }
+            decrypt(&entry.value, &identity).unwrap_or_else(|| {
+                error.get_or_insert_with(|| ErrorKind::DecryptValue {
+                    key: entry.key.clone(),
+                });
+                String::new()
+            })
+        });
+
+        match error {
+            Some(error) => Err(error.into()),
+            None => Ok(doc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use age::secrecy::ExposeSecret;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_encrypt_multiple_recipients() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+        let encrypted = encrypt("s3cret", &[alice.to_public(), bob.to_public()]).unwrap();
+
+        assert_eq!("s3cret", decrypt(&encrypted, &alice).unwrap());
+        assert_eq!("s3cret", decrypt(&encrypted, &bob).unwrap());
+        assert!(decrypt(&encrypted, &Identity::generate()).is_none());
+        assert!(decrypt("enc:not base64!", &alice).is_none());
+    }
+
+    #[test]
+    fn test_decrypt_values() {
+        let identity = Identity::generate();
+        let fs = MockFileSystem::new();
+        fs.add_dir("global");
+        fs.add_file(
+            "global/identity.txt",
+            format!(
+                "# created: today\n# public key: {}\n{}\n",
+                identity.to_public(),
+                identity.to_string().expose_secret()
+            ),
+        );
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.global_dir = Some(PathBuf::from("global"));
+        let manager = ProfileManager::new(config, fs);
+
+        let recipients = manager.recipients().unwrap();
+        let encrypted = encrypt("hunter2", &recipients).unwrap();
+        let doc = Document::parse(&format!("PORT=8080\nDB_PASSWORD={}\n", encrypted)).unwrap();
+
+        let doc = manager.decrypt_values(doc).unwrap();
+        assert_eq!("PORT=8080\nDB_PASSWORD=hunter2\n", doc.to_string());
+
+        let other = encrypt("hunter2", &[Identity::generate().to_public()]).unwrap();
+        let doc = Document::parse(&format!("DB_PASSWORD={}\n", other)).unwrap();
+        let err = manager.decrypt_values(doc).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::DecryptValue { ref key } if key == "DB_PASSWORD"));
+    }
+}
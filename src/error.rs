@@ -2,6 +2,9 @@ use std::path::PathBuf;
 use std::result;
 use std::{error, fmt};
 
+use schemars::JsonSchema;
+use serde::Serialize;
+
 /// A specialized [`result::Result`] type for Envoke operations.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -25,6 +28,9 @@ pub enum ErrorKind {
     /// The file already exists.
     FileExists { file: PathBuf },
 
+    /// The file is managed by envoke, such as a profile or the linked `.env`.
+    ManagedFile { file: PathBuf },
+
     /// Failed to open a file.
     OpenFile {
         file: PathBuf,
@@ -43,6 +49,12 @@ pub enum ErrorKind {
         source: std::io::Error,
     },
 
+    /// Failed to remove a directory.
+    RemoveDir {
+        file: PathBuf,
+        source: std::io::Error,
+    },
+
     /// Failed to write contents to a file.
     WriteFile {
         file: PathBuf,
@@ -68,6 +80,13 @@ pub enum ErrorKind {
         source: std::io::Error,
     },
 
+    /// Failed to create a hard link.
+    CreateHardlink {
+        link: PathBuf,
+        original: PathBuf,
+        source: std::io::Error,
+    },
+
     /// Failed to read a symlink.
     ReadLink {
         file: PathBuf,
@@ -75,7 +94,354 @@ pub enum ErrorKind {
     },
 
     /// The .env is not a symlink.
-    NonLinkedEnv,
+    NonLinkedEnv {
+        /// The command that replaces the `.env` when run with `--force`.
+        command: String,
+    },
+
+    /// Failed to read the contents of a file.
+    ReadFile {
+        file: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// Failed to read the metadata of a file.
+    ReadMetadata {
+        file: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// Failed to copy a file.
+    CopyFile {
+        from: PathBuf,
+        to: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// Failed to rename a file.
+    RenameFile {
+        from: PathBuf,
+        to: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A profile contains invalid dotenv syntax.
+    ParseProfile {
+        file: PathBuf,
+        line: usize,
+        reason: String,
+    },
+
+    /// The configuration file is invalid.
+    ParseConfig { file: PathBuf, reason: String },
+
+    /// A project's configuration sets something only the user may set.
+    UserOnlySetting {
+        setting: &'static str,
+        file: PathBuf,
+    },
+
+    /// The metadata file is invalid.
+    ParseMetadata { file: PathBuf, reason: String },
+
+    /// A duration could not be parsed.
+    InvalidDuration { input: String },
+
+    /// One or more keys exceed the maximum allowed age.
+    StaleKeys { count: usize },
+
+    /// The key does not exist in the profile.
+    KeyNotFound { key: String, profile: String },
+
+    /// The name cannot be used as a key.
+    InvalidKey { key: String },
+
+    /// A command-line override is not a `KEY=VALUE` assignment.
+    InvalidOverride { input: String },
+
+    /// The pattern is not a valid regular expression.
+    InvalidPattern { pattern: String, reason: String },
+
+    /// Renaming a key would overwrite a key some profiles already define.
+    KeyRenameConflict { key: String, count: usize },
+
+    /// The example file does not exist.
+    ExampleNotFound { file: PathBuf },
+
+    /// Neither the schema nor the example file declares any keys.
+    NoDeclaredKeys,
+
+    /// One or more profiles do not match the example file.
+    VerificationFailed { count: usize },
+
+    /// One or more profiles changed since envoke last wrote them.
+    IntegrityFailed { count: usize },
+
+    /// A profile changed since envoke last wrote it.
+    ProfileModified { profile: String },
+
+    /// A `[signing]` setting needed to sign or verify profiles is not set.
+    SigningNotConfigured {
+        setting: &'static str,
+        file: PathBuf,
+    },
+
+    /// The signing tool failed to sign a profile.
+    SigningFailed { reason: String },
+
+    /// A profile has no signature.
+    SignatureMissing { profile: String },
+
+    /// A profile's signature does not match it or is not by a trusted key.
+    SignatureInvalid { profile: String, reason: String },
+
+    /// A file included by a profile has no signature.
+    IncludeUnsigned { file: PathBuf, profile: String },
+
+    /// One or more profiles lack a valid signature.
+    SignaturesFailed { count: usize },
+
+    /// One or more profiles are missing keys that other profiles define.
+    KeysMissing { count: usize },
+
+    /// The profile lacks keys a command requires.
+    RequiredKeysMissing { keys: Vec<String> },
+
+    /// A patch cannot be parsed or applied.
+    InvalidPatch { reason: String },
+
+    /// One or more changes of a patch conflict with the profile.
+    PatchConflict { count: usize },
+
+    /// Files holding values are tracked by git.
+    TrackedByGit { files: Vec<PathBuf> },
+
+    /// Profiles cannot be switched or removed while the directory is locked.
+    Locked {
+        user: String,
+        time: String,
+        reason: Option<String>,
+    },
+
+    /// A reason must be given to lock or unlock the directory.
+    ReasonRequired,
+
+    /// The command has effects that `--dry-run` cannot hold back.
+    DryRunUnsupported,
+
+    /// There are no backups of `.env` or a profile to restore.
+    NoBackups { target: String },
+
+    /// A backup does not exist.
+    BackupNotFound { backup: String },
+
+    /// No backup was given and none could be chosen interactively.
+    BackupRequired,
+
+    /// One or more values look like leaked secrets.
+    SecretsFound { count: usize },
+
+    /// No schema has been defined.
+    SchemaNotFound,
+
+    /// A docker-compose file cannot be parsed.
+    ParseCompose { file: PathBuf, reason: String },
+
+    /// A terraform variables or outputs file cannot be parsed.
+    ParseTerraform { file: PathBuf, reason: String },
+
+    /// The docker-compose file has no such service.
+    ServiceNotFound {
+        service: String,
+        services: Vec<String>,
+    },
+
+    /// One or more profiles violate the schema.
+    ValidationFailed { count: usize },
+
+    /// The template does not exist.
+    TemplateNotFound { template: String },
+
+    /// The template already exists.
+    TemplateExists { template: String },
+
+    /// The global profile does not exist.
+    GlobalProfileNotFound { profile: String, dir: PathBuf },
+
+    /// An included file does not exist.
+    IncludeNotFound { file: PathBuf, from: PathBuf },
+
+    /// A file includes itself, directly or indirectly.
+    IncludeCycle { file: PathBuf },
+
+    /// The profile name cannot be used.
+    InvalidProfileName { profile: String },
+
+    /// Failed to run a command.
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+
+    /// No profile was activated in the current shell.
+    NotActivated,
+
+    /// The fallback profile is the profile being removed.
+    InvalidFallback { profile: String },
+
+    /// Some of the profiles could not be created, with the error of each.
+    ProfilesNotCreated { failures: Vec<(String, Error)> },
+
+    /// A hook exited unsuccessfully.
+    HookFailed { hook: String, status: String },
+
+    /// A profile's hooks have not been approved since they last changed.
+    HooksUntrusted { profile: String },
+
+    /// A hook of `config.toml` has not been approved by the user.
+    ConfigHookUntrusted { hook: String },
+
+    /// A bundle cannot be built or read.
+    Bundle { reason: String },
+
+    /// A bundle cannot be decrypted.
+    Decrypt,
+
+    /// No identity is available to decrypt values.
+    IdentityNotFound,
+
+    /// An identity cannot be parsed.
+    InvalidIdentity { origin: String },
+
+    /// A recipient cannot be parsed.
+    InvalidRecipient { recipient: String },
+
+    /// The recipient is already configured.
+    RecipientExists { recipient: String },
+
+    /// The recipient is not configured.
+    RecipientNotFound { recipient: String },
+
+    /// No recipients are configured to encrypt values to.
+    NoRecipients,
+
+    /// A value cannot be encrypted.
+    Encrypt { reason: String },
+
+    /// An encrypted value cannot be decrypted.
+    DecryptValue { key: String },
+
+    /// The group is not defined in the configuration.
+    GroupNotFound { group: String },
+
+    /// The remote does not exist.
+    RemoteNotFound { remote: String },
+
+    /// A remote with the same name already exists.
+    RemoteExists { remote: String },
+
+    /// Nothing has been pushed to the remote yet.
+    RemoteEmpty { remote: String },
+
+    /// Both the remote and the local profiles changed since the last sync.
+    RemoteConflict { remote: String, pull: bool },
+
+    /// Profiles changed both locally and in a remote since the last sync.
+    ProfilesConflict {
+        remote: String,
+        profiles: Vec<String>,
+    },
+
+    /// A command used to access a remote failed.
+    RemoteCommand { command: String, reason: String },
+
+    /// Referenced environment variables are not set.
+    UnresolvedReferences { names: Vec<String> },
+
+    /// No profile was given and none could be chosen interactively.
+    ProfileRequired,
+
+    /// There are no profiles to choose from.
+    NoProfiles,
+
+    /// An interactive command was run without a terminal.
+    TerminalRequired,
+
+    /// A destructive command needs confirmation but cannot ask for it.
+    ConfirmationRequired,
+
+    /// Failed to draw to or read from the terminal.
+    Terminal { source: std::io::Error },
+
+    /// A file could not be downloaded.
+    Download { url: String, reason: String },
+
+    /// A URL that must be fetched over HTTPS uses another scheme.
+    InsecureUrl { url: String },
+
+    /// A download does not match the checksum it was pinned to.
+    ChecksumPinMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// The description of a release cannot be parsed.
+    ReleaseInvalid { reason: String },
+
+    /// A release does not include a file.
+    ReleaseAssetMissing { asset: String },
+
+    /// A downloaded release build is not signed with the release key.
+    ReleaseSignatureInvalid { asset: String, reason: String },
+
+    /// No shell was given and it could not be detected.
+    UnknownShell,
+
+    /// The home directory cannot be determined.
+    UnknownHome,
+
+    /// Completions cannot be installed for a shell.
+    CompletionsInstall { shell: String },
+
+    /// The user-wide state directory cannot be determined.
+    StateDirUnavailable,
+
+    /// Failed to watch files for changes.
+    Watch { reason: String },
+
+    /// The user-wide envoke directory cannot be determined.
+    GlobalDirUnavailable,
+
+    /// The directory is not a registered project.
+    ProjectNotRegistered { path: PathBuf },
+
+    /// The directory is not managed by envoke.
+    ProjectUninitialized { path: PathBuf },
+
+    /// The profile does not exist in another project.
+    ProjectProfileNotFound { profile: String, path: PathBuf },
+
+    /// Switching failed in some of the registered projects.
+    ProjectSwitchFailed { count: usize },
+
+    /// Failed to write the output of a command.
+    Output { source: std::io::Error },
+
+    /// Failed to read the input piped to a command.
+    Input { source: std::io::Error },
+
+    /// Failed to read an answer to a prompt.
+    Prompt { source: std::io::Error },
+
+    /// No supported clipboard utility is installed.
+    ClipboardUnavailable,
+
+    /// The clipboard utility failed.
+    Clipboard {
+        command: String,
+        source: std::io::Error,
+    },
 }
 
 impl ErrorKind {
@@ -92,19 +458,278 @@ impl ErrorKind {
 
         match self {
             Initialized => "This directory is already initialized.".into(),
-            Uninitialized => "Directory has not been initialized - please run `envoke init`.".into(),
-            ProfileNotFound { profile } => format!( "Profile `{}` does not exist. Run `envoke create {}` to create the profile.", profile, profile),
-            NoActiveProfile  => "No active profile - activate a profile with: `envoke switch <profile>`.".into(),
+            Uninitialized => "Directory has not been initialized.".into(),
+            ProfileNotFound { profile } => format!("Profile `{}` does not exist.", profile),
+            NoActiveProfile  => "No active profile.".into(),
             FileExists { file } => format!("The file `{}` already exists.", file.to_string_lossy()),
-            OpenFile { file, .. } => format!("Failed to open file `{}`.", file.to_string_lossy()).into(),
-            CreateFile { file, .. } => format!("Failed to create file `{}`.", file.to_string_lossy()).into(),
-            RemoveFile { file, .. } => format!("Failed to remove file `{}`.", file.to_string_lossy()).into(),
-            CreateDir { file, .. } => format!("Failed to create directory `{}`.", file.to_string_lossy()).into(),
-            ReadDir { file, .. } => format!("Failed to read contents of directory `{}`.", file.to_string_lossy()) .into(),
-            WriteFile { file, .. } => format!("Failed to write contents to file `{}`.", file.to_string_lossy()) .into(),
-            CreateSymlink { link, original, .. } => format!("Failed to link `{}` to `{}`.", link.to_string_lossy(), original.to_string_lossy()) .into(),
+            ManagedFile { file } => format!("The file `{}` is managed by envoke.", file.to_string_lossy()),
+            OpenFile { file, .. } => format!("Failed to open file `{}`.", file.to_string_lossy()),
+            CreateFile { file, .. } => format!("Failed to create file `{}`.", file.to_string_lossy()),
+            RemoveFile { file, .. } => format!("Failed to remove file `{}`.", file.to_string_lossy()),
+            RemoveDir { file, .. } => format!("Failed to remove directory `{}`.", file.to_string_lossy()),
+            CreateDir { file, .. } => format!("Failed to create directory `{}`.", file.to_string_lossy()),
+            ReadDir { file, .. } => format!("Failed to read contents of directory `{}`.", file.to_string_lossy()),
+            WriteFile { file, .. } => format!("Failed to write contents to file `{}`.", file.to_string_lossy()),
+            CreateSymlink { link, original, .. } => format!("Failed to link `{}` to `{}`.", link.to_string_lossy(), original.to_string_lossy()),
+            CreateHardlink { link, original, .. } => format!("Failed to hard link `{}` to `{}`. Both must be on the same filesystem.", link.to_string_lossy(), original.to_string_lossy()),
             ReadLink { file, .. } => format!("Failed to read the link at `{}`.", file.to_string_lossy()),
-            NonLinkedEnv => "The current `.env` is not managed by envoke. Backup your changes and delete the `.env`, or run `envoke switch <profile> --force`.".to_string(),
+            NonLinkedEnv { .. } => "The current `.env` is not managed by envoke.".to_string(),
+            ReadFile { file, .. } => format!("Failed to read contents of file `{}`.", file.to_string_lossy()),
+            ReadMetadata { file, .. } => format!("Failed to read metadata of `{}`.", file.to_string_lossy()),
+            CopyFile { from, to, .. } => format!("Failed to copy `{}` to `{}`.", from.to_string_lossy(), to.to_string_lossy()),
+            RenameFile { from, to, .. } => format!("Failed to rename `{}` to `{}`.", from.to_string_lossy(), to.to_string_lossy()),
+            ParseProfile { file, line, reason } => format!("Failed to parse `{}` at line {}: {}.", file.to_string_lossy(), line, reason),
+            ParseConfig { file, reason } => format!("Invalid configuration in `{}`: {}", file.to_string_lossy(), reason.trim_end()),
+            UserOnlySetting { setting, file } => format!("`{}` can only be set in the user configuration, not in `{}`.", setting, file.to_string_lossy()),
+            ParseMetadata { file, reason } => format!("Invalid metadata in `{}`: {}. Delete the file to rebuild it.", file.to_string_lossy(), reason),
+            InvalidDuration { input } => format!("Invalid duration `{}` - use values such as `90d`, `12h` or `2w`.", input),
+            StaleKeys { count } => format!("{} key(s) exceed the maximum age - rotate them and update the profile.", count),
+            KeyNotFound { key, profile } => format!("Key `{}` does not exist in profile `{}`.", key, profile),
+            InvalidKey { key } => format!("`{}` is not a valid key. Keys start with a letter or `_`, followed by letters, digits, `_` or `.`.", key),
+            InvalidOverride { input } => format!("`{}` is not an assignment. Use `KEY=VALUE`.", input),
+            InvalidPattern { pattern, reason } => format!("`{}` is not a valid regular expression: {}", pattern, reason),
+            KeyRenameConflict { key, count } => format!("{} profile(s) already define `{}`. Remove it from them first.", count, key),
+            ExampleNotFound { file } => format!("The example file `{}` does not exist.", file.to_string_lossy()),
+            NoDeclaredKeys => "There are no keys to prompt for. Declare them in `.envoke/schema.toml` or `.env.example`.".into(),
+            VerificationFailed { count } => format!("{} profile(s) do not match the example file.", count),
+            IntegrityFailed { count } => format!("{} profile(s) changed outside envoke. Review them before trusting the changes.", count),
+            ProfileModified { profile } => format!("Profile `{}` changed outside envoke since it was last written.", profile),
+            SigningNotConfigured { setting, file } => format!("Signing is not configured - set `{}` in the `[signing]` section of `{}`.", setting, file.to_string_lossy()),
+            SigningFailed { reason } => format!("Failed to sign the profile: {}", reason),
+            SignatureMissing { profile } => format!("Profile `{}` is not signed. Sign it once it has been reviewed.", profile),
+            SignatureInvalid { profile, reason } => format!("The signature of profile `{}` is not valid: {}", profile, reason),
+            IncludeUnsigned { file, profile } => format!("`{}` is included by profile `{}` but is not signed.", file.to_string_lossy(), profile),
+            SignaturesFailed { count } => format!("{} profile(s) lack a valid signature.", count),
+            KeysMissing { count } => format!("{} profile(s) are missing keys that other profiles define.", count),
+            RequiredKeysMissing { keys } => format!("Required variable(s) missing or empty, so nothing was run: {}.", keys.join(", ")),
+            InvalidPatch { reason } => format!("Invalid patch: {}", reason),
+            PatchConflict { count } => format!("{} change(s) conflict with the profile - nothing was applied.", count),
+            Locked { user, time, reason: Some(reason) } => format!("Locked by {} at {}: {}.", user, time, reason),
+            Locked { user, time, reason: None } => format!("Locked by {} at {}.", user, time),
+            ReasonRequired => "A reason is required - pass `--reason`.".into(),
+            DryRunUnsupported => "This command cannot be previewed with `--dry-run`.".into(),
+            NoBackups { target } => format!("There are no backups of {}.", target),
+            BackupNotFound { backup } => format!("Backup `{}` does not exist.", backup),
+            BackupRequired => "No backup given - pass `--backup <NAME>`, or run in a terminal to choose one.".into(),
+            TrackedByGit { files } => {
+                let files: Vec<_> = files.iter().map(|f| f.to_string_lossy()).collect();
+                format!("Tracked by git, exposing values to anyone who can read the repository: {}.", files.join(", "))
+            }
+            SecretsFound { count } => format!("{} value(s) look like leaked secrets - add the keys to `[scan] allow` or comment `# envoke:allow` if they are expected.", count),
+            SchemaNotFound => "No schema defined - create `.envoke/schema.toml` to describe your keys.".into(),
+            ParseCompose { file, reason } => format!("Invalid compose file `{}`: {}", file.to_string_lossy(), reason.trim_end()),
+            ParseTerraform { file, reason } => format!("Invalid terraform file `{}`: {}", file.to_string_lossy(), reason.trim_end()),
+            ServiceNotFound { service, services } if services.is_empty() => format!("Service `{}` does not exist - the compose file defines no services.", service),
+            ServiceNotFound { service, services } => format!("Service `{}` does not exist. Available services: {}.", service, services.join(", ")),
+            ValidationFailed { count } => format!("{} profile(s) do not satisfy the schema.", count),
+            TemplateNotFound { template } => format!("Template `{}` does not exist.", template),
+            TemplateExists { template } => format!("Template `{}` already exists.", template),
+            GlobalProfileNotFound { profile, dir } => format!("Global profile `{}` does not exist in `{}`.", profile, dir.to_string_lossy()),
+            IncludeNotFound { file, from } => format!("The file `{}` included from `{}` does not exist.", file.to_string_lossy(), from.to_string_lossy()),
+            IncludeCycle { file } => format!("The file `{}` includes itself.", file.to_string_lossy()),
+            InvalidProfileName { profile } => format!("Invalid profile name `{}` - `+` is reserved for layering profiles.", profile),
+            Spawn { command, .. } => format!("Failed to run `{}`.", command),
+            NotActivated => "No profile is activated in this shell - activate one with: `eval \"$(envoke activate <profile>)\"`.".into(),
+            InvalidFallback { profile } => format!("Cannot switch to `{}` - it is the profile being removed.", profile),
+            ProfilesNotCreated { failures } => format!("{} profile(s) could not be created:{}", failures.len(), failures.iter().map(|(profile, e)| format!("\n  {}: {}", profile, e)).collect::<String>()),
+            HookFailed { hook, status } => format!("The `{}` hook failed ({}).", hook, status),
+            ConfigHookUntrusted { hook } => format!("Skipped the `{}` hook of `config.toml` as it has not been approved. Run envoke in a terminal to review it.", hook),
+            HooksUntrusted { profile } => format!("Skipped the hooks of profile `{}` as they have not been approved.", profile),
+            Bundle { reason } => format!("Invalid bundle: {}.", reason),
+            Decrypt => "Failed to decrypt the bundle - check the passphrase.".into(),
+            IdentityNotFound => "No identity to decrypt values with - create one or set ENVOKE_IDENTITY.".into(),
+            InvalidIdentity { origin } => format!("Invalid identity in {} - expected an `AGE-SECRET-KEY-1...` key.", origin),
+            InvalidRecipient { recipient } => format!("Invalid recipient `{}` - expected an `age1...` public key.", recipient),
+            RecipientExists { recipient } => format!("`{}` is already a recipient.", recipient),
+            RecipientNotFound { recipient } => format!("`{}` is not a recipient.", recipient),
+            NoRecipients => "No recipients to encrypt values to.".into(),
+            Encrypt { reason } => format!("Failed to encrypt the value: {}", reason),
+            DecryptValue { key } => format!("Failed to decrypt `{}` - it was not encrypted to your identity.", key),
+            GroupNotFound { group } => format!("Group `{}` is not defined. Add it under `[groups]` in `.envoke/config.toml`, e.g. `{} = [\"dev\", \"staging\"]`.", group, group),
+            RemoteNotFound { remote } => format!("Remote `{}` does not exist.", remote),
+            RemoteExists { remote } => format!("Remote `{}` already exists.", remote),
+            RemoteEmpty { remote } => format!("Nothing has been pushed to `{}` yet.", remote),
+            RemoteConflict { remote, pull: true } => format!("Both the local profiles and `{}` changed since the last sync, and conflicting keys need a terminal to resolve.", remote),
+            ProfilesConflict { remote, profiles } => format!("Changed both locally and in `{}` since the last sync: {} - nothing was pulled.", remote, profiles.join(", ")),
+            RemoteConflict { remote, pull: false } => format!("`{}` changed since the last sync.", remote),
+            RemoteCommand { command, reason } => format!("`{}` failed: {}", command, reason),
+            UnresolvedReferences { names } => format!("Referenced environment variable(s) not set: {}.", names.join(", ")),
+            ProfileRequired => "No profile given - pass a profile name, or run in a terminal to choose one.".into(),
+            NoProfiles => "There are no profiles to choose from.".into(),
+            TerminalRequired => "This command must be run in a terminal.".into(),
+            ConfirmationRequired => "Confirmation required - run in a terminal, or pass `--yes`.".into(),
+            Terminal { .. } => "Failed to use the terminal.".into(),
+            Download { url, reason } => format!("Cannot download `{}`: {}", url, reason),
+            InsecureUrl { url } => format!("Refusing to download `{}` - only `https://` URLs are allowed.", url),
+            ChecksumPinMismatch { url, expected, actual } => format!("`{}` does not match the pinned checksum - expected {} but got {}. Nothing was imported.", url, expected, actual),
+            ReleaseInvalid { reason } => format!("The latest release cannot be read: {}", reason),
+            ReleaseAssetMissing { asset } => format!("The latest release has no `{}` build for this platform.", asset),
+            ReleaseSignatureInvalid { asset, reason } => format!("`{}` is not signed with the envoke release key ({}) - nothing was installed.", asset, reason),
+            UnknownShell => "Cannot detect your shell - pass it explicitly, e.g. `envoke completions zsh`.".into(),
+            UnknownHome => "Cannot locate your home directory - set `HOME`.".into(),
+            CompletionsInstall { shell } => format!("Completions cannot be installed for {} - redirect `envoke completions {}` into your profile instead.", shell, shell),
+            StateDirUnavailable => "Cannot locate the envoke state directory - set `XDG_STATE_HOME` or `HOME`.".into(),
+            Output { .. } => "Failed to write output.".into(),
+            Input { .. } => "Failed to read input from stdin.".into(),
+            Watch { reason } => format!("Failed to watch for changes: {}.", reason),
+            GlobalDirUnavailable => "Cannot locate the global envoke directory - set `XDG_CONFIG_HOME` or `HOME`.".into(),
+            ProjectUninitialized { path } => format!("`{}` is not managed by envoke.", path.display()),
+            ProjectProfileNotFound { profile, path } => format!("Profile `{}` does not exist in `{}`.", profile, path.display()),
+            ProjectNotRegistered { path } => format!("`{}` is not a registered project - see `envoke projects`.", path.display()),
+            ProjectSwitchFailed { count } => format!("Failed to switch {} project(s).", count),
+            Prompt { .. } => "Failed to read input.".into(),
+            ClipboardUnavailable => "No clipboard utility found - install `wl-clipboard`, `xclip` or `xsel`.".into(),
+            Clipboard { command, .. } => format!("Failed to access the clipboard using `{}`.", command),
+        }
+    }
+
+    /// Suggests what to do next about the `ErrorKind` variant.
+    ///
+    /// # Examples
+    /// ```
+    /// use envoke::error::{ErrorKind, Hint};
+    ///
+    /// let expected = vec![Hint::Command("envoke init".into())];
+    /// assert_eq!(expected, ErrorKind::Uninitialized.hints());
+    /// assert!(ErrorKind::ClipboardUnavailable.hints().is_empty());
+    /// ```
+    pub fn hints(&self) -> Vec<Hint> {
+        use ErrorKind::*;
+
+        let command = |command: String| Hint::Command(command);
+        match self {
+            Initialized => vec![command("envoke init --ok-if-initialized".into())],
+            Uninitialized => vec![command("envoke init".into())],
+            ProfileNotFound { profile } => vec![
+                command(format!("envoke create {}", profile)),
+                command("envoke list".into()),
+            ],
+            NoActiveProfile => vec![command("envoke switch".into())],
+            NonLinkedEnv { command: replace } => vec![command(format!("{} --force", replace))],
+            ProfileModified { profile } => {
+                vec![command(format!(
+                    "envoke verify-integrity --accept {}",
+                    profile
+                ))]
+            }
+            TrackedByGit { files } => {
+                let files: Vec<_> = files.iter().map(|f| f.to_string_lossy()).collect();
+                vec![command(format!("git rm --cached {}", files.join(" ")))]
+            }
+            HooksUntrusted { profile } => vec![command(format!("envoke switch {}", profile))],
+            TemplateExists { template } => {
+                vec![command(format!("envoke template remove {}", template))]
+            }
+            RemoteConflict { remote, pull: true } => vec![
+                command(format!("envoke pull {}", remote)),
+                command(format!("envoke pull {} --strategy ours", remote)),
+                command(format!("envoke pull {} --strategy theirs", remote)),
+                command(format!("envoke pull {} --on-conflict copy", remote)),
+                command(format!("envoke pull {} --force", remote)),
+            ],
+            RemoteConflict {
+                remote,
+                pull: false,
+            } => vec![
+                command(format!("envoke pull {}", remote)),
+                command(format!("envoke push {} --force", remote)),
+            ],
+            ProfilesConflict { remote, .. } => vec![
+                command(format!("envoke pull {} --on-conflict merge", remote)),
+                command(format!("envoke pull {} --on-conflict copy", remote)),
+            ],
+            ProjectUninitialized { path } => {
+                vec![command(format!("cd {} && envoke init", path.display()))]
+            }
+            ExampleNotFound { .. } => vec![command("envoke example".into())],
+            IntegrityFailed { .. } => vec![
+                command("envoke verify-integrity --accept".into()),
+                Hint::docs("integrity-checks"),
+            ],
+            SignatureMissing { profile } => vec![
+                command(format!("envoke sign {}", profile)),
+                Hint::docs("signed-profiles"),
+            ],
+            IncludeUnsigned { profile, .. } => vec![
+                command(format!("envoke sign {}", profile)),
+                Hint::docs("signed-profiles"),
+            ],
+            SigningNotConfigured { .. } | UserOnlySetting { .. } | SignaturesFailed { .. } => {
+                vec![Hint::docs("signed-profiles")]
+            }
+            Locked { .. } => vec![command("envoke unlock".into())],
+            BackupNotFound { .. } => vec![command("envoke restore --list".into())],
+            SchemaNotFound | ValidationFailed { .. } => vec![Hint::docs("schema")],
+            NoProfiles => vec![Hint::docs("profile-management")],
+            ProfilesNotCreated { failures } => {
+                let mut hints: Vec<Hint> = Vec::new();
+                for hint in failures.iter().flat_map(|(_, e)| e.hints()) {
+                    if !hints.contains(hint) {
+                        hints.push(hint.clone());
+                    }
+                }
+                hints
+            }
+            TemplateNotFound { .. } => vec![command("envoke template list".into())],
+            IdentityNotFound => vec![
+                command("envoke keygen".into()),
+                Hint::docs("encrypted-values"),
+            ],
+            RecipientNotFound { .. } => vec![command("envoke recipients list".into())],
+            RemoteNotFound { remote } => {
+                vec![command(format!("envoke remote add {} <url>", remote))]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Where the documentation is published, for hints pointing to a section.
+pub const DOCS_URL: &str = "https://github.com/TSxo/envoke";
+
+/// A suggestion shown beneath an error on what to do next.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Hint {
+    /// A command that resolves the error.
+    Command(String),
+
+    /// The URL of the documentation explaining the error.
+    Docs(String),
+}
+
+impl Hint {
+    /// Creates a hint pointing to a section of the documentation.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor` - The anchor of the section, e.g. `schema`.
+    ///
+    /// # Examples
+    /// ```
+    /// use envoke::error::Hint;
+    ///
+    /// let expected = "https://github.com/TSxo/envoke#schema";
+    /// assert_eq!(Hint::Docs(expected.into()), Hint::docs("schema"));
+    /// ```
+    pub fn docs(anchor: &str) -> Self {
+        Hint::Docs(format!("{}#{}", DOCS_URL, anchor))
+    }
+}
+
+impl fmt::Display for Hint {
+    /// Shows the hint as a sentence fragment, e.g. "run `envoke init`".
+    ///
+    /// # Examples
+    /// ```
+    /// use envoke::error::Hint;
+    ///
+    /// assert_eq!("run `envoke init`", Hint::Command("envoke init".into()).to_string());
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hint::Command(command) => write!(f, "run `{}`", command),
+            Hint::Docs(url) => write!(f, "see {}", url),
         }
     }
 }
@@ -129,11 +754,24 @@ impl error::Error for ErrorKind {
         match self {
             ErrorKind::OpenFile { source, .. } => Some(source),
             ErrorKind::CreateFile { source, .. } => Some(source),
+            ErrorKind::RemoveFile { source, .. } => Some(source),
+            ErrorKind::RemoveDir { source, .. } => Some(source),
             ErrorKind::CreateDir { source, .. } => Some(source),
             ErrorKind::ReadDir { source, .. } => Some(source),
             ErrorKind::WriteFile { source, .. } => Some(source),
             ErrorKind::CreateSymlink { source, .. } => Some(source),
+            ErrorKind::CreateHardlink { source, .. } => Some(source),
             ErrorKind::ReadLink { source, .. } => Some(source),
+            ErrorKind::ReadFile { source, .. } => Some(source),
+            ErrorKind::ReadMetadata { source, .. } => Some(source),
+            ErrorKind::CopyFile { source, .. } => Some(source),
+            ErrorKind::RenameFile { source, .. } => Some(source),
+            ErrorKind::Clipboard { source, .. } => Some(source),
+            ErrorKind::Prompt { source } => Some(source),
+            ErrorKind::Spawn { source, .. } => Some(source),
+            ErrorKind::Output { source } => Some(source),
+            ErrorKind::Input { source } => Some(source),
+            ErrorKind::Terminal { source } => Some(source),
             _ => None,
         }
     }
@@ -155,7 +793,17 @@ impl From<ErrorKind> for Error {
     /// assert_eq!(expected, format!("{error}"));
     /// ```
     fn from(kind: ErrorKind) -> Self {
-        Error { kind }
+        Error::new(kind)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    /// Converts a failure to write command output into an [`Error`].
+    ///
+    /// This allows `?` on `writeln!` in commands. Other I/O errors should be
+    /// wrapped in an [`ErrorKind`] naming the file involved instead.
+    fn from(source: std::io::Error) -> Self {
+        Error::new(ErrorKind::Output { source })
     }
 }
 
@@ -163,9 +811,49 @@ impl From<ErrorKind> for Error {
 #[derive(Debug)]
 pub struct Error {
     pub(crate) kind: ErrorKind,
+    hints: Vec<Hint>,
 }
 
+/// Exit code for errors not covered by a more specific code.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for a profile that fails a check, such as schema validation.
+pub const EXIT_CHECK_FAILED: i32 = 3;
+
+/// Exit code for `switch --if-exists` when the profile does not exist.
+pub const EXIT_SKIPPED: i32 = 4;
+
 impl Error {
+    /// Gets the process exit code for the error.
+    ///
+    /// Failed checks use a distinct code so scripts can tell a bad profile
+    /// apart from a broken setup. Code 2 is reserved for usage errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::error::{EXIT_CHECK_FAILED, EXIT_FAILURE, Error, ErrorKind};
+    ///
+    /// assert_eq!(EXIT_FAILURE, Error::new(ErrorKind::Initialized).exit_code());
+    /// assert_eq!(
+    ///     EXIT_CHECK_FAILED,
+    ///     Error::new(ErrorKind::ValidationFailed { count: 1 }).exit_code()
+    /// );
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self.kind {
+            ErrorKind::StaleKeys { .. }
+            | ErrorKind::VerificationFailed { .. }
+            | ErrorKind::IntegrityFailed { .. }
+            | ErrorKind::SignaturesFailed { .. }
+            | ErrorKind::KeysMissing { .. }
+            | ErrorKind::SecretsFound { .. }
+            | ErrorKind::ValidationFailed { .. }
+            | ErrorKind::UnresolvedReferences { .. } => EXIT_CHECK_FAILED,
+            _ => EXIT_FAILURE,
+        }
+    }
+
     /// Creates a new `Error` from an [`ErrorKind`].
     ///
     /// # Examples
@@ -178,7 +866,28 @@ impl Error {
     /// assert_eq!(expected, error.to_string());
     /// ```
     pub fn new(kind: ErrorKind) -> Self {
-        Error { kind }
+        let hints = kind.hints();
+        Error { kind, hints }
+    }
+
+    /// Adds a hint, shown after those suggested for the [`ErrorKind`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::error::{Error, ErrorKind, Hint};
+    ///
+    /// let error = Error::new(ErrorKind::Uninitialized).with_hint(Hint::docs("installation"));
+    /// assert_eq!(2, error.hints().len());
+    /// ```
+    pub fn with_hint(mut self, hint: Hint) -> Self {
+        self.hints.push(hint);
+        self
+    }
+
+    /// Gets the hints on what to do next, most relevant first.
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
     }
 }
 
@@ -222,7 +931,7 @@ mod tests {
         );
 
         assert_eq!(
-            "Directory has not been initialized - please run `envoke init`.",
+            "Directory has not been initialized.",
             ErrorKind::Uninitialized.as_string()
         );
 
@@ -333,4 +1042,40 @@ mod tests {
             result.unwrap_err().to_string()
         );
     }
+
+    #[test]
+    fn test_error_hints() {
+        let error = Error::from(ErrorKind::ProfileNotFound {
+            profile: "dev".into(),
+        });
+        assert_eq!(
+            [
+                Hint::Command("envoke create dev".into()),
+                Hint::Command("envoke list".into())
+            ],
+            error.hints()
+        );
+
+        let error = Error::new(ErrorKind::Initialized).with_hint(Hint::docs("installation"));
+        assert_eq!(
+            "see https://github.com/TSxo/envoke#installation",
+            error.hints()[1].to_string()
+        );
+
+        // Advice is given as hints rather than repeated in the message.
+        let error = Error::from(ErrorKind::NonLinkedEnv {
+            command: "envoke switch dev".into(),
+        });
+        assert_eq!(
+            "The current `.env` is not managed by envoke.",
+            error.to_string()
+        );
+        assert_eq!(
+            [Hint::Command("envoke switch dev --force".into())],
+            error.hints()
+        );
+
+        let json = serde_json::to_string(&Hint::Command("envoke init".into())).unwrap();
+        assert_eq!(r#"{"command":"envoke init"}"#, json);
+    }
 }
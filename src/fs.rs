@@ -2,21 +2,38 @@
 //!
 //! This module provides an abstract `FileSystem` trait and a concrete implementation
 //! `EnvokeFileSystem` that wraps standard filesystem operations. This abstraction
-//! enables easier testing and potential alternative implementations.
+//! enables easier testing and potential alternative implementations. With the
+//! `testing` feature, an in-memory `MockFileSystem` is also available.
 
 use crate::error::Error;
 use crate::error::ErrorKind;
 use crate::error::Result;
 use std::fs;
 use std::fs::File;
-use std::fs::ReadDir;
-use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use tracing::{debug, trace};
+
+mod dry_run;
+#[cfg(any(test, feature = "testing"))]
+mod mock;
+
+pub use dry_run::{Change, DryRunFile, DryRunFileSystem};
+
+#[cfg(any(test, feature = "testing"))]
+pub use mock::MockFileSystem;
 
 /// Trait defining essential filesystem operations.
 ///
 /// This trait abstracts filesystem interactions, allowing for different
 /// implementations including mock implementations for testing purposes.
 pub trait FileSystem {
+    /// A handle to an open file.
+    type File: Read + Write;
+
     /// Checks if a path exists in the filesystem.
     ///
     /// # Arguments
@@ -48,7 +65,7 @@ pub trait FileSystem {
     /// # Returns
     ///
     /// `Ok(())` on success, or an `Error` if file creation fails.
-    fn create_file(&self, path: &Path) -> Result<File>;
+    fn create_file(&self, path: &Path) -> Result<Self::File>;
 
     /// Reads the contents of a directory.
     ///
@@ -58,8 +75,8 @@ pub trait FileSystem {
     ///
     /// # Returns
     ///
-    /// `Ok(ReadDir)` iterator on success, or an `Error` if reading fails.
-    fn read_dir(&self, path: &Path) -> Result<ReadDir>;
+    /// The paths of the entries in the directory, or an `Error` if reading fails.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
 
     /// Opens a file with the specified options and returns a handle to it.
     ///
@@ -71,7 +88,7 @@ pub trait FileSystem {
     /// # Returns
     ///
     /// `Ok(File)` handle on success, or an `Error` if opening fails.
-    fn open_file(&self, path: &Path, options: &fs::OpenOptions) -> Result<File>;
+    fn open_file(&self, path: &Path, options: &OpenOptions) -> Result<Self::File>;
 
     /// Checks if a path is a symbolic link.
     ///
@@ -107,6 +124,31 @@ pub trait FileSystem {
     /// `Ok(PathBuf)` containing the target path on success, or an `Error` if operation fails.
     fn read_link(&self, path: &Path) -> Result<PathBuf>;
 
+    /// Creates a hard link, a second name for the same file.
+    ///
+    /// # Arguments
+    ///
+    /// * `original` - The existing file.
+    /// * `link` - The path where the link should be created.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the link cannot be created, e.g.
+    /// because the paths are on different filesystems.
+    fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()>;
+
+    /// Checks whether two paths name the same file, as hard links do.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The first path.
+    /// * `b` - The second path.
+    ///
+    /// # Returns
+    ///
+    /// `true` if both paths exist and refer to the same file.
+    fn same_file(&self, a: &Path, b: &Path) -> bool;
+
     /// Removes a file or symlink at the specified path.
     ///
     /// # Arguments
@@ -117,6 +159,330 @@ pub trait FileSystem {
     ///
     /// `Ok(())` on success, or an `Error` if removal fails.
     fn remove_file(&self, path: &Path) -> Result<()>;
+
+    /// Removes a directory and everything beneath it. Symlinks inside it are
+    /// removed, not followed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory to remove.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if removal fails.
+    fn remove_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// Queries the metadata of a file or directory, following symlinks.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to query.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Metadata)` on success, or an `Error` if the path cannot be queried.
+    fn metadata(&self, path: &Path) -> Result<Metadata>;
+
+    /// Reads the entire contents of a file as UTF-8 text.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(String)` containing the contents on success, or an `Error` if the
+    /// file cannot be read or is not valid UTF-8.
+    fn read_to_string(&self, path: &Path) -> Result<String>;
+
+    /// Writes contents to a file, creating it or replacing its contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    /// * `contents` - The bytes to write.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the file cannot be written.
+    fn write_all(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Replaces the contents of a file so that a crash leaves either the old
+    /// or the new contents, never a mix.
+    ///
+    /// The contents are written to a temporary file in the same directory,
+    /// flushed to disk and renamed over the file. Symlinks are followed, so a
+    /// linked file is updated in place and the link is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    /// * `contents` - The bytes to write.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the file cannot be written.
+    fn write_atomic(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Copies the contents of a file, following symlinks, replacing the
+    /// destination if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The file to copy.
+    /// * `to` - The path of the copy.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the file cannot be copied.
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// Renames a file, directory or symlink, replacing the destination if it
+    /// is a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The current path.
+    /// * `to` - The new path.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the path cannot be renamed.
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// Gets the type of the filesystem holding a path, as named in
+    /// `/proc/mounts`, e.g. `ext4` or `drvfs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to check.
+    ///
+    /// # Returns
+    ///
+    /// The filesystem type, or `None` if it cannot be determined.
+    fn filesystem_type(&self, path: &Path) -> Option<String>;
+}
+
+/// Filesystems on which symbolic links cannot be created, or are not followed
+/// by every tool: Windows drives under WSL, FAT and exFAT volumes, and SMB
+/// and NFS shares.
+pub const UNRELIABLE_SYMLINK_FILESYSTEMS: &[&str] = &[
+    "drvfs", "vfat", "msdos", "exfat", "cifs", "smb3", "smbfs", "nfs", "nfs4",
+];
+
+/// Checks whether symbolic links are unreliable on a type of filesystem.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::fs::symlinks_unreliable;
+///
+/// assert!(symlinks_unreliable("drvfs"));
+/// assert!(!symlinks_unreliable("ext4"));
+/// ```
+pub fn symlinks_unreliable(filesystem_type: &str) -> bool {
+    UNRELIABLE_SYMLINK_FILESYSTEMS.contains(&filesystem_type)
+}
+
+/// Finds the type of the filesystem holding a path in a mount table.
+///
+/// WSL 2 mounts Windows drives as `9p` filesystems with `aname=drvfs`, which
+/// are reported as `drvfs`.
+///
+/// # Arguments
+///
+/// * `mounts` - The contents of `/proc/mounts`.
+/// * `path` - An absolute path.
+///
+/// # Returns
+///
+/// The type of the mount with the longest mount point containing the path,
+/// or `None` if there is none.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use envoke::fs::mount_type;
+///
+/// let mounts = "/dev/sda1 / ext4 rw 0 0\nC:\\134 /mnt/c 9p rw,aname=drvfs;path=C:\\ 0 0\n";
+/// assert_eq!(Some("drvfs".to_string()), mount_type(mounts, Path::new("/mnt/c/app")));
+/// assert_eq!(Some("ext4".to_string()), mount_type(mounts, Path::new("/home/app")));
+/// ```
+pub fn mount_type(mounts: &str, path: &Path) -> Option<String> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, point, fs_type, options) = (
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+            );
+            // Spaces in mount points are escaped as `\040`.
+            let point = PathBuf::from(point.replace("\\040", " "));
+            if !path.starts_with(&point) {
+                return None;
+            }
+
+            let fs_type = if fs_type == "9p" && options.contains("aname=drvfs") {
+                "drvfs"
+            } else {
+                fs_type
+            };
+            Some((point.components().count(), fs_type.to_string()))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Options describing how a file is opened.
+///
+/// This mirrors [`std::fs::OpenOptions`], but can be inspected so that
+/// implementations other than the local filesystem can honor it.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::fs::OpenOptions;
+///
+/// let mut options = OpenOptions::new();
+/// options.write(true).create(true).truncate(true);
+/// assert!(options.is_write());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+}
+
+impl OpenOptions {
+    /// Creates options with every flag unset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the file can be read.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    /// Sets whether the file can be written.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Sets whether writes are appended to the end of the file.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    /// Sets whether an existing file is emptied when opened.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Sets whether the file is created if it does not exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Checks whether the file can be read.
+    pub fn is_read(&self) -> bool {
+        self.read
+    }
+
+    /// Checks whether the file can be written, including by appending.
+    pub fn is_write(&self) -> bool {
+        self.write || self.append
+    }
+
+    /// Checks whether writes are appended to the end of the file.
+    pub fn is_append(&self) -> bool {
+        self.append
+    }
+
+    /// Checks whether an existing file is emptied when opened.
+    pub fn is_truncate(&self) -> bool {
+        self.truncate
+    }
+
+    /// Checks whether the file is created if it does not exist.
+    pub fn is_create(&self) -> bool {
+        self.create
+    }
+
+    /// Converts the options for use with the standard library.
+    pub fn to_std(&self) -> fs::OpenOptions {
+        let mut options = fs::OpenOptions::new();
+        options
+            .read(self.read)
+            .write(self.write)
+            .append(self.append)
+            .truncate(self.truncate)
+            .create(self.create);
+        options
+    }
+}
+
+/// Metadata about a file or directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    is_dir: bool,
+    len: u64,
+    modified: SystemTime,
+}
+
+impl Metadata {
+    /// Creates metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `is_dir` - Whether the path is a directory.
+    /// * `len` - The size of the file in bytes.
+    /// * `modified` - When the file was last modified.
+    pub fn new(is_dir: bool, len: u64, modified: SystemTime) -> Self {
+        Metadata {
+            is_dir,
+            len,
+            modified,
+        }
+    }
+
+    /// Checks whether the path is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Checks whether the path is a regular file.
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Checks whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns when the file was last modified.
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
 }
 
 /// Standard implementation of the `FileSystem` trait using the local filesystem.
@@ -136,12 +502,21 @@ impl EnvokeFileSystem {
     }
 }
 
+impl Default for EnvokeFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FileSystem for EnvokeFileSystem {
+    type File = File;
+
     fn path_exists(&self, path: &Path) -> bool {
         path.exists()
     }
 
     fn create_dir(&self, path: &Path) -> Result<()> {
+        debug!(path = %path.display(), "creating directory");
         fs::create_dir_all(path).map_err(|e| {
             Error::new(ErrorKind::CreateDir {
                 file: path.to_path_buf(),
@@ -151,6 +526,7 @@ impl FileSystem for EnvokeFileSystem {
     }
 
     fn create_file(&self, path: &Path) -> Result<File> {
+        debug!(path = %path.display(), "creating file");
         fs::File::create_new(path).map_err(|e| {
             Error::new(ErrorKind::CreateFile {
                 file: path.to_path_buf(),
@@ -159,17 +535,24 @@ impl FileSystem for EnvokeFileSystem {
         })
     }
 
-    fn read_dir(&self, path: &Path) -> Result<ReadDir> {
-        fs::read_dir(path).map_err(|e| {
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        trace!(path = %path.display(), "reading directory");
+        let error = |e| {
             Error::new(ErrorKind::ReadDir {
                 file: path.to_path_buf(),
                 source: e,
             })
-        })
+        };
+
+        fs::read_dir(path)
+            .map_err(error)?
+            .map(|entry| entry.map(|entry| entry.path()).map_err(error))
+            .collect()
     }
 
-    fn open_file(&self, path: &Path, options: &fs::OpenOptions) -> Result<File> {
-        options.open(path).map_err(|e| {
+    fn open_file(&self, path: &Path, options: &OpenOptions) -> Result<File> {
+        trace!(path = %path.display(), "opening file");
+        options.to_std().open(path).map_err(|e| {
             Error::new(ErrorKind::OpenFile {
                 file: path.to_path_buf(),
                 source: e,
@@ -185,6 +568,7 @@ impl FileSystem for EnvokeFileSystem {
     }
 
     fn create_symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        debug!(link = %link.display(), original = %original.display(), "creating symlink");
         std::os::unix::fs::symlink(original, link).map_err(|e| {
             Error::new(ErrorKind::CreateSymlink {
                 link: link.to_path_buf(),
@@ -195,6 +579,7 @@ impl FileSystem for EnvokeFileSystem {
     }
 
     fn read_link(&self, path: &Path) -> Result<std::path::PathBuf> {
+        trace!(path = %path.display(), "reading symlink");
         fs::read_link(path).map_err(|e| {
             Error::new(ErrorKind::ReadLink {
                 file: path.to_path_buf(),
@@ -203,7 +588,28 @@ impl FileSystem for EnvokeFileSystem {
         })
     }
 
+    fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()> {
+        debug!(link = %link.display(), original = %original.display(), "creating hard link");
+        fs::hard_link(original, link).map_err(|e| {
+            Error::new(ErrorKind::CreateHardlink {
+                link: link.to_path_buf(),
+                original: original.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
     fn remove_file(&self, path: &Path) -> Result<()> {
+        debug!(path = %path.display(), "removing file");
         fs::remove_file(path).map_err(|e| {
             Error::new(ErrorKind::RemoveFile {
                 file: path.to_path_buf(),
@@ -211,6 +617,192 @@ impl FileSystem for EnvokeFileSystem {
             })
         })
     }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        debug!(path = %path.display(), "removing directory");
+        fs::remove_dir_all(path).map_err(|e| {
+            Error::new(ErrorKind::RemoveDir {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        let error = |e| {
+            Error::new(ErrorKind::ReadMetadata {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        };
+
+        let metadata = fs::metadata(path).map_err(error)?;
+        let modified = metadata.modified().map_err(error)?;
+
+        Ok(Metadata::new(metadata.is_dir(), metadata.len(), modified))
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        trace!(path = %path.display(), "reading file");
+        fs::read_to_string(path).map_err(|e| {
+            Error::new(ErrorKind::ReadFile {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn write_all(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        debug!(path = %path.display(), "writing file");
+        fs::write(path, contents).map_err(|e| {
+            Error::new(ErrorKind::WriteFile {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn write_atomic(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let error = |e| {
+            Error::new(ErrorKind::WriteFile {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        };
+
+        let target = follow_links(path).map_err(error)?;
+        let dir = match target.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let permissions = fs::metadata(&target).ok().map(|meta| meta.permissions());
+
+        let (temp_path, mut file) = create_temp_file(dir, &target).map_err(error)?;
+        debug!(path = %target.display(), temp = %temp_path.display(), "writing file atomically");
+
+        let written = file
+            .write_all(contents)
+            .and_then(|_| match permissions {
+                Some(permissions) => file.set_permissions(permissions),
+                None => Ok(()),
+            })
+            .and_then(|_| file.sync_all());
+        drop(file);
+
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error(e));
+        }
+
+        if let Err(e) = fs::rename(&temp_path, &target) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(Error::new(ErrorKind::RenameFile {
+                from: temp_path,
+                to: target,
+                source: e,
+            }));
+        }
+
+        // Persist the rename itself. Some filesystems cannot sync directories,
+        // and the new contents are in place either way.
+        if let Err(e) = File::open(dir).and_then(|dir| dir.sync_all()) {
+            trace!(dir = %dir.display(), error = %e, "failed to sync directory");
+        }
+
+        Ok(())
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<()> {
+        debug!(from = %from.display(), to = %to.display(), "copying file");
+        fs::copy(from, to).map(|_| ()).map_err(|e| {
+            Error::new(ErrorKind::CopyFile {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        debug!(from = %from.display(), to = %to.display(), "renaming file");
+        fs::rename(from, to).map_err(|e| {
+            Error::new(ErrorKind::RenameFile {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source: e,
+            })
+        })
+    }
+
+    fn filesystem_type(&self, path: &Path) -> Option<String> {
+        let mounts = fs::read_to_string("/proc/self/mounts").ok()?;
+        let fs_type = mount_type(&mounts, &fs::canonicalize(path).ok()?);
+        trace!(path = %path.display(), ?fs_type, "detected filesystem type");
+        fs_type
+    }
+}
+
+/// The most symbolic links followed when resolving a path.
+const MAX_LINKS: usize = 40;
+
+/// Removes `.` components and resolves `..` components lexically.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Follows symbolic links to the file they point to, which need not exist.
+fn follow_links(path: &Path) -> std::io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..MAX_LINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+
+    Err(std::io::Error::other("too many levels of symbolic links"))
+}
+
+/// Creates a uniquely named temporary file next to `target`.
+///
+/// The file is created with the default permissions for new files, rather
+/// than the owner-only permissions of [`tempfile`].
+fn create_temp_file(dir: &Path, target: &Path) -> std::io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    loop {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = dir.join(format!(".{}.{}.{}.tmp", name, process::id(), count));
+
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -218,6 +810,7 @@ mod tests {
     use super::*;
     use std::fs;
     use std::io::{Read, Write};
+    use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
 
     fn setup() -> (EnvokeFileSystem, tempfile::TempDir) {
@@ -281,11 +874,9 @@ mod tests {
         let result = fs_impl.read_dir(temp_dir.path());
         assert!(result.is_ok());
 
-        let entries: Vec<_> = result
-            .unwrap()
-            .collect::<std::io::Result<Vec<_>>>()
-            .unwrap();
+        let entries = result.unwrap();
         assert_eq!(entries.len(), 2);
+        assert!(entries.contains(&file1));
 
         let non_existent_dir = temp_dir.path().join("non_existent_dir");
         let result = fs_impl.read_dir(&non_existent_dir);
@@ -336,7 +927,7 @@ mod tests {
         let mut file = fs_impl.create_file(&file_path).unwrap();
         file.write_all(b"Hello, world!").unwrap();
 
-        let mut options = fs::OpenOptions::new();
+        let mut options = OpenOptions::new();
         options.read(true);
 
         let result = fs_impl.open_file(&file_path, &options);
@@ -379,7 +970,7 @@ mod tests {
         assert_eq!(read_target, original_path);
 
         // Test reading content through the symlink.
-        let mut options = fs::OpenOptions::new();
+        let mut options = OpenOptions::new();
         options.read(true);
         let mut link_file = fs_impl.open_file(&link_path, &options).unwrap();
         let mut content = String::new();
@@ -387,6 +978,31 @@ mod tests {
         assert_eq!(content, "Target content");
     }
 
+    #[test]
+    fn test_hardlink_operations() {
+        let (fs_impl, temp_dir) = setup();
+        let original_path = temp_dir.path().join("target_file.txt");
+        let link_path = temp_dir.path().join("hardlink_file.txt");
+        fs_impl
+            .write_all(&original_path, b"Target content")
+            .unwrap();
+
+        fs_impl.create_hardlink(&original_path, &link_path).unwrap();
+        assert!(!fs_impl.is_symlink(&link_path));
+        assert!(fs_impl.same_file(&original_path, &link_path));
+
+        // Writes in place are seen through both names.
+        fs_impl.write_all(&original_path, b"Changed").unwrap();
+        assert_eq!("Changed", fs_impl.read_to_string(&link_path).unwrap());
+
+        // An atomic write replaces the file, leaving the link behind.
+        fs_impl.write_atomic(&original_path, b"Replaced").unwrap();
+        assert!(!fs_impl.same_file(&original_path, &link_path));
+        assert!(!fs_impl.same_file(&original_path, &temp_dir.path().join("missing")));
+
+        assert!(fs_impl.create_hardlink(&original_path, &link_path).is_err());
+    }
+
     #[test]
     fn test_remove_symlink() {
         let (fs_impl, temp_dir) = setup();
@@ -411,4 +1027,149 @@ mod tests {
         assert!(!fs_impl.path_exists(&link_path));
         assert!(fs_impl.path_exists(&original_path));
     }
+
+    #[test]
+    fn test_metadata() {
+        let (fs_impl, temp_dir) = setup();
+
+        let file_path = temp_dir.path().join("test_file.txt");
+        let mut file = fs_impl.create_file(&file_path).unwrap();
+        file.write_all(b"Hello").unwrap();
+
+        let metadata = fs_impl.metadata(&file_path).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), 5);
+
+        let non_existent_file = temp_dir.path().join("non_existent_file.txt");
+        match fs_impl.metadata(&non_existent_file).unwrap_err().kind {
+            ErrorKind::ReadMetadata { .. } => (),
+            _ => panic!("Expected ReadMetadata error"),
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_to_string() {
+        let (fs_impl, temp_dir) = setup();
+        let file_path = temp_dir.path().join("test_file.txt");
+
+        fs_impl.write_all(&file_path, b"first").unwrap();
+        fs_impl.write_all(&file_path, b"second").unwrap();
+        assert_eq!(fs_impl.read_to_string(&file_path).unwrap(), "second");
+
+        let non_existent_file = temp_dir.path().join("non_existent_file.txt");
+        match fs_impl.read_to_string(&non_existent_file).unwrap_err().kind {
+            ErrorKind::ReadFile { .. } => (),
+            _ => panic!("Expected ReadFile error"),
+        }
+
+        let missing_dir_file = temp_dir.path().join("missing/file.txt");
+        match fs_impl.write_all(&missing_dir_file, b"").unwrap_err().kind {
+            ErrorKind::WriteFile { .. } => (),
+            _ => panic!("Expected WriteFile error"),
+        }
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let (fs_impl, temp_dir) = setup();
+        let file_path = temp_dir.path().join("dev.env");
+
+        fs_impl.write_atomic(&file_path, b"A=1\n").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o600)).unwrap();
+        fs_impl.write_atomic(&file_path, b"A=2\n").unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "A=2\n");
+        let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Writing through a link updates the linked file and keeps the link.
+        let link_path = temp_dir.path().join(".env");
+        std::os::unix::fs::symlink("dev.env", &link_path).unwrap();
+        fs_impl.write_atomic(&link_path, b"A=3\n").unwrap();
+        assert!(link_path.is_symlink());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "A=3\n");
+
+        // No temporary files are left behind.
+        let entries = fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(entries, 2);
+
+        let missing_dir_file = temp_dir.path().join("missing/file.txt");
+        match fs_impl
+            .write_atomic(&missing_dir_file, b"")
+            .unwrap_err()
+            .kind
+        {
+            ErrorKind::WriteFile { .. } => (),
+            _ => panic!("Expected WriteFile error"),
+        }
+    }
+
+    #[test]
+    fn test_copy_and_rename() {
+        let (fs_impl, temp_dir) = setup();
+        let original = temp_dir.path().join("original.txt");
+        let copy = temp_dir.path().join("copy.txt");
+        let renamed = temp_dir.path().join("renamed.txt");
+        fs::write(&original, "Hello").unwrap();
+
+        fs_impl.copy_file(&original, &copy).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "Hello");
+
+        fs_impl.rename(&copy, &renamed).unwrap();
+        assert!(!copy.exists());
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "Hello");
+
+        match fs_impl.copy_file(&copy, &original).unwrap_err().kind {
+            ErrorKind::CopyFile { .. } => (),
+            _ => panic!("Expected CopyFile error"),
+        }
+        match fs_impl.rename(&copy, &original).unwrap_err().kind {
+            ErrorKind::RenameFile { .. } => (),
+            _ => panic!("Expected RenameFile error"),
+        }
+    }
+
+    #[test]
+    fn test_remove_dir_all() {
+        let (fs_impl, temp_dir) = setup();
+        let dir = temp_dir.path().join("dir");
+        let kept = temp_dir.path().join("kept.txt");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/file.txt"), "Hello").unwrap();
+        fs::write(&kept, "Kept").unwrap();
+        std::os::unix::fs::symlink(&kept, dir.join("link")).unwrap();
+
+        fs_impl.remove_dir_all(&dir).unwrap();
+        assert!(!dir.exists());
+        assert!(kept.exists());
+
+        match fs_impl.remove_dir_all(&dir).unwrap_err().kind {
+            ErrorKind::RemoveDir { .. } => (),
+            _ => panic!("Expected RemoveDir error"),
+        }
+    }
+
+    #[test]
+    fn test_mount_type() {
+        let mounts = "\
+/dev/sda1 / ext4 rw 0 0
+server:/export /home/app nfs4 rw 0 0
+/dev/sdb1 /media/My\\040Stick vfat rw 0 0
+";
+        assert_eq!(
+            Some("nfs4".to_string()),
+            mount_type(mounts, Path::new("/home/app/src"))
+        );
+        assert_eq!(
+            Some("ext4".to_string()),
+            mount_type(mounts, Path::new("/home/application"))
+        );
+        assert_eq!(
+            Some("vfat".to_string()),
+            mount_type(mounts, Path::new("/media/My Stick/app"))
+        );
+        assert_eq!(None, mount_type("", Path::new("/home")));
+
+        assert!(symlinks_unreliable("drvfs"));
+        assert!(!symlinks_unreliable("ext4"));
+    }
 }
@@ -0,0 +1,630 @@
+//! A `FileSystem` that records changes instead of making them.
+//!
+//! `DryRunFileSystem` wraps another filesystem. Reads go to the wrapped
+//! filesystem, while writes, links and removals are kept in an in-memory
+//! overlay and recorded as [`Change`]s. Later reads see the overlay, so a
+//! command that creates a profile and then switches to it behaves as it would
+//! for real. This is what `--dry-run` uses, so every command that goes through
+//! the `FileSystem` trait can be previewed without knowing about it.
+//!
+//! Data written through a handle from `create_file` or `open_file` is
+//! discarded, as envoke only writes the operation log that way.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use super::{FileSystem, MAX_LINKS, Metadata, OpenOptions, normalize};
+use crate::error::{Error, ErrorKind, Result};
+
+/// A change a dry run would have made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A directory would be created.
+    CreateDir(PathBuf),
+
+    /// A file would be created.
+    Create(PathBuf),
+
+    /// An existing file would be rewritten.
+    Rewrite(PathBuf),
+
+    /// Data would be appended to a file.
+    Append(PathBuf),
+
+    /// A file, link or directory would be removed.
+    Remove(PathBuf),
+
+    /// A symbolic link would be created.
+    Link { link: PathBuf, original: PathBuf },
+
+    /// A hard link would be created.
+    Hardlink { link: PathBuf, original: PathBuf },
+
+    /// A path would be renamed.
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::CreateDir(path) => write!(f, "create directory {}", path.display()),
+            Change::Create(path) => write!(f, "create {}", path.display()),
+            Change::Rewrite(path) => write!(f, "rewrite {}", path.display()),
+            Change::Append(path) => write!(f, "append to {}", path.display()),
+            Change::Remove(path) => write!(f, "remove {}", path.display()),
+            Change::Link { link, original } => {
+                write!(f, "link {} -> {}", link.display(), original.display())
+            }
+            Change::Hardlink { link, original } => {
+                write!(f, "hard link {} to {}", link.display(), original.display())
+            }
+            Change::Rename { from, to } => {
+                write!(f, "rename {} to {}", from.display(), to.display())
+            }
+        }
+    }
+}
+
+/// A filesystem that records changes instead of making them.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use envoke::fs::{Change, DryRunFileSystem, EnvokeFileSystem, FileSystem};
+///
+/// let fs = DryRunFileSystem::new(EnvokeFileSystem::new());
+/// fs.write_atomic(Path::new("dev.env"), b"PORT=8080\n").unwrap();
+///
+/// assert_eq!("PORT=8080\n", fs.read_to_string(Path::new("dev.env")).unwrap());
+/// assert_eq!(vec![Change::Create("dev.env".into())], fs.changes());
+/// assert!(!Path::new("dev.env").exists());
+/// ```
+#[derive(Debug)]
+pub struct DryRunFileSystem<F> {
+    inner: F,
+    state: Mutex<State>,
+}
+
+/// The overlay and changes of a `DryRunFileSystem`.
+#[derive(Debug, Default)]
+struct State {
+    overlay: BTreeMap<PathBuf, Node>,
+    changes: Vec<Change>,
+}
+
+/// An entry in the overlay.
+#[derive(Debug, Clone)]
+enum Node {
+    Dir,
+    /// A directory created where a removed one was, hiding whatever the
+    /// wrapped filesystem holds beneath it.
+    EmptyDir,
+    File(Vec<u8>),
+    Symlink(PathBuf),
+    Removed,
+}
+
+/// A handle to a file in a `DryRunFileSystem`.
+#[derive(Debug)]
+pub enum DryRunFile<T> {
+    /// A file read from the wrapped filesystem.
+    Real(T),
+
+    /// A file in the overlay, or a write that is discarded.
+    Buffer(Cursor<Vec<u8>>),
+}
+
+impl<T: Read> Read for DryRunFile<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            DryRunFile::Real(file) => file.read(buf),
+            DryRunFile::Buffer(buffer) => buffer.read(buf),
+        }
+    }
+}
+
+impl<T: Write> Write for DryRunFile<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            DryRunFile::Real(file) => file.write(buf),
+            DryRunFile::Buffer(buffer) => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            DryRunFile::Real(file) => file.flush(),
+            DryRunFile::Buffer(_) => Ok(()),
+        }
+    }
+}
+
+impl<F: FileSystem> DryRunFileSystem<F> {
+    /// Wraps a filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The filesystem to read from.
+    pub fn new(inner: F) -> Self {
+        DryRunFileSystem {
+            inner,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Lists the changes that would have been made, in order.
+    pub fn changes(&self) -> Vec<Change> {
+        self.lock().changes.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Finds the overlay entry of a path, treating paths beneath a removed
+    /// directory as removed.
+    fn node(&self, path: &Path) -> Option<Node> {
+        let path = normalize(path);
+        let state = self.lock();
+        if let Some(node) = state.overlay.get(&path) {
+            return Some(node.clone());
+        }
+
+        path.ancestors()
+            .skip(1)
+            .find_map(|ancestor| match state.overlay.get(ancestor) {
+                Some(Node::Removed | Node::EmptyDir) => Some(Node::Removed),
+                _ => None,
+            })
+    }
+
+    /// Follows symbolic links in the overlay and the wrapped filesystem,
+    /// returning where the final link points even if nothing exists there.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let mut path = normalize(path);
+
+        for _ in 0..MAX_LINKS {
+            let target = match self.node(&path) {
+                Some(Node::Symlink(target)) => target,
+                Some(_) => break,
+                None if self.inner.is_symlink(&path) => match self.inner.read_link(&path) {
+                    Ok(target) => target,
+                    Err(_) => break,
+                },
+                None => break,
+            };
+            let base = path.parent().unwrap_or(Path::new(""));
+            path = normalize(&base.join(target));
+        }
+
+        path
+    }
+
+    /// Checks whether a path exists, without following a final link.
+    fn entry_exists(&self, path: &Path) -> bool {
+        match self.node(path) {
+            Some(Node::Removed) => false,
+            Some(_) => true,
+            None => self.inner.is_symlink(path) || self.inner.path_exists(path),
+        }
+    }
+
+    fn set(&self, path: PathBuf, node: Node) {
+        self.lock().overlay.insert(path, node);
+    }
+
+    fn record(&self, change: Change) {
+        let mut state = self.lock();
+        if !state.changes.contains(&change) {
+            state.changes.push(change);
+        }
+    }
+
+    /// Records a write to a file, following symbolic links.
+    fn write(&self, path: &Path, contents: &[u8]) {
+        let target = self.resolve(path);
+        let change = if self.entry_exists(&target) {
+            Change::Rewrite(target.clone())
+        } else {
+            Change::Create(target.clone())
+        };
+
+        // A file created by this run is still new however often it is written.
+        if !self
+            .lock()
+            .changes
+            .contains(&Change::Create(target.clone()))
+        {
+            self.record(change);
+        }
+        self.set(target, Node::File(contents.to_vec()));
+    }
+}
+
+impl<F: FileSystem> FileSystem for DryRunFileSystem<F> {
+    type File = DryRunFile<F::File>;
+
+    fn path_exists(&self, path: &Path) -> bool {
+        let target = self.resolve(path);
+        match self.node(&target) {
+            Some(Node::Removed) => false,
+            Some(_) => true,
+            None => self.inner.path_exists(&target),
+        }
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        if !self.path_exists(path) {
+            let node = match self.node(path) {
+                Some(Node::Removed) => Node::EmptyDir,
+                _ => Node::Dir,
+            };
+            self.set(normalize(path), node);
+            self.record(Change::CreateDir(normalize(path)));
+        }
+        Ok(())
+    }
+
+    fn create_file(&self, path: &Path) -> Result<Self::File> {
+        if self.entry_exists(path) {
+            return Err(ErrorKind::CreateFile {
+                file: path.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::AlreadyExists),
+            }
+            .into());
+        }
+
+        self.write(path, &[]);
+        Ok(DryRunFile::Buffer(Cursor::new(Vec::new())))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let dir = self.resolve(path);
+        let mut entries = match self.node(&dir) {
+            Some(Node::Dir | Node::EmptyDir) => Vec::new(),
+            Some(_) => {
+                return Err(ErrorKind::ReadDir {
+                    file: path.to_path_buf(),
+                    source: io::Error::from(io::ErrorKind::NotFound),
+                }
+                .into());
+            }
+            None => self.inner.read_dir(path)?,
+        };
+
+        entries.retain(|entry| !matches!(self.node(entry), Some(Node::Removed)));
+        let added: Vec<_> = self
+            .lock()
+            .overlay
+            .iter()
+            .filter(|(entry, node)| {
+                entry.parent() == Some(dir.as_path()) && !matches!(node, Node::Removed)
+            })
+            .filter_map(|(entry, _)| entry.file_name().map(|name| path.join(name)))
+            .collect();
+        for entry in added {
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn open_file(&self, path: &Path, options: &OpenOptions) -> Result<Self::File> {
+        let target = self.resolve(path);
+        let error = |kind| {
+            Error::new(ErrorKind::OpenFile {
+                file: path.to_path_buf(),
+                source: io::Error::from(kind),
+            })
+        };
+
+        if options.is_write() {
+            let exists = self.path_exists(&target);
+            if !exists && !options.is_create() {
+                return Err(error(io::ErrorKind::NotFound));
+            }
+
+            if exists && options.is_append() {
+                self.record(Change::Append(target));
+            } else if exists && !options.is_truncate() {
+                self.record(Change::Rewrite(target));
+            } else {
+                self.write(&target, &[]);
+            }
+            return Ok(DryRunFile::Buffer(Cursor::new(Vec::new())));
+        }
+
+        match self.node(&target) {
+            Some(Node::File(contents)) => Ok(DryRunFile::Buffer(Cursor::new(contents))),
+            Some(Node::Dir | Node::EmptyDir) => Err(error(io::ErrorKind::IsADirectory)),
+            Some(_) => Err(error(io::ErrorKind::NotFound)),
+            None => Ok(DryRunFile::Real(self.inner.open_file(&target, options)?)),
+        }
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        match self.node(path) {
+            Some(Node::Symlink(_)) => true,
+            Some(_) => false,
+            None => self.inner.is_symlink(path),
+        }
+    }
+
+    fn create_symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        if self.entry_exists(link) {
+            return Err(ErrorKind::CreateSymlink {
+                link: link.to_path_buf(),
+                original: original.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::AlreadyExists),
+            }
+            .into());
+        }
+
+        self.set(normalize(link), Node::Symlink(original.to_path_buf()));
+        self.record(Change::Link {
+            link: link.to_path_buf(),
+            original: original.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()> {
+        let error = |kind| {
+            Error::new(ErrorKind::CreateHardlink {
+                link: link.to_path_buf(),
+                original: original.to_path_buf(),
+                source: io::Error::from(kind),
+            })
+        };
+        if self.entry_exists(link) {
+            return Err(error(io::ErrorKind::AlreadyExists));
+        }
+
+        let mut contents = Vec::new();
+        let mut options = OpenOptions::new();
+        options.read(true);
+        self.open_file(original, &options)
+            .map_err(|_| error(io::ErrorKind::NotFound))?
+            .read_to_end(&mut contents)
+            .map_err(|e| error(e.kind()))?;
+
+        self.set(normalize(link), Node::File(contents));
+        self.record(Change::Hardlink {
+            link: link.to_path_buf(),
+            original: original.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        // Links made during the dry run are copies in the overlay, so only
+        // files left untouched can be the same.
+        if self.node(a).is_some() || self.node(b).is_some() {
+            return normalize(a) == normalize(b) && self.path_exists(a);
+        }
+        self.inner.same_file(a, b)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf> {
+        match self.node(path) {
+            Some(Node::Symlink(target)) => Ok(target),
+            Some(_) => Err(ErrorKind::ReadLink {
+                file: path.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::InvalidInput),
+            }
+            .into()),
+            None => self.inner.read_link(path),
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let error = |kind| {
+            Error::new(ErrorKind::RemoveFile {
+                file: path.to_path_buf(),
+                source: io::Error::from(kind),
+            })
+        };
+
+        match self.node(path) {
+            Some(Node::Removed) => return Err(error(io::ErrorKind::NotFound)),
+            Some(Node::Dir | Node::EmptyDir) => return Err(error(io::ErrorKind::IsADirectory)),
+            Some(_) => {}
+            None if !self.entry_exists(path) => return Err(error(io::ErrorKind::NotFound)),
+            None => {}
+        }
+
+        self.set(normalize(path), Node::Removed);
+        self.record(Change::Remove(path.to_path_buf()));
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        if !self.entry_exists(path) {
+            return Err(ErrorKind::RemoveDir {
+                file: path.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::NotFound),
+            }
+            .into());
+        }
+
+        let dir = normalize(path);
+        let mut state = self.lock();
+        state.overlay.retain(|entry, _| !entry.starts_with(&dir));
+        state.overlay.insert(dir, Node::Removed);
+        drop(state);
+
+        self.record(Change::Remove(path.to_path_buf()));
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        let target = self.resolve(path);
+        match self.node(&target) {
+            Some(Node::File(contents)) => Ok(Metadata::new(
+                false,
+                contents.len() as u64,
+                SystemTime::now(),
+            )),
+            Some(Node::Dir | Node::EmptyDir) => Ok(Metadata::new(true, 0, SystemTime::now())),
+            Some(_) => Err(ErrorKind::ReadMetadata {
+                file: path.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::NotFound),
+            }
+            .into()),
+            None => self.inner.metadata(&target),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let target = self.resolve(path);
+        let error = |kind| {
+            Error::new(ErrorKind::ReadFile {
+                file: path.to_path_buf(),
+                source: io::Error::from(kind),
+            })
+        };
+
+        match self.node(&target) {
+            Some(Node::File(contents)) => {
+                String::from_utf8(contents).map_err(|_| error(io::ErrorKind::InvalidData))
+            }
+            Some(_) => Err(error(io::ErrorKind::NotFound)),
+            None => self.inner.read_to_string(&target),
+        }
+    }
+
+    fn write_all(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.write(path, contents);
+        Ok(())
+    }
+
+    fn write_atomic(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.write(path, contents);
+        Ok(())
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut contents = Vec::new();
+        let mut options = OpenOptions::new();
+        options.read(true);
+        self.open_file(from, &options)?
+            .read_to_end(&mut contents)
+            .map_err(|e| {
+                Error::new(ErrorKind::ReadFile {
+                    file: from.to_path_buf(),
+                    source: e,
+                })
+            })?;
+
+        self.write(to, &contents);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if !self.entry_exists(from) {
+            return Err(ErrorKind::RenameFile {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source: io::Error::from(io::ErrorKind::NotFound),
+            }
+            .into());
+        }
+
+        let node = match self.node(from) {
+            Some(node) => node,
+            None if self.inner.is_symlink(from) => Node::Symlink(self.inner.read_link(from)?),
+            None if self.inner.metadata(from)?.is_dir() => Node::Dir,
+            None => Node::File(self.read_to_string(from)?.into_bytes()),
+        };
+        self.set(normalize(from), Node::Removed);
+        self.set(normalize(to), node);
+        self.record(Change::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    fn filesystem_type(&self, path: &Path) -> Option<String> {
+        self.inner.filesystem_type(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_records_changes() {
+        let inner = MockFileSystem::new();
+        inner.add_file(".envoke/dev.env", "PORT=8080\n");
+        inner.add_file(".envoke/old.env", "PORT=80\n");
+        inner
+            .create_symlink(Path::new(".envoke/old.env"), Path::new(".env"))
+            .unwrap();
+        let fs = DryRunFileSystem::new(inner.clone());
+
+        fs.write_atomic(Path::new(".envoke/new.env"), b"A=1\n")
+            .unwrap();
+        fs.write_atomic(Path::new(".env"), b"PORT=81\n").unwrap();
+        fs.remove_file(Path::new(".env")).unwrap();
+        fs.create_symlink(Path::new(".envoke/new.env"), Path::new(".env"))
+            .unwrap();
+
+        assert_eq!("A=1\n", fs.read_to_string(Path::new(".env")).unwrap());
+        assert_eq!(
+            "PORT=81\n",
+            fs.read_to_string(Path::new(".envoke/old.env")).unwrap()
+        );
+        let mut names = fs.read_dir(Path::new(".envoke")).unwrap();
+        names.sort();
+        assert_eq!(3, names.len());
+
+        assert_eq!(
+            vec![
+                Change::Create(".envoke/new.env".into()),
+                Change::Rewrite(".envoke/old.env".into()),
+                Change::Remove(".env".into()),
+                Change::Link {
+                    link: ".env".into(),
+                    original: ".envoke/new.env".into()
+                },
+            ],
+            fs.changes()
+        );
+
+        // Nothing reached the wrapped filesystem.
+        assert!(!inner.path_exists(Path::new(".envoke/new.env")));
+        assert_eq!(Some("PORT=80\n".to_string()), inner.contents(".env"));
+    }
+
+    #[test]
+    fn test_remove_dir_all() {
+        let inner = MockFileSystem::new();
+        inner.add_file(".envoke/dev.env", "PORT=8080\n");
+        let fs = DryRunFileSystem::new(inner);
+
+        fs.remove_dir_all(Path::new(".envoke")).unwrap();
+        assert!(!fs.path_exists(Path::new(".envoke/dev.env")));
+        assert!(fs.read_to_string(Path::new(".envoke/dev.env")).is_err());
+
+        fs.create_dir(Path::new(".envoke")).unwrap();
+        assert!(fs.read_dir(Path::new(".envoke")).unwrap().is_empty());
+        assert_eq!(
+            vec![
+                Change::Remove(".envoke".into()),
+                Change::CreateDir(".envoke".into())
+            ],
+            fs.changes()
+        );
+    }
+}
@@ -0,0 +1,967 @@
+//! In-memory implementation of the `FileSystem` trait.
+//!
+//! `MockFileSystem` keeps files, directories and symbolic links in memory, so
+//! tests can exercise envoke without touching disk. Paths are used exactly as
+//! given, apart from `.` and `..` components, and relative paths such as
+//! `.env` are treated as relative to an imaginary working directory.
+//!
+//! Failures can be injected with [`MockFileSystem::fail_on`], making every
+//! operation on a path report a permission error.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use super::{FileSystem, MAX_LINKS, Metadata, OpenOptions, normalize};
+use crate::error::{Error, ErrorKind, Result};
+
+/// An in-memory filesystem for tests.
+///
+/// Clones share the same contents, so a test can keep a handle to the
+/// filesystem after moving a clone into a `ProfileManager`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use envoke::fs::{FileSystem, MockFileSystem};
+///
+/// let fs = MockFileSystem::new();
+/// fs.add_file(".envoke/dev.env", "PORT=8080\n");
+///
+/// assert!(fs.path_exists(Path::new(".envoke")));
+/// assert_eq!(Some("PORT=8080\n".to_string()), fs.contents(".envoke/dev.env"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockFileSystem {
+    state: Arc<Mutex<State>>,
+}
+
+/// The contents of a `MockFileSystem`.
+#[derive(Debug, Default)]
+struct State {
+    nodes: BTreeMap<PathBuf, Node>,
+    failures: BTreeSet<PathBuf>,
+    filesystem_type: Option<String>,
+    /// Pairs of paths naming the same file, the lesser path first.
+    hardlinks: BTreeSet<(PathBuf, PathBuf)>,
+}
+
+/// An entry in a `MockFileSystem`.
+#[derive(Debug, Clone)]
+enum Node {
+    Dir,
+    File {
+        contents: Vec<u8>,
+        modified: SystemTime,
+    },
+    Symlink(PathBuf),
+}
+
+/// A handle to a file in a `MockFileSystem`.
+///
+/// Writes are visible to the filesystem immediately.
+#[derive(Debug)]
+pub struct MockFile {
+    state: Arc<Mutex<State>>,
+    path: PathBuf,
+    position: usize,
+    options: OpenOptions,
+}
+
+impl MockFileSystem {
+    /// Creates an empty filesystem.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, creating its parent directories and replacing any
+    /// existing file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `contents` - The contents of the file.
+    pub fn add_file<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) {
+        let path = normalize(path.as_ref());
+        let mut state = self.lock();
+
+        if let Some(parent) = path.parent() {
+            state.create_dirs(parent);
+        }
+        state.nodes.insert(
+            path,
+            Node::File {
+                contents: contents.as_ref().to_vec(),
+                modified: SystemTime::now(),
+            },
+        );
+    }
+
+    /// Adds a directory and its parents.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    pub fn add_dir<P: AsRef<Path>>(&self, path: P) {
+        self.lock().create_dirs(&normalize(path.as_ref()));
+    }
+
+    /// Reads a file, following symbolic links.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// The contents of the file, or `None` if it does not exist or is not
+    /// valid UTF-8.
+    pub fn contents<P: AsRef<Path>>(&self, path: P) -> Option<String> {
+        let state = self.lock();
+        let path = state.resolve(path.as_ref())?;
+
+        match state.nodes.get(&path) {
+            Some(Node::File { contents, .. }) => String::from_utf8(contents.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Sets when a file was last modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `time` - The modification time.
+    pub fn set_modified<P: AsRef<Path>>(&self, path: P, time: SystemTime) {
+        let mut state = self.lock();
+        let Some(path) = state.resolve(path.as_ref()) else {
+            return;
+        };
+
+        if let Some(Node::File { modified, .. }) = state.nodes.get_mut(&path) {
+            *modified = time;
+        }
+    }
+
+    /// Makes every operation on a path, or anything beneath it, fail with a
+    /// permission error.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to fail on.
+    pub fn fail_on<P: AsRef<Path>>(&self, path: P) {
+        self.lock().failures.insert(normalize(path.as_ref()));
+    }
+
+    /// Stops injecting failures.
+    pub fn clear_failures(&self) {
+        self.lock().failures.clear();
+    }
+
+    /// Sets the filesystem type reported for every path, which is unknown
+    /// by default.
+    pub fn set_filesystem_type(&self, filesystem_type: &str) {
+        self.lock().filesystem_type = Some(filesystem_type.to_string());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    /// Follows symbolic links until a path that is not a link is reached.
+    ///
+    /// Returns `None` if a link does not resolve to an existing entry.
+    fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        let mut path = normalize(path);
+
+        for _ in 0..MAX_LINKS {
+            match self.nodes.get(&path) {
+                Some(Node::Symlink(target)) => {
+                    let base = path.parent().unwrap_or(Path::new(""));
+                    path = normalize(&base.join(target));
+                }
+                Some(_) => return Some(path),
+                None if is_root(&path) => return Some(path),
+                None => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Follows symbolic links, returning where the final link points even if
+    /// nothing exists there.
+    fn resolve_target(&self, path: &Path) -> PathBuf {
+        let mut path = normalize(path);
+
+        for _ in 0..MAX_LINKS {
+            match self.nodes.get(&path) {
+                Some(Node::Symlink(target)) => {
+                    let base = path.parent().unwrap_or(Path::new(""));
+                    path = normalize(&base.join(target));
+                }
+                _ => break,
+            }
+        }
+
+        path
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        match self.resolve(path) {
+            Some(path) => is_root(&path) || matches!(self.nodes.get(&path), Some(Node::Dir)),
+            None => false,
+        }
+    }
+
+    fn parent_exists(&self, path: &Path) -> bool {
+        path.parent().is_none_or(|parent| self.is_dir(parent))
+    }
+
+    fn create_dirs(&mut self, path: &Path) {
+        let mut current = PathBuf::new();
+        for component in path.components() {
+            current.push(component);
+            if !is_root(&current) {
+                self.nodes.entry(current.clone()).or_insert(Node::Dir);
+            }
+        }
+    }
+
+    /// Replaces the contents of a file, creating it if its parent exists.
+    fn write(&mut self, path: PathBuf, contents: &[u8]) -> io::Result<()> {
+        if matches!(self.nodes.get(&path), Some(Node::Dir)) || is_root(&path) {
+            return Err(io::Error::from(io::ErrorKind::IsADirectory));
+        }
+        if !self.parent_exists(&path) {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        }
+
+        self.nodes.insert(
+            path,
+            Node::File {
+                contents: contents.to_vec(),
+                modified: SystemTime::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Gets the paths hard linked to a file.
+    fn linked(&self, path: &Path) -> Vec<PathBuf> {
+        self.hardlinks
+            .iter()
+            .filter_map(|(a, b)| match (a == path, b == path) {
+                (true, _) => Some(b.clone()),
+                (_, true) => Some(a.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forgets the hard links of a path whose file was removed or replaced.
+    fn unlink(&mut self, path: &Path) {
+        self.hardlinks.retain(|(a, b)| a != path && b != path);
+    }
+
+    fn check(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        if self
+            .failures
+            .iter()
+            .any(|failure| path.starts_with(failure))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "injected failure",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl FileSystem for MockFileSystem {
+    type File = MockFile;
+
+    fn path_exists(&self, path: &Path) -> bool {
+        self.lock().resolve(path).is_some()
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::CreateDir {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        // Every ancestor must be a directory, or not exist yet.
+        let mut current = PathBuf::new();
+        for component in normalize(path).components() {
+            current.push(component);
+            if state.resolve(&current).is_some() && !state.is_dir(&current) {
+                return Err(error(io::Error::from(io::ErrorKind::AlreadyExists)));
+            }
+        }
+
+        let target = state.resolve_target(path);
+        state.create_dirs(&target);
+
+        Ok(())
+    }
+
+    fn create_file(&self, path: &Path) -> Result<MockFile> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::CreateFile {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let normalized = normalize(path);
+        if state.nodes.contains_key(&normalized) {
+            return Err(error(io::Error::from(io::ErrorKind::AlreadyExists)));
+        }
+        if !state.parent_exists(&normalized) {
+            return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        state.nodes.insert(
+            normalized.clone(),
+            Node::File {
+                contents: Vec::new(),
+                modified: SystemTime::now(),
+            },
+        );
+
+        let mut options = OpenOptions::new();
+        options.write(true);
+
+        Ok(MockFile {
+            state: Arc::clone(&self.state),
+            path: normalized,
+            position: 0,
+            options,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::ReadDir {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        if !state.is_dir(path) {
+            return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        // Entries are listed under the path as given, like `std::fs::read_dir`.
+        let dir = state.resolve(path).unwrap_or_default();
+        let entries = state
+            .nodes
+            .keys()
+            .filter(|entry| entry.parent() == Some(dir.as_path()))
+            .filter_map(|entry| entry.file_name())
+            .map(|name| path.join(name))
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn open_file(&self, path: &Path, options: &OpenOptions) -> Result<MockFile> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::OpenFile {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let target = state.resolve_target(path);
+        state.check(&target).map_err(error)?;
+
+        match state.nodes.get_mut(&target) {
+            Some(Node::File { contents, modified }) => {
+                if options.is_truncate() && options.is_write() {
+                    contents.clear();
+                    *modified = SystemTime::now();
+                }
+            }
+            Some(_) => return Err(error(io::Error::from(io::ErrorKind::IsADirectory))),
+            None if options.is_create() && options.is_write() => {
+                if !state.parent_exists(&target) {
+                    return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+                }
+                state.nodes.insert(
+                    target.clone(),
+                    Node::File {
+                        contents: Vec::new(),
+                        modified: SystemTime::now(),
+                    },
+                );
+            }
+            None => return Err(error(io::Error::from(io::ErrorKind::NotFound))),
+        }
+
+        Ok(MockFile {
+            state: Arc::clone(&self.state),
+            path: target,
+            position: 0,
+            options: *options,
+        })
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        let state = self.lock();
+
+        state.resolve(path).is_some()
+            && matches!(state.nodes.get(&normalize(path)), Some(Node::Symlink(_)))
+    }
+
+    fn create_symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::CreateSymlink {
+                link: link.to_path_buf(),
+                original: original.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(link).map_err(error)?;
+
+        let normalized = normalize(link);
+        if state.nodes.contains_key(&normalized) {
+            return Err(error(io::Error::from(io::ErrorKind::AlreadyExists)));
+        }
+        if !state.parent_exists(&normalized) {
+            return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        state
+            .nodes
+            .insert(normalized, Node::Symlink(original.to_path_buf()));
+
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf> {
+        let state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::ReadLink {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        match state.nodes.get(&normalize(path)) {
+            Some(Node::Symlink(target)) => Ok(target.clone()),
+            Some(_) => Err(error(io::Error::from(io::ErrorKind::InvalidInput))),
+            None => Err(error(io::Error::from(io::ErrorKind::NotFound))),
+        }
+    }
+
+    fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::CreateHardlink {
+                link: link.to_path_buf(),
+                original: original.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(original).map_err(error)?;
+        state.check(link).map_err(error)?;
+
+        let original = state
+            .resolve(original)
+            .ok_or_else(|| error(io::Error::from(io::ErrorKind::NotFound)))?;
+        let Some(Node::File { contents, .. }) = state.nodes.get(&original) else {
+            return Err(error(io::Error::from(io::ErrorKind::PermissionDenied)));
+        };
+        let contents = contents.clone();
+
+        let link = normalize(link);
+        if state.nodes.contains_key(&link) {
+            return Err(error(io::Error::from(io::ErrorKind::AlreadyExists)));
+        }
+        state.write(link.clone(), &contents).map_err(error)?;
+
+        for linked in state.linked(&original) {
+            state.hardlinks.insert(link_pair(linked, link.clone()));
+        }
+        state.hardlinks.insert(link_pair(original, link));
+
+        Ok(())
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        let state = self.lock();
+        match (state.resolve(a), state.resolve(b)) {
+            (Some(a), Some(b)) => a == b || state.hardlinks.contains(&link_pair(a, b)),
+            _ => false,
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::RemoveFile {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let normalized = normalize(path);
+        match state.nodes.get(&normalized) {
+            Some(Node::Dir) => Err(error(io::Error::from(io::ErrorKind::IsADirectory))),
+            Some(_) => {
+                state.nodes.remove(&normalized);
+                state.unlink(&normalized);
+                Ok(())
+            }
+            None => Err(error(io::Error::from(io::ErrorKind::NotFound))),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::RemoveDir {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let normalized = normalize(path);
+        match state.nodes.get(&normalized) {
+            Some(Node::Dir) => {
+                state.nodes.retain(|path, _| !path.starts_with(&normalized));
+                Ok(())
+            }
+            Some(_) => Err(error(io::Error::from(io::ErrorKind::NotADirectory))),
+            None => Err(error(io::Error::from(io::ErrorKind::NotFound))),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        let state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::ReadMetadata {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let resolved = state
+            .resolve(path)
+            .ok_or_else(|| error(io::Error::from(io::ErrorKind::NotFound)))?;
+
+        match state.nodes.get(&resolved) {
+            Some(Node::File { contents, modified }) => {
+                Ok(Metadata::new(false, contents.len() as u64, *modified))
+            }
+            _ => Ok(Metadata::new(true, 0, SystemTime::UNIX_EPOCH)),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::ReadFile {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let resolved = state
+            .resolve(path)
+            .ok_or_else(|| error(io::Error::from(io::ErrorKind::NotFound)))?;
+        state.check(&resolved).map_err(error)?;
+
+        match state.nodes.get(&resolved) {
+            Some(Node::File { contents, .. }) => String::from_utf8(contents.clone())
+                .map_err(|_| error(io::Error::from(io::ErrorKind::InvalidData))),
+            _ => Err(error(io::Error::from(io::ErrorKind::IsADirectory))),
+        }
+    }
+
+    fn write_all(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::WriteFile {
+                file: path.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(path).map_err(error)?;
+
+        let target = state.resolve_target(path);
+        state.check(&target).map_err(error)?;
+        for linked in state.linked(&target) {
+            state.write(linked, contents).map_err(error)?;
+        }
+        state.write(target, contents).map_err(error)
+    }
+
+    fn write_atomic(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        // Writes to memory cannot be interrupted, so they are already atomic,
+        // but like a rename over the file they leave its hard links behind.
+        let target = self.lock().resolve_target(path);
+        self.lock().unlink(&target);
+        self.write_all(path, contents)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::CopyFile {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(from).map_err(error)?;
+        state.check(to).map_err(error)?;
+
+        let source = state
+            .resolve(from)
+            .ok_or_else(|| error(io::Error::from(io::ErrorKind::NotFound)))?;
+        let Some(Node::File { contents, .. }) = state.nodes.get(&source) else {
+            return Err(error(io::Error::from(io::ErrorKind::IsADirectory)));
+        };
+        let contents = contents.clone();
+
+        let target = state.resolve_target(to);
+        state.check(&target).map_err(error)?;
+        state.write(target, &contents).map_err(error)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut state = self.lock();
+        let error = |source| {
+            Error::new(ErrorKind::RenameFile {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source,
+            })
+        };
+
+        state.check(from).map_err(error)?;
+        state.check(to).map_err(error)?;
+
+        let from = normalize(from);
+        let to = normalize(to);
+        if !state.nodes.contains_key(&from) {
+            return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+        }
+        if from == to {
+            return Ok(());
+        }
+        if matches!(state.nodes.get(&to), Some(Node::Dir)) {
+            return Err(error(io::Error::from(io::ErrorKind::IsADirectory)));
+        }
+        if to.starts_with(&from) || !state.parent_exists(&to) {
+            return Err(error(io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        // Move the entry itself and, for a directory, everything beneath it.
+        let moved: Vec<PathBuf> = state
+            .nodes
+            .keys()
+            .filter(|path| path.starts_with(&from))
+            .cloned()
+            .collect();
+        for path in moved {
+            let node = state.nodes.remove(&path).expect("entry exists");
+            let suffix = path.strip_prefix(&from).expect("entry is beneath `from`");
+            state.nodes.insert(to.join(suffix), node);
+        }
+
+        // The renamed file keeps its hard links, while the one replaced loses them.
+        state.unlink(&to);
+        for linked in state.linked(&from) {
+            state
+                .hardlinks
+                .remove(&link_pair(from.clone(), linked.clone()));
+            state.hardlinks.insert(link_pair(to.clone(), linked));
+        }
+
+        Ok(())
+    }
+
+    fn filesystem_type(&self, _path: &Path) -> Option<String> {
+        self.lock().filesystem_type.clone()
+    }
+}
+
+impl Read for MockFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.options.is_read() {
+            return Err(io::Error::other("file is not open for reading"));
+        }
+
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(Node::File { contents, .. }) = state.nodes.get(&self.path) else {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        };
+
+        let remaining = contents.get(self.position..).unwrap_or_default();
+        let count = remaining.len().min(buf.len());
+        buf[..count].copy_from_slice(&remaining[..count]);
+        self.position += count;
+
+        Ok(count)
+    }
+}
+
+impl Write for MockFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.options.is_write() {
+            return Err(io::Error::other("file is not open for writing"));
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.check(&self.path)?;
+
+        let Some(Node::File { contents, modified }) = state.nodes.get_mut(&self.path) else {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        };
+
+        if self.options.is_append() {
+            self.position = contents.len();
+        }
+
+        let end = self.position + buf.len();
+        if contents.len() < end {
+            contents.resize(end, 0);
+        }
+        contents[self.position..end].copy_from_slice(buf);
+        self.position = end;
+        *modified = SystemTime::now();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Orders two hard linked paths as they are kept in `State::hardlinks`.
+fn link_pair(a: PathBuf, b: PathBuf) -> (PathBuf, PathBuf) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+/// Checks whether a path is the filesystem root or the working directory.
+fn is_root(path: &Path) -> bool {
+    path.as_os_str().is_empty() || path.parent().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(fs: &MockFileSystem, path: &str) -> String {
+        let mut options = OpenOptions::new();
+        options.read(true);
+
+        let mut contents = String::new();
+        fs.open_file(Path::new(path), &options)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_files_and_dirs() {
+        let fs = MockFileSystem::new();
+
+        assert!(fs.create_file(Path::new("a/b.txt")).is_err());
+        fs.create_dir(Path::new("a")).unwrap();
+
+        let mut file = fs.create_file(Path::new("a/b.txt")).unwrap();
+        file.write_all(b"hello").unwrap();
+        assert!(fs.create_file(Path::new("a/b.txt")).is_err());
+
+        assert_eq!("hello", read(&fs, "a/b.txt"));
+        assert_eq!("hello", read(&fs, "./a/../a/b.txt"));
+        assert_eq!(5, fs.metadata(Path::new("a/b.txt")).unwrap().len());
+        assert!(fs.metadata(Path::new("a")).unwrap().is_dir());
+
+        fs.add_file("a/c/d.env", "");
+        assert_eq!(
+            vec![PathBuf::from("a/b.txt"), PathBuf::from("a/c")],
+            fs.read_dir(Path::new("a")).unwrap()
+        );
+
+        assert!(fs.remove_file(Path::new("a")).is_err());
+        fs.remove_file(Path::new("a/b.txt")).unwrap();
+        assert!(!fs.path_exists(Path::new("a/b.txt")));
+    }
+
+    #[test]
+    fn test_open_options() {
+        let fs = MockFileSystem::new();
+        fs.add_file("log", "one\n");
+
+        let mut options = OpenOptions::new();
+        options.append(true);
+        writeln!(fs.open_file(Path::new("log"), &options).unwrap(), "two").unwrap();
+        assert_eq!("one\ntwo\n", read(&fs, "log"));
+
+        options = OpenOptions::new();
+        options.write(true).truncate(true);
+        write!(fs.open_file(Path::new("log"), &options).unwrap(), "three").unwrap();
+        assert_eq!("three", read(&fs, "log"));
+
+        assert!(fs.open_file(Path::new("new"), &options).is_err());
+        options.create(true);
+        fs.open_file(Path::new("new"), &options).unwrap();
+        assert!(fs.path_exists(Path::new("new")));
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let fs = MockFileSystem::new();
+        fs.add_file(".envoke/dev.env", "PORT=1\n");
+
+        fs.create_symlink(Path::new(".envoke/dev.env"), Path::new(".env"))
+            .unwrap();
+        assert!(fs.is_symlink(Path::new(".env")));
+        assert!(!fs.is_symlink(Path::new(".envoke/dev.env")));
+        assert_eq!(
+            PathBuf::from(".envoke/dev.env"),
+            fs.read_link(Path::new(".env")).unwrap()
+        );
+        assert_eq!("PORT=1\n", read(&fs, ".env"));
+
+        fs.remove_file(Path::new(".env")).unwrap();
+        assert!(fs.path_exists(Path::new(".envoke/dev.env")));
+
+        // A dangling link does not exist.
+        fs.create_symlink(Path::new("missing"), Path::new("link"))
+            .unwrap();
+        assert!(!fs.path_exists(Path::new("link")));
+        assert!(!fs.is_symlink(Path::new("link")));
+    }
+
+    #[test]
+    fn test_hardlinks() {
+        let fs = MockFileSystem::new();
+        fs.add_file(".envoke/dev.env", "PORT=1\n");
+
+        fs.create_hardlink(Path::new(".envoke/dev.env"), Path::new(".env"))
+            .unwrap();
+        assert!(!fs.is_symlink(Path::new(".env")));
+        assert!(fs.same_file(Path::new(".env"), Path::new(".envoke/dev.env")));
+        assert!(
+            fs.create_hardlink(Path::new("missing"), Path::new("link"))
+                .is_err()
+        );
+
+        fs.write_all(Path::new(".envoke/dev.env"), b"PORT=2\n")
+            .unwrap();
+        assert_eq!("PORT=2\n", read(&fs, ".env"));
+
+        fs.write_atomic(Path::new(".envoke/dev.env"), b"PORT=3\n")
+            .unwrap();
+        assert_eq!("PORT=2\n", read(&fs, ".env"));
+        assert!(!fs.same_file(Path::new(".env"), Path::new(".envoke/dev.env")));
+    }
+
+    #[test]
+    fn test_remove_dir_all() {
+        let fs = MockFileSystem::new();
+        fs.add_file("a/one.env", "ONE=1\n");
+        fs.add_file("a/nested/two.env", "TWO=2\n");
+        fs.add_file("ab.env", "AB=1\n");
+
+        assert!(fs.remove_dir_all(Path::new("ab.env")).is_err());
+        fs.remove_dir_all(Path::new("a")).unwrap();
+        assert!(!fs.path_exists(Path::new("a")));
+        assert!(!fs.path_exists(Path::new("a/nested/two.env")));
+        assert!(fs.path_exists(Path::new("ab.env")));
+        assert!(fs.remove_dir_all(Path::new("a")).is_err());
+    }
+
+    #[test]
+    fn test_copy_and_rename() {
+        let fs = MockFileSystem::new();
+        fs.add_file("a/one.env", "ONE=1\n");
+        fs.add_file("a/nested/two.env", "TWO=2\n");
+        fs.create_symlink(Path::new("a/one.env"), Path::new("link"))
+            .unwrap();
+
+        fs.write_all(Path::new("link"), b"ONE=one\n").unwrap();
+        assert_eq!(
+            "ONE=one\n",
+            fs.read_to_string(Path::new("a/one.env")).unwrap()
+        );
+        assert!(fs.write_all(Path::new("missing/file"), b"").is_err());
+        assert!(fs.read_to_string(Path::new("a")).is_err());
+
+        fs.copy_file(Path::new("link"), Path::new("copy.env"))
+            .unwrap();
+        assert!(!fs.is_symlink(Path::new("copy.env")));
+        assert_eq!(Some("ONE=one\n".to_string()), fs.contents("copy.env"));
+        assert!(fs.copy_file(Path::new("a"), Path::new("b")).is_err());
+
+        fs.rename(Path::new("a"), Path::new("b")).unwrap();
+        assert!(!fs.path_exists(Path::new("a/nested/two.env")));
+        assert_eq!(Some("TWO=2\n".to_string()), fs.contents("b/nested/two.env"));
+        assert!(!fs.path_exists(Path::new("link")));
+        assert!(fs.rename(Path::new("a"), Path::new("c")).is_err());
+        assert!(fs.rename(Path::new("copy.env"), Path::new("b")).is_err());
+    }
+
+    #[test]
+    fn test_fail_on() {
+        let fs = MockFileSystem::new();
+        fs.add_file("dir/file", "contents");
+        fs.fail_on("dir");
+
+        let error = fs.remove_file(Path::new("dir/file")).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::RemoveFile { .. }));
+        assert!(fs.metadata(Path::new("dir/file")).is_err());
+        assert!(fs.path_exists(Path::new("dir/file")));
+
+        fs.clear_failures();
+        assert!(fs.remove_file(Path::new("dir/file")).is_ok());
+    }
+}
@@ -0,0 +1,101 @@
+//! Git module for the envoke CLI tool.
+//!
+//! This module asks the enclosing git repository, if there is one, about the
+//! files envoke manages. Git is optional: outside a repository, or without
+//! git installed, nothing is tracked.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tracing::debug;
+
+/// Checks whether a directory is inside a git work tree.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to check.
+///
+/// # Returns
+///
+/// `true` if `git` is installed and `dir` is inside a work tree.
+pub fn is_repository(dir: &Path) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Lists the files under the given paths that are tracked by git.
+///
+/// # Arguments
+///
+/// * `dir` - The directory the paths are relative to.
+/// * `paths` - The files or directories to check.
+///
+/// # Returns
+///
+/// The tracked files, relative to `dir`. Nothing is returned outside a git
+/// repository or if git cannot be run.
+pub fn tracked<P: AsRef<Path>>(dir: &Path, paths: &[P]) -> Vec<PathBuf> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z", "--"])
+        .args(paths.iter().map(AsRef::as_ref))
+        .current_dir(dir)
+        .stderr(Stdio::null())
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => output
+            .stdout
+            .split(|&b| b == 0)
+            .filter(|file| !file.is_empty())
+            .map(|file| PathBuf::from(String::from_utf8_lossy(file).into_owned()))
+            .collect(),
+        Ok(_) => {
+            debug!("not inside a git repository");
+            Vec::new()
+        }
+        Err(e) => {
+            debug!(error = %e, "could not run git");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tracked() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join(".env"), "PORT=8080\n").unwrap();
+        fs::write(dir.join("README.md"), "# App\n").unwrap();
+        assert!(!is_repository(dir));
+        assert!(tracked(dir, &[".env"]).is_empty());
+
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", ".env", "README.md"]);
+
+        assert!(is_repository(dir));
+        assert_eq!(
+            vec![PathBuf::from(".env")],
+            tracked(dir, &[".env", ".envoke"])
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Glob matching module for the envoke CLI tool.
+//!
+//! This module provides minimal wildcard matching for key names and profile
+//! names. Only `*` (any sequence of characters) and `?` (any single character)
+//! are supported.
+
+/// Checks whether `text` matches a glob `pattern`.
+///
+/// Matching is case-insensitive, since environment variable naming
+/// conventions vary between projects. Use [`matches_exact`] for names that
+/// are case-sensitive.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern, using `*` and `?` as wildcards.
+/// * `text` - The text to match.
+///
+/// # Returns
+///
+/// `true` if the whole of `text` matches `pattern`, `false` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::glob::matches;
+///
+/// assert!(matches("AWS_*", "AWS_REGION"));
+/// assert!(matches("*_secret", "DB_SECRET"));
+/// assert!(!matches("AWS_*", "GCP_REGION"));
+/// ```
+pub fn matches(pattern: &str, text: &str) -> bool {
+    matches_exact(&pattern.to_lowercase(), &text.to_lowercase())
+}
+
+/// Checks whether `text` matches a glob `pattern`, respecting case.
+///
+/// Used for profile names, which are file names.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern, using `*` and `?` as wildcards.
+/// * `text` - The text to match.
+///
+/// # Returns
+///
+/// `true` if the whole of `text` matches `pattern`, `false` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::glob::matches_exact;
+///
+/// assert!(matches_exact("Legacy-*", "Legacy-a"));
+/// assert!(!matches_exact("Legacy-*", "legacy-b"));
+/// ```
+pub fn matches_exact(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` absorb one more character and retry.
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Checks whether `text` matches any of the given glob patterns.
+///
+/// # Arguments
+///
+/// * `patterns` - The glob patterns.
+/// * `text` - The text to match.
+///
+/// # Returns
+///
+/// `true` if at least one pattern matches, `false` otherwise.
+pub fn matches_any<S: AsRef<str>>(patterns: &[S], text: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| matches(pattern.as_ref(), text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal() {
+        assert!(matches("KEY", "KEY"));
+        assert!(matches("key", "KEY"));
+        assert!(!matches("KEY", "KEYS"));
+        assert!(!matches("KEYS", "KEY"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "ANYTHING"));
+        assert!(matches("*TOKEN*", "GITHUB_TOKEN"));
+        assert!(matches("*TOKEN*", "TOKEN_URL"));
+        assert!(matches("A*B*C", "AXXBYYC"));
+        assert!(!matches("A*B*C", "AXXBYY"));
+        assert!(matches("?B", "AB"));
+        assert!(!matches("?B", "B"));
+        assert!(matches("legacy-*", "legacy-api"));
+    }
+
+    #[test]
+    fn test_matches_exact() {
+        assert!(matches_exact("Legacy-*", "Legacy-api"));
+        assert!(!matches_exact("Legacy-*", "legacy-b"));
+        assert!(matches_exact("?ev", "dev"));
+        assert!(!matches_exact("DEV", "dev"));
+    }
+
+    #[test]
+    fn test_matches_any() {
+        let patterns = ["*_SECRET", "AWS_*"];
+        assert!(matches_any(&patterns, "DB_SECRET"));
+        assert!(matches_any(&patterns, "AWS_REGION"));
+        assert!(!matches_any(&patterns, "PORT"));
+        assert!(!matches_any::<&str>(&[], "PORT"));
+    }
+}
@@ -0,0 +1,465 @@
+//! Hooks module for the envoke CLI tool.
+//!
+//! This module runs the shell commands configured in the `[hooks]` section of
+//! `config.toml` before and after profiles change, so teams can restart
+//! services or notify tooling automatically. Hooks run with `sh -c` in the
+//! project directory and receive the details of the change in environment
+//! variables:
+//!
+//! * `ENVOKE_HOOK` - The name of the hook, e.g. `post_switch`.
+//! * `ENVOKE_OLD_PROFILE` - The profile being replaced or removed, if any.
+//! * `ENVOKE_NEW_PROFILE` - The profile being activated or created, if any.
+//! * `ENVOKE_TARGET` - The file being changed: `.env` or the profile.
+//!
+//! A profile can also declare commands to run after it is switched to, with
+//! `# envoke:post-switch <command>` comments. As profiles and `config.toml`
+//! arrive from remotes, bundles and cloned repositories, neither kind of hook
+//! runs until the user has approved it in a terminal, and again whenever it
+//! changes. Approvals are personal, so they are kept in the user's state
+//! directory, keyed by the path of each project's envoke directory, where a
+//! repository cannot ship its own.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::digest::sha256_hex;
+use crate::dotenv::Document;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+use crate::prompt;
+
+/// Directive declaring a command to run after a profile is switched to.
+pub const POST_SWITCH_DIRECTIVE: &str = "post-switch";
+
+/// Name of the file recording approved hooks within the state directory.
+pub const TRUSTED_HOOKS_FILE: &str = "trusted-hooks.json";
+
+/// The approved hooks of every project.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustedHooks {
+    /// A digest of the approved commands of each profile or `config.toml`
+    /// hook, keyed by the absolute path of the project's envoke directory.
+    pub projects: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// A point at which a configured command can run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreSwitch,
+    PostSwitch,
+    PreCreate,
+    PostCreate,
+    PreRemove,
+    PostRemove,
+}
+
+impl Hook {
+    /// Returns the name of the hook, as used in `config.toml`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::hooks::Hook;
+    ///
+    /// assert_eq!("post_switch", Hook::PostSwitch.name());
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PreSwitch => "pre_switch",
+            Hook::PostSwitch => "post_switch",
+            Hook::PreCreate => "pre_create",
+            Hook::PostCreate => "post_create",
+            Hook::PreRemove => "pre_remove",
+            Hook::PostRemove => "post_remove",
+        }
+    }
+}
+
+/// The details of a change, passed to hooks as environment variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookContext {
+    /// The profile being replaced or removed.
+    pub old_profile: Option<String>,
+
+    /// The profile being activated or created.
+    pub new_profile: Option<String>,
+
+    /// The file being changed.
+    pub target: Option<PathBuf>,
+}
+
+/// Runs the command configured for a hook, if any, and waits for it.
+///
+/// A command the user has not approved, or that changed since, is shown and
+/// must be confirmed first.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager, whose configuration holds the hooks.
+/// * `hook` - The hook to run.
+/// * `context` - The details of the change.
+///
+/// # Returns
+///
+/// `Ok(())` if no command is configured or it succeeds, or an `Error` if it
+/// is not approved, cannot be started or exits unsuccessfully.
+pub fn run<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    hook: Hook,
+    context: &HookContext,
+) -> Result<()> {
+    let Some(script) = manager.config.hooks.command(hook) else {
+        return Ok(());
+    };
+    if manager.config.dry_run {
+        return Ok(());
+    }
+
+    ensure_approved(
+        manager,
+        &format!("[hooks] {}", hook.name()),
+        &format!(
+            "`config.toml` runs this command as its `{}` hook:",
+            hook.name()
+        ),
+        &[script],
+        || ErrorKind::ConfigHookUntrusted {
+            hook: hook.name().to_string(),
+        },
+    )?;
+
+    run_script(manager, hook, script, context)
+}
+
+/// Lists the `# envoke:post-switch` commands of a profile, in order.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::Document;
+/// use envoke::hooks::profile_hooks;
+///
+/// let doc = Document::parse("# envoke:post-switch docker compose restart api\nPORT=80\n").unwrap();
+/// assert_eq!(vec!["docker compose restart api"], profile_hooks(&doc));
+/// ```
+pub fn profile_hooks(doc: &Document) -> Vec<&str> {
+    doc.lines()
+        .iter()
+        .filter_map(|line| match line.directive() {
+            Some((POST_SWITCH_DIRECTIVE, command)) if !command.is_empty() => Some(command),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Runs the `# envoke:post-switch` commands of a profile.
+///
+/// Commands the user has not approved, or that changed since, are shown
+/// and must be confirmed first.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+/// * `profile` - The profile whose hooks are run.
+/// * `context` - The details of the change.
+///
+/// # Returns
+///
+/// `Ok(())` if the profile has no hooks or they all succeed, or an `Error` if
+/// they are not approved or one fails.
+pub fn run_profile_hooks<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    profile: &str,
+    context: &HookContext,
+) -> Result<()> {
+    let doc = manager.read_profile(profile)?;
+    let commands = profile_hooks(&doc);
+    if commands.is_empty() || manager.config.dry_run {
+        return Ok(());
+    }
+
+    ensure_approved(
+        manager,
+        profile,
+        &format!(
+            "Profile `{}` runs these commands when switched to:",
+            profile
+        ),
+        &commands,
+        || ErrorKind::HooksUntrusted {
+            profile: profile.to_string(),
+        },
+    )?;
+
+    for command in commands {
+        run_script(manager, Hook::PostSwitch, command, context)?;
+    }
+
+    Ok(())
+}
+
+/// Asks the user to approve commands the first time they would run, and
+/// again whenever they change.
+///
+/// # Arguments
+///
+/// * `manager` - The profile manager.
+/// * `key` - What the approval is recorded under, e.g. the profile name.
+/// * `intro` - The line shown before the commands.
+/// * `commands` - The commands to approve.
+/// * `untrusted` - The error returned if they are not approved.
+fn ensure_approved<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    key: &str,
+    intro: &str,
+    commands: &[&str],
+    untrusted: impl Fn() -> ErrorKind,
+) -> Result<()> {
+    let digest = sha256_hex(commands.join("\n"));
+    let mut trusted = manager.load_trusted_hooks()?;
+    if trusted.get(key) == Some(&digest) {
+        return Ok(());
+    }
+
+    if !prompt::is_interactive() {
+        return Err(untrusted().into());
+    }
+
+    eprintln!("{}", intro);
+    for command in commands {
+        eprintln!("  {}", command);
+    }
+    if !prompt::confirm("Allow them to run?")? {
+        return Err(untrusted().into());
+    }
+
+    trusted.insert(key.to_string(), digest);
+    manager.save_trusted_hooks(&trusted)
+}
+
+/// Runs a hook command with `sh -c` and waits for it.
+fn run_script<F: FileSystem>(
+    manager: &ProfileManager<F>,
+    hook: Hook,
+    script: &str,
+    context: &HookContext,
+) -> Result<()> {
+    if manager.config.dry_run {
+        debug!(hook = hook.name(), script, "dry run, not running hook");
+        return Ok(());
+    }
+
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(script)
+        .env("ENVOKE_HOOK", hook.name());
+    if let Some(dir) = manager.config.envoke_dir.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        command.current_dir(dir);
+    }
+    if let Some(profile) = &context.old_profile {
+        command.env("ENVOKE_OLD_PROFILE", profile);
+    }
+    if let Some(profile) = &context.new_profile {
+        command.env("ENVOKE_NEW_PROFILE", profile);
+    }
+    if let Some(target) = &context.target {
+        command.env("ENVOKE_TARGET", target);
+    }
+
+    debug!(hook = hook.name(), script, "running hook");
+    let status = command.status().map_err(|e| {
+        Error::new(ErrorKind::Spawn {
+            command: script.to_string(),
+            source: e,
+        })
+    })?;
+
+    if !status.success() {
+        return Err(ErrorKind::HookFailed {
+            hook: hook.name().to_string(),
+            status: status.to_string(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the path of the approved hooks file.
+    ///
+    /// # Returns
+    ///
+    /// The path, or an `Error` if the state directory is unknown.
+    pub fn trusted_hooks_path(&self) -> Result<PathBuf> {
+        self.config
+            .state_dir
+            .as_ref()
+            .map(|dir| dir.join(TRUSTED_HOOKS_FILE))
+            .ok_or_else(|| ErrorKind::StateDirUnavailable.into())
+    }
+
+    /// Loads the approved hooks of this project.
+    ///
+    /// # Returns
+    ///
+    /// A digest of the approved commands of each profile or `config.toml`
+    /// hook, or an `Error` if the file cannot be read or parsed.
+    pub fn load_trusted_hooks(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self
+            .load_all_trusted_hooks()?
+            .projects
+            .remove(&self.project_key())
+            .unwrap_or_default())
+    }
+
+    /// Saves the approved hooks of this project.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the file cannot be written.
+    pub fn save_trusted_hooks(&self, trusted: &BTreeMap<String, String>) -> Result<()> {
+        let mut all = self.load_all_trusted_hooks()?;
+        all.projects.insert(self.project_key(), trusted.clone());
+
+        let path = self.trusted_hooks_path()?;
+        if let Some(dir) = path.parent() {
+            self.fs.create_dir(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&all).expect("digests are serializable");
+        self.write_file(&path, json + "\n")
+    }
+
+    /// Loads the approved hooks of every project.
+    fn load_all_trusted_hooks(&self) -> Result<TrustedHooks> {
+        let path = self.trusted_hooks_path()?;
+        if !self.fs.path_exists(&path) {
+            return Ok(TrustedHooks::default());
+        }
+
+        serde_json::from_str(&self.read_file(&path)?).map_err(|e| {
+            Error::new(ErrorKind::ParseMetadata {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::EnvokeFileSystem;
+
+    fn manager(dir: &std::path::Path) -> ProfileManager<EnvokeFileSystem> {
+        let mut config = Config::new(dir.join(".envoke"));
+        config.state_dir = Some(dir.join("state"));
+        config.hooks.pre_switch = Some("exit 3".to_string());
+        config.hooks.post_switch =
+            Some("echo \"$ENVOKE_HOOK $ENVOKE_OLD_PROFILE $ENVOKE_NEW_PROFILE\" > hook.txt".into());
+        ProfileManager::new(config, EnvokeFileSystem::new())
+    }
+
+    /// Approves the configured hooks, as a user would in a terminal.
+    fn approve(manager: &ProfileManager<EnvokeFileSystem>) {
+        std::fs::create_dir_all(&manager.config.envoke_dir).unwrap();
+        let mut trusted = manager.load_trusted_hooks().unwrap();
+        for hook in [Hook::PreSwitch, Hook::PostSwitch] {
+            let script = manager.config.hooks.command(hook).unwrap();
+            trusted.insert(format!("[hooks] {}", hook.name()), sha256_hex(script));
+        }
+        manager.save_trusted_hooks(&trusted).unwrap();
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = manager(temp.path());
+        let context = HookContext {
+            old_profile: Some("dev".to_string()),
+            new_profile: Some("prod".to_string()),
+            target: None,
+        };
+
+        // Unapproved hooks never run without a terminal.
+        let err = run(&manager, Hook::PostSwitch, &context).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ConfigHookUntrusted { .. }));
+        assert!(!temp.path().join("hook.txt").exists());
+
+        approve(&manager);
+        run(&manager, Hook::PostSwitch, &context).unwrap();
+        let output = std::fs::read_to_string(temp.path().join("hook.txt")).unwrap();
+        assert_eq!("post_switch dev prod\n", output);
+
+        // Unconfigured hooks do nothing.
+        run(&manager, Hook::PostCreate, &context).unwrap();
+    }
+
+    #[test]
+    fn test_failing_hook() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = manager(temp.path());
+        approve(&manager);
+
+        let err = run(&manager, Hook::PreSwitch, &HookContext::default()).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::HookFailed { ref hook, .. } if hook == "pre_switch"));
+    }
+
+    #[test]
+    fn test_run_profile_hooks() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = manager(temp.path());
+        std::fs::create_dir(temp.path().join(".envoke")).unwrap();
+        std::fs::write(
+            temp.path().join(".envoke/dev.env"),
+            "# envoke:post-switch echo \"$ENVOKE_NEW_PROFILE\" > profile-hook.txt\nPORT=80\n",
+        )
+        .unwrap();
+        let context = HookContext {
+            new_profile: Some("dev".to_string()),
+            ..HookContext::default()
+        };
+
+        // Unapproved hooks never run without a terminal.
+        let err = run_profile_hooks(&manager, "dev", &context).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::HooksUntrusted { .. }));
+        assert!(!temp.path().join("profile-hook.txt").exists());
+
+        let doc = manager.read_profile("dev").unwrap();
+        let digest = sha256_hex(profile_hooks(&doc).join("\n"));
+        let trusted = BTreeMap::from([("dev".to_string(), digest)]);
+        manager.save_trusted_hooks(&trusted).unwrap();
+
+        run_profile_hooks(&manager, "dev", &context).unwrap();
+        let output = std::fs::read_to_string(temp.path().join("profile-hook.txt")).unwrap();
+        assert_eq!("dev\n", output);
+
+        // Approvals are kept outside the project, where it cannot ship its own.
+        assert!(temp.path().join("state").join(TRUSTED_HOOKS_FILE).exists());
+        assert!(
+            !temp
+                .path()
+                .join(".envoke")
+                .join(TRUSTED_HOOKS_FILE)
+                .exists()
+        );
+
+        // Changed hooks must be approved again.
+        std::fs::write(
+            temp.path().join(".envoke/dev.env"),
+            "# envoke:post-switch rm -rf important\n",
+        )
+        .unwrap();
+        assert!(run_profile_hooks(&manager, "dev", &context).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Download module for the envoke CLI tool.
+//!
+//! Files are downloaded with the `curl` command-line tool rather than an HTTP
+//! library, which keeps TLS, proxies and certificates configured the way the
+//! rest of the machine has them.
+
+use std::process::Command;
+
+use tracing::debug;
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Downloads a URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL, of any scheme `curl` supports.
+///
+/// # Returns
+///
+/// The downloaded data, or an `Error` if `curl` cannot be run or the download
+/// fails.
+pub fn download(url: &str) -> Result<Vec<u8>> {
+    curl(url, &[])
+}
+
+/// Downloads an HTTPS URL, refusing to follow redirects to other schemes.
+///
+/// # Arguments
+///
+/// * `url` - The URL, which must start with `https://`.
+///
+/// # Returns
+///
+/// The downloaded data, or an `Error` if the URL is not HTTPS or the download
+/// fails.
+pub fn download_https(url: &str) -> Result<Vec<u8>> {
+    if !is_https(url) {
+        return Err(ErrorKind::InsecureUrl {
+            url: url.to_string(),
+        }
+        .into());
+    }
+
+    curl(url, &["--proto", "=https", "--proto-redir", "=https"])
+}
+
+/// Checks whether a URL uses HTTPS.
+fn is_https(url: &str) -> bool {
+    url.get(..8)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+        && url.len() > 8
+}
+
+fn curl(url: &str, args: &[&str]) -> Result<Vec<u8>> {
+    debug!(url, "downloading");
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(args)
+        .arg(url)
+        .output()
+        .map_err(|e| {
+            Error::new(ErrorKind::Spawn {
+                command: "curl".to_string(),
+                source: e,
+            })
+        })?;
+
+    if !output.status.success() {
+        return Err(ErrorKind::Download {
+            url: url.to_string(),
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_https() {
+        assert!(is_https("https://internal/configs/staging.env"));
+        assert!(is_https("HTTPS://internal/staging.env"));
+        assert!(!is_https("https://"));
+        assert!(!is_https("http://internal/configs/staging.env"));
+        assert!(!is_https("file:///etc/passwd"));
+
+        let err = download_https("http://internal/staging.env").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::InsecureUrl { .. }));
+    }
+}
@@ -0,0 +1,211 @@
+//! Profile index module for the envoke CLI tool.
+//!
+//! This module provides the `Index` struct, a cache of facts about each
+//! profile that would otherwise require reading and parsing it: a digest of
+//! its contents, how many variables it defines and its description. Detailed
+//! listings and `status` read the index instead of every profile.
+//!
+//! Each entry remembers the size and modification time of the file it was
+//! built from. When either differs, for example because the profile was
+//! edited outside envoke, the entry is rebuilt from the file. The index is a
+//! cache: it is stored as JSON in the envoke directory, and a missing or
+//! corrupt index is simply rebuilt.
+
+use std::collections::BTreeMap;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::sha256_hex;
+use crate::dotenv::Document;
+use crate::fs::Metadata;
+
+/// Name of the index file within the envoke directory.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Cached facts about every profile, keyed by profile name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Index {
+    /// Per-profile entries.
+    pub profiles: BTreeMap<String, IndexEntry>,
+
+    /// Whether the index changed since it was loaded.
+    #[serde(skip)]
+    changed: bool,
+}
+
+/// Cached facts about a single profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// A digest of the profile's contents.
+    pub hash: String,
+
+    /// The number of variables the profile defines.
+    pub variables: usize,
+
+    /// The `# envoke:description` of the profile, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The size of the profile file in bytes.
+    pub len: u64,
+
+    /// The modification time of the profile file, in nanoseconds since the
+    /// Unix epoch.
+    pub modified: u64,
+}
+
+impl Index {
+    /// Gets the entry for a profile, if it is up to date.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `metadata` - The current metadata of the profile file.
+    ///
+    /// # Returns
+    ///
+    /// The entry, or `None` if there is none or the file has changed since it
+    /// was built.
+    pub fn get(&self, profile: &str, metadata: &Metadata) -> Option<&IndexEntry> {
+        self.profiles
+            .get(profile)
+            .filter(|entry| entry.len == metadata.len() && entry.modified == nanos(metadata))
+    }
+
+    /// Records the entry for a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `entry` - The entry to record.
+    pub fn insert(&mut self, profile: &str, entry: IndexEntry) {
+        if self.profiles.get(profile) != Some(&entry) {
+            self.profiles.insert(profile.to_string(), entry);
+            self.changed = true;
+        }
+    }
+
+    /// Removes the entry for a profile.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the profile had an entry.
+    pub fn forget(&mut self, profile: &str) -> bool {
+        let removed = self.profiles.remove(profile).is_some();
+        self.changed |= removed;
+        removed
+    }
+
+    /// Removes the entries of profiles that no longer exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `profiles` - The names of the existing profiles.
+    pub fn retain<S: AsRef<str>>(&mut self, profiles: &[S]) {
+        let before = self.profiles.len();
+        self.profiles
+            .retain(|name, _| profiles.iter().any(|profile| profile.as_ref() == name));
+        self.changed |= self.profiles.len() != before;
+    }
+
+    /// Checks whether the index changed since it was loaded and should be
+    /// saved.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl IndexEntry {
+    /// Builds the entry for a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The raw contents of the profile.
+    /// * `doc` - The parsed contents of the profile.
+    /// * `description` - The description of the profile, if any.
+    /// * `metadata` - The metadata of the profile file.
+    pub fn new(
+        contents: &str,
+        doc: &Document,
+        description: Option<String>,
+        metadata: &Metadata,
+    ) -> Self {
+        IndexEntry {
+            hash: sha256_hex(contents),
+            variables: doc.entries().count(),
+            description,
+            len: metadata.len(),
+            modified: nanos(metadata),
+        }
+    }
+}
+
+/// Converts the modification time of a file to nanoseconds since the epoch.
+fn nanos(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn entry(contents: &str, metadata: &Metadata) -> IndexEntry {
+        IndexEntry::new(
+            contents,
+            &Document::parse(contents).unwrap(),
+            None,
+            metadata,
+        )
+    }
+
+    #[test]
+    fn test_freshness() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let metadata = Metadata::new(false, 8, modified);
+
+        let mut index = Index::default();
+        index.insert("dev", entry("A=1\nB=2\n", &metadata));
+        assert!(index.is_changed());
+        assert_eq!(2, index.get("dev", &metadata).unwrap().variables);
+
+        // A different size or modification time means the file changed.
+        assert!(
+            index
+                .get("dev", &Metadata::new(false, 9, modified))
+                .is_none()
+        );
+        let later = modified + Duration::from_nanos(1);
+        assert!(index.get("dev", &Metadata::new(false, 8, later)).is_none());
+        assert!(index.get("prod", &metadata).is_none());
+    }
+
+    #[test]
+    fn test_changes() {
+        let metadata = Metadata::new(false, 4, SystemTime::UNIX_EPOCH);
+
+        let mut index: Index = serde_json::from_str("{}").unwrap();
+        assert!(!index.is_changed());
+
+        index.insert("dev", entry("A=1\n", &metadata));
+        index.insert("prod", entry("A=1\n", &metadata));
+        let mut index: Index =
+            serde_json::from_str(&serde_json::to_string(&index).unwrap()).unwrap();
+
+        // Recording the same entry again is not a change.
+        index.insert("dev", entry("A=1\n", &metadata));
+        assert!(!index.is_changed());
+
+        index.retain(&["dev"]);
+        assert!(index.is_changed());
+        assert!(index.forget("dev"));
+        assert!(!index.forget("dev"));
+        assert!(index.profiles.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Profile integrity module for the envoke CLI tool.
+//!
+//! Whenever envoke writes a profile it records a checksum of the contents in
+//! the profile metadata. Comparing a profile with its checksum reveals changes
+//! made out of band, for example by an editor, another tool or a file sync
+//! that resolved a conflict, so `switch` can warn before activating them and
+//! `envoke verify-integrity` can report them.
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::digest::sha256_hex;
+use crate::error::Result;
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+
+/// How a profile compares with the checksum envoke recorded for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Integrity {
+    /// The profile is as envoke last wrote it.
+    Intact,
+
+    /// The profile changed since envoke last wrote it.
+    Modified,
+
+    /// No checksum has been recorded, as envoke has not written the profile
+    /// since checksums were introduced.
+    Unrecorded,
+}
+
+impl Integrity {
+    /// Returns the lowercase name of the state.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Integrity::Intact => "intact",
+            Integrity::Modified => "modified",
+            Integrity::Unrecorded => "unrecorded",
+        }
+    }
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Writes a profile and records the checksum of its new contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `contents` - The new contents.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the profile or metadata cannot be
+    /// written.
+    pub fn save_profile<S, C>(&self, profile: S, contents: C) -> Result<()>
+    where
+        S: AsRef<str>,
+        C: AsRef<[u8]>,
+    {
+        self.write_file(&self.profile_path(&profile), contents.as_ref())?;
+        self.record_checksum(profile, contents)
+    }
+
+    /// Records the checksum of a profile's contents as the expected one.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `contents` - The contents of the profile.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the metadata cannot be written.
+    pub fn record_checksum<S, C>(&self, profile: S, contents: C) -> Result<()>
+    where
+        S: AsRef<str>,
+        C: AsRef<[u8]>,
+    {
+        let name = self.profile_name(profile);
+        let checksum = sha256_hex(contents);
+
+        let mut metadata = self.load_metadata()?;
+        let entry = metadata.profiles.entry(name).or_default();
+        if entry.checksum.as_deref() == Some(checksum.as_str()) {
+            return Ok(());
+        }
+
+        entry.checksum = Some(checksum);
+        self.save_metadata(&metadata)
+    }
+
+    /// Compares a profile with the checksum recorded for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The state of the profile, or an `Error` if it or the metadata cannot be
+    /// read.
+    pub fn check_integrity<S: AsRef<str>>(&self, profile: S) -> Result<Integrity> {
+        let name = self.profile_name(profile);
+        let metadata = self.load_metadata()?;
+        let Some(expected) = metadata
+            .profiles
+            .get(&name)
+            .and_then(|meta| meta.checksum.as_ref())
+        else {
+            return Ok(Integrity::Unrecorded);
+        };
+
+        let actual = sha256_hex(self.read_bytes(&self.profile_path(&name))?);
+        if &actual == expected {
+            Ok(Integrity::Intact)
+        } else {
+            debug!(profile = %name, %expected, %actual, "profile changed out of band");
+            Ok(Integrity::Modified)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_check_integrity() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        fs.add_file(".envoke/legacy.env", "A=1\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+
+        assert_eq!(
+            Integrity::Unrecorded,
+            manager.check_integrity("legacy").unwrap()
+        );
+
+        manager.save_profile("dev", "PORT=8080\n").unwrap();
+        assert_eq!(Integrity::Intact, manager.check_integrity("dev").unwrap());
+
+        manager.fs.add_file(".envoke/dev.env", "PORT=9090\n");
+        assert_eq!(Integrity::Modified, manager.check_integrity("dev").unwrap());
+
+        // Accepting the change records the new contents.
+        manager.record_checksum("dev", "PORT=9090\n").unwrap();
+        assert_eq!(Integrity::Intact, manager.check_integrity("dev").unwrap());
+
+        // Observing key ages keeps the checksum.
+        let mut metadata = manager.load_metadata().unwrap();
+        manager.observe_profile(&mut metadata, "dev").unwrap();
+        manager.save_metadata(&metadata).unwrap();
+        assert_eq!(Integrity::Intact, manager.check_integrity("dev").unwrap());
+    }
+}
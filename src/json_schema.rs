@@ -0,0 +1,168 @@
+//! JSON Schema module for the envoke CLI tool.
+//!
+//! Wrappers and editor plugins read `config.toml` and the output of `--json`
+//! flags. This module describes each of those documents with a JSON Schema,
+//! printed by `envoke schema` and published in the `schemas` directory, so
+//! they can be validated against a stable contract.
+
+use std::collections::BTreeMap;
+
+use schemars::{Schema, schema_for};
+
+use crate::commands::audit_keys::KeyReport;
+use crate::commands::projects::ProjectStatus;
+use crate::commands::scan::Finding;
+use crate::config::Config;
+use crate::operations::{ActiveProfile, ProfileInfo, Status};
+use crate::oplog::LogEntry;
+use crate::patch::Operation;
+use crate::presenter::ErrorReport;
+
+/// Where the published schemas can be downloaded from.
+pub const SCHEMAS_URL: &str = "https://raw.githubusercontent.com/TSxo/envoke/main/schemas";
+
+/// A file envoke reads, or output it writes, that has a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    /// `.envoke/config.toml`.
+    Config,
+
+    /// The output of `envoke list --json`.
+    List,
+
+    /// The output of `envoke current --json`.
+    Current,
+
+    /// The output of `envoke status --json`.
+    Status,
+
+    /// The output of `envoke env --json` and `envoke export --format json`.
+    Env,
+
+    /// The output of `envoke projects list --json`.
+    Projects,
+
+    /// The output of `envoke scan --json`.
+    Scan,
+
+    /// The output of `envoke audit-keys --json`.
+    AuditKeys,
+
+    /// Each line written by `envoke log --json`.
+    Log,
+
+    /// The output of `envoke diff --format json`, as read by `envoke apply`.
+    Diff,
+
+    /// Errors written with `--error-format json`.
+    Error,
+}
+
+impl Subject {
+    /// Every subject, in the order they are documented.
+    pub const ALL: [Subject; 11] = [
+        Subject::Config,
+        Subject::List,
+        Subject::Current,
+        Subject::Status,
+        Subject::Env,
+        Subject::Projects,
+        Subject::Scan,
+        Subject::AuditKeys,
+        Subject::Log,
+        Subject::Diff,
+        Subject::Error,
+    ];
+
+    /// Gets the name of the subject, as used for its published file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::json_schema::Subject;
+    ///
+    /// assert_eq!("audit-keys", Subject::AuditKeys.name());
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subject::Config => "config",
+            Subject::List => "list",
+            Subject::Current => "current",
+            Subject::Status => "status",
+            Subject::Env => "env",
+            Subject::Projects => "projects",
+            Subject::Scan => "scan",
+            Subject::AuditKeys => "audit-keys",
+            Subject::Log => "log",
+            Subject::Diff => "diff",
+            Subject::Error => "error",
+        }
+    }
+
+    /// Gets the name of the file the schema is published as.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::json_schema::Subject;
+    ///
+    /// assert_eq!("config.schema.json", Subject::Config.file_name());
+    /// ```
+    pub fn file_name(&self) -> String {
+        format!("{}.schema.json", self.name())
+    }
+
+    /// Generates the JSON Schema of the subject.
+    ///
+    /// # Returns
+    ///
+    /// The schema, with an `$id` pointing to where it is published.
+    pub fn schema(&self) -> Schema {
+        let (mut schema, title) = match self {
+            Subject::Config => (schema_for!(Config), "envoke config.toml"),
+            Subject::List => (schema_for!(Vec<ProfileInfo>), "envoke list --json"),
+            Subject::Current => (schema_for!(ActiveProfile), "envoke current --json"),
+            Subject::Status => (schema_for!(Status), "envoke status --json"),
+            Subject::Env => (schema_for!(BTreeMap<String, String>), "envoke env --json"),
+            Subject::Projects => (
+                schema_for!(Vec<ProjectStatus>),
+                "envoke projects list --json",
+            ),
+            Subject::Scan => (schema_for!(Vec<Finding>), "envoke scan --json"),
+            Subject::AuditKeys => (schema_for!(KeyReport), "envoke audit-keys --json"),
+            Subject::Log => (schema_for!(LogEntry), "envoke log --json"),
+            Subject::Diff => (schema_for!(Vec<Operation>), "envoke diff --format json"),
+            Subject::Error => (schema_for!(ErrorReport), "envoke --error-format json"),
+        };
+
+        schema.insert(
+            "$id".to_string(),
+            format!("{}/{}", SCHEMAS_URL, self.file_name()).into(),
+        );
+        schema.insert("title".to_string(), title.into());
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas() {
+        for subject in Subject::ALL {
+            let schema = subject.schema();
+            assert_eq!(
+                format!("{}/{}", SCHEMAS_URL, subject.file_name()),
+                schema.get("$id").unwrap().as_str().unwrap()
+            );
+        }
+
+        // Unknown settings are rejected, so the schema must reject them too.
+        let config = Subject::Config.schema();
+        assert_eq!(Some(&false.into()), config.get("additionalProperties"));
+        let properties = config.get("properties").unwrap();
+        assert!(properties.get("default_profile").is_some());
+        assert!(properties.get("envoke_dir").is_none());
+    }
+}
@@ -1,6 +1,44 @@
+pub mod backup;
+pub mod bundle;
 pub mod cli;
+pub mod clipboard;
+pub mod codegen;
 pub mod commands;
+pub mod completion;
+pub mod compose;
 pub mod config;
+pub mod digest;
+pub mod dotenv;
+pub mod encryption;
 pub mod error;
 pub mod fs;
+pub mod git;
+pub mod glob;
+pub mod hooks;
+pub mod http;
+pub mod index;
+pub mod integrity;
+pub mod json_schema;
+pub mod lock;
+pub mod merge;
+pub mod metadata;
+pub mod operations;
+pub mod oplog;
+pub mod patch;
+pub mod pins;
+pub mod presenter;
 pub mod profile;
+pub mod prompt;
+pub mod redact;
+pub mod registry;
+pub mod remote;
+pub mod scan;
+pub mod schema;
+pub mod signing;
+pub mod template;
+pub mod terraform;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "self-update")]
+pub mod update;
+pub mod usage;
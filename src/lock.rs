@@ -0,0 +1,151 @@
+//! Lock module for the envoke CLI tool.
+//!
+//! A lock freezes the active profile of a directory on one machine, for
+//! example on a production host where switching by accident would be costly.
+//! While `.envoke/lock.json` exists, commands that change, switch or remove
+//! profiles refuse to run, as does `deinit`. The lock records who set it, when and why, so the error
+//! explains whom to ask. It is machine-local state and is not committed.
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::oplog;
+use crate::profile::ProfileManager;
+
+/// Name of the lock file within the envoke directory.
+pub const LOCK_FILE: &str = "lock.json";
+
+/// Who locked a directory, when and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock {
+    /// When the lock was set, in RFC 3339 format.
+    pub time: String,
+
+    /// The user who set the lock.
+    pub user: String,
+
+    /// Why the directory is locked, if a reason was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Lock {
+    /// Creates a lock set by the current user now.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the directory is locked.
+    ///
+    /// # Returns
+    ///
+    /// A new `Lock` stamped with the current time and user.
+    pub fn new(reason: Option<String>) -> Self {
+        Lock {
+            time: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            user: oplog::current_user(),
+            reason,
+        }
+    }
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Loads the lock of the directory.
+    ///
+    /// # Returns
+    ///
+    /// The lock, `None` if the directory is not locked, or an `Error` if the
+    /// lock file cannot be read or parsed.
+    pub fn load_lock(&self) -> Result<Option<Lock>> {
+        let path = self.config.envoke_dir.join(LOCK_FILE);
+        if !self.fs.path_exists(&path) {
+            return Ok(None);
+        }
+
+        let contents = self.read_file(&path)?;
+        serde_json::from_str(&contents).map(Some).map_err(|e| {
+            Error::new(ErrorKind::ParseConfig {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    /// Locks the directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `lock` - Who is locking the directory and why.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the lock file cannot be written.
+    pub fn save_lock(&self, lock: &Lock) -> Result<()> {
+        let contents = serde_json::to_string_pretty(lock).expect("locks are serializable");
+        self.write_file(&self.config.envoke_dir.join(LOCK_FILE), contents + "\n")
+    }
+
+    /// Unlocks the directory.
+    ///
+    /// # Returns
+    ///
+    /// The lock that was removed, `None` if the directory was not locked, or
+    /// an `Error` if the lock file cannot be removed.
+    pub fn remove_lock(&self) -> Result<Option<Lock>> {
+        let lock = self.load_lock()?;
+        if lock.is_some() {
+            self.fs
+                .remove_file(&self.config.envoke_dir.join(LOCK_FILE))?;
+        }
+        Ok(lock)
+    }
+
+    /// Fails if the directory is locked.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if profiles may be changed, switched or removed, or an
+    /// `Error` describing the lock.
+    pub fn ensure_unlocked(&self) -> Result<()> {
+        match self.load_lock()? {
+            Some(lock) => Err(ErrorKind::Locked {
+                user: lock.user,
+                time: lock.time,
+                reason: lock.reason,
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_lock_round_trip() {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+        assert!(manager.ensure_unlocked().is_ok());
+
+        let lock = Lock::new(Some("release freeze".into()));
+        manager.save_lock(&lock).unwrap();
+        assert_eq!(Some(lock.clone()), manager.load_lock().unwrap());
+        let err = manager.ensure_unlocked().unwrap_err();
+        assert!(
+            matches!(err.kind, ErrorKind::Locked { reason: Some(ref r), .. } if r == "release freeze")
+        );
+
+        assert_eq!(Some(lock), manager.remove_lock().unwrap());
+        assert_eq!(None, manager.remove_lock().unwrap());
+        assert!(manager.ensure_unlocked().is_ok());
+    }
+}
@@ -1,29 +1,499 @@
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
-use clap::Parser;
-use envoke::cli::{Cli, Command};
-use envoke::commands::{create, current, init, list, remove, switch};
-use envoke::config::Config;
-use envoke::fs;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+use envoke::cli::{
+    ActivateShell, BundleCommand, Cli, Command, ExportFormat, ProjectsCommand, RecipientsCommand,
+    RemoteCommand, TemplateCommand,
+};
+#[cfg(feature = "self-update")]
+use envoke::commands::self_update;
+use envoke::commands::{
+    activate, apply, audit, audit_keys, bundle, ci, codegen, completions, create, current, deinit,
+    diff, encrypt, env, example, export, gc, get, import, init, keygen, list, lock, log, man,
+    mv_key, pin, projects, prompt, recipients, redact, remote, remove, rename, replace, resolve,
+    restore, run, scan, schema, shell, show, sign, status, switch, template, ui, use_profile,
+    validate, verify, verify_integrity, verify_signature, watch,
+};
+use envoke::config::{Config, ENVOKE_DIR, LogLocation};
+use envoke::error::{EXIT_FAILURE, EXIT_SKIPPED, ErrorKind, Result};
+use envoke::fs::{self, DryRunFileSystem, EnvokeFileSystem, FileSystem};
+use envoke::operations::ProfileOrder;
+use envoke::oplog;
+use envoke::presenter::{self, Format, Stream};
 use envoke::profile::ProfileManager;
+use tracing::level_filters::LevelFilter;
 
 fn main() {
+    // Answers completion requests from the shell scripts printed by `completions`.
+    CompleteEnv::with_factory(Cli::command).complete();
+
     let args = Cli::parse();
-    let config = Config::default();
+    presenter::set_color_choice(args.color);
+    presenter::set_quiet(args.quiet);
+    init_logging(args.verbose, args.quiet);
+
+    let error_format = args.error_format;
+    // The prompt is drawn on every command line, so it fails silently - a
+    // broken config must not print an error each time. Starship hides the
+    // module when the command fails.
+    let prompt = match args.command {
+        Command::Prompt { starship, .. } => Some(starship),
+        _ => None,
+    };
+    if let Err(e) = run(args) {
+        if let Some(starship) = prompt {
+            process::exit(if starship { EXIT_FAILURE } else { 0 });
+        }
+        // Failing to write to stderr leaves nothing else to report to.
+        let _ = presenter::report_error(&mut io::stderr(), &e, error_format);
+        process::exit(e.exit_code());
+    }
+}
+
+/// Logs to stderr, at a level chosen by `-v` and `-q`.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .without_time()
+        .with_ansi(presenter::color_enabled(Stream::Stderr))
+        .init();
+}
+
+fn run(args: Cli) -> Result<()> {
+    let mut config = Config::load(PathBuf::from(ENVOKE_DIR))?;
+
+    // Commands run against an overlay that records changes, and nothing is
+    // logged as nothing happened.
+    if args.dry_run {
+        if !args.command.supports_dry_run() {
+            return Err(ErrorKind::DryRunUnsupported.into());
+        }
+        config.dry_run = true;
+        let manager = ProfileManager::new(config, DryRunFileSystem::new(EnvokeFileSystem::new()));
+        execute(&manager, args)?;
+        return presenter::changes(&mut io::stdout(), &manager.fs.changes());
+    }
+
     let fs = fs::EnvokeFileSystem::new();
     let manager = ProfileManager::new(config, fs);
 
-    let out = match args.command {
-        Command::Init => init::run(&manager),
-        Command::Create { profile } => create::run(&manager, profile),
-        Command::Switch { profile, force } => switch::run(&manager, profile, force),
-        Command::Remove { profile } => remove::run(&manager, profile),
-        Command::List => list::run(&manager),
-        Command::Current => current::run(&manager),
+    let operation = args.command.operation();
+    // `watch` runs until it is interrupted, so it is recorded as it starts.
+    let record_first = matches!(args.command, Command::Watch);
+    if let Some(operation) = operation.filter(|_| record_first)
+        && (manager.config.log.location == LogLocation::State || manager.is_initialized())
+    {
+        oplog::record(&manager, operation, None);
+    }
+
+    let result = execute(&manager, args);
+
+    // Retention is enforced whenever something changed, so backups do not
+    // pile up between runs of `envoke gc`.
+    if operation.is_some()
+        && result.is_ok()
+        && manager.is_initialized()
+        && let Err(e) = manager.gc()
+    {
+        presenter::warn_error(e);
+    }
+
+    // A project log is only kept once the directory has been initialized.
+    if let Some(operation) = operation.filter(|_| !record_first)
+        && (manager.config.log.location == LogLocation::State || manager.is_initialized())
+    {
+        let error = result.as_ref().err().map(ToString::to_string);
+        oplog::record(&manager, operation, error);
+    }
+
+    result
+}
+
+fn execute<F: FileSystem>(manager: &ProfileManager<F>, args: Cli) -> Result<()> {
+    if args.command.respects_lock() {
+        manager.ensure_unlocked()?;
+    }
+
+    let auto = args.auto;
+    let mut report: Box<dyn Write> = match args.quiet {
+        true => Box::new(io::sink()),
+        false => Box::new(io::stderr()),
+    };
+    match dispatch(manager, args) {
+        // Retry once the default profile is active, with freshly parsed
+        // arguments as the first attempt consumed them. The switch is
+        // reported on stderr so it never mixes into output like exports.
+        Err(e) if switch::activate_default(manager, &mut report, &e, auto)? => {
+            dispatch(manager, Cli::parse())
+        }
+        result => result,
+    }
+}
+
+fn dispatch<F: FileSystem>(manager: &ProfileManager<F>, args: Cli) -> Result<()> {
+    // Values are only masked when a person is likely to be reading them.
+    let mask = !args.show_secrets && io::stdout().is_terminal();
+    let redactor = manager.config.secrets.redactor(mask);
+    // Commands that change profiles only report progress, which `--quiet`
+    // silences; output that was asked for is always printed.
+    let mut out: Box<dyn Write> = match args.quiet && args.command.operation().is_some() {
+        true => Box::new(io::sink()),
+        false => Box::new(io::stdout()),
     };
+    let out = &mut out;
 
-    if let Err(e) = out {
-        eprintln!("{}", e);
-        process::exit(1);
+    match args.command {
+        Command::Init {
+            starship_snippet: true,
+            ..
+        } => init::starship_snippet(out),
+        Command::Init {
+            profiles,
+            template,
+            switch,
+            default,
+            ok_if_initialized,
+            ..
+        } => init::run(
+            manager,
+            out,
+            profiles,
+            template,
+            switch,
+            default,
+            ok_if_initialized,
+        ),
+        Command::Deinit { eject, yes } => deinit::run(manager, out, eject, yes),
+        Command::Create {
+            profiles,
+            template,
+            stdin,
+            interactive,
+            switch,
+            no_switch,
+            force,
+            if_missing,
+        } => {
+            let seed = match template {
+                Some(template) => create::Seed::Template(template),
+                None if interactive => create::Seed::Interactive,
+                None if stdin => create::Seed::Contents(envoke::prompt::read_stdin()?),
+                None => create::Seed::Empty,
+            };
+            let switch = switch || (manager.config.create.switch && !no_switch);
+            create::run_many(manager, out, profiles, &seed, switch, force, if_missing)
+        }
+        Command::Switch {
+            profile,
+            force,
+            validate,
+            strict,
+            if_exists,
+        } => {
+            let profile = match profile {
+                Some(profile) => profile,
+                None => switch::pick(manager)?,
+            };
+            // Scripts tell a skipped switch apart from a failed one by the
+            // exit code alone.
+            if if_exists && switch::is_missing(manager, &profile) {
+                process::exit(EXIT_SKIPPED);
+            }
+            switch::run(manager, out, profile, force, validate, strict)
+        }
+        Command::Remove { profile, switch_to } => remove::run(manager, out, profile, switch_to),
+        Command::Lock { reason } => lock::lock(manager, out, reason),
+        Command::Unlock { reason } => lock::unlock(manager, out, reason),
+        Command::Gc => gc::run(manager, out),
+        Command::Restore {
+            profile,
+            backup,
+            list,
+            yes,
+        } => restore::run(manager, out, profile, backup, list, yes, &redactor),
+        Command::Use {
+            profile,
+            global,
+            copy,
+            force,
+        } => use_profile::run(manager, out, profile, global, copy, force),
+        Command::List {
+            global,
+            long,
+            json,
+            sort,
+            recent,
+            group,
+        } => {
+            let order = if recent { ProfileOrder::Recent } else { sort };
+            list::run(
+                manager,
+                out,
+                global,
+                Format::from_flags(long, json),
+                order,
+                group,
+            )
+        }
+        Command::Pin { profiles } => pin::pin(manager, out, profiles),
+        Command::Unpin { profiles } => pin::unpin(manager, out, profiles),
+        Command::Projects { command } => match command {
+            None => projects::list(manager, out, false),
+            Some(ProjectsCommand::List { json }) => projects::list(manager, out, json),
+            Some(ProjectsCommand::Switch {
+                profile,
+                projects: paths,
+                all,
+            }) => projects::switch(manager, out, profile, paths, all),
+            Some(ProjectsCommand::Forget { path }) => projects::forget(manager, out, path),
+        },
+        Command::Current { long, json, is } => {
+            // With `--quiet` or `--is`, the exit code alone answers whether
+            // the profile is active.
+            if !current::run(manager, out, Format::from_flags(long, json), is, args.quiet)? {
+                process::exit(EXIT_FAILURE);
+            }
+            Ok(())
+        }
+        Command::Status { long, json } => status::run(manager, out, Format::from_flags(long, json)),
+        Command::Watch => watch::run(manager, out),
+        Command::Show { profile, filter } => show::run(manager, out, profile, &filter, &redactor),
+        Command::Export {
+            profile,
+            format,
+            shell,
+            no_local,
+            filter,
+        } => {
+            let format = shell.map_or(format, |_| ExportFormat::Shell);
+            let shell = shell.unwrap_or(ActivateShell::Posix);
+            export::run(
+                manager, out, profile, format, shell, !no_local, &filter, &redactor,
+            )
+        }
+        Command::Diff {
+            profile,
+            other,
+            env,
+            format,
+        } => diff::run(manager, out, profile, other, env, format, &redactor),
+        Command::Apply { profile, patch } => apply::run(manager, out, profile, &patch),
+        Command::Scan {
+            profile,
+            no_history,
+            json,
+        } => {
+            let history = !no_history && manager.config.scan.history;
+            scan::run(manager, out, profile, history, json, &redactor)
+        }
+        Command::Keygen { force } => keygen::run(manager, out, force),
+        Command::Encrypt { profile, keys } => encrypt::run(manager, out, profile, keys),
+        Command::Recipients(command) => match command {
+            RecipientsCommand::List => recipients::list(manager, out),
+            RecipientsCommand::Add { recipient } => recipients::add(manager, out, recipient),
+            RecipientsCommand::Remove { recipient } => recipients::remove(manager, out, recipient),
+        },
+        Command::Resolve {
+            key,
+            profile,
+            no_local,
+            precedence,
+        } => {
+            let precedence = precedence.unwrap_or(manager.config.run.precedence);
+            resolve::run(manager, out, key, profile, !no_local, precedence, &redactor)
+        }
+        Command::Import {
+            profile,
+            from_compose,
+            service,
+            from_tfvars,
+            from_tf_output,
+            url,
+            sha256,
+            from_project,
+            link,
+            prefix,
+            transform,
+        } => {
+            let source = match (from_compose, from_tfvars, from_tf_output, url, from_project) {
+                (Some(file), _, _, _, _) => import::Source::Compose {
+                    file,
+                    service: service.unwrap_or_default(),
+                },
+                (_, Some(file), _, _, _) => import::Source::Tfvars(file),
+                (_, _, Some(file), _, _) => import::Source::TfOutput(file),
+                (_, _, _, Some(url), _) => import::Source::Url {
+                    url,
+                    sha256: sha256.unwrap_or_default(),
+                },
+                (_, _, _, _, Some(dir)) => import::Source::Project { dir, link },
+                (None, None, None, None, None) => unreachable!("clap requires a source"),
+            };
+            import::run(manager, out, profile, &source, prefix.as_deref(), transform)
+        }
+        Command::Redact { profile, all } => {
+            redact::run(manager, io::stdin().lock(), out, profile, all)
+        }
+        Command::Env {
+            no_local,
+            json,
+            filter,
+        } => env::run(manager, out, !no_local, json, &filter, &redactor),
+        Command::Activate {
+            profile,
+            shell,
+            no_local,
+        } => activate::run(manager, out, profile, shell, !no_local),
+        Command::Deactivate { shell } => activate::deactivate(out, shell),
+        Command::Shell { profile, no_local } => {
+            // Exit with the status of the last command run in the subshell.
+            let code = shell::run(manager, profile, !no_local)?;
+            if code != 0 {
+                process::exit(code);
+            }
+            Ok(())
+        }
+        Command::Run {
+            profile,
+            no_local,
+            precedence,
+            isolated,
+            allow,
+            overrides,
+            command,
+        } => {
+            let precedence = precedence.unwrap_or(manager.config.run.precedence);
+            let isolated = isolated.then(|| [manager.config.run.allow.clone(), allow].concat());
+            run::run(
+                manager, profile, !no_local, precedence, isolated, overrides, command,
+            )
+        }
+        Command::Get {
+            key,
+            profile,
+            copy,
+            clear_after,
+        } => get::run(manager, out, key, profile, copy, clear_after, &redactor),
+        Command::Example {
+            profile,
+            output,
+            placeholder,
+            force,
+        } => example::run(manager, out, profile, output, placeholder, force),
+        Command::Verify {
+            profile,
+            example,
+            strict,
+            group,
+        } => verify::run(manager, out, profile, group, &example, strict),
+        Command::VerifyIntegrity { profile, accept } => {
+            verify_integrity::run(manager, out, profile, accept)
+        }
+        Command::Sign { profiles } => sign::run(manager, out, profiles),
+        Command::VerifySignature { profile } => verify_signature::run(manager, out, profile),
+        Command::Validate { profile } => validate::run(manager, out, profile),
+        Command::Codegen { language, output } => codegen::run(manager, out, language, output),
+        Command::Schema { subject } => schema::run(out, subject.subject()),
+        Command::Template(command) => match command {
+            TemplateCommand::List => template::list(manager, out),
+            TemplateCommand::Add {
+                template,
+                from,
+                file,
+                global,
+            } => template::add(manager, out, template, from, file, global),
+            TemplateCommand::Remove { template, global } => {
+                template::remove(manager, out, template, global)
+            }
+            TemplateCommand::Show { template } => template::show(manager, out, template, &redactor),
+        },
+        Command::Bundle(command) => match command {
+            BundleCommand::Export {
+                out: archive,
+                profiles,
+                group,
+                encrypt,
+            } => {
+                let profiles = match group {
+                    Some(group) => manager.group_profiles(group)?,
+                    None => profiles,
+                };
+                bundle::export(manager, out, archive, profiles, encrypt)
+            }
+            BundleCommand::Import { file, force } => bundle::import(manager, out, file, force),
+        },
+        Command::Remote(command) => match command {
+            RemoteCommand::List => remote::list(manager, out),
+            RemoteCommand::Add { name, url } => remote::add(manager, out, name, url),
+            RemoteCommand::Remove { name } => remote::remove(manager, out, name),
+        },
+        Command::Push { remote, force } => remote::push(manager, out, remote, force),
+        Command::Pull {
+            remote,
+            force,
+            on_conflict,
+            strategy,
+        } => remote::pull(manager, out, remote, force, on_conflict, strategy),
+        Command::Ci { profile } => ci::run(manager, out, profile),
+        Command::Audit { profile, max_age } => audit::run(manager, out, profile, max_age),
+        Command::Rename {
+            name,
+            renamed,
+            pattern,
+        } => rename::run(manager, out, name, renamed, pattern),
+        Command::MvKey {
+            old,
+            new,
+            profiles,
+            group,
+        } => mv_key::run(manager, out, old, new, profiles, group),
+        Command::Replace {
+            value_matching,
+            replacement,
+            regex,
+            profiles,
+        } => replace::run(
+            manager,
+            out,
+            value_matching,
+            replacement,
+            regex,
+            profiles,
+            &redactor,
+        ),
+        Command::AuditKeys { json } => audit_keys::run(manager, out, json),
+        Command::Log { limit, json } => log::run(manager, out, limit, json),
+        Command::Completions { shell, install } => completions::run(manager, out, shell, install),
+        Command::Man { out_dir } => man::run(manager, out, out_dir),
+        Command::Prompt {
+            format,
+            style,
+            shell,
+            starship,
+        } => {
+            // Starship only shows a custom module whose `when` command succeeds.
+            if !prompt::run(manager, out, format, style, shell, starship)? && starship {
+                process::exit(EXIT_FAILURE);
+            }
+            Ok(())
+        }
+        #[cfg(feature = "self-update")]
+        Command::SelfUpdate { check } => self_update::run(out, check),
+        Command::Ui => ui::run(manager, out, &redactor),
+        Command::ClearClipboard { millis } => get::clear_clipboard(Duration::from_millis(millis)),
     }
 }
@@ -0,0 +1,137 @@
+//! Merge module for the envoke CLI tool.
+//!
+//! This module combines two versions of a profile key by key, for example the
+//! local copy and the copy pulled from a remote. Keys found in only one
+//! version are kept, and keys whose values differ are conflicts that a
+//! resolver settles, either by asking the user or by following a
+//! [`MergeStrategy`]. The local layout, comments included, is preserved.
+
+use crate::cli::MergeStrategy;
+use crate::dotenv::Document;
+use crate::error::Result;
+
+/// A key whose value differs between the two versions of a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict<'a> {
+    /// The profile being merged.
+    pub profile: &'a str,
+
+    /// The conflicting key.
+    pub key: &'a str,
+
+    /// The local value.
+    pub ours: &'a str,
+
+    /// The incoming value.
+    pub theirs: &'a str,
+}
+
+impl MergeStrategy {
+    /// Settles a conflict without asking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::cli::MergeStrategy;
+    /// use envoke::merge::Conflict;
+    ///
+    /// let conflict = Conflict { profile: "dev", key: "PORT", ours: "80", theirs: "8080" };
+    /// assert_eq!("8080", MergeStrategy::Theirs.resolve(&conflict));
+    /// ```
+    pub fn resolve<'a>(&self, conflict: &Conflict<'a>) -> &'a str {
+        match self {
+            MergeStrategy::Ours => conflict.ours,
+            MergeStrategy::Theirs => conflict.theirs,
+        }
+    }
+}
+
+/// Merges the incoming version of a profile into the local one.
+///
+/// # Arguments
+///
+/// * `profile` - The name of the profile.
+/// * `ours` - The local version.
+/// * `theirs` - The incoming version.
+/// * `resolve` - Chooses the value of each conflicting key.
+///
+/// # Returns
+///
+/// The merged profile and the number of conflicts, or the first `Error`
+/// returned by `resolve`.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::Document;
+/// use envoke::merge::merge;
+///
+/// let ours = Document::parse("# Web\nPORT=80\n").unwrap();
+/// let theirs = Document::parse("PORT=8080\nHOST=localhost\n").unwrap();
+///
+/// let (merged, conflicts) = merge("dev", &ours, &theirs, |c| Ok(c.ours.to_string())).unwrap();
+/// assert_eq!("# Web\nPORT=80\nHOST=localhost\n", merged.to_string());
+/// assert_eq!(1, conflicts);
+/// ```
+pub fn merge<R>(
+    profile: &str,
+    ours: &Document,
+    theirs: &Document,
+    mut resolve: R,
+) -> Result<(Document, usize)>
+where
+    R: FnMut(&Conflict) -> Result<String>,
+{
+    let mut merged = ours.clone();
+    let mut conflicts = 0;
+
+    for key in theirs.key_set() {
+        let incoming = theirs.get(key).unwrap_or_default();
+        match ours.get(key) {
+            None => {
+                let entry = theirs.entries().filter(|entry| entry.key == key).last();
+                merged.push(entry.expect("the key is assigned").clone());
+            }
+            Some(local) if local == incoming => {}
+            Some(local) => {
+                conflicts += 1;
+                let value = resolve(&Conflict {
+                    profile,
+                    key,
+                    ours: local,
+                    theirs: incoming,
+                })?;
+                if value != local {
+                    merged.set(key, value);
+                }
+            }
+        }
+    }
+
+    Ok((merged, conflicts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let ours = Document::parse("A=1\n# Shared\nB=2\nC=3\n").unwrap();
+        let theirs = Document::parse("B=20\nC=3\nD=\"four words\"\n").unwrap();
+
+        let mut seen = Vec::new();
+        let (merged, conflicts) = merge("dev", &ours, &theirs, |conflict| {
+            seen.push(conflict.key.to_string());
+            Ok(MergeStrategy::Theirs.resolve(conflict).to_string())
+        })
+        .unwrap();
+
+        assert_eq!(vec!["B"], seen);
+        assert_eq!(1, conflicts);
+        assert_eq!(
+            "A=1\n# Shared\nB=20\nC=3\nD=\"four words\"\n",
+            merged.to_string()
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Profile metadata module for the envoke CLI tool.
+//!
+//! This module provides the `Metadata` struct which records facts about
+//! profiles that cannot be derived from their contents alone, such as when
+//! each value last changed. Metadata is stored as JSON in the envoke directory.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::hmac_sha256_hex;
+use crate::dotenv::Document;
+
+/// Name of the metadata file within the envoke directory.
+pub const METADATA_FILE: &str = "metadata.json";
+
+/// Name of the file within the envoke directory holding the project's key for
+/// digesting values, kept out of version control.
+pub const METADATA_KEY_FILE: &str = "metadata.key";
+
+/// Metadata for all profiles, keyed by profile name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    /// Per-profile metadata.
+    pub profiles: BTreeMap<String, ProfileMetadata>,
+}
+
+/// Metadata recorded for a single profile.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileMetadata {
+    /// Per-key metadata.
+    pub keys: BTreeMap<String, KeyMetadata>,
+
+    /// A digest of the contents envoke last wrote to the profile, used to
+    /// detect changes made by other tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+/// Metadata recorded for a single key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    /// A keyed digest of the key's value, used to detect changes without
+    /// storing it.
+    pub hash: String,
+
+    /// Unix timestamp (in seconds) of when the value was last seen to change.
+    pub changed_at: u64,
+}
+
+/// The age of a key's value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyAge {
+    /// The variable name.
+    pub key: String,
+
+    /// How long ago the value last changed.
+    pub age: Duration,
+}
+
+impl Metadata {
+    /// Records the current values of a profile.
+    ///
+    /// Keys whose value is new or differs from the last observation are
+    /// marked as changed at `modified`, keys that no longer exist are dropped,
+    /// and unchanged keys keep their original timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `doc` - The parsed contents of the profile.
+    /// * `modified` - When the profile file was last modified.
+    /// * `key` - The project's key for digesting values.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the recorded metadata changed, `false` otherwise.
+    pub fn observe(
+        &mut self,
+        profile: &str,
+        doc: &Document,
+        modified: SystemTime,
+        key: &[u8],
+    ) -> bool {
+        let modified = unix_seconds(modified);
+        let previous = self.profiles.remove(profile).unwrap_or_default();
+        let mut current = ProfileMetadata {
+            checksum: previous.checksum.clone(),
+            ..ProfileMetadata::default()
+        };
+
+        for entry in doc.entries() {
+            let hash = hash_value(key, &entry.key, &entry.value);
+            let changed_at = match previous.keys.get(&entry.key) {
+                Some(known) if known.hash == hash => known.changed_at,
+                _ => modified,
+            };
+
+            current
+                .keys
+                .insert(entry.key.clone(), KeyMetadata { hash, changed_at });
+        }
+
+        let changed = previous != current;
+        self.profiles.insert(profile.to_string(), current);
+        changed
+    }
+
+    /// Digests the values of a profile again with this project's key, keeping
+    /// when each last changed. Metadata recorded by another project, such as
+    /// in a bundle, was digested with that project's key.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `doc` - The parsed contents of the profile.
+    /// * `key` - The project's key for digesting values.
+    pub fn rehash(&mut self, profile: &str, doc: &Document, key: &[u8]) {
+        let Some(meta) = self.profiles.get_mut(profile) else {
+            return;
+        };
+
+        for entry in doc.entries() {
+            if let Some(known) = meta.keys.get_mut(&entry.key) {
+                known.hash = hash_value(key, &entry.key, &entry.value);
+            }
+        }
+    }
+
+    /// Forgets everything recorded about a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the profile had recorded metadata, `false` otherwise.
+    pub fn forget(&mut self, profile: &str) -> bool {
+        self.profiles.remove(profile).is_some()
+    }
+
+    /// Finds the keys of a profile whose value is older than `max_age`.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `max_age` - The maximum allowed age.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// The stale keys, oldest first.
+    pub fn stale_keys(&self, profile: &str, max_age: Duration, now: SystemTime) -> Vec<KeyAge> {
+        let mut stale: Vec<KeyAge> = self
+            .key_ages(profile, now)
+            .into_iter()
+            .filter(|key| key.age > max_age)
+            .collect();
+
+        stale.sort_by(|a, b| b.age.cmp(&a.age).then_with(|| a.key.cmp(&b.key)));
+        stale
+    }
+
+    /// Returns the age of every recorded key of a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// The age of each key, in key order.
+    pub fn key_ages(&self, profile: &str, now: SystemTime) -> Vec<KeyAge> {
+        let now = unix_seconds(now);
+
+        self.profiles
+            .get(profile)
+            .map(|meta| {
+                meta.keys
+                    .iter()
+                    .map(|(key, known)| KeyAge {
+                        key: key.clone(),
+                        age: Duration::from_secs(now.saturating_sub(known.changed_at)),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Formats an age compactly, using the largest whole unit.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::metadata::format_age;
+/// use std::time::Duration;
+///
+/// assert_eq!("3d", format_age(Duration::from_secs(3 * 86400 + 5)));
+/// assert_eq!("2h", format_age(Duration::from_secs(7200)));
+/// assert_eq!("0m", format_age(Duration::from_secs(10)));
+/// ```
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs >= 86400 {
+        format!("{}d", secs / 86400)
+    } else if secs >= 3600 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}m", secs / 60)
+    }
+}
+
+/// Converts a `SystemTime` into whole seconds since the Unix epoch.
+pub fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Digests a value together with its name so equal values under different
+/// names do not share a hash. The digest is keyed, so low-entropy values cannot
+/// be guessed from the metadata alone.
+fn hash_value(secret: &[u8], key: &str, value: &str) -> String {
+    hmac_sha256_hex(secret, format!("{}={}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86400;
+    const KEY: &[u8] = b"project key";
+
+    fn at(days: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(days * DAY)
+    }
+
+    #[test]
+    fn test_observe_records_keys() {
+        let mut metadata = Metadata::default();
+        let doc = Document::parse("A=1\nB=2\n").unwrap();
+
+        assert!(metadata.observe("dev", &doc, at(10), KEY));
+        assert!(!metadata.observe("dev", &doc, at(20), KEY));
+
+        let keys = &metadata.profiles["dev"].keys;
+        assert_eq!(2, keys.len());
+        assert_eq!(10 * DAY, keys["A"].changed_at);
+        assert_eq!(10 * DAY, keys["B"].changed_at);
+    }
+
+    #[test]
+    fn test_observe_tracks_changes() {
+        let mut metadata = Metadata::default();
+        metadata.observe("dev", &Document::parse("A=1\nB=2\n").unwrap(), at(10), KEY);
+        metadata.observe(
+            "dev",
+            &Document::parse("A=1\nB=3\nC=4\n").unwrap(),
+            at(20),
+            KEY,
+        );
+
+        let keys = &metadata.profiles["dev"].keys;
+        assert_eq!(10 * DAY, keys["A"].changed_at);
+        assert_eq!(20 * DAY, keys["B"].changed_at);
+        assert_eq!(20 * DAY, keys["C"].changed_at);
+
+        metadata.observe("dev", &Document::parse("C=4\n").unwrap(), at(30), KEY);
+        let keys = &metadata.profiles["dev"].keys;
+        assert_eq!(1, keys.len());
+        assert_eq!(20 * DAY, keys["C"].changed_at);
+    }
+
+    #[test]
+    fn test_hash_does_not_store_value() {
+        let mut metadata = Metadata::default();
+        metadata.observe(
+            "dev",
+            &Document::parse("SECRET=hunter2\n").unwrap(),
+            at(1),
+            KEY,
+        );
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(!json.contains("hunter2"));
+
+        // Without the key, the value cannot be guessed from its digest.
+        let hash = &metadata.profiles["dev"].keys["SECRET"].hash;
+        assert_ne!(&crate::digest::sha256_hex("SECRET=hunter2"), hash);
+        assert_eq!(&hash_value(KEY, "SECRET", "hunter2"), hash);
+        assert_ne!(&hash_value(b"other key", "SECRET", "hunter2"), hash);
+    }
+
+    #[test]
+    fn test_rehash() {
+        let mut metadata = Metadata::default();
+        let doc = Document::parse("A=1\nB=2\n").unwrap();
+        metadata.observe("dev", &doc, at(10), b"other project");
+
+        // Another project's digests look like changes, unless rehashed.
+        let mut copy = metadata.clone();
+        assert!(copy.observe("dev", &doc, at(20), KEY));
+        assert_eq!(20 * DAY, copy.profiles["dev"].keys["A"].changed_at);
+
+        metadata.rehash("dev", &doc, KEY);
+        assert!(!metadata.observe("dev", &doc, at(20), KEY));
+        assert_eq!(10 * DAY, metadata.profiles["dev"].keys["A"].changed_at);
+    }
+
+    #[test]
+    fn test_stale_keys() {
+        let mut metadata = Metadata::default();
+        metadata.observe("dev", &Document::parse("A=1\nB=2\n").unwrap(), at(10), KEY);
+        metadata.observe("dev", &Document::parse("A=1\nB=3\n").unwrap(), at(50), KEY);
+
+        let stale = metadata.stale_keys("dev", Duration::from_secs(90 * DAY), at(120));
+        assert_eq!(1, stale.len());
+        assert_eq!("A", stale[0].key);
+        assert_eq!(Duration::from_secs(110 * DAY), stale[0].age);
+
+        let stale = metadata.stale_keys("dev", Duration::from_secs(30 * DAY), at(120));
+        assert_eq!(
+            vec!["A", "B"],
+            stale.iter().map(|k| k.key.as_str()).collect::<Vec<_>>()
+        );
+
+        assert!(
+            metadata
+                .stale_keys("prod", Duration::ZERO, at(120))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_forget() {
+        let mut metadata = Metadata::default();
+        metadata.observe("dev", &Document::parse("A=1\n").unwrap(), at(1), KEY);
+
+        assert!(metadata.forget("dev"));
+        assert!(!metadata.forget("dev"));
+        assert!(metadata.profiles.is_empty());
+    }
+}
@@ -0,0 +1,1156 @@
+//! Profile operations module for the envoke CLI tool.
+//!
+//! This module implements the operations behind `create`, `switch`, `remove`,
+//! `rename`, `list`, `current` and `status` as methods of [`ProfileManager`]. They never
+//! print or prompt: each returns a typed result describing what happened, which
+//! the commands hand to the presenter. Editor plugins and other Rust tools can call them
+//! directly to embed envoke.
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//!
+//! use envoke::config::Config;
+//! use envoke::fs::EnvokeFileSystem;
+//! use envoke::profile::ProfileManager;
+//!
+//! let config = Config::load(PathBuf::from(".envoke"))?;
+//! let manager = ProfileManager::new(config, EnvokeFileSystem::new());
+//!
+//! let outcome = manager.switch_profile("dev", false)?;
+//! println!("{} is active", outcome.profile);
+//! # Ok::<(), envoke::error::Error>(())
+//! ```
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::config::LinkMode;
+use crate::dotenv::Document;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::{FileSystem, symlinks_unreliable};
+use crate::index::Index;
+use crate::integrity::Integrity;
+use crate::metadata::KeyAge;
+use crate::profile::{
+    ENV_FILE, HARDLINK_FILE, INCLUDE_DIRECTIVE, LAYER_SEPARATOR, LOCAL_PROFILE, ProfileManager,
+    USER_SUFFIX,
+};
+
+/// Header written at the top of every new profile.
+const PROFILE_HEADER: &str = "\
+# ------------------------------------------------------------------------------
+# Profile: ";
+
+/// A profile in the envoke directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ProfileInfo {
+    /// The name of the profile, without extension.
+    pub name: String,
+
+    /// The path of the profile file.
+    pub path: PathBuf,
+
+    /// The `# envoke:description` of the profile, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The number of variables the profile defines, if it was read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<usize>,
+
+    /// The user overlay applied on top of the profile, if one exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_overlay: Option<String>,
+
+    /// Whether the profile is, or is a layer of, the active profile.
+    pub active: bool,
+}
+
+/// A profile found while iterating over the envoke directory.
+///
+/// Only the name is known up front; details are read from disk on request.
+pub struct ProfileEntry<'a, F: FileSystem> {
+    manager: &'a ProfileManager<F>,
+    name: String,
+    path: PathBuf,
+    active: bool,
+}
+
+/// An iterator over the profiles in the envoke directory, in name order
+/// unless [`Profiles::sorted`] is used.
+///
+/// The directory is read once when the iterator is created, but each entry is
+/// only checked to be a file when it is reached, so the first profiles can be
+/// shown before the last ones are examined.
+pub struct Profiles<'a, F: FileSystem> {
+    manager: &'a ProfileManager<F>,
+    candidates: std::vec::IntoIter<(String, PathBuf)>,
+    active_layers: Vec<String>,
+    name_width: usize,
+}
+
+/// The order in which profiles are listed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileOrder {
+    /// Alphabetically by name.
+    #[default]
+    Name,
+
+    /// Most recently switched to or run with first.
+    Recent,
+
+    /// Most recently modified first.
+    Modified,
+}
+
+/// How a profile was made the active `.env`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    /// `.env` is a symbolic link to the profile.
+    Linked,
+
+    /// `.env` is a hard link to the profile.
+    Hardlinked,
+
+    /// `.env` holds the resolved contents of the profile.
+    Written,
+
+    /// The profile is selected with `ENVOKE_PROFILE`, regardless of `.env`.
+    Environment,
+}
+
+/// The profile or stack that `.env` currently holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ActiveProfile {
+    /// The profile or stack, e.g. `dev+ci`.
+    pub profile: String,
+
+    /// The layers of the profile, in order.
+    pub layers: Vec<String>,
+
+    /// How `.env` was created.
+    pub activation: Activation,
+}
+
+/// An overview of the envoke directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Status {
+    /// The path of the envoke directory.
+    pub envoke_dir: PathBuf,
+
+    /// The active profile, if `.env` is managed by envoke.
+    pub active: Option<ActiveProfile>,
+
+    /// Whether a `.env` exists that envoke did not create.
+    pub unmanaged_env: bool,
+
+    /// The profiles in the envoke directory, sorted by name.
+    pub profiles: Vec<ProfileInfo>,
+}
+
+/// The result of switching profiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwitchOutcome {
+    /// The profile or stack that is now active, e.g. `dev+ci`.
+    pub profile: String,
+
+    /// The layers of the profile, in order.
+    pub layers: Vec<String>,
+
+    /// How `.env` was created.
+    pub activation: Activation,
+
+    /// Whether an existing `.env` was replaced.
+    pub replaced: bool,
+
+    /// Where a `.env` envoke did not manage was moved to.
+    pub backup: Option<PathBuf>,
+
+    /// The type of filesystem on which symbolic links are unreliable, if
+    /// `.env` was copied rather than linked because of it.
+    pub unreliable_fs: Option<String>,
+
+    /// Keys older than the configured maximum age, for each layer.
+    pub stale_keys: Vec<(String, KeyAge)>,
+}
+
+/// The result of removing a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveOutcome {
+    /// The name of the removed profile.
+    pub profile: String,
+
+    /// Whether `.env` was removed because the profile was active.
+    pub unlinked: bool,
+}
+
+/// The result of renaming a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameOutcome {
+    /// The previous name of the profile.
+    pub profile: String,
+
+    /// The new name of the profile.
+    pub renamed: String,
+
+    /// The profile or stack `.env` was switched to again, if the profile was
+    /// active.
+    pub relinked: Option<String>,
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Describes a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// Information about the profile, or an `Error` if it does not exist.
+    pub fn profile_info<S: AsRef<str>>(&self, profile: S) -> Result<ProfileInfo> {
+        let name = self.profile_name(profile);
+        let path = self.profile_path(&name);
+        if !self.fs.path_exists(&path) {
+            return Err(ErrorKind::ProfileNotFound { profile: name }.into());
+        }
+
+        let active = self
+            .active_profile()
+            .map(|active| self.profile_layers(active).contains(&name))
+            .unwrap_or(false);
+
+        let mut index = self.load_index();
+        let entry = self.index_profile(&mut index, &name)?;
+        self.save_index(&index);
+
+        Ok(ProfileInfo {
+            description: entry.description,
+            variables: Some(entry.variables),
+            user_overlay: self
+                .has_user_overlay(&name)
+                .then(|| self.user_overlay(&name)),
+            active,
+            path,
+            name,
+        })
+    }
+
+    /// Iterates over the profiles in the envoke directory, sorted by name.
+    ///
+    /// # Returns
+    ///
+    /// A lazy iterator over the profiles, or an `Error` if the directory
+    /// cannot be read.
+    pub fn iter_profiles(&self) -> Result<Profiles<'_, F>> {
+        let mut candidates: Vec<(String, PathBuf)> = self
+            .fs
+            .read_dir(&self.config.envoke_dir)?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "env"))
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?.to_string();
+                (name != LOCAL_PROFILE && !name.ends_with(USER_SUFFIX)).then_some((name, path))
+            })
+            .collect();
+        candidates.sort();
+
+        let active_layers = self
+            .active_profile()
+            .map(|active| self.profile_layers(active))
+            .unwrap_or_default();
+
+        Ok(Profiles {
+            manager: self,
+            name_width: candidates
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0),
+            candidates: candidates.into_iter(),
+            active_layers,
+        })
+    }
+
+    /// Describes every profile in the envoke directory, sorted by name.
+    ///
+    /// # Returns
+    ///
+    /// Information about each profile, or an `Error` if the directory is not
+    /// initialized or cannot be read.
+    pub fn list_profiles(&self) -> Result<Vec<ProfileInfo>> {
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        let mut index = self.load_index();
+        let profiles = self
+            .iter_profiles()?
+            .map(|entry| entry.info(&mut index))
+            .collect::<Result<Vec<_>>>()?;
+
+        let names: Vec<&str> = profiles.iter().map(|info| info.name.as_str()).collect();
+        index.retain(&names);
+        self.save_index(&index);
+
+        Ok(profiles)
+    }
+
+    /// Describes the active profile.
+    ///
+    /// # Returns
+    ///
+    /// The active profile, or an `Error` if the directory is not initialized,
+    /// there is no `.env` or the `.env` is not managed by envoke. A profile
+    /// selected with `ENVOKE_PROFILE` takes precedence over `.env`, and is an
+    /// `Error` if any of its layers does not exist.
+    pub fn current_profile(&self) -> Result<ActiveProfile> {
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        if let Some(profile) = &self.config.profile_override {
+            let layers = self.profile_layers(profile);
+            if let Some(missing) = layers
+                .iter()
+                .find(|layer| !self.fs.path_exists(&self.profile_path(layer)))
+            {
+                return Err(ErrorKind::ProfileNotFound {
+                    profile: missing.clone(),
+                }
+                .into());
+            }
+
+            return Ok(ActiveProfile {
+                profile: layers.join(&LAYER_SEPARATOR.to_string()),
+                layers,
+                activation: Activation::Environment,
+            });
+        }
+
+        let profile = self.active_profile()?;
+        let activation = if self.fs.is_symlink(Path::new(ENV_FILE)) {
+            Activation::Linked
+        } else if self.hardlinked_profile().is_some() {
+            Activation::Hardlinked
+        } else {
+            Activation::Written
+        };
+
+        Ok(ActiveProfile {
+            layers: self.profile_layers(&profile),
+            profile,
+            activation,
+        })
+    }
+
+    /// Describes the envoke directory, its profiles and the active profile.
+    ///
+    /// # Returns
+    ///
+    /// An overview of the directory, or an `Error` if it is not initialized or
+    /// cannot be read.
+    pub fn status(&self) -> Result<Status> {
+        let profiles = self.list_profiles()?;
+        let active = match self.current_profile() {
+            Ok(active) => Some(active),
+            Err(e)
+                if matches!(
+                    e.kind,
+                    ErrorKind::NoActiveProfile | ErrorKind::NonLinkedEnv { .. }
+                ) =>
+            {
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(Status {
+            envoke_dir: self.config.envoke_dir.clone(),
+            unmanaged_env: active.is_none() && self.fs.path_exists(Path::new(ENV_FILE)),
+            active,
+            profiles,
+        })
+    }
+
+    /// Checks that a profile can be created, before any work goes into it.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The path the profile will be written to, or an `Error` if the name is
+    /// invalid or the profile exists.
+    pub fn check_new_profile<S: AsRef<str>>(&self, profile: S) -> Result<PathBuf> {
+        let profile = profile.as_ref();
+
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        if profile.contains(LAYER_SEPARATOR) {
+            return Err(ErrorKind::InvalidProfileName {
+                profile: profile.to_string(),
+            }
+            .into());
+        }
+
+        let path = self.profile_path(profile);
+        if self.fs.path_exists(&path) {
+            return Err(ErrorKind::FileExists { file: path }.into());
+        }
+
+        Ok(path)
+    }
+
+    /// Creates a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `body` - The initial contents, e.g. a rendered template.
+    ///
+    /// # Returns
+    ///
+    /// Information about the new profile, or an `Error` if the name is
+    /// invalid, the profile exists or the contents cannot be parsed.
+    pub fn create_profile<S: AsRef<str>>(&self, profile: S, body: &str) -> Result<ProfileInfo> {
+        let profile = profile.as_ref();
+        let path = self.check_new_profile(profile)?;
+
+        Document::parse(body).map_err(|e| {
+            Error::new(ErrorKind::ParseProfile {
+                file: path.clone(),
+                line: e.line,
+                reason: e.reason,
+            })
+        })?;
+
+        self.save_profile(profile, format!("{}{}\n{}", PROFILE_HEADER, profile, body))?;
+
+        self.profile_info(profile)
+    }
+
+    /// Makes a profile or stack the active `.env`.
+    ///
+    /// A single profile is linked where possible. Stacks, profiles that
+    /// include other files and profiles with a user or local overlay are
+    /// resolved and written out instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack, e.g. `dev+ci`.
+    /// * `force` - Replace a `.env` that envoke did not create.
+    ///
+    /// # Returns
+    ///
+    /// What was activated, or an `Error` if a layer does not exist or `.env`
+    /// cannot be replaced.
+    pub fn switch_profile<S: AsRef<str>>(&self, profile: S, force: bool) -> Result<SwitchOutcome> {
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        let layers = self.profile_layers(profile);
+        let profile = layers.join(&LAYER_SEPARATOR.to_string());
+        let env_path = Path::new(ENV_FILE);
+
+        if layers.is_empty() {
+            return Err(ErrorKind::ProfileNotFound { profile }.into());
+        }
+
+        for layer in &layers {
+            if !self.fs.path_exists(&self.profile_path(layer)) {
+                return Err(ErrorKind::ProfileNotFound {
+                    profile: layer.to_string(),
+                }
+                .into());
+            }
+        }
+
+        let replaced = self.fs.path_exists(env_path);
+        let mut backup = None;
+        if replaced {
+            if self.is_managed_env(env_path) || (force && !self.config.switch.backup) {
+                debug!("replacing existing .env");
+                self.fs.remove_file(env_path)?;
+            } else if force {
+                backup = Some(self.backup_env()?);
+            } else {
+                return Err(ErrorKind::NonLinkedEnv {
+                    command: format!("envoke switch {}", profile),
+                }
+                .into());
+            }
+        } else if self.fs.read_link(env_path).is_ok() {
+            // A link to a profile that no longer exists.
+            debug!("removing dangling .env link");
+            self.fs.remove_file(env_path)?;
+        }
+
+        // Stacks, profiles composed from fragments and profiles with a user or
+        // local overlay cannot be linked, so their resolved contents are written out.
+        let linkable = layers.len() == 1
+            && (profile == LOCAL_PROFILE || !self.has_local_profile())
+            && !self.has_user_overlay(&profile)
+            && !has_includes(&self.read_profile(&profile)?);
+
+        let mut link_mode = if linkable {
+            self.config.switch.link_mode
+        } else {
+            LinkMode::Copy
+        };
+        let mut unreliable_fs = None;
+        if link_mode == LinkMode::Symlink {
+            unreliable_fs = self
+                .fs
+                .filesystem_type(Path::new("."))
+                .filter(|fs_type| symlinks_unreliable(fs_type));
+            if unreliable_fs.is_some() {
+                link_mode = LinkMode::Copy;
+            }
+        }
+
+        let marker = self.config.envoke_dir.join(HARDLINK_FILE);
+        if link_mode != LinkMode::Hardlink && self.fs.path_exists(&marker) {
+            self.fs.remove_file(&marker)?;
+        }
+
+        info!(%profile, ?link_mode, "activating profile");
+        let activation = match link_mode {
+            LinkMode::Symlink => {
+                self.fs
+                    .create_symlink(&self.profile_path(&profile), env_path)?;
+                Activation::Linked
+            }
+            LinkMode::Hardlink => {
+                self.hardlink_env(&profile)?;
+                Activation::Hardlinked
+            }
+            LinkMode::Copy => {
+                let resolved = self.resolve_env(&profile, true)?;
+                self.write_generated_env(&profile, &resolved)?;
+                Activation::Written
+            }
+        };
+
+        let mut stale_keys = Vec::new();
+        if let Some(max_age) = self.config.audit.max_age {
+            let mut metadata = self.load_metadata()?;
+            let mut changed = false;
+            for layer in &layers {
+                changed |= self.observe_profile(&mut metadata, layer)?;
+            }
+            if changed {
+                self.save_metadata(&metadata)?;
+            }
+
+            for layer in &layers {
+                for stale in metadata.stale_keys(layer, max_age, SystemTime::now()) {
+                    stale_keys.push((layer.clone(), stale));
+                }
+            }
+        }
+
+        Ok(SwitchOutcome {
+            profile,
+            layers,
+            activation,
+            replaced,
+            backup,
+            unreliable_fs,
+            stale_keys,
+        })
+    }
+
+    /// Deletes a profile, unlinking `.env` if the profile is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// What was removed, or an `Error` if the profile does not exist.
+    pub fn remove_profile<S: AsRef<str>>(&self, profile: S) -> Result<RemoveOutcome> {
+        let profile = self.profile_name(profile);
+
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        let profile_path = self.profile_path(&profile);
+        if !self.fs.path_exists(&profile_path) {
+            return Err(ErrorKind::ProfileNotFound { profile }.into());
+        }
+
+        let active = self.active_profile().unwrap_or_default();
+        let unlinked = self.profile_layers(&active).contains(&profile);
+        if unlinked {
+            self.fs.remove_file(Path::new(ENV_FILE))?;
+        }
+
+        self.snapshot_profile(&profile)?;
+        self.fs.remove_file(&profile_path)?;
+
+        let mut metadata = self.load_metadata()?;
+        if metadata.forget(&profile) {
+            self.save_metadata(&metadata)?;
+        }
+
+        let mut index = self.load_index();
+        index.forget(&profile);
+        self.save_index(&index);
+
+        Ok(RemoveOutcome { profile, unlinked })
+    }
+
+    /// Renames a profile, along with its signature, user overlay and
+    /// metadata, switching `.env` to the new name if the profile is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `renamed` - The new name.
+    ///
+    /// # Returns
+    ///
+    /// What was renamed, or an `Error` if the profile does not exist or the
+    /// new name is invalid or taken.
+    pub fn rename_profile<S, T>(&self, profile: S, renamed: T) -> Result<RenameOutcome>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        let profile = self.profile_name(profile);
+        let renamed = self.profile_name(renamed);
+
+        if !self.is_initialized() {
+            return Err(ErrorKind::Uninitialized.into());
+        }
+
+        let profile_path = self.profile_path(&profile);
+        if !self.fs.path_exists(&profile_path) {
+            return Err(ErrorKind::ProfileNotFound { profile }.into());
+        }
+        let renamed_path = self.check_new_profile(&renamed)?;
+
+        let active = self.active_profile().unwrap_or_default();
+        let intact = self.check_integrity(&profile)? == Integrity::Intact;
+
+        self.fs.rename(&profile_path, &renamed_path)?;
+        let signature = self.signature_path(&profile);
+        if self.fs.path_exists(&signature) {
+            self.fs.rename(&signature, &self.signature_path(&renamed))?;
+        }
+        if self.has_user_overlay(&profile) {
+            self.fs.rename(
+                &self.profile_path(self.user_overlay(&profile)),
+                &self.profile_path(self.user_overlay(&renamed)),
+            )?;
+        }
+
+        let mut metadata = self.load_metadata()?;
+        if let Some(meta) = metadata.profiles.remove(&profile) {
+            metadata.profiles.insert(renamed.clone(), meta);
+            self.save_metadata(&metadata)?;
+        }
+
+        // Keep the header of profiles envoke created naming the profile. The
+        // checksum is only updated if the profile had not changed outside
+        // envoke, so such changes are still reported.
+        let contents = self.read_file(&renamed_path)?;
+        if let Some(body) = contents.strip_prefix(&format!("{}{}\n", PROFILE_HEADER, profile)) {
+            let contents = format!("{}{}\n{}", PROFILE_HEADER, renamed, body);
+            match intact {
+                true => self.save_profile(&renamed, contents)?,
+                false => self.write_file(&renamed_path, contents)?,
+            }
+        }
+
+        let mut index = self.load_index();
+        index.forget(&profile);
+        self.save_index(&index);
+
+        let layers = self.profile_layers(&active);
+        let relinked = match layers.contains(&profile) {
+            true => {
+                let stack: Vec<String> = layers
+                    .into_iter()
+                    .map(|layer| {
+                        if layer == profile {
+                            renamed.clone()
+                        } else {
+                            layer
+                        }
+                    })
+                    .collect();
+                let stack = stack.join(&LAYER_SEPARATOR.to_string());
+                self.switch_profile(&stack, false)?;
+                Some(stack)
+            }
+            false => None,
+        };
+
+        Ok(RenameOutcome {
+            profile,
+            renamed,
+            relinked,
+        })
+    }
+}
+
+impl<'a, F: FileSystem> ProfileEntry<'a, F> {
+    /// Returns the name of the profile, without extension.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path of the profile file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks whether the profile is, or is a layer of, the active profile.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Describes the profile without reading it.
+    ///
+    /// # Returns
+    ///
+    /// Information about the profile, leaving out its description and
+    /// variables.
+    pub fn summary(&self) -> ProfileInfo {
+        ProfileInfo {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            description: None,
+            variables: None,
+            user_overlay: self
+                .manager
+                .has_user_overlay(&self.name)
+                .then(|| self.manager.user_overlay(&self.name)),
+            active: self.active,
+        }
+    }
+
+    /// Describes the profile in full, reading it only if the index is out of
+    /// date.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The profile index, updated if the profile changed.
+    ///
+    /// # Returns
+    ///
+    /// Information about the profile, or an `Error` if it cannot be read.
+    pub fn info(&self, index: &mut Index) -> Result<ProfileInfo> {
+        let entry = self.manager.index_profile(index, &self.name)?;
+
+        Ok(ProfileInfo {
+            description: entry.description,
+            variables: Some(entry.variables),
+            ..self.summary()
+        })
+    }
+}
+
+impl<'a, F: FileSystem> Profiles<'a, F> {
+    /// Returns the length of the longest profile name, for aligning output.
+    pub fn name_width(&self) -> usize {
+        self.name_width
+    }
+
+    /// Reorders the profiles that have not been reached yet, pinned profiles
+    /// first.
+    ///
+    /// Profiles that were never used, or whose modification time cannot be
+    /// read, follow the others in name order.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order to list the profiles in.
+    pub fn sorted(mut self, order: ProfileOrder) -> Self {
+        let mut candidates: Vec<(String, PathBuf)> = self.candidates.collect();
+        let pinned = self.manager.pinned_profiles();
+        match order {
+            ProfileOrder::Name => (),
+            ProfileOrder::Recent => {
+                let usage = self.manager.load_usage();
+                candidates.sort_by_key(|(name, _)| Reverse(usage.last_used(name)));
+            }
+            ProfileOrder::Modified => {
+                let fs = &self.manager.fs;
+                candidates.sort_by_cached_key(|(_, path)| {
+                    Reverse(fs.metadata(path).ok().map(|meta| meta.modified()))
+                });
+            }
+        }
+
+        candidates.sort_by_key(|(name, _)| !pinned.contains(name));
+
+        self.candidates = candidates.into_iter();
+        self
+    }
+}
+
+impl<'a, F: FileSystem> Iterator for Profiles<'a, F> {
+    type Item = ProfileEntry<'a, F>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (name, path) in self.candidates.by_ref() {
+            if !self
+                .manager
+                .fs
+                .metadata(&path)
+                .is_ok_and(|meta| meta.is_file())
+            {
+                continue;
+            }
+
+            return Some(ProfileEntry {
+                manager: self.manager,
+                active: self.active_layers.contains(&name),
+                name,
+                path,
+            });
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.candidates.size_hint().1)
+    }
+}
+
+/// Checks whether a profile includes other files.
+fn has_includes(doc: &Document) -> bool {
+    doc.lines()
+        .iter()
+        .any(|line| matches!(line.directive(), Some((INCLUDE_DIRECTIVE, _))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    fn profile_manager() -> ProfileManager<MockFileSystem> {
+        let fs = MockFileSystem::new();
+        fs.add_dir(".envoke");
+
+        ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs)
+    }
+
+    #[test]
+    fn test_create_profile() {
+        let manager = profile_manager();
+
+        let info = manager
+            .create_profile("dev", "# envoke:description Development\nPORT=8080\n")
+            .unwrap();
+        assert_eq!("dev", info.name);
+        assert_eq!(Some("Development".to_string()), info.description);
+        assert!(!info.active);
+
+        let contents = manager.fs.contents(&info.path).unwrap();
+        assert!(contents.starts_with(PROFILE_HEADER));
+        assert!(contents.ends_with("PORT=8080\n"));
+
+        assert!(manager.create_profile("dev", "").is_err());
+        assert!(manager.create_profile("dev+ci", "").is_err());
+        assert!(manager.create_profile("broken", "NOT VALID\n").is_err());
+    }
+
+    #[test]
+    fn test_switch_profile() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "PORT=8080\n").unwrap();
+        manager.create_profile("ci", "CI=true\n").unwrap();
+
+        let outcome = manager.switch_profile("dev", false).unwrap();
+        assert_eq!(Activation::Linked, outcome.activation);
+        assert!(!outcome.replaced);
+        assert!(manager.fs.is_symlink(Path::new(ENV_FILE)));
+        assert_eq!("dev", manager.active_profile().unwrap());
+
+        let outcome = manager.switch_profile("dev+ci", false).unwrap();
+        assert_eq!(Activation::Written, outcome.activation);
+        assert_eq!(vec!["dev", "ci"], outcome.layers);
+        assert!(outcome.replaced);
+        assert!(manager.fs.contents(ENV_FILE).unwrap().contains("CI=true"));
+
+        assert!(manager.switch_profile("missing", false).is_err());
+
+        // A `.env` that envoke did not create is only replaced when forced.
+        manager.fs.remove_file(Path::new(ENV_FILE)).unwrap();
+        manager.fs.add_file(ENV_FILE, "HANDWRITTEN=1\n");
+        assert!(manager.switch_profile("dev", false).is_err());
+        let backup = manager.switch_profile("dev", true).unwrap().backup.unwrap();
+        assert!(backup.starts_with(".envoke/backups"));
+        assert_eq!(
+            Some("HANDWRITTEN=1\n".to_string()),
+            manager.fs.contents(&backup)
+        );
+        assert!(manager.fs.is_symlink(Path::new(ENV_FILE)));
+    }
+
+    #[test]
+    fn test_switch_profile_copies_on_unreliable_filesystem() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "PORT=8080\n").unwrap();
+        manager.fs.set_filesystem_type("drvfs");
+
+        let outcome = manager.switch_profile("dev", false).unwrap();
+        assert_eq!(Activation::Written, outcome.activation);
+        assert_eq!(Some("drvfs".to_string()), outcome.unreliable_fs);
+        assert!(!manager.fs.is_symlink(Path::new(ENV_FILE)));
+        assert_eq!("dev", manager.active_profile().unwrap());
+
+        // Copying is not reported when it was asked for.
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.switch.link_mode = LinkMode::Copy;
+        let manager = ProfileManager::new(config, manager.fs);
+        let outcome = manager.switch_profile("dev", false).unwrap();
+        assert_eq!(Activation::Written, outcome.activation);
+        assert_eq!(None, outcome.unreliable_fs);
+    }
+
+    #[test]
+    fn test_switch_profile_hardlink() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.switch.link_mode = LinkMode::Hardlink;
+        let manager = ProfileManager::new(config, profile_manager().fs);
+        let profile_path = manager.create_profile("dev", "PORT=8080\n").unwrap().path;
+        manager.create_profile("ci", "CI=true\n").unwrap();
+
+        let outcome = manager.switch_profile("dev", false).unwrap();
+        assert_eq!(Activation::Hardlinked, outcome.activation);
+        assert!(!manager.fs.is_symlink(Path::new(ENV_FILE)));
+        assert!(manager.is_managed_env(Path::new(ENV_FILE)));
+        assert_eq!("dev", manager.active_profile().unwrap());
+        assert_eq!(
+            Activation::Hardlinked,
+            manager.current_profile().unwrap().activation
+        );
+
+        // Rewriting the profile replaces it, so `.env` is linked again.
+        manager.write_file(&profile_path, "PORT=9090\n").unwrap();
+        assert_eq!("dev", manager.active_profile().unwrap());
+        assert_eq!(
+            Some("PORT=9090\n".to_string()),
+            manager.fs.contents(ENV_FILE)
+        );
+
+        // A `.env` that is no longer the profile is not managed.
+        manager.fs.remove_file(Path::new(ENV_FILE)).unwrap();
+        manager.fs.add_file(ENV_FILE, "PORT=9090\n");
+        assert!(!manager.is_managed_env(Path::new(ENV_FILE)));
+        manager.fs.remove_file(Path::new(ENV_FILE)).unwrap();
+
+        // Stacks cannot be linked, and switching to one forgets the link.
+        let outcome = manager.switch_profile("dev+ci", false).unwrap();
+        assert_eq!(Activation::Written, outcome.activation);
+        assert!(
+            !manager
+                .fs
+                .path_exists(&manager.config.envoke_dir.join(HARDLINK_FILE))
+        );
+    }
+
+    #[test]
+    fn test_iter_profiles() {
+        let manager = profile_manager();
+        manager
+            .create_profile("prod", "# envoke:description Production\n")
+            .unwrap();
+        manager.create_profile("dev", "").unwrap();
+        manager.fs.add_file(".envoke/dev.user.env", "");
+        manager.fs.add_file(".envoke/local.env", "");
+        manager.fs.add_dir(".envoke/dir.env");
+        manager.switch_profile("dev", false).unwrap();
+
+        let profiles = manager.iter_profiles().unwrap();
+        assert_eq!(4, profiles.name_width());
+
+        let entries: Vec<_> = profiles.collect();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name()).collect();
+        assert_eq!(vec!["dev", "prod"], names);
+        assert!(entries[0].is_active());
+        assert_eq!(
+            Some("dev.user".to_string()),
+            entries[0].summary().user_overlay
+        );
+
+        // Descriptions are only read on request.
+        assert_eq!(None, entries[1].summary().description);
+        let mut index = manager.load_index();
+        let info = entries[1].info(&mut index).unwrap();
+        assert_eq!(Some("Production".to_string()), info.description);
+        assert_eq!(manager.profile_info("prod").unwrap(), info);
+    }
+
+    #[test]
+    fn test_profile_index() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "A=1\n").unwrap();
+        manager.create_profile("prod", "A=1\nB=2\n").unwrap();
+
+        let profiles = manager.list_profiles().unwrap();
+        assert_eq!(Some(2), profiles[1].variables);
+        assert_eq!(2, manager.load_index().profiles.len());
+
+        // Listings use the index while the profile is unchanged.
+        let path = manager.profile_path("prod");
+        let mut index = manager.load_index();
+        let mut entry = index.profiles["prod"].clone();
+        entry.variables = 10;
+        index.insert("prod", entry);
+        manager.save_index(&index);
+        assert_eq!(Some(10), manager.list_profiles().unwrap()[1].variables);
+
+        // A profile edited outside envoke is read again.
+        manager
+            .fs
+            .add_file(&path, "# envoke:description Production\nA=1\n");
+        let info = &manager.list_profiles().unwrap()[1];
+        assert_eq!(Some(1), info.variables);
+        assert_eq!(Some("Production".to_string()), info.description);
+
+        // Removed profiles are dropped from the index.
+        manager.remove_profile("dev").unwrap();
+        manager.fs.remove_file(&path).unwrap();
+        manager.list_profiles().unwrap();
+        assert!(manager.load_index().profiles.is_empty());
+
+        // A corrupt index is rebuilt.
+        manager.fs.add_file(manager.index_path(), "not json");
+        manager.create_profile("ci", "").unwrap();
+        assert_eq!(1, manager.load_index().profiles.len());
+    }
+
+    #[test]
+    fn test_current_profile_and_status() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "").unwrap();
+        manager.create_profile("ci", "").unwrap();
+
+        assert!(manager.current_profile().is_err());
+        let status = manager.status().unwrap();
+        assert_eq!(None, status.active);
+        assert!(!status.unmanaged_env);
+        assert_eq!(2, status.profiles.len());
+
+        manager.switch_profile("dev+ci", false).unwrap();
+        let current = manager.current_profile().unwrap();
+        assert_eq!("dev+ci", current.profile);
+        assert_eq!(vec!["dev", "ci"], current.layers);
+        assert_eq!(Activation::Written, current.activation);
+        assert_eq!(Some(current), manager.status().unwrap().active);
+
+        manager.fs.remove_file(Path::new(ENV_FILE)).unwrap();
+        manager.fs.add_file(ENV_FILE, "HANDWRITTEN=1\n");
+        let status = manager.status().unwrap();
+        assert_eq!(None, status.active);
+        assert!(status.unmanaged_env);
+    }
+
+    #[test]
+    fn test_current_profile_override() {
+        let mut manager = profile_manager();
+        manager.create_profile("dev", "").unwrap();
+        manager.create_profile("ci", "").unwrap();
+        manager.switch_profile("dev", false).unwrap();
+
+        manager.config.profile_override = Some("ci.env+dev".to_string());
+        let current = manager.current_profile().unwrap();
+        assert_eq!("ci+dev", current.profile);
+        assert_eq!(Activation::Environment, current.activation);
+        assert_eq!("ci.env+dev", manager.selected_profile().unwrap());
+
+        // `.env` is left alone.
+        assert_eq!("dev", manager.active_profile().unwrap());
+
+        manager.config.profile_override = Some("missing".to_string());
+        let err = manager.current_profile().unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ProfileNotFound { .. }));
+    }
+
+    #[test]
+    fn test_list_and_remove_profiles() {
+        let manager = profile_manager();
+
+        manager.create_profile("prod", "").unwrap();
+        manager.create_profile("dev", "").unwrap();
+        manager.switch_profile("dev", false).unwrap();
+
+        let profiles = manager.list_profiles().unwrap();
+        let names: Vec<&str> = profiles.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(vec!["dev", "prod"], names);
+        assert!(profiles[0].active);
+        assert!(!profiles[1].active);
+
+        let outcome = manager.remove_profile("dev.env").unwrap();
+        assert_eq!("dev", outcome.profile);
+        assert!(outcome.unlinked);
+        assert!(!manager.fs.path_exists(Path::new(ENV_FILE)));
+        assert_eq!(1, manager.list_profiles().unwrap().len());
+
+        assert!(manager.remove_profile("dev").is_err());
+    }
+
+    #[test]
+    fn test_rename_profile() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "PORT=8080\n").unwrap();
+        manager.create_profile("ci", "CI=true\n").unwrap();
+        manager.fs.add_file(".envoke/dev.user.env", "EDITOR=vim\n");
+        manager.switch_profile("dev", false).unwrap();
+
+        let outcome = manager.rename_profile("dev", "local-dev").unwrap();
+        assert_eq!(Some("local-dev".to_string()), outcome.relinked);
+        assert_eq!("local-dev", manager.active_profile().unwrap());
+        assert!(!manager.fs.path_exists(&manager.profile_path("dev")));
+        assert!(manager.has_user_overlay("local-dev"));
+        let contents = manager.fs.contents(".envoke/local-dev.env").unwrap();
+        assert!(contents.starts_with(&format!("{}local-dev\n", PROFILE_HEADER)));
+        assert_eq!(
+            Integrity::Intact,
+            manager.check_integrity("local-dev").unwrap()
+        );
+
+        // Inactive profiles leave `.env` alone.
+        let outcome = manager.rename_profile("ci", "pipeline").unwrap();
+        assert_eq!(None, outcome.relinked);
+        assert_eq!("local-dev", manager.active_profile().unwrap());
+
+        assert!(manager.rename_profile("missing", "other").is_err());
+        assert!(manager.rename_profile("pipeline", "local-dev").is_err());
+        assert!(manager.rename_profile("pipeline", "a+b").is_err());
+    }
+
+    #[test]
+    fn test_remove_profile_failure() {
+        let manager = profile_manager();
+        manager.create_profile("dev", "").unwrap();
+
+        manager.fs.fail_on(".envoke/dev.env");
+        assert!(manager.remove_profile("dev").is_err());
+
+        manager.fs.clear_failures();
+        assert!(manager.remove_profile("dev").is_ok());
+    }
+}
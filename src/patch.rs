@@ -0,0 +1,478 @@
+//! Patch module for the envoke CLI tool.
+//!
+//! This module describes how two sets of variables differ, and writes and
+//! reads those differences as patches so they can be reviewed and applied
+//! elsewhere. Two formats are supported:
+//!
+//! * A unified diff of the sorted `KEY=VALUE` assignments, without context
+//!   lines so unchanged values are never shared.
+//! * A JSON patch (RFC 6902), where every `remove` and `replace` is preceded
+//!   by a `test` of the value it expects.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::dotenv::{Document, Entry};
+use crate::error::{ErrorKind, Result};
+use crate::redact::Redactor;
+
+/// How a variable differs between two sets of variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The key is only in the new set.
+    Added { key: String, value: String },
+
+    /// The key is only in the old set.
+    Removed { key: String, value: String },
+
+    /// The key is in both sets with different values.
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl Change {
+    /// Returns the key of the changed variable.
+    pub fn key(&self) -> &str {
+        match self {
+            Change::Added { key, .. }
+            | Change::Removed { key, .. }
+            | Change::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// An operation of a JSON patch, naming the variable with a JSON pointer such
+/// as `/PORT`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    /// Sets a variable that did not exist.
+    Add { path: String, value: String },
+
+    /// Deletes a variable.
+    Remove { path: String },
+
+    /// Changes the value of a variable.
+    Replace { path: String, value: String },
+
+    /// Checks the value a variable has before it is removed or replaced.
+    Test { path: String, value: String },
+}
+
+/// Describes how the variables of `new` differ from those of `old`.
+///
+/// # Arguments
+///
+/// * `old` - The variables before the change.
+/// * `new` - The variables after the change.
+///
+/// # Returns
+///
+/// The changes, sorted by key.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use envoke::patch::{Change, changes};
+///
+/// let old = BTreeMap::from([("PORT".to_string(), "80".to_string())]);
+/// let new = BTreeMap::from([("PORT".to_string(), "8080".to_string())]);
+///
+/// assert_eq!("PORT", changes(&old, &new)[0].key());
+/// ```
+pub fn changes(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Vec<Change> {
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    keys.into_iter()
+        .filter_map(|key| match (old.get(key), new.get(key)) {
+            (None, Some(value)) => Some(Change::Added {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            (Some(value), None) => Some(Change::Removed {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            (Some(old), Some(new)) if old != new => Some(Change::Changed {
+                key: key.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Writes the differences between two sets of variables as a unified diff.
+///
+/// The diff compares the sorted assignments of both sets, with one hunk per
+/// run of adjacent changes.
+///
+/// # Arguments
+///
+/// * `old_name` - The name of the old set, for the `---` header.
+/// * `new_name` - The name of the new set, for the `+++` header.
+/// * `old` - The variables before the change.
+/// * `new` - The variables after the change.
+/// * `redactor` - Masks secret values.
+///
+/// # Returns
+///
+/// The diff, with only the headers if nothing changed.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use envoke::patch::unified;
+/// use envoke::redact::Redactor;
+///
+/// let old = BTreeMap::from([("PORT".to_string(), "80".to_string())]);
+/// let new = BTreeMap::from([("PORT".to_string(), "8080".to_string())]);
+/// let redactor = Redactor::new(Vec::new(), false);
+///
+/// assert_eq!(
+///     "--- dev\n+++ prod\n@@ -1,1 +1,1 @@\n-PORT=80\n+PORT=8080\n",
+///     unified("dev", "prod", &old, &new, &redactor)
+/// );
+/// ```
+pub fn unified(
+    old_name: &str,
+    new_name: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    redactor: &Redactor,
+) -> String {
+    let line = |key: &str, value: &str| {
+        Entry::new(key, redactor.redact(key, value))
+            .raw()
+            .to_string()
+    };
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    // The line numbers reached so far, and the hunk being collected.
+    let (mut old_line, mut new_line) = (0, 0);
+    let mut hunk: Option<Hunk> = None;
+
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let (before, after) = (old.get(key), new.get(key));
+        if before == after {
+            if let Some(hunk) = hunk.take() {
+                hunk.write(&mut diff);
+            }
+            old_line += 1;
+            new_line += 1;
+            continue;
+        }
+
+        let hunk = hunk.get_or_insert_with(|| Hunk {
+            old_start: old_line + 1,
+            new_start: new_line + 1,
+            removed: Vec::new(),
+            added: Vec::new(),
+        });
+        if let Some(value) = before {
+            hunk.removed.push(line(key, value));
+            old_line += 1;
+        }
+        if let Some(value) = after {
+            hunk.added.push(line(key, value));
+            new_line += 1;
+        }
+    }
+
+    if let Some(hunk) = hunk {
+        hunk.write(&mut diff);
+    }
+
+    diff
+}
+
+/// Writes changes as a JSON patch.
+///
+/// # Arguments
+///
+/// * `changes` - The changes to write.
+/// * `redactor` - Masks secret values.
+///
+/// # Returns
+///
+/// The pretty-printed JSON array of operations.
+pub fn json(changes: &[Change], redactor: &Redactor) -> String {
+    let mut operations = Vec::new();
+    for change in changes {
+        let key = change.key();
+        let path = format!("/{}", key.replace('~', "~0").replace('/', "~1"));
+        let value = |value: &str| redactor.redact(key, value).to_string();
+
+        match change {
+            Change::Added { value: new, .. } => {
+                operations.push(Operation::Add {
+                    path,
+                    value: value(new),
+                });
+            }
+            Change::Removed { value: old, .. } => {
+                operations.push(Operation::Test {
+                    path: path.clone(),
+                    value: value(old),
+                });
+                operations.push(Operation::Remove { path });
+            }
+            Change::Changed { old, new, .. } => {
+                operations.push(Operation::Test {
+                    path: path.clone(),
+                    value: value(old),
+                });
+                operations.push(Operation::Replace {
+                    path,
+                    value: value(new),
+                });
+            }
+        }
+    }
+
+    serde_json::to_string_pretty(&operations).expect("patches are serializable")
+}
+
+/// Reads the changes from a unified diff or JSON patch.
+///
+/// # Arguments
+///
+/// * `input` - The patch, as written by [`unified`] or [`json`].
+/// * `current` - The current variables. A JSON `remove` or `replace` without
+///   a preceding `test` expects the current value, so it always applies.
+///
+/// # Returns
+///
+/// The changes, or an `Error` if the patch cannot be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use envoke::patch::{Change, parse};
+///
+/// let patch = "--- dev\n+++ prod\n@@ -1,1 +1,1 @@\n-PORT=80\n+PORT=8080\n";
+/// let changes = parse(patch, &BTreeMap::new()).unwrap();
+///
+/// assert_eq!(
+///     vec![Change::Changed { key: "PORT".into(), old: "80".into(), new: "8080".into() }],
+///     changes
+/// );
+/// ```
+pub fn parse(input: &str, current: &BTreeMap<String, String>) -> Result<Vec<Change>> {
+    if input.trim_start().starts_with('[') {
+        parse_json(input, current)
+    } else {
+        parse_unified(input)
+    }
+}
+
+/// A run of adjacent changes in a unified diff.
+struct Hunk {
+    old_start: usize,
+    new_start: usize,
+    removed: Vec<String>,
+    added: Vec<String>,
+}
+
+impl Hunk {
+    fn write(self, diff: &mut String) {
+        // An empty range starts at the line before it, as in `diff -U0`.
+        let range = |start: usize, count: usize| match count {
+            0 => format!("{},0", start - 1),
+            _ => format!("{},{}", start, count),
+        };
+
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(self.old_start, self.removed.len()),
+            range(self.new_start, self.added.len())
+        ));
+        for line in &self.removed {
+            diff.push_str(&format!("-{}\n", line));
+        }
+        for line in &self.added {
+            diff.push_str(&format!("+{}\n", line));
+        }
+    }
+}
+
+fn parse_unified(input: &str) -> Result<Vec<Change>> {
+    let mut removed = BTreeMap::new();
+    let mut added = BTreeMap::new();
+    let mut in_hunk = false;
+
+    for (number, line) in input.lines().enumerate() {
+        if line.starts_with("@@") {
+            in_hunk = true;
+            continue;
+        }
+
+        // Headers such as `---`, `+++` and `diff --git` come before the first hunk.
+        if !in_hunk {
+            continue;
+        }
+
+        let target = match line.chars().next() {
+            Some('-') => &mut removed,
+            Some('+') => &mut added,
+            Some(' ') | Some('\\') | None => continue,
+            Some(_) => {
+                return Err(invalid(format!("unexpected line {}", number + 1)));
+            }
+        };
+
+        let entry = Document::parse(&line[1..])
+            .ok()
+            .and_then(|doc| doc.entries().next().cloned())
+            .ok_or_else(|| invalid(format!("line {} is not an assignment", number + 1)))?;
+        target.insert(entry.key, entry.value);
+    }
+
+    let keys: BTreeSet<String> = removed.keys().chain(added.keys()).cloned().collect();
+    Ok(keys
+        .into_iter()
+        .map(|key| match (removed.remove(&key), added.remove(&key)) {
+            (Some(old), Some(new)) => Change::Changed { key, old, new },
+            (Some(value), None) => Change::Removed { key, value },
+            (None, Some(value)) => Change::Added { key, value },
+            (None, None) => unreachable!("keys come from either side"),
+        })
+        .collect())
+}
+
+fn parse_json(input: &str, current: &BTreeMap<String, String>) -> Result<Vec<Change>> {
+    let operations: Vec<Operation> =
+        serde_json::from_str(input).map_err(|e| invalid(e.to_string()))?;
+
+    let mut expected = BTreeMap::new();
+    let mut changes = Vec::new();
+    for operation in operations {
+        match operation {
+            Operation::Test { path, value } => {
+                expected.insert(key(&path)?, value);
+            }
+            Operation::Add { path, value } => changes.push(Change::Added {
+                key: key(&path)?,
+                value,
+            }),
+            Operation::Remove { path } => {
+                let key = key(&path)?;
+                let value = expected_value(&mut expected, current, &key);
+                changes.push(Change::Removed { key, value });
+            }
+            Operation::Replace { path, value } => {
+                let key = key(&path)?;
+                let old = expected_value(&mut expected, current, &key);
+                changes.push(Change::Changed {
+                    key,
+                    old,
+                    new: value,
+                });
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Converts a JSON pointer such as `/PORT` to a key.
+fn key(path: &str) -> Result<String> {
+    let key = path
+        .strip_prefix('/')
+        .filter(|key| !key.is_empty() && !key.contains('/'))
+        .ok_or_else(|| invalid(format!("`{}` does not name a variable", path)))?;
+
+    Ok(key.replace("~1", "/").replace("~0", "~"))
+}
+
+fn expected_value(
+    expected: &mut BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+    key: &str,
+) -> String {
+    expected
+        .remove(key)
+        .or_else(|| current.get(key).cloned())
+        .unwrap_or_default()
+}
+
+fn invalid(reason: String) -> crate::error::Error {
+    ErrorKind::InvalidPatch { reason }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unified_round_trip() {
+        let old = variables(&[("A", "1"), ("B", "2"), ("C", "3"), ("E", "5")]);
+        let new = variables(&[("A", "1"), ("B", "two words"), ("D", "4"), ("E", "5")]);
+        let redactor = Redactor::new(Vec::new(), false);
+
+        let diff = unified("old", "new", &old, &new, &redactor);
+        assert_eq!(
+            "--- old\n+++ new\n\
+             @@ -2,2 +2,2 @@\n\
+             -B=2\n\
+             -C=3\n\
+             +B=\"two words\"\n\
+             +D=4\n",
+            diff
+        );
+        assert_eq!(changes(&old, &new), parse(&diff, &old).unwrap());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let old = variables(&[("A", "1"), ("B", "2")]);
+        let new = variables(&[("B", "3"), ("C/D", "4")]);
+        let redactor = Redactor::new(Vec::new(), false);
+
+        let patch = json(&changes(&old, &new), &redactor);
+        assert!(patch.contains("\"path\": \"/C~1D\""));
+        assert_eq!(changes(&old, &new), parse(&patch, &old).unwrap());
+
+        // Without a test, the current value is expected.
+        let patch = r#"[{ "op": "remove", "path": "/A" }]"#;
+        assert_eq!(
+            vec![Change::Removed {
+                key: "A".into(),
+                value: "1".into()
+            }],
+            parse(patch, &old).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let current = BTreeMap::new();
+        assert!(parse("@@ -1 +1 @@\n+not an assignment\n", &current).is_err());
+        assert!(parse("@@ -1 +1 @@\n?A=1\n", &current).is_err());
+        assert!(
+            parse(
+                r#"[{ "op": "move", "from": "/A", "path": "/B" }]"#,
+                &current
+            )
+            .is_err()
+        );
+        assert!(parse(r#"[{ "op": "add", "path": "", "value": "1" }]"#, &current).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Pinned profiles module for the envoke CLI tool.
+//!
+//! `envoke pin <profile>` marks a profile as a favorite, listing it before
+//! the others in `envoke list` and the profile picker. Favorites are personal,
+//! so they are kept in the user's state directory, keyed by the path of each
+//! project's envoke directory, rather than in the repository.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+
+/// Name of the pins file within the state directory.
+pub const PINS_FILE: &str = "pins.json";
+
+/// The pinned profiles of every project.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pins {
+    /// Pinned profiles, in the order they were pinned, keyed by the absolute
+    /// path of the project's envoke directory.
+    pub projects: BTreeMap<String, Vec<String>>,
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the path of the pins file.
+    ///
+    /// # Returns
+    ///
+    /// The path, or an `Error` if the state directory is unknown.
+    pub fn pins_path(&self) -> Result<PathBuf> {
+        self.config
+            .state_dir
+            .as_ref()
+            .map(|dir| dir.join(PINS_FILE))
+            .ok_or_else(|| ErrorKind::StateDirUnavailable.into())
+    }
+
+    /// Loads the pinned profiles of every project.
+    ///
+    /// # Returns
+    ///
+    /// The stored `Pins`, empty if nothing has been pinned yet, or an `Error`
+    /// if the file cannot be read or parsed.
+    pub fn load_pins(&self) -> Result<Pins> {
+        let path = self.pins_path()?;
+        if !self.fs.path_exists(&path) {
+            return Ok(Pins::default());
+        }
+
+        let contents = self.read_file(&path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseMetadata {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    /// Gets the profiles pinned in this project.
+    ///
+    /// Pins only order listings, so they are treated as empty if they cannot
+    /// be loaded.
+    ///
+    /// # Returns
+    ///
+    /// The pinned profiles, in the order they were pinned.
+    pub fn pinned_profiles(&self) -> Vec<String> {
+        match self.load_pins() {
+            Ok(mut pins) => pins
+                .projects
+                .remove(&self.project_key())
+                .unwrap_or_default(),
+            Err(e) => {
+                debug!(error = %e, "ignoring unreadable pins");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Pins or unpins a profile of this project.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `pinned` - Whether the profile should be pinned.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the pins changed, or an `Error` if they cannot be saved.
+    pub fn set_pinned<S: AsRef<str>>(&self, profile: S, pinned: bool) -> Result<bool> {
+        let profile = self.profile_name(profile);
+        let mut pins = self.load_pins()?;
+        let key = self.project_key();
+        let profiles = pins.projects.entry(key.clone()).or_default();
+
+        match (profiles.contains(&profile), pinned) {
+            (false, true) => profiles.push(profile),
+            (true, false) => profiles.retain(|p| *p != profile),
+            _ => return Ok(false),
+        }
+        if profiles.is_empty() {
+            pins.projects.remove(&key);
+        }
+
+        let path = self.pins_path()?;
+        if let Some(dir) = path.parent() {
+            self.fs.create_dir(dir)?;
+        }
+        let contents = serde_json::to_string_pretty(&pins).expect("pins are serializable");
+        self.write_file(&path, contents + "\n")?;
+
+        Ok(true)
+    }
+
+    /// Identifies this project in user-wide state by the absolute path of
+    /// its envoke directory.
+    pub(crate) fn project_key(&self) -> String {
+        std::fs::canonicalize(&self.config.envoke_dir)
+            .unwrap_or_else(|_| self.config.envoke_dir.clone())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_set_pinned() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        assert!(manager.set_pinned("dev", true).is_err());
+        assert!(manager.pinned_profiles().is_empty());
+
+        config.state_dir = Some(PathBuf::from("/state/envoke"));
+        let manager = ProfileManager::new(config, manager.fs);
+        assert!(manager.set_pinned("prod", true).unwrap());
+        assert!(manager.set_pinned("dev.env", true).unwrap());
+        assert!(!manager.set_pinned("dev", true).unwrap());
+        assert_eq!(vec!["prod", "dev"], manager.pinned_profiles());
+
+        assert!(manager.set_pinned("prod", false).unwrap());
+        assert!(!manager.set_pinned("prod", false).unwrap());
+        assert_eq!(vec!["dev"], manager.pinned_profiles());
+
+        manager.set_pinned("dev", false).unwrap();
+        assert_eq!(
+            Some("{\n  \"projects\": {}\n}\n".to_string()),
+            manager.fs.contents("/state/envoke/pins.json")
+        );
+    }
+}
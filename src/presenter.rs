@@ -0,0 +1,545 @@
+//! Presentation module for the envoke CLI tool.
+//!
+//! This module decides how output looks. Messages can be styled to mark
+//! success, warnings, errors and the active profile. Styles are applied only
+//! when `--color` allows it: by default, output is colored when it goes to a
+//! terminal and the `NO_COLOR` environment variable is not set.
+//!
+//! Commands that report on profiles hand the data returned by
+//! [`ProfileManager`](crate::profile::ProfileManager) to this module, which
+//! writes it in the requested [`Format`].
+
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::config::Color;
+use crate::error::{Error, Hint, Result};
+use crate::fs::Change;
+use crate::operations::{Activation, ActiveProfile, ProfileInfo, Status};
+
+/// ANSI escape sequence that restores the default style.
+pub const RESET: &str = "\x1b[0m";
+
+static COLOR_CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// When output should be colored.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output written to a terminal, unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+
+    /// Always color output.
+    Always,
+
+    /// Never color output.
+    Never,
+}
+
+/// How errors are written to stderr.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// The message, followed by a line for each hint.
+    #[default]
+    Text,
+
+    /// A JSON object with the message, hints and exit code, for wrappers.
+    Json,
+}
+
+/// An error as written with `--error-format json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ErrorReport {
+    /// The message describing what went wrong.
+    pub error: String,
+
+    /// The code envoke exits with.
+    pub exit_code: i32,
+
+    /// What to do next, most relevant first.
+    pub hints: Vec<Hint>,
+}
+
+/// How command output is written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One line per item, for people and simple scripts.
+    #[default]
+    Plain,
+
+    /// Every detail, aligned for people.
+    Verbose,
+
+    /// Pretty-printed JSON, for programs.
+    Json,
+}
+
+impl Format {
+    /// Picks the format from the `--long` and `--json` flags of a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `long` - Whether `--long` was given.
+    /// * `json` - Whether `--json` was given. Takes precedence over `long`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::presenter::Format;
+    ///
+    /// assert_eq!(Format::Plain, Format::from_flags(false, false));
+    /// assert_eq!(Format::Verbose, Format::from_flags(true, false));
+    /// assert_eq!(Format::Json, Format::from_flags(true, true));
+    /// ```
+    pub fn from_flags(long: bool, json: bool) -> Self {
+        match (long, json) {
+            (_, true) => Format::Json,
+            (true, false) => Format::Verbose,
+            (false, false) => Format::Plain,
+        }
+    }
+}
+
+/// An output stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn is_terminal(&self) -> bool {
+        match self {
+            Stream::Stdout => io::stdout().is_terminal(),
+            Stream::Stderr => io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// Sets when output is colored for the rest of the process.
+///
+/// Only the first call has an effect; without a call, `ColorChoice::Auto` is
+/// used.
+pub fn set_color_choice(choice: ColorChoice) {
+    let _ = COLOR_CHOICE.set(choice);
+}
+
+/// Returns when output is colored.
+pub fn color_choice() -> ColorChoice {
+    COLOR_CHOICE.get().copied().unwrap_or_default()
+}
+
+/// Suppresses warnings for the rest of the process.
+///
+/// Only the first call has an effect.
+pub fn set_quiet(quiet: bool) {
+    let _ = QUIET.set(quiet);
+}
+
+/// Checks whether only errors should be reported.
+pub fn is_quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+/// Checks whether colors are allowed at all, regardless of where output goes.
+///
+/// This is for output that is captured by another program and shown in a
+/// terminal later, such as a shell prompt segment.
+pub fn color_allowed() -> bool {
+    match color_choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => !no_color_env(),
+    }
+}
+
+/// Checks whether output written to a stream should be colored.
+///
+/// # Arguments
+///
+/// * `stream` - The stream the output is written to.
+///
+/// # Returns
+///
+/// `true` if the output should be colored.
+pub fn color_enabled(stream: Stream) -> bool {
+    enabled(color_choice(), no_color_env(), stream.is_terminal())
+}
+
+/// Colors text written to a stream, if colors are enabled for it.
+///
+/// # Arguments
+///
+/// * `text` - The text to color.
+/// * `color` - The color to use.
+/// * `stream` - The stream the text is written to.
+///
+/// # Returns
+///
+/// The text, wrapped in escape sequences when colors are enabled.
+pub fn paint<D: Display>(text: D, color: Color, stream: Stream) -> String {
+    if color_enabled(stream) {
+        format!("{}{}{}", color.ansi(), text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Styles a message confirming that a command succeeded.
+pub fn success<D: Display>(text: D) -> String {
+    paint(text, Color::Green, Stream::Stdout)
+}
+
+/// Styles a message reporting that a check failed.
+pub fn failure<D: Display>(text: D) -> String {
+    paint(text, Color::Red, Stream::Stdout)
+}
+
+/// Styles the active profile in a listing.
+pub fn active<D: Display>(text: D) -> String {
+    paint(text, Color::Cyan, Stream::Stdout)
+}
+
+/// Styles a warning, prefixed with `Warning:`.
+pub fn warning<D: Display>(text: D) -> String {
+    paint(format!("Warning: {}", text), Color::Yellow, Stream::Stderr)
+}
+
+/// Prints a styled warning to stderr, unless output is quiet.
+pub fn warn<D: Display>(text: D) {
+    if !is_quiet() {
+        eprintln!("{}", warning(text));
+    }
+}
+
+/// Prints an error that did not stop the command as a styled warning, with
+/// its hints, unless output is quiet.
+pub fn warn_error<E: Into<Error>>(error: E) {
+    if is_quiet() {
+        return;
+    }
+
+    let error = error.into();
+    eprintln!("{}", warning(&error));
+    for hint in error.hints() {
+        eprintln!("Hint: {}", hint);
+    }
+}
+
+/// Styles an error.
+pub fn error<D: Display>(text: D) -> String {
+    paint(text, Color::Red, Stream::Stderr)
+}
+
+/// Writes an error that ended a command, with its hints.
+///
+/// # Arguments
+///
+/// * `out` - Where to write, usually stderr.
+/// * `error` - The error to describe.
+/// * `format` - How to write it.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::error::{Error, ErrorKind};
+/// use envoke::presenter::{self, ErrorFormat};
+///
+/// let mut out = Vec::new();
+/// let error = Error::new(ErrorKind::Uninitialized);
+/// presenter::report_error(&mut out, &error, ErrorFormat::Text).unwrap();
+/// let expected = "Directory has not been initialized.\nHint: run `envoke init`\n";
+/// assert_eq!(expected, String::from_utf8(out).unwrap());
+/// ```
+pub fn report_error(out: &mut impl Write, error: &Error, format: ErrorFormat) -> Result<()> {
+    if format == ErrorFormat::Json {
+        let report = ErrorReport {
+            error: error.to_string(),
+            hints: error.hints().to_vec(),
+            exit_code: error.exit_code(),
+        };
+        return json(out, &report);
+    }
+
+    writeln!(out, "{}", self::error(error))?;
+    for hint in error.hints() {
+        writeln!(out, "Hint: {}", hint)?;
+    }
+    Ok(())
+}
+
+/// Writes the profiles of the envoke directory as they are produced.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `profiles` - The profiles, in the order to write them.
+/// * `width` - The length of the longest profile name, for alignment.
+/// * `format` - How to write them.
+///
+/// # Returns
+///
+/// `Ok(())` once every profile is written, or the first `Error` produced by
+/// `profiles`.
+pub fn profiles<I>(out: &mut impl Write, profiles: I, width: usize, format: Format) -> Result<()>
+where
+    I: IntoIterator<Item = Result<ProfileInfo>>,
+{
+    let mut count = 0;
+    for profile in profiles {
+        let profile = profile?;
+
+        if format == Format::Json {
+            // Streams the same layout as a pretty-printed array.
+            let item = serde_json::to_string_pretty(&profile).expect("profiles are serializable");
+            write!(
+                out,
+                "{}\n  {}",
+                if count == 0 { "[" } else { "," },
+                item.replace('\n', "\n  ")
+            )?;
+            count += 1;
+            continue;
+        }
+        count += 1;
+
+        let mut line = match format {
+            Format::Verbose => format!(
+                "{} {:<width$}",
+                if profile.active { "*" } else { " " },
+                profile.name,
+                width = width
+            ),
+            _ => profile.name.clone(),
+        };
+
+        if let Some(overlay) = &profile.user_overlay {
+            line += &format!(" (+ {})", overlay);
+        }
+
+        if format == Format::Verbose
+            && let Some(description) = &profile.description
+        {
+            line += &format!("  {}", description);
+        }
+
+        if profile.active {
+            writeln!(out, "{}", active(line.trim_end()))?;
+        } else {
+            writeln!(out, "{}", line.trim_end())?;
+        }
+    }
+
+    match (format, count) {
+        (Format::Json, 0) => writeln!(out, "[]")?,
+        (Format::Json, _) => writeln!(out, "\n]")?,
+        (_, 0) => writeln!(
+            out,
+            "No profiles found. Run `envoke create <profile>` to get started!"
+        )?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Writes the global profiles.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `dir` - The directory the global profiles are read from.
+/// * `profiles` - The names of the global profiles.
+/// * `format` - How to write them.
+pub fn global_profiles(
+    out: &mut impl Write,
+    dir: &Path,
+    profiles: &[String],
+    format: Format,
+) -> Result<()> {
+    if format == Format::Json {
+        return json(out, &profiles);
+    }
+
+    if profiles.is_empty() {
+        writeln!(
+            out,
+            "No global profiles found. Add `<profile>.env` files to `{}`.",
+            dir.to_string_lossy()
+        )?;
+        return Ok(());
+    }
+
+    for profile in profiles {
+        match format {
+            Format::Verbose => writeln!(
+                out,
+                "{}  {}",
+                profile,
+                dir.join(format!("{}.env", profile)).to_string_lossy()
+            )?,
+            _ => writeln!(out, "{}", profile)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the active profile.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `current` - The active profile.
+/// * `format` - How to write it.
+pub fn current(out: &mut impl Write, current: &ActiveProfile, format: Format) -> Result<()> {
+    match format {
+        Format::Plain => writeln!(out, "{}", current.profile)?,
+        Format::Verbose => {
+            writeln!(out, "Profile:    {}", current.profile)?;
+            writeln!(out, "Layers:     {}", current.layers.join(", "))?;
+            writeln!(out, "Activation: {}", activation(current.activation))?;
+        }
+        Format::Json => json(out, current)?,
+    }
+
+    Ok(())
+}
+
+/// Writes the changes a dry run would have made.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `changes` - The changes, in the order they would have been made.
+pub fn changes(out: &mut impl Write, changes: &[Change]) -> Result<()> {
+    if changes.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            paint(
+                "Dry run - nothing would change.",
+                Color::Yellow,
+                Stream::Stdout
+            )
+        )?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        paint(
+            "Dry run - nothing was changed. Would:",
+            Color::Yellow,
+            Stream::Stdout
+        )
+    )?;
+    for change in changes {
+        writeln!(out, "  {}", change)?;
+    }
+
+    Ok(())
+}
+
+/// Writes an overview of the envoke directory.
+///
+/// # Arguments
+///
+/// * `out` - Where to write.
+/// * `status` - The overview, as returned by `status`.
+/// * `format` - How to write it.
+pub fn status(out: &mut impl Write, status: &Status, format: Format) -> Result<()> {
+    if format == Format::Json {
+        return json(out, status);
+    }
+
+    match &status.active {
+        Some(current) => writeln!(
+            out,
+            "Active profile: {} ({})",
+            active(&current.profile),
+            activation(current.activation)
+        )?,
+        None if status.unmanaged_env => writeln!(
+            out,
+            "Active profile: none - `.env` is not managed by envoke"
+        )?,
+        None => writeln!(out, "Active profile: none")?,
+    }
+    writeln!(out, "Profiles:       {}", status.profiles.len())?;
+
+    if format == Format::Verbose {
+        writeln!(
+            out,
+            "Directory:      {}",
+            status.envoke_dir.to_string_lossy()
+        )?;
+        if !status.profiles.is_empty() {
+            let width = status.profiles.iter().map(|p| p.name.len()).max();
+            writeln!(out)?;
+            profiles(
+                out,
+                status.profiles.iter().cloned().map(Ok),
+                width.unwrap_or(0),
+                format,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Describes how a profile was activated.
+fn activation(activation: Activation) -> &'static str {
+    match activation {
+        Activation::Linked => "linked",
+        Activation::Hardlinked => "hard linked",
+        Activation::Written => "written",
+        Activation::Environment => "ENVOKE_PROFILE",
+    }
+}
+
+/// Writes a value as pretty-printed JSON.
+fn json<T: Serialize + ?Sized>(out: &mut impl Write, value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).expect("presented values are serializable");
+    writeln!(out, "{}", json)?;
+
+    Ok(())
+}
+
+fn no_color_env() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+fn enabled(choice: ColorChoice, no_color: bool, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && !no_color,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled() {
+        assert!(enabled(ColorChoice::Auto, false, true));
+        assert!(!enabled(ColorChoice::Auto, true, true));
+        assert!(!enabled(ColorChoice::Auto, false, false));
+
+        assert!(enabled(ColorChoice::Always, true, false));
+        assert!(!enabled(ColorChoice::Never, false, true));
+    }
+}
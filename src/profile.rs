@@ -4,9 +4,81 @@
 //! to environment profiles, including listing available profiles, checking profile
 //! existence, and managing profile paths.
 
-use crate::{config::Config, error::Result, fs::FileSystem};
+use crate::config::{Config, LogLocation};
+use crate::dotenv::{DIRECTIVE_PREFIX, Document, Line};
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::{FileSystem, OpenOptions, normalize};
+use crate::index::{INDEX_FILE, Index, IndexEntry};
+use crate::metadata::{METADATA_FILE, METADATA_KEY_FILE, Metadata};
+use crate::oplog::{LOG_FILE, LogEntry};
+use crate::remote::{REMOTES_FILE, Remotes};
+use crate::schema::{SCHEMA_FILE, Schema};
+use crate::template::TEMPLATES_DIR;
 
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace};
+
+/// The path of the environment file managed by envoke.
+pub const ENV_FILE: &str = ".env";
+
+/// Name of the machine-specific profile overlaid on the active profile.
+pub const LOCAL_PROFILE: &str = "local";
+
+/// Suffix of per-user overlays, e.g. `dev.user.env` for the `dev` profile.
+pub const USER_SUFFIX: &str = ".user";
+
+/// Separates the profiles of a layered stack, e.g. `dev+local`.
+pub const LAYER_SEPARATOR: char = '+';
+
+/// Directive including another file into a profile.
+pub const INCLUDE_DIRECTIVE: &str = "include";
+
+/// Directive describing a profile, shown when choosing one.
+pub const DESCRIPTION_DIRECTIVE: &str = "description";
+
+/// Directive marking a `.env` written by envoke rather than linked.
+pub const GENERATED_DIRECTIVE: &str = "generated";
+
+/// Name of the file in the envoke directory naming the profile hard linked to
+/// `.env`, as a hard link cannot be read to find the file it shares.
+pub const HARDLINK_FILE: &str = "hardlinked";
+
+/// Name of the directory holding profiles within the global directory.
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Where a profile or template is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Stored in the project's envoke directory.
+    Project,
+
+    /// Stored in the user-wide envoke directory.
+    Global,
+}
+
+impl Scope {
+    /// Returns the lowercase name of the scope.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Project => "project",
+            Scope::Global => "global",
+        }
+    }
+}
+
+/// An assignment of a key, and where it was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// The file containing the assignment.
+    pub file: PathBuf,
+
+    /// The line number of the assignment, starting at 1.
+    pub line: usize,
+
+    /// The assigned value.
+    pub value: String,
+}
 
 /// Manages environment profiles for the envoke CLI tool.
 ///
@@ -17,87 +89,1229 @@ pub struct ProfileManager<F: FileSystem> {
     /// Configuration settings for paths.
     pub config: Config,
 
-    /// Filesystem implementation for I/O operations.
-    pub fs: F,
-}
+    /// Filesystem implementation for I/O operations.
+    pub fs: F,
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Creates a new `ProfileManager` with the specified configuration and
+    /// filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration containing path information.
+    /// * `fs` - Implementation of the `FileSystem` trait for I/O operations.
+    ///
+    /// # Returns
+    ///
+    /// A new `ProfileManager` instance.
+    pub fn new(config: Config, fs: F) -> Self {
+        Self { config, fs }
+    }
+
+    /// Checks whether the environment directory has been initialized.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the directory exists, `false` otherwise.
+    pub fn is_initialized(&self) -> bool {
+        self.fs.path_exists(&self.config.envoke_dir)
+    }
+
+    /// Gets the full path for a profile with the given name.
+    ///
+    /// Automatically appends ".env" extension if not already present.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The full path to the profile file.
+    pub fn profile_path<S: AsRef<str>>(&self, profile: S) -> PathBuf {
+        let mut profile = profile.as_ref().to_string();
+        if !profile.ends_with(".env") {
+            profile += ".env"
+        }
+
+        self.config.envoke_dir.join(profile)
+    }
+
+    /// Gets the canonical name of a profile, without the ".env" extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile, with or without extension.
+    ///
+    /// # Returns
+    ///
+    /// The profile name.
+    pub fn profile_name<S: AsRef<str>>(&self, profile: S) -> String {
+        let profile = profile.as_ref();
+        profile.strip_suffix(".env").unwrap_or(profile).to_string()
+    }
+
+    /// Splits a layered profile such as `dev+local` into its profiles.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack of profiles.
+    ///
+    /// # Returns
+    ///
+    /// The canonical name of each layer, lowest precedence first.
+    pub fn profile_layers<S: AsRef<str>>(&self, profile: S) -> Vec<String> {
+        profile
+            .as_ref()
+            .split(LAYER_SEPARATOR)
+            .map(|layer| self.profile_name(layer.trim()))
+            .filter(|layer| !layer.is_empty())
+            .collect()
+    }
+
+    /// Gets the name of the profile currently linked to `.env`.
+    ///
+    /// # Returns
+    ///
+    /// The active profile name, or an `Error` if there is no `.env`, the `.env`
+    /// is not managed by envoke, or the link cannot be read.
+    pub fn active_profile(&self) -> Result<String> {
+        let env_path = Path::new(ENV_FILE);
+
+        if !self.fs.path_exists(env_path) {
+            return Err(ErrorKind::NoActiveProfile.into());
+        }
+
+        if !self.fs.is_symlink(env_path) {
+            if let Some(profile) = self.hardlinked_profile() {
+                trace!(%profile, "found hard linked .env");
+                return Ok(profile);
+            }
+            return self.generated_profile(env_path)?.ok_or_else(|| {
+                ErrorKind::NonLinkedEnv {
+                    command: "envoke switch".into(),
+                }
+                .into()
+            });
+        }
+
+        let target = self.fs.read_link(env_path)?;
+        trace!(target = %target.display(), "found linked .env");
+        let target = target.file_stem().unwrap_or_default();
+
+        Ok(target.to_string_lossy().into_owned())
+    }
+
+    /// Gets the profile that commands loading variables should use: the one
+    /// selected with `ENVOKE_PROFILE` if set, or else the active profile.
+    ///
+    /// # Returns
+    ///
+    /// The selected profile or stack, or an `Error` if none is selected and
+    /// there is no active profile.
+    pub fn selected_profile(&self) -> Result<String> {
+        match &self.config.profile_override {
+            Some(profile) => Ok(profile.clone()),
+            None => self.active_profile(),
+        }
+    }
+
+    /// Checks whether a `.env` file is managed by envoke.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the `.env` file.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file is a symlink, a hard link to the profile recorded
+    /// in the envoke directory, or was generated by envoke.
+    pub fn is_managed_env(&self, path: &Path) -> bool {
+        self.fs.is_symlink(path)
+            || self
+                .hardlinked_profile()
+                .is_some_and(|_| self.fs.same_file(path, Path::new(ENV_FILE)))
+            || matches!(self.generated_profile(path), Ok(Some(_)))
+    }
+
+    /// Gets the profile `.env` is hard linked to.
+    ///
+    /// The profile is recorded in the envoke directory when the link is made,
+    /// and trusted only while `.env` and the profile are still the same file.
+    ///
+    /// # Returns
+    ///
+    /// The profile name, or `None` if `.env` is not a hard link to a profile.
+    pub fn hardlinked_profile(&self) -> Option<String> {
+        let marker = self.config.envoke_dir.join(HARDLINK_FILE);
+        if !self.fs.path_exists(&marker) || self.fs.is_symlink(Path::new(ENV_FILE)) {
+            return None;
+        }
+
+        let profile = self.fs.read_to_string(&marker).ok()?.trim().to_string();
+        (!profile.is_empty()
+            && self
+                .fs
+                .same_file(Path::new(ENV_FILE), &self.profile_path(&profile)))
+        .then_some(profile)
+    }
+
+    /// Hard links `.env` to a profile, recording the profile so it can be
+    /// found again.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result` on success, or an `Error` if the link cannot be made.
+    pub fn hardlink_env<S: AsRef<str>>(&self, profile: S) -> Result<()> {
+        let profile = self.profile_name(profile);
+        debug!(%profile, "hard linking .env");
+        self.fs
+            .create_hardlink(&self.profile_path(&profile), Path::new(ENV_FILE))?;
+        self.fs.write_atomic(
+            &self.config.envoke_dir.join(HARDLINK_FILE),
+            format!("{}\n", profile).as_bytes(),
+        )
+    }
+
+    /// Gets the profile a generated `.env` file was written from.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the `.env` file.
+    ///
+    /// # Returns
+    ///
+    /// The profile name, `None` if the file was not generated by envoke, or an
+    /// `Error` if the file cannot be read.
+    pub fn generated_profile(&self, path: &Path) -> Result<Option<String>> {
+        let contents = self.read_file(path)?;
+        let first = contents.lines().next().unwrap_or_default();
+        let Ok(doc) = Document::parse(first) else {
+            return Ok(None);
+        };
+
+        Ok(doc.lines().first().and_then(|line| match line.directive() {
+            Some((GENERATED_DIRECTIVE, profile)) if !profile.is_empty() => {
+                Some(profile.to_string())
+            }
+            _ => None,
+        }))
+    }
+
+    /// Writes a resolved profile to `.env`, marked as generated by envoke.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `doc` - The resolved contents of the profile.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result` on success, or an `Error` if the file cannot be written.
+    pub fn write_generated_env<S: AsRef<str>>(&self, profile: S, doc: &Document) -> Result<()> {
+        let profile = self.profile_name(profile);
+        debug!(%profile, "writing generated .env");
+        self.write_file(Path::new(ENV_FILE), self.generated_env(&profile, doc))
+    }
+
+    /// Renders a resolved profile as a `.env` marked as generated by envoke.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `doc` - The resolved contents of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The contents of the generated `.env`.
+    pub fn generated_env<S: AsRef<str>>(&self, profile: S, doc: &Document) -> String {
+        let profile = self.profile_name(profile);
+        format!(
+            "# {}{} {}\n# Generated by envoke - edit the profile and run `envoke switch {}` instead.\n{}",
+            DIRECTIVE_PREFIX, GENERATED_DIRECTIVE, profile, profile, doc
+        )
+    }
+
+    /// Gets the directory holding templates of the given scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - Whether to use the project or the global directory.
+    ///
+    /// # Returns
+    ///
+    /// The template directory, or an `Error` if the global directory is
+    /// requested but cannot be determined.
+    pub fn template_dir(&self, scope: Scope) -> Result<PathBuf> {
+        match scope {
+            Scope::Project => Ok(self.config.envoke_dir.join(TEMPLATES_DIR)),
+            Scope::Global => Ok(self.global_dir()?.join(TEMPLATES_DIR)),
+        }
+    }
+
+    /// Gets the full path for a template with the given name and scope.
+    ///
+    /// Automatically appends ".env" extension if not already present.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The name of the template.
+    /// * `scope` - Whether the template belongs to the project or is global.
+    ///
+    /// # Returns
+    ///
+    /// The full path to the template file.
+    pub fn template_path<S: AsRef<str>>(&self, template: S, scope: Scope) -> Result<PathBuf> {
+        let mut template = template.as_ref().to_string();
+        if !template.ends_with(".env") {
+            template += ".env"
+        }
+
+        Ok(self.template_dir(scope)?.join(template))
+    }
+
+    /// Finds a template, preferring the project's templates over global ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The name of the template.
+    ///
+    /// # Returns
+    ///
+    /// The path to the template file, or an `Error` if no template with that
+    /// name exists.
+    pub fn find_template<S: AsRef<str>>(&self, template: S) -> Result<PathBuf> {
+        for scope in [Scope::Project, Scope::Global] {
+            if let Ok(path) = self.template_path(&template, scope)
+                && self.fs.path_exists(&path)
+            {
+                return Ok(path);
+            }
+        }
+
+        Err(ErrorKind::TemplateNotFound {
+            template: self.profile_name(template),
+        }
+        .into())
+    }
+
+    /// Lists all available templates, project templates first.
+    ///
+    /// # Returns
+    ///
+    /// The name and scope of each template, or an `Error` if a template
+    /// directory exists but cannot be read.
+    pub fn templates(&self) -> Result<Vec<(String, Scope)>> {
+        let mut templates = Vec::new();
+
+        for scope in [Scope::Project, Scope::Global] {
+            let Ok(dir) = self.template_dir(scope) else {
+                continue;
+            };
+
+            if !self.fs.path_exists(&dir) {
+                continue;
+            }
+
+            let mut names = self.env_files(&dir)?;
+            names.sort();
+            templates.extend(names.into_iter().map(|name| (name, scope)));
+        }
+
+        Ok(templates)
+    }
+
+    /// Gets the user-wide envoke directory.
+    ///
+    /// # Returns
+    ///
+    /// The global directory, or an `Error` if it cannot be determined.
+    pub fn global_dir(&self) -> Result<&Path> {
+        self.config
+            .global_dir
+            .as_deref()
+            .ok_or_else(|| ErrorKind::GlobalDirUnavailable.into())
+    }
+
+    /// Gets the full path for a global profile with the given name.
+    ///
+    /// Automatically appends ".env" extension if not already present.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The full path to the global profile file, or an `Error` if the global
+    /// directory cannot be determined.
+    pub fn global_profile_path<S: AsRef<str>>(&self, profile: S) -> Result<PathBuf> {
+        let mut profile = profile.as_ref().to_string();
+        if !profile.ends_with(".env") {
+            profile += ".env"
+        }
+
+        Ok(self.global_dir()?.join(PROFILES_DIR).join(profile))
+    }
+
+    /// Lists all profiles in the global directory.
+    ///
+    /// # Returns
+    ///
+    /// The sorted names of the global profiles, or an `Error` if the global
+    /// directory cannot be determined or read.
+    pub fn global_profiles(&self) -> Result<Vec<String>> {
+        let dir = self.global_dir()?.join(PROFILES_DIR);
+        if !self.fs.path_exists(&dir) {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles = self.env_files(&dir)?;
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// Lists all available profiles.
+    ///
+    /// Reads the envoke directory and returns the names of all valid profiles,
+    /// excluding the file extension.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of profile names on success, or an error
+    /// if the directory cannot be read.
+    pub fn profiles(&self) -> Result<Vec<String>> {
+        Ok(self
+            .iter_profiles()?
+            .map(|entry| entry.name().to_string())
+            .collect())
+    }
+
+    /// Gets the profiles of a group defined under `[groups]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The name of the group.
+    ///
+    /// # Returns
+    ///
+    /// The profiles of the group, in the order they are listed, or an `Error`
+    /// if the group is not defined.
+    pub fn group_profiles<S: AsRef<str>>(&self, group: S) -> Result<Vec<String>> {
+        let group = group.as_ref();
+        self.config
+            .groups
+            .get(group)
+            .map(|profiles| profiles.iter().map(|p| self.profile_name(p)).collect())
+            .ok_or_else(|| {
+                ErrorKind::GroupNotFound {
+                    group: group.to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// Lists the names of all `.env` files in a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to read.
+    ///
+    /// # Returns
+    ///
+    /// The file names without extension, or an `Error` if the directory
+    /// cannot be read.
+    pub fn env_files(&self, dir: &Path) -> Result<Vec<String>> {
+        let entries = self.fs.read_dir(dir)?;
+
+        let files = entries
+            .into_iter()
+            .filter_map(|path| {
+                // Only include .env files.
+                if path.extension().is_none_or(|ext| ext != "env")
+                    || !self.fs.metadata(&path).is_ok_and(|meta| meta.is_file())
+                {
+                    return None;
+                }
+
+                // Extract and convert the filename stem.
+                path.file_stem().and_then(|s| s.to_str()).map(String::from)
+            })
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Reads and parses a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `Document`, or an `Error` if the profile does not exist,
+    /// cannot be read, or contains invalid syntax.
+    pub fn read_profile<S: AsRef<str>>(&self, profile: S) -> Result<Document> {
+        let path = self.profile_path(&profile);
+        if !self.fs.path_exists(&path) {
+            return Err(ErrorKind::ProfileNotFound {
+                profile: profile.as_ref().to_string(),
+            }
+            .into());
+        }
+
+        self.read_document(&path)
+    }
+
+    /// Gets the description of a profile from its `# envoke:description`.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The description, `None` if there is none, or an `Error` if the profile
+    /// cannot be read.
+    pub fn profile_description<S: AsRef<str>>(&self, profile: S) -> Result<Option<String>> {
+        Ok(description(&self.read_profile(profile)?))
+    }
+
+    /// Reads a profile with all `# envoke:include` directives expanded.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The resolved `Document`, or an `Error` if the profile or an included
+    /// file does not exist, cannot be read, or includes itself.
+    pub fn resolve_profile<S: AsRef<str>>(&self, profile: S) -> Result<Document> {
+        let path = self.profile_path(&profile);
+        if !self.fs.path_exists(&path) {
+            return Err(ErrorKind::ProfileNotFound {
+                profile: profile.as_ref().to_string(),
+            }
+            .into());
+        }
+
+        debug!(profile = profile.as_ref(), "resolving profile");
+        self.resolve_includes(&path, &mut Vec::new())
+    }
+
+    /// Resolves a profile or a layered stack of profiles such as `dev+local`.
+    ///
+    /// Each layer is resolved with its includes expanded, and later layers
+    /// override the keys of earlier ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack of profiles.
+    ///
+    /// # Returns
+    ///
+    /// The merged `Document`, or an `Error` if any layer cannot be resolved.
+    pub fn resolve_stack<S: AsRef<str>>(&self, profile: S) -> Result<Document> {
+        let layers = self
+            .profile_layers(profile)
+            .iter()
+            .map(|layer| self.resolve_profile(layer))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Document::merge(layers))
+    }
+
+    /// Gets the name of the per-user overlay of a profile.
+    pub fn user_overlay<S: AsRef<str>>(&self, profile: S) -> String {
+        format!("{}{}", self.profile_name(profile), USER_SUFFIX)
+    }
+
+    /// Checks whether a profile has a per-user overlay.
+    pub fn has_user_overlay<S: AsRef<str>>(&self, profile: S) -> bool {
+        self.fs
+            .path_exists(&self.profile_path(self.user_overlay(profile)))
+    }
+
+    /// Checks whether a machine-specific `local.env` exists.
+    pub fn has_local_profile(&self) -> bool {
+        self.fs.path_exists(&self.profile_path(LOCAL_PROFILE))
+    }
+
+    /// Resolves a profile or stack, optionally overlaid with `local.env`.
+    ///
+    /// Each profile with a per-user overlay is immediately followed by it, so
+    /// personal values override shared ones but not later profiles.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack of profiles.
+    /// * `local` - Whether to overlay `local.env` if it exists.
+    ///
+    /// # Returns
+    ///
+    /// The merged `Document`, or an `Error` if any layer cannot be resolved.
+    pub fn resolve_env<S: AsRef<str>>(&self, profile: S, local: bool) -> Result<Document> {
+        let layers = self.env_layers(profile, local);
+        debug!(?layers, "resolved layers");
+
+        self.resolve_stack(layers.join(&LAYER_SEPARATOR.to_string()))
+    }
+
+    /// Finds every assignment of a key that contributes to a resolved profile.
+    ///
+    /// Layers, user overlays, includes and `local.env` are searched in the
+    /// same order as [`resolve_env`](Self::resolve_env) applies them.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack of profiles.
+    /// * `local` - Whether to include `local.env` if it exists.
+    /// * `key` - The variable name.
+    ///
+    /// # Returns
+    ///
+    /// The assignments, lowest precedence first, so the last one is the final
+    /// value, or an `Error` if any layer cannot be read.
+    pub fn key_origins<S: AsRef<str>>(
+        &self,
+        profile: S,
+        local: bool,
+        key: &str,
+    ) -> Result<Vec<Origin>> {
+        let mut origins = Vec::new();
+        for layer in self.env_layers(profile, local) {
+            let path = self.profile_path(&layer);
+            if !self.fs.path_exists(&path) {
+                return Err(ErrorKind::ProfileNotFound { profile: layer }.into());
+            }
+
+            self.trace_includes(&path, key, &mut Vec::new(), &mut origins)?;
+        }
+
+        Ok(origins)
+    }
+
+    /// Lists the layers of a resolved profile, with user overlays and
+    /// optionally `local.env`.
+    pub(crate) fn env_layers<S: AsRef<str>>(&self, profile: S, local: bool) -> Vec<String> {
+        let mut layers = Vec::new();
+        for layer in self.profile_layers(profile) {
+            let overlay = self.user_overlay(&layer);
+            let has_overlay = !layer.ends_with(USER_SUFFIX) && self.has_user_overlay(&layer);
+
+            layers.push(layer);
+            if has_overlay {
+                layers.push(overlay);
+            }
+        }
+
+        if local && self.has_local_profile() && !layers.iter().any(|l| l == LOCAL_PROFILE) {
+            layers.push(LOCAL_PROFILE.to_string());
+        }
+
+        layers
+    }
+
+    /// Recursively expands the includes of a file.
+    ///
+    /// Include paths are relative to the directory of the including file.
+    /// `stack` holds the files currently being expanded, to detect cycles.
+    fn resolve_includes(&self, path: &Path, stack: &mut Vec<PathBuf>) -> Result<Document> {
+        if in_stack(stack, path) {
+            return Err(ErrorKind::IncludeCycle {
+                file: path.to_path_buf(),
+            }
+            .into());
+        }
+
+        let doc = self.read_document(path)?;
+        stack.push(path.to_path_buf());
+
+        let mut lines = Vec::new();
+        for line in doc.into_lines() {
+            let Some((INCLUDE_DIRECTIVE, include)) = line.directive() else {
+                lines.push(line);
+                continue;
+            };
+
+            let file = path.parent().unwrap_or(Path::new("")).join(include);
+            if !self.fs.path_exists(&file) {
+                return Err(ErrorKind::IncludeNotFound {
+                    file,
+                    from: path.to_path_buf(),
+                }
+                .into());
+            }
+
+            trace!(file = %file.display(), from = %path.display(), "expanding include");
+            lines.extend(self.resolve_includes(&file, stack)?.into_lines());
+        }
+
+        stack.pop();
+        Ok(Document::from_lines(lines))
+    }
+
+    /// Collects the assignments of a key in a file and the files it includes,
+    /// in the order the includes are expanded.
+    fn trace_includes(
+        &self,
+        path: &Path,
+        key: &str,
+        stack: &mut Vec<PathBuf>,
+        origins: &mut Vec<Origin>,
+    ) -> Result<()> {
+        if in_stack(stack, path) {
+            return Err(ErrorKind::IncludeCycle {
+                file: path.to_path_buf(),
+            }
+            .into());
+        }
+
+        let doc = self.read_document(path)?;
+        stack.push(path.to_path_buf());
+
+        let mut number = 1;
+        for line in doc.lines() {
+            if let Line::Entry(entry) = line
+                && entry.key == key
+            {
+                origins.push(Origin {
+                    file: path.to_path_buf(),
+                    line: number,
+                    value: entry.value.clone(),
+                });
+            } else if let Some((INCLUDE_DIRECTIVE, include)) = line.directive() {
+                let file = path.parent().unwrap_or(Path::new("")).join(include);
+                if !self.fs.path_exists(&file) {
+                    return Err(ErrorKind::IncludeNotFound {
+                        file,
+                        from: path.to_path_buf(),
+                    }
+                    .into());
+                }
+                self.trace_includes(&file, key, stack, origins)?;
+            }
+
+            // Quoted values may span several lines.
+            number += line.raw().matches('\n').count() + 1;
+        }
+
+        stack.pop();
+        Ok(())
+    }
+
+    /// Lists the files a file includes, directly or through other includes,
+    /// in the order they are expanded.
+    pub(crate) fn included_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.collect_includes(path, &mut Vec::new(), &mut files)?;
+        Ok(files)
+    }
+
+    /// Collects the files included by a file into `files`.
+    fn collect_includes(
+        &self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        if in_stack(stack, path) {
+            return Err(ErrorKind::IncludeCycle {
+                file: path.to_path_buf(),
+            }
+            .into());
+        }
+
+        let doc = self.read_document(path)?;
+        stack.push(path.to_path_buf());
+
+        for line in doc.lines() {
+            let Some((INCLUDE_DIRECTIVE, include)) = line.directive() else {
+                continue;
+            };
+
+            let file = path.parent().unwrap_or(Path::new("")).join(include);
+            if !self.fs.path_exists(&file) {
+                return Err(ErrorKind::IncludeNotFound {
+                    file,
+                    from: path.to_path_buf(),
+                }
+                .into());
+            }
+
+            if !files.contains(&file) {
+                files.push(file.clone());
+            }
+            self.collect_includes(&file, stack, files)?;
+        }
+
+        stack.pop();
+        Ok(())
+    }
+
+    /// Reads and parses a dotenv file at an arbitrary path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `Document`, or an `Error` if the file cannot be read or
+    /// contains invalid syntax.
+    pub fn read_document(&self, path: &Path) -> Result<Document> {
+        let contents = self.read_file(path)?;
+        Document::parse(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseProfile {
+                file: path.to_path_buf(),
+                line: e.line,
+                reason: e.reason,
+            })
+        })
+    }
+
+    /// Reads the entire contents of a file into a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// The file contents, or an `Error` if the file cannot be read.
+    pub fn read_file(&self, path: &Path) -> Result<String> {
+        self.fs.read_to_string(path)
+    }
+
+    /// Reads the entire contents of a file as raw bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// The file contents, or an `Error` if the file cannot be read.
+    pub fn read_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        let mut file = self.fs.open_file(path, OpenOptions::new().read(true))?;
+        let mut contents = Vec::new();
 
-impl<F: FileSystem> ProfileManager<F> {
-    /// Creates a new `ProfileManager` with the specified configuration and
-    /// filesystem.
+        file.read_to_end(&mut contents).map_err(|e| {
+            Error::new(ErrorKind::ReadFile {
+                file: path.to_path_buf(),
+                source: e,
+            })
+        })?;
+
+        Ok(contents)
+    }
+
+    /// Writes to a file, creating or replacing it.
+    ///
+    /// The file is replaced atomically, so an interrupted write never leaves a
+    /// profile half-written.
     ///
     /// # Arguments
     ///
-    /// * `config` - Configuration containing path information.
-    /// * `fs` - Implementation of the `FileSystem` trait for I/O operations.
+    /// * `path` - The path to the file.
+    /// * `contents` - The contents to write.
     ///
     /// # Returns
     ///
-    /// A new `ProfileManager` instance.
-    pub fn new(config: Config, fs: F) -> Self {
-        Self { config, fs }
+    /// `Ok(())` on success, or an `Error` if the file cannot be written.
+    pub fn write_file<C: AsRef<[u8]>>(&self, path: &Path, contents: C) -> Result<()> {
+        // Replacing a profile breaks a hard link to it, so `.env` is linked again.
+        let relink = path.extension().is_some_and(|ext| ext == "env")
+            && self
+                .hardlinked_profile()
+                .is_some_and(|profile| self.fs.same_file(path, &self.profile_path(profile)));
+
+        self.fs.write_atomic(path, contents.as_ref())?;
+
+        if relink {
+            let env_path = Path::new(ENV_FILE);
+            self.fs.remove_file(env_path)?;
+            self.fs.create_hardlink(path, env_path)?;
+        }
+
+        Ok(())
     }
 
-    /// Checks whether the environment directory has been initialized.
+    /// Gets the path of the metadata file.
+    pub fn metadata_path(&self) -> PathBuf {
+        self.config.envoke_dir.join(METADATA_FILE)
+    }
+
+    /// Loads the recorded profile metadata.
     ///
     /// # Returns
     ///
-    /// `true` if the directory exists, `false` otherwise.
-    pub fn is_initialized(&self) -> bool {
-        self.fs.path_exists(&self.config.envoke_dir)
+    /// The stored `Metadata`, empty metadata if none has been recorded yet, or
+    /// an `Error` if the metadata file cannot be read or parsed.
+    pub fn load_metadata(&self) -> Result<Metadata> {
+        let path = self.metadata_path();
+        if !self.fs.path_exists(&path) {
+            return Ok(Metadata::default());
+        }
+
+        let contents = self.read_file(&path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseMetadata {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
     }
 
-    /// Gets the full path for a profile with the given name.
+    /// Saves profile metadata, replacing what was previously recorded.
     ///
-    /// Automatically appends ".env" extension if not already present.
+    /// # Arguments
+    ///
+    /// * `metadata` - The metadata to store.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the metadata file cannot be written.
+    pub fn save_metadata(&self, metadata: &Metadata) -> Result<()> {
+        let contents = serde_json::to_string_pretty(metadata).expect("metadata is serializable");
+        self.write_file(&self.metadata_path(), &(contents + "\n"))
+    }
+
+    /// Gets the path of the project's key for digesting values.
+    pub fn metadata_key_path(&self) -> PathBuf {
+        self.config.envoke_dir.join(METADATA_KEY_FILE)
+    }
+
+    /// Loads the project's key for digesting values in the metadata,
+    /// generating one the first time it is needed.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an `Error` if it cannot be read or written.
+    pub fn metadata_key(&self) -> Result<Vec<u8>> {
+        let path = self.metadata_key_path();
+        if self.fs.path_exists(&path) {
+            return Ok(self.read_file(&path)?.trim().as_bytes().to_vec());
+        }
+
+        let mut bytes = [0u8; 32];
+        getrandom::fill(&mut bytes).expect("the system random number generator is available");
+        let key: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.write_file(&path, format!("{}\n", key))?;
+        debug!(path = %path.display(), "generated metadata key");
+
+        Ok(key.into_bytes())
+    }
+
+    /// Gets the path of the profile index.
+    pub fn index_path(&self) -> PathBuf {
+        self.config.envoke_dir.join(INDEX_FILE)
+    }
+
+    /// Loads the profile index.
+    ///
+    /// The index is a cache, so a missing or unreadable index is treated as
+    /// empty and rebuilt.
+    ///
+    /// # Returns
+    ///
+    /// The stored `Index`, or an empty index.
+    pub fn load_index(&self) -> Index {
+        let path = self.index_path();
+        if !self.fs.path_exists(&path) {
+            return Index::default();
+        }
+
+        self.read_file(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_else(|| {
+                debug!(path = %path.display(), "rebuilding unreadable index");
+                Index::default()
+            })
+    }
+
+    /// Saves the profile index, if it changed since it was loaded.
+    ///
+    /// Failing to save the index only costs speed, so errors are logged
+    /// rather than returned, e.g. when the envoke directory is read-only.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index to store.
+    pub fn save_index(&self, index: &Index) {
+        if !index.is_changed() {
+            return;
+        }
+
+        let contents = serde_json::to_string(index).expect("index is serializable");
+        if let Err(e) = self.write_file(&self.index_path(), contents + "\n") {
+            debug!(error = %e, "failed to save index");
+        }
+    }
+
+    /// Gets the index entry of a profile, rebuilding it if the profile changed.
     ///
     /// # Arguments
     ///
+    /// * `index` - The index to read and update.
     /// * `profile` - The name of the profile.
     ///
     /// # Returns
     ///
-    /// The full path to the profile file.
-    pub fn profile_path<S: AsRef<str>>(&self, profile: S) -> PathBuf {
-        let mut profile = profile.as_ref().to_string();
-        if !profile.ends_with(".env") {
-            profile += ".env"
+    /// The up-to-date entry, or an `Error` if the profile cannot be read.
+    pub fn index_profile<S: AsRef<str>>(
+        &self,
+        index: &mut Index,
+        profile: S,
+    ) -> Result<IndexEntry> {
+        let name = self.profile_name(profile);
+        let path = self.profile_path(&name);
+        let metadata = self.fs.metadata(&path)?;
+
+        if let Some(entry) = index.get(&name, &metadata) {
+            return Ok(entry.clone());
         }
 
-        self.config.envoke_dir.join(profile)
+        trace!(profile = %name, "indexing profile");
+        let contents = self.read_file(&path)?;
+        let doc = Document::parse(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseProfile {
+                file: path.clone(),
+                line: e.line,
+                reason: e.reason,
+            })
+        })?;
+
+        let entry = IndexEntry::new(&contents, &doc, description(&doc), &metadata);
+        index.insert(&name, entry.clone());
+
+        Ok(entry)
     }
 
-    /// Lists all available profiles.
+    /// Loads the configured remotes.
     ///
-    /// Reads the envoke directory and returns the names of all valid profiles,
-    /// excluding the file extension.
+    /// # Returns
+    ///
+    /// The `Remotes`, empty if none are configured, or an `Error` if the
+    /// remotes file cannot be read or parsed.
+    pub fn load_remotes(&self) -> Result<Remotes> {
+        let path = self.config.envoke_dir.join(REMOTES_FILE);
+        if !self.fs.path_exists(&path) {
+            return Ok(Remotes::default());
+        }
+
+        let contents = self.read_file(&path)?;
+        toml::from_str(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseConfig {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    /// Saves the configured remotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `remotes` - The remotes to store.
     ///
     /// # Returns
     ///
-    /// A Result containing a vector of profile names on success, or an error
-    /// if the directory cannot be read.
-    pub fn profiles(&self) -> Result<Vec<String>> {
-        let entries = self.fs.read_dir(&self.config.envoke_dir)?;
+    /// `Ok(())` on success, or an `Error` if the remotes file cannot be written.
+    pub fn save_remotes(&self, remotes: &Remotes) -> Result<()> {
+        let contents = toml::to_string(remotes).expect("remotes are serializable");
+        self.write_file(&self.config.envoke_dir.join(REMOTES_FILE), contents)
+    }
 
-        let profiles = entries
-            .filter_map(|entry_result| {
-                // Skip entries with errors.
-                let entry = entry_result.ok()?;
-                let path = entry.path();
+    /// Gets the path of the operation log.
+    ///
+    /// # Returns
+    ///
+    /// The log file in the envoke or state directory, depending on the
+    /// configuration, or an `Error` if the state directory is unknown.
+    pub fn log_path(&self) -> Result<PathBuf> {
+        match self.config.log.location {
+            LogLocation::Project => Ok(self.config.envoke_dir.join(LOG_FILE)),
+            LogLocation::State => self
+                .config
+                .state_dir
+                .as_ref()
+                .map(|dir| dir.join(LOG_FILE))
+                .ok_or_else(|| ErrorKind::StateDirUnavailable.into()),
+        }
+    }
 
-                // Only include .env files.
-                if !path.is_file() || path.extension().map_or(true, |ext| ext != "env") {
-                    return None;
-                }
+    /// Appends an entry to the operation log.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to record.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the log cannot be written.
+    pub fn append_log(&self, entry: &LogEntry) -> Result<()> {
+        let path = self.log_path()?;
+        if let Some(dir) = path.parent() {
+            self.fs.create_dir(dir)?;
+        }
 
-                // Extract and convert the filename stem.
-                path.file_stem().and_then(|s| s.to_str()).map(String::from)
+        let mut line = serde_json::to_string(entry).expect("log entries are serializable");
+        line.push('\n');
+
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+
+        let mut file = self.fs.open_file(&path, &options)?;
+        file.write_all(line.as_bytes()).map_err(|e| {
+            Error::new(ErrorKind::WriteFile {
+                file: path.clone(),
+                source: e,
+            })
+        })
+    }
+
+    /// Loads the operations recorded for this project, oldest first.
+    ///
+    /// Lines that cannot be parsed, such as a line cut short by a crash, are
+    /// skipped.
+    ///
+    /// # Returns
+    ///
+    /// The recorded entries, or an `Error` if the log cannot be read.
+    pub fn load_log(&self) -> Result<Vec<LogEntry>> {
+        let path = self.log_path()?;
+        if !self.fs.path_exists(&path) {
+            return Ok(Vec::new());
+        }
+
+        let contents = self.read_file(&path)?;
+        let mut entries: Vec<LogEntry> = contents
+            .lines()
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    debug!(reason = %e, "skipping malformed log line");
+                    None
+                }
             })
             .collect();
 
-        Ok(profiles)
+        // A shared log holds the operations of every project.
+        if self.config.log.location == LogLocation::State {
+            let dir = std::env::current_dir().unwrap_or_default();
+            entries.retain(|entry| entry.dir == dir);
+        }
+
+        Ok(entries)
+    }
+
+    /// Loads the schema, if one has been defined.
+    ///
+    /// # Returns
+    ///
+    /// The `Schema`, `None` if no schema file exists, or an `Error` if the
+    /// schema file cannot be read or parsed.
+    pub fn load_schema(&self) -> Result<Option<Schema>> {
+        let path = self.config.envoke_dir.join(SCHEMA_FILE);
+        if !self.fs.path_exists(&path) {
+            return Ok(None);
+        }
+
+        let contents = self.read_file(&path)?;
+        toml::from_str(&contents).map(Some).map_err(|e| {
+            Error::new(ErrorKind::ParseConfig {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    /// Fills in the schema's defaults for the keys a resolved profile omits.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc` - The resolved profile.
+    ///
+    /// # Returns
+    ///
+    /// The profile with defaults added, or an `Error` if the schema cannot be
+    /// loaded.
+    pub fn apply_defaults(&self, mut doc: Document) -> Result<Document> {
+        if let Some(schema) = self.load_schema()? {
+            schema.apply_defaults(&mut doc);
+        }
+
+        Ok(doc)
+    }
+
+    /// Resolves the variables a profile provides to the environment.
+    ///
+    /// Every command that hands a profile to a process or shell goes through
+    /// this, so they all see the same values: the resolved layers, then the
+    /// schema's defaults for keys they omit, then decryption.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile or stack of profiles.
+    /// * `local` - Whether to overlay `local.env` if it exists.
+    ///
+    /// # Returns
+    ///
+    /// The plaintext `Document`, or an `Error` if a layer cannot be resolved,
+    /// the schema cannot be loaded or a value cannot be decrypted.
+    pub fn load_env<S: AsRef<str>>(&self, profile: S, local: bool) -> Result<Document> {
+        let doc = self.apply_defaults(self.resolve_env(profile, local)?)?;
+        self.decrypt_values(doc)
+    }
+
+    /// Records the current values of a profile in the given metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - The metadata to update.
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the metadata changed, or an `Error` if the profile cannot be read.
+    pub fn observe_profile<S: AsRef<str>>(
+        &self,
+        metadata: &mut Metadata,
+        profile: S,
+    ) -> Result<bool> {
+        let path = self.profile_path(&profile);
+        let doc = self.read_profile(&profile)?;
+        let modified = self.fs.metadata(&path)?.modified();
+        let key = self.metadata_key()?;
+
+        Ok(metadata.observe(&self.profile_name(profile), &doc, modified, &key))
     }
 }
 
+/// Gets the `# envoke:description` of a profile.
+fn description(doc: &Document) -> Option<String> {
+    doc.lines().iter().find_map(|line| match line.directive() {
+        Some((DESCRIPTION_DIRECTIVE, description)) => Some(description.to_string()),
+        _ => None,
+    })
+}
+
+/// Checks whether a file is already being expanded, comparing normalized
+/// paths so `a.env` and `./a.env` are recognised as the same file.
+fn in_stack(stack: &[PathBuf], path: &Path) -> bool {
+    let path = normalize(path);
+    stack.iter().any(|p| normalize(p) == path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +1355,225 @@ mod tests {
         assert_eq!(path.file_name().unwrap(), "prod.env");
     }
 
+    #[test]
+    fn test_profile_name() {
+        let manager = profile_manager();
+
+        assert_eq!(manager.profile_name("dev"), "dev");
+        assert_eq!(manager.profile_name("prod.env"), "prod");
+    }
+
+    #[test]
+    fn test_profile_description() {
+        let manager = profile_manager();
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+
+        let path = manager.profile_path("dev");
+        manager.write_file(&path, "A=1\n").unwrap();
+        assert_eq!(None, manager.profile_description("dev").unwrap());
+
+        manager
+            .write_file(
+                &path,
+                "# Profile: dev\n# envoke:description Local development\n",
+            )
+            .unwrap();
+        assert_eq!(
+            Some("Local development".to_string()),
+            manager.profile_description("dev").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_profile_layers() {
+        let manager = profile_manager();
+
+        assert_eq!(manager.profile_layers("dev"), vec!["dev"]);
+        assert_eq!(
+            manager.profile_layers("dev.env+local+"),
+            vec!["dev", "local"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let manager = profile_manager();
+        let fragments = manager.config.envoke_dir.join("fragments");
+        manager.fs.create_dir(&fragments).unwrap();
+
+        manager
+            .write_file(
+                &fragments.join("db.env"),
+                "# envoke:include common.env\nDB=db\n",
+            )
+            .unwrap();
+        manager
+            .write_file(&fragments.join("common.env"), "LOG=info\n")
+            .unwrap();
+        manager
+            .write_file(
+                &manager.profile_path("dev"),
+                "A=1\n# envoke:include fragments/db.env\nLOG=debug\n",
+            )
+            .unwrap();
+
+        let doc = manager.resolve_profile("dev").unwrap();
+        assert_eq!("A=1\nLOG=info\nDB=db\nLOG=debug\n", doc.to_string());
+        assert_eq!(Some("debug"), doc.get("LOG"));
+
+        // User overlays are merged directly after their profile.
+        manager
+            .write_file(&manager.profile_path("dev.user"), "A=user\nB=user\n")
+            .unwrap();
+        assert_eq!(
+            Some("user"),
+            manager.resolve_env("dev", false).unwrap().get("A")
+        );
+
+        // Fragments and overlays are not profiles.
+        manager
+            .write_file(&manager.profile_path(LOCAL_PROFILE), "A=2\n")
+            .unwrap();
+        assert_eq!(
+            Some("2"),
+            manager.resolve_env("dev", true).unwrap().get("A")
+        );
+        assert_eq!(
+            Some("user"),
+            manager.resolve_env("dev", false).unwrap().get("A")
+        );
+        assert_eq!(vec!["dev"], manager.profiles().unwrap());
+
+        manager
+            .write_file(&fragments.join("common.env"), "# envoke:include db.env\n")
+            .unwrap();
+        match manager.resolve_profile("dev").unwrap_err().kind {
+            ErrorKind::IncludeCycle { .. } => (),
+            _ => panic!("Expected IncludeCycle error"),
+        }
+
+        // The same file spelled differently is still a cycle.
+        manager
+            .write_file(
+                &fragments.join("common.env"),
+                "# envoke:include ./common.env\n",
+            )
+            .unwrap();
+        match manager.resolve_profile("dev").unwrap_err().kind {
+            ErrorKind::IncludeCycle { .. } => (),
+            _ => panic!("Expected IncludeCycle error"),
+        }
+
+        manager
+            .write_file(
+                &manager.profile_path("dev"),
+                "# envoke:include missing.env\n",
+            )
+            .unwrap();
+        match manager.resolve_profile("dev").unwrap_err().kind {
+            ErrorKind::IncludeNotFound { .. } => (),
+            _ => panic!("Expected IncludeNotFound error"),
+        }
+    }
+
+    #[test]
+    fn test_key_origins() {
+        let manager = profile_manager();
+        let fragments = manager.config.envoke_dir.join("fragments");
+        manager.fs.create_dir(&fragments).unwrap();
+        manager
+            .write_file(&fragments.join("db.env"), "LOG=info\n")
+            .unwrap();
+        manager
+            .write_file(
+                &manager.profile_path("dev"),
+                "NOTE=\"two\nlines\"\n# envoke:include fragments/db.env\nLOG=debug\n",
+            )
+            .unwrap();
+        manager
+            .write_file(&manager.profile_path(LOCAL_PROFILE), "LOG=trace\n")
+            .unwrap();
+
+        let origins = manager.key_origins("dev", true, "LOG").unwrap();
+        let origins: Vec<_> = origins
+            .iter()
+            .map(|o| {
+                (
+                    o.file.file_name().unwrap().to_str().unwrap(),
+                    o.line,
+                    o.value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("db.env", 1, "info"),
+                ("dev.env", 4, "debug"),
+                ("local.env", 1, "trace")
+            ],
+            origins
+        );
+
+        assert_eq!(2, manager.key_origins("dev", false, "LOG").unwrap().len());
+        assert!(
+            manager
+                .key_origins("dev", true, "MISSING")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::new(temp_dir.path().join(".envoke"));
+        config.global_dir = Some(temp_dir.path().join("global"));
+        let manager = ProfileManager::new(config, EnvokeFileSystem::new());
+
+        // No template directories exist yet.
+        assert!(manager.templates().unwrap().is_empty());
+
+        let project = manager.template_dir(Scope::Project).unwrap();
+        let global = manager.template_dir(Scope::Global).unwrap();
+        manager.fs.create_dir(&project).unwrap();
+        manager.fs.create_dir(&global).unwrap();
+
+        manager.fs.create_file(&project.join("web.env")).unwrap();
+        manager.fs.create_file(&global.join("web.env")).unwrap();
+        manager.fs.create_file(&global.join("cli.env")).unwrap();
+
+        let templates = manager.templates().unwrap();
+        assert_eq!(
+            templates,
+            vec![
+                ("web".to_string(), Scope::Project),
+                ("cli".to_string(), Scope::Global),
+                ("web".to_string(), Scope::Global),
+            ]
+        );
+
+        // Project templates shadow global ones.
+        assert_eq!(
+            manager.find_template("web").unwrap(),
+            project.join("web.env")
+        );
+        assert_eq!(
+            manager.find_template("cli.env").unwrap(),
+            global.join("cli.env")
+        );
+        assert!(manager.find_template("missing").is_err());
+    }
+
+    #[test]
+    fn test_global_dir_unavailable() {
+        let manager = profile_manager();
+
+        match manager.template_dir(Scope::Global).unwrap_err().kind {
+            ErrorKind::GlobalDirUnavailable => (),
+            _ => panic!("Expected GlobalDirUnavailable error"),
+        }
+    }
+
     #[test]
     fn test_profiles_with_files() {
         let manager = profile_manager();
@@ -192,4 +1625,60 @@ mod tests {
         let result = manager.profiles();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_read_profile() {
+        let manager = profile_manager();
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+
+        // Missing profiles are reported by name.
+        match manager.read_profile("dev").unwrap_err().kind {
+            ErrorKind::ProfileNotFound { profile } => assert_eq!(profile, "dev"),
+            _ => panic!("Expected ProfileNotFound error"),
+        }
+
+        let path = manager.profile_path("dev");
+        manager
+            .write_file(&path, "# Profile: dev\nKEY=value\n")
+            .unwrap();
+
+        let doc = manager.read_profile("dev").unwrap();
+        assert_eq!(Some("value"), doc.get("KEY"));
+
+        // Invalid syntax is reported with the line number.
+        manager.write_file(&path, "KEY=value\ninvalid\n").unwrap();
+        match manager.read_profile("dev").unwrap_err().kind {
+            ErrorKind::ParseProfile { line, .. } => assert_eq!(line, 2),
+            _ => panic!("Expected ParseProfile error"),
+        }
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let manager = profile_manager();
+        manager.fs.create_dir(&manager.config.envoke_dir).unwrap();
+
+        // No metadata has been recorded yet.
+        let mut metadata = manager.load_metadata().unwrap();
+        assert!(metadata.profiles.is_empty());
+
+        let path = manager.profile_path("dev");
+        manager.write_file(&path, "KEY=value\n").unwrap();
+
+        assert!(manager.observe_profile(&mut metadata, "dev").unwrap());
+        manager.save_metadata(&metadata).unwrap();
+
+        let loaded = manager.load_metadata().unwrap();
+        assert_eq!(metadata, loaded);
+        assert!(loaded.profiles["dev"].keys.contains_key("KEY"));
+
+        // A corrupt metadata file is reported rather than silently discarded.
+        manager
+            .write_file(&manager.metadata_path(), "not json")
+            .unwrap();
+        match manager.load_metadata().unwrap_err().kind {
+            ErrorKind::ParseMetadata { .. } => (),
+            _ => panic!("Expected ParseMetadata error"),
+        }
+    }
 }
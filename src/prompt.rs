@@ -0,0 +1,134 @@
+//! Interactive prompt module for the envoke CLI tool.
+//!
+//! This module asks the user for input. When stdin is a terminal, secret
+//! answers are read without echoing them; otherwise answers are read line by
+//! line from stdin, allowing values to be piped in from scripts. Choosing from
+//! a list is only possible on a terminal.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use dialoguer::{Confirm, FuzzySelect};
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Asks the user a question and returns their answer.
+///
+/// # Arguments
+///
+/// * `question` - The text shown before the cursor.
+/// * `hidden` - Whether the answer should be hidden as it is typed.
+///
+/// # Returns
+///
+/// The answer without its trailing newline, or an `Error` if input cannot be
+/// read or ends before an answer is given.
+pub fn ask(question: &str, hidden: bool) -> Result<String> {
+    let stdin = io::stdin();
+
+    if hidden && stdin.is_terminal() {
+        return rpassword::prompt_password(question).map_err(prompt_error);
+    }
+
+    if stdin.is_terminal() {
+        eprint!("{}", question);
+        io::stderr().flush().map_err(prompt_error)?;
+    }
+
+    read_answer(&mut stdin.lock())
+}
+
+/// Reads everything piped to stdin.
+///
+/// # Returns
+///
+/// The input, or an `Error` if it cannot be read or is not valid UTF-8.
+pub fn read_stdin() -> Result<String> {
+    io::read_to_string(io::stdin()).map_err(prompt_error)
+}
+
+/// Checks whether the user can be asked to choose interactively.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Asks the user to pick one of several items, with fuzzy search.
+///
+/// # Arguments
+///
+/// * `question` - The text shown above the items.
+/// * `items` - The items to choose from.
+/// * `default` - The index of the item selected initially.
+///
+/// # Returns
+///
+/// The index of the chosen item, `None` if the user cancelled, or an `Error`
+/// if the terminal cannot be used.
+pub fn select(question: &str, items: &[String], default: Option<usize>) -> Result<Option<usize>> {
+    let mut picker = FuzzySelect::new().with_prompt(question).items(items);
+    if let Some(default) = default {
+        picker = picker.default(default);
+    }
+
+    picker.interact_opt().map_err(|e| match e {
+        dialoguer::Error::IO(source) => prompt_error(source),
+    })
+}
+
+/// Asks the user a yes or no question, defaulting to yes.
+///
+/// # Arguments
+///
+/// * `question` - The question to ask.
+///
+/// # Returns
+///
+/// `true` if the user agreed, `false` if they declined or cancelled, or an
+/// `Error` if the terminal cannot be used.
+pub fn confirm(question: &str) -> Result<bool> {
+    let answer = Confirm::new()
+        .with_prompt(question)
+        .default(true)
+        .interact_opt()
+        .map_err(|e| match e {
+            dialoguer::Error::IO(source) => prompt_error(source),
+        })?;
+
+    Ok(answer.unwrap_or(false))
+}
+
+/// Reads a single answer from a reader.
+fn read_answer<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut answer = String::new();
+    let read = reader.read_line(&mut answer).map_err(prompt_error)?;
+
+    if read == 0 {
+        return Err(prompt_error(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    let trimmed = answer.trim_end_matches(['\n', '\r']).len();
+    answer.truncate(trimmed);
+    Ok(answer)
+}
+
+fn prompt_error(source: io::Error) -> Error {
+    Error::new(ErrorKind::Prompt { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_answer() {
+        let mut input = "first\r\nsecond\n".as_bytes();
+        assert_eq!("first", read_answer(&mut input).unwrap());
+        assert_eq!("second", read_answer(&mut input).unwrap());
+
+        match read_answer(&mut input).unwrap_err().kind {
+            ErrorKind::Prompt { source } => {
+                assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof)
+            }
+            _ => panic!("Expected Prompt error"),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Redaction module for the envoke CLI tool.
+//!
+//! This module provides the `Redactor`, which decides whether a value may be
+//! printed. Every command that prints values routes them through a `Redactor`
+//! so that secrets are masked consistently.
+
+use crate::glob;
+
+/// The text printed in place of a secret value.
+///
+/// The mask has a fixed length so it does not reveal the length of the value.
+pub const MASK: &str = "********";
+
+/// Key patterns treated as secrets when none are configured.
+pub const DEFAULT_SECRET_PATTERNS: &[&str] = &[
+    "*SECRET*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*TOKEN*",
+    "*KEY*",
+    "*PRIVATE*",
+    "*CREDENTIAL*",
+    "*AUTH*",
+    "*DSN*",
+    "*DATABASE_URL*",
+];
+
+/// Masks the values of secret keys.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::redact::{Redactor, MASK};
+///
+/// let redactor = Redactor::new(vec!["*TOKEN*".to_string()], true);
+/// assert_eq!(MASK, redactor.redact("GITHUB_TOKEN", "ghp_123"));
+/// assert_eq!("8080", redactor.redact("PORT", "8080"));
+///
+/// let disabled = Redactor::new(vec!["*TOKEN*".to_string()], false);
+/// assert_eq!("ghp_123", disabled.redact("GITHUB_TOKEN", "ghp_123"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// Glob patterns matching secret key names.
+    patterns: Vec<String>,
+
+    /// Whether secret values are masked.
+    enabled: bool,
+}
+
+impl Redactor {
+    /// Creates a new `Redactor`.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - Glob patterns matching secret key names.
+    /// * `enabled` - Whether secret values should be masked.
+    ///
+    /// # Returns
+    ///
+    /// A new `Redactor` instance.
+    pub fn new(patterns: Vec<String>, enabled: bool) -> Self {
+        Redactor { patterns, enabled }
+    }
+
+    /// Checks whether a key is considered a secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key matches a secret pattern, `false` otherwise.
+    pub fn is_secret(&self, key: &str) -> bool {
+        glob::matches_any(&self.patterns, key)
+    }
+
+    /// Checks whether secret values are being masked.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the printable form of a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The variable name.
+    /// * `value` - The value.
+    ///
+    /// # Returns
+    ///
+    /// The mask if the key is a secret and masking is enabled, otherwise the
+    /// value unchanged.
+    pub fn redact<'a>(&self, key: &str, value: &'a str) -> &'a str {
+        if self.enabled && self.is_secret(key) {
+            MASK
+        } else {
+            value
+        }
+    }
+}
+
+/// Values shorter than this are not masked in free text, as common short
+/// values such as `1` or `dev` would mangle unrelated output.
+pub const MIN_SCRUB_LEN: usize = 4;
+
+/// Masks known values wherever they appear in free text, such as build logs.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::redact::Scrubber;
+///
+/// let scrubber = Scrubber::new(["ghp_123", "1"]);
+/// assert_eq!("token=******** retries=1", scrubber.scrub("token=ghp_123 retries=1"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scrubber {
+    /// The values to mask, longest first so no value is partly masked by a
+    /// shorter one it contains.
+    values: Vec<String>,
+}
+
+impl Scrubber {
+    /// Creates a new `Scrubber`.
+    ///
+    /// Each line of a multi-line value is masked on its own, so the value is
+    /// masked even when text is scrubbed line by line.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values to mask.
+    pub fn new<I, S>(values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut values: Vec<String> = values
+            .into_iter()
+            .flat_map(|value| {
+                value
+                    .as_ref()
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| line.len() >= MIN_SCRUB_LEN)
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        values.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        values.dedup();
+
+        Scrubber { values }
+    }
+
+    /// Replaces every known value in a text with the mask.
+    pub fn scrub(&self, text: &str) -> String {
+        self.values
+            .iter()
+            .fold(text.to_string(), |text, value| text.replace(value, MASK))
+    }
+}
+
+impl Default for Redactor {
+    /// Creates a `Redactor` that masks the default secret patterns.
+    fn default() -> Self {
+        let patterns = DEFAULT_SECRET_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+
+        Redactor::new(patterns, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_patterns() {
+        let redactor = Redactor::default();
+
+        assert!(redactor.is_secret("AWS_SECRET_ACCESS_KEY"));
+        assert!(redactor.is_secret("db_password"));
+        assert!(redactor.is_secret("GITHUB_TOKEN"));
+        assert!(redactor.is_secret("STRIPE_API_KEY"));
+        assert!(redactor.is_secret("DATABASE_URL"));
+        assert!(!redactor.is_secret("PORT"));
+        assert!(!redactor.is_secret("LOG_LEVEL"));
+    }
+
+    #[test]
+    fn test_redact() {
+        let redactor = Redactor::default();
+        assert_eq!(MASK, redactor.redact("API_TOKEN", "abc"));
+        assert_eq!(MASK, redactor.redact("API_TOKEN", ""));
+        assert_eq!("debug", redactor.redact("LOG_LEVEL", "debug"));
+    }
+
+    #[test]
+    fn test_scrubber() {
+        let scrubber = Scrubber::new(["secret", "secret-long", "-----BEGIN KEY-----\nabcdef\n"]);
+        assert_eq!(
+            "a=******** b=******** c=********",
+            scrubber.scrub("a=secret b=secret-long c=abcdef")
+        );
+        assert_eq!("no values", scrubber.scrub("no values"));
+        assert_eq!("x", Scrubber::default().scrub("x"));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let redactor = Redactor::new(vec!["*".into()], false);
+        assert!(!redactor.is_enabled());
+        assert!(redactor.is_secret("ANYTHING"));
+        assert_eq!("abc", redactor.redact("API_TOKEN", "abc"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Project registry module for the envoke CLI tool.
+//!
+//! Every directory envoke is initialized or used in is recorded in
+//! `projects.json` in the global directory, so `envoke projects` can show
+//! the active profile of each project and `envoke projects switch` can
+//! switch many projects at once.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+
+/// Name of the registry file within the global directory.
+pub const PROJECTS_FILE: &str = "projects.json";
+
+/// The directories envoke manages.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Registry {
+    /// The absolute path of each project, i.e. the directory holding its
+    /// envoke directory.
+    pub projects: BTreeSet<PathBuf>,
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the path of the project registry.
+    ///
+    /// # Returns
+    ///
+    /// The path, or an `Error` if the global directory is unknown.
+    pub fn registry_path(&self) -> Result<PathBuf> {
+        Ok(self.global_dir()?.join(PROJECTS_FILE))
+    }
+
+    /// Loads the registered projects.
+    ///
+    /// # Returns
+    ///
+    /// The stored `Registry`, empty if no project has been registered, or an
+    /// `Error` if the file cannot be read or parsed.
+    pub fn load_registry(&self) -> Result<Registry> {
+        let path = self.registry_path()?;
+        if !self.fs.path_exists(&path) {
+            return Ok(Registry::default());
+        }
+
+        let contents = self.read_file(&path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            Error::new(ErrorKind::ParseMetadata {
+                file: path,
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    /// Saves the registered projects.
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The projects to store.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `Error` if the registry cannot be written.
+    pub fn save_registry(&self, registry: &Registry) -> Result<()> {
+        let path = self.registry_path()?;
+        if let Some(dir) = path.parent() {
+            self.fs.create_dir(dir)?;
+        }
+
+        let contents = serde_json::to_string_pretty(registry).expect("registry is serializable");
+        self.write_file(&path, contents + "\n")
+    }
+
+    /// Adds the current project to the registry, if it is not there yet.
+    ///
+    /// The registry is a convenience, so failing to update it is logged
+    /// rather than returned, and nothing is recorded on a dry run.
+    pub fn register_project(&self) {
+        if self.config.dry_run {
+            return;
+        }
+
+        let Some(project) = self.project_root() else {
+            return;
+        };
+        let result = self.load_registry().and_then(|mut registry| {
+            if registry.projects.insert(project) {
+                self.save_registry(&registry)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            debug!(error = %e, "failed to register project");
+        }
+    }
+
+    /// Removes the current project from the registry, e.g. before its envoke
+    /// directory is deleted.
+    ///
+    /// As with `register_project`, failing to update the registry is logged
+    /// rather than returned, and nothing is changed on a dry run.
+    pub fn unregister_project(&self) {
+        if self.config.dry_run {
+            return;
+        }
+
+        let Some(project) = self.project_root() else {
+            return;
+        };
+        let result = self.load_registry().and_then(|mut registry| {
+            if registry.projects.remove(&project) {
+                self.save_registry(&registry)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            debug!(error = %e, "failed to unregister project");
+        }
+    }
+
+    /// Gets the absolute path of the directory holding the envoke directory.
+    ///
+    /// # Returns
+    ///
+    /// The project directory, or `None` if the envoke directory does not
+    /// exist on disk.
+    pub fn project_root(&self) -> Option<PathBuf> {
+        std::fs::canonicalize(&self.config.envoke_dir)
+            .ok()?
+            .parent()
+            .map(Path::to_path_buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_registry() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.global_dir = Some(PathBuf::from("/config/envoke"));
+        let manager = ProfileManager::new(config, MockFileSystem::new());
+        assert_eq!(Registry::default(), manager.load_registry().unwrap());
+
+        let mut registry = Registry::default();
+        registry.projects.insert(PathBuf::from("/work/api"));
+        registry.projects.insert(PathBuf::from("/work/app"));
+        manager.save_registry(&registry).unwrap();
+        assert_eq!(registry, manager.load_registry().unwrap());
+
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), manager.fs);
+        assert!(manager.load_registry().is_err());
+    }
+}
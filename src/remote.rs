@@ -0,0 +1,389 @@
+//! Remote synchronisation module for the envoke CLI tool.
+//!
+//! This module stores profiles in a shared location so a team can exchange
+//! them without a third-party service. Profiles are always pushed as a single
+//! encrypted bundle. Git repositories and S3 buckets are supported, using the
+//! `git` and `aws` command-line tools respectively.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::bundle::BundleFile;
+use crate::digest::sha256_hex;
+use crate::error::{Error, ErrorKind, Result};
+use crate::metadata::METADATA_FILE;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Name of the remotes file within the envoke directory.
+pub const REMOTES_FILE: &str = "remotes.toml";
+
+/// Name of the bundle stored in a remote.
+pub const BUNDLE_FILE: &str = "envoke.tar.age";
+
+/// Message used for commits to git remotes.
+const COMMIT_MESSAGE: &str = "Update envoke profiles";
+
+/// The configured remotes, keyed by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Remotes {
+    /// Per-remote settings.
+    pub remotes: BTreeMap<String, Remote>,
+}
+
+/// A single remote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Remote {
+    /// A git URL or `s3://bucket/prefix`.
+    pub url: String,
+
+    /// The digest of the profiles at the last push or pull.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced: Option<String>,
+
+    /// The SHA-256 digest of each profile at the last push or pull, the base
+    /// against which local and remote changes are told apart.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bases: BTreeMap<String, String>,
+}
+
+/// Which side changed a profile since the last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// Both sides hold the same contents.
+    Same,
+
+    /// Only the local profile changed, so it is kept.
+    Local,
+
+    /// Only the remote profile changed, so it can be taken as is.
+    Remote,
+
+    /// Both sides changed, or there is no base to tell which did.
+    Both,
+}
+
+impl Remotes {
+    /// Selects a remote by name, or the default remote.
+    ///
+    /// Without a name, the only remote is used, or the one called `origin`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the remote, if given.
+    ///
+    /// # Returns
+    ///
+    /// The name of the remote, or an `Error` if it cannot be determined.
+    pub fn select(&self, name: Option<&str>) -> Result<String> {
+        let name = match name {
+            Some(name) => name,
+            None if self.remotes.len() == 1 => self.remotes.keys().next().unwrap(),
+            None => "origin",
+        };
+
+        if !self.remotes.contains_key(name) {
+            return Err(ErrorKind::RemoteNotFound {
+                remote: name.to_string(),
+            }
+            .into());
+        }
+
+        Ok(name.to_string())
+    }
+}
+
+/// Works out which side changed a profile since the last sync.
+///
+/// # Arguments
+///
+/// * `base` - The digest of the profile at the last sync, if known.
+/// * `ours` - The digest of the local profile.
+/// * `theirs` - The digest of the remote profile.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::remote::{Divergence, divergence};
+///
+/// assert_eq!(Divergence::Remote, divergence(Some("a"), "a", "b"));
+/// assert_eq!(Divergence::Both, divergence(Some("a"), "b", "c"));
+/// assert_eq!(Divergence::Both, divergence(None, "b", "c"));
+/// ```
+pub fn divergence(base: Option<&str>, ours: &str, theirs: &str) -> Divergence {
+    if ours == theirs {
+        Divergence::Same
+    } else if base == Some(theirs) {
+        Divergence::Local
+    } else if base == Some(ours) {
+        Divergence::Remote
+    } else {
+        Divergence::Both
+    }
+}
+
+/// Computes a digest of the profiles in a bundle.
+///
+/// Metadata is excluded so that auditing does not look like an edit.
+///
+/// # Arguments
+///
+/// * `files` - The files of the bundle.
+///
+/// # Returns
+///
+/// The hex-encoded digest.
+pub fn digest(files: &[BundleFile]) -> String {
+    let mut files: Vec<&BundleFile> = files
+        .iter()
+        .filter(|file| file.path != METADATA_FILE)
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut data = Vec::new();
+    for file in files {
+        data.extend_from_slice(file.path.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&(file.contents.len() as u64).to_le_bytes());
+        data.extend_from_slice(&file.contents);
+    }
+
+    sha256_hex(data)
+}
+
+/// Downloads the bundle stored in a remote.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the remote.
+///
+/// # Returns
+///
+/// The bundle, `None` if nothing has been pushed yet, or an `Error` if the
+/// remote cannot be reached.
+pub fn fetch(url: &str) -> Result<Option<Vec<u8>>> {
+    if url.starts_with("s3://") {
+        let object = s3_object(url);
+        // `s3 ls` fails without output when the object does not exist, and
+        // explains itself when the bucket cannot be reached.
+        match run("aws", &["s3", "ls", &object], None, None) {
+            Err(e) if matches!(&e.kind, ErrorKind::RemoteCommand { reason, .. } if reason.is_empty()) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+            Ok(_) => {}
+        }
+
+        return run("aws", &["s3", "cp", "--quiet", &object, "-"], None, None).map(Some);
+    }
+
+    let clone = git_clone(url)?;
+    let path = clone.path().join(BUNDLE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    std::fs::read(&path).map(Some).map_err(|e| {
+        Error::new(ErrorKind::ReadFile {
+            file: path,
+            source: e,
+        })
+    })
+}
+
+/// Uploads a bundle to a remote, replacing what was stored.
+///
+/// # Arguments
+///
+/// * `url` - The URL of the remote.
+/// * `data` - The encrypted bundle.
+///
+/// # Returns
+///
+/// `Ok(())` on success, or an `Error` if the remote cannot be updated.
+pub fn store(url: &str, data: &[u8]) -> Result<()> {
+    if url.starts_with("s3://") {
+        let object = s3_object(url);
+        return run(
+            "aws",
+            &["s3", "cp", "--quiet", "-", &object],
+            None,
+            Some(data),
+        )
+        .map(|_| ());
+    }
+
+    let clone = git_clone(url)?;
+    let dir = clone.path();
+    let path = dir.join(BUNDLE_FILE);
+    std::fs::write(&path, data).map_err(|e| {
+        Error::new(ErrorKind::WriteFile {
+            file: path,
+            source: e,
+        })
+    })?;
+
+    run("git", &["add", BUNDLE_FILE], Some(dir), None)?;
+
+    // Fall back to a placeholder identity so pushing works on fresh machines.
+    let mut commit = vec![];
+    if run("git", &["config", "user.email"], Some(dir), None).is_err() {
+        commit.extend([
+            "-c",
+            "user.name=envoke",
+            "-c",
+            "user.email=envoke@localhost",
+        ]);
+    }
+    commit.extend(["commit", "--quiet", "--allow-empty", "-m", COMMIT_MESSAGE]);
+
+    run("git", &commit, Some(dir), None)?;
+    run(
+        "git",
+        &["push", "--quiet", "origin", "HEAD"],
+        Some(dir),
+        None,
+    )?;
+
+    Ok(())
+}
+
+/// Clones a git remote into a temporary directory.
+fn git_clone(url: &str) -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir().map_err(|e| {
+        Error::new(ErrorKind::CreateDir {
+            file: std::env::temp_dir(),
+            source: e,
+        })
+    })?;
+
+    let target = dir.path().to_string_lossy().into_owned();
+    run(
+        "git",
+        &["clone", "--quiet", "--depth", "1", "--", url, &target],
+        None,
+        None,
+    )?;
+
+    Ok(dir)
+}
+
+/// Gets the object holding the bundle in an S3 bucket.
+fn s3_object(url: &str) -> String {
+    format!("{}/{}", url.trim_end_matches('/'), BUNDLE_FILE)
+}
+
+/// Runs a command, returning its standard output.
+fn run(program: &str, args: &[&str], dir: Option<&Path>, input: Option<&[u8]>) -> Result<Vec<u8>> {
+    let name = format!("{} {}", program, args.first().unwrap_or(&""));
+    let spawn_error = |e| {
+        Error::new(ErrorKind::Spawn {
+            command: name.clone(),
+            source: e,
+        })
+    };
+
+    debug!(program, ?args, "running external command");
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+
+    let mut child = command.spawn().map_err(spawn_error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.unwrap_or_default())
+            .map_err(spawn_error)?;
+    }
+
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(ErrorKind::RemoteCommand {
+            command: name,
+            reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remotes(names: &[&str]) -> Remotes {
+        let remotes = names
+            .iter()
+            .map(|name| {
+                let remote = Remote {
+                    url: format!("git@example.com:{}.git", name),
+                    synced: None,
+                    bases: BTreeMap::new(),
+                };
+                (name.to_string(), remote)
+            })
+            .collect();
+
+        Remotes { remotes }
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!("team", remotes(&["team"]).select(None).unwrap());
+        assert_eq!("origin", remotes(&["origin", "team"]).select(None).unwrap());
+        assert_eq!(
+            "team",
+            remotes(&["origin", "team"]).select(Some("team")).unwrap()
+        );
+
+        assert!(remotes(&[]).select(None).is_err());
+        assert!(remotes(&["a", "b"]).select(None).is_err());
+        assert!(remotes(&["a"]).select(Some("b")).is_err());
+    }
+
+    #[test]
+    fn test_divergence() {
+        assert_eq!(Divergence::Same, divergence(None, "a", "a"));
+        assert_eq!(Divergence::Local, divergence(Some("a"), "b", "a"));
+        assert_eq!(Divergence::Remote, divergence(Some("a"), "a", "b"));
+        assert_eq!(Divergence::Both, divergence(Some("a"), "b", "c"));
+        assert_eq!(Divergence::Both, divergence(None, "a", "b"));
+    }
+
+    #[test]
+    fn test_digest_ignores_metadata_and_order() {
+        let dev = BundleFile {
+            path: "profiles/dev.env".into(),
+            contents: b"A=1\n".to_vec(),
+        };
+        let prod = BundleFile {
+            path: "profiles/prod.env".into(),
+            contents: b"A=2\n".to_vec(),
+        };
+        let metadata = BundleFile {
+            path: METADATA_FILE.into(),
+            contents: b"{}\n".to_vec(),
+        };
+
+        let a = digest(&[dev.clone(), prod.clone()]);
+        let b = digest(&[prod.clone(), metadata, dev.clone()]);
+        assert_eq!(a, b);
+
+        let changed = BundleFile {
+            contents: b"A=3\n".to_vec(),
+            ..prod
+        };
+        assert_ne!(a, digest(&[dev, changed]));
+    }
+}
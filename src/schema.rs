@@ -0,0 +1,483 @@
+//! Schema module for the envoke CLI tool.
+//!
+//! This module provides the `Schema` struct, loaded from `schema.toml` in the
+//! envoke directory, which declares the keys a profile must define and the
+//! constraints their values must satisfy.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::dotenv::{Document, Entry};
+use crate::redact::Redactor;
+
+/// Name of the schema file within the envoke directory.
+pub const SCHEMA_FILE: &str = "schema.toml";
+
+/// Directive naming keys a profile must define, e.g.
+/// `# envoke:required DATABASE_URL API_KEY`.
+pub const REQUIRED_DIRECTIVE: &str = "required";
+
+/// Declares the keys of a profile and the constraints on their values.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::Document;
+/// use envoke::redact::Redactor;
+/// use envoke::schema::Schema;
+///
+/// let schema: Schema = toml::from_str(r#"
+///     [keys.PORT]
+///     required = true
+///     type = "int"
+/// "#).unwrap();
+///
+/// let doc = Document::parse("PORT=http\n").unwrap();
+/// let violations = schema.validate(&doc, &Redactor::new(vec![], true));
+/// assert_eq!(1, violations.len());
+/// assert_eq!("PORT", violations[0].key);
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schema {
+    /// Rules for each key, keyed by name.
+    pub keys: BTreeMap<String, KeyRule>,
+}
+
+/// The rules a single key must satisfy.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyRule {
+    /// Whether the key must be present with a non-empty value.
+    pub required: bool,
+
+    /// The type the value must parse as.
+    #[serde(rename = "type")]
+    pub value_type: ValueType,
+
+    /// The allowed values when `value_type` is `enum`.
+    pub values: Vec<String>,
+
+    /// A regular expression the whole value must match, compiled anchored at
+    /// both ends.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Option<Regex>,
+
+    /// The value used when a profile loaded into an environment omits the
+    /// key.
+    pub default: Option<String>,
+}
+
+/// The type of a value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    /// Any string.
+    #[default]
+    String,
+
+    /// A signed integer.
+    Int,
+
+    /// A boolean such as `true`, `false`, `1`, `0`, `yes` or `no`.
+    Bool,
+
+    /// An absolute URL with a scheme, such as `https://example.com`.
+    Url,
+
+    /// A TCP or UDP port number, from 1 to 65535.
+    Port,
+
+    /// One of the values listed in the rule.
+    Enum,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::String => "string",
+            ValueType::Int => "int",
+            ValueType::Bool => "bool",
+            ValueType::Url => "url",
+            ValueType::Port => "port",
+            ValueType::Enum => "enum",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A constraint that a profile does not satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The key that violates the schema.
+    pub key: String,
+
+    /// A description of the problem.
+    pub message: String,
+
+    /// The file and line (starting at 1) of the offending assignment, when
+    /// known.
+    pub location: Option<(PathBuf, usize)>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((file, line)) = &self.location {
+            write!(f, "{}:{}: ", file.display(), line)?;
+        }
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl Schema {
+    /// Validates a profile against the schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc` - The parsed profile.
+    /// * `redactor` - Masks the values of secret keys in the messages.
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, in key order. An empty vector means the profile
+    /// is valid.
+    pub fn validate(&self, doc: &Document, redactor: &Redactor) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        for (key, rule) in &self.keys {
+            let value = match doc.get(key) {
+                Some(value) if !value.is_empty() => value,
+                _ => {
+                    if rule.required {
+                        violations.push(Violation {
+                            key: key.clone(),
+                            message: "is required but missing or empty".into(),
+                            location: None,
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(message) = rule.check(value, redactor.redact(key, value)) {
+                violations.push(Violation {
+                    key: key.clone(),
+                    message,
+                    location: None,
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Adds the default value of every key a resolved profile omits.
+    ///
+    /// Keys present with an empty value are kept as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use envoke::dotenv::Document;
+    /// use envoke::schema::Schema;
+    ///
+    /// let schema: Schema = toml::from_str(r#"
+    ///     [keys.PORT]
+    ///     default = "8080"
+    ///     [keys.HOST]
+    ///     default = "localhost"
+    /// "#).unwrap();
+    ///
+    /// let mut doc = Document::parse("HOST=db\n").unwrap();
+    /// schema.apply_defaults(&mut doc);
+    /// assert_eq!("HOST=db\nPORT=8080\n", doc.to_string());
+    /// ```
+    pub fn apply_defaults(&self, doc: &mut Document) {
+        for (key, rule) in &self.keys {
+            if let Some(default) = &rule.default
+                && doc.get(key).is_none()
+            {
+                doc.push(Entry::new(key, default));
+            }
+        }
+    }
+}
+
+/// Lists the required keys a resolved profile lacks or leaves empty.
+///
+/// Keys are required by the schema or by `# envoke:required` directives in
+/// any layer of the profile.
+///
+/// # Arguments
+///
+/// * `schema` - The schema, if one has been defined.
+/// * `doc` - The resolved profile.
+///
+/// # Returns
+///
+/// The missing keys, sorted and without duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::dotenv::Document;
+/// use envoke::schema::missing_required;
+///
+/// let doc = Document::parse("# envoke:required API_KEY, PORT\nPORT=80\nAPI_KEY=\n").unwrap();
+/// assert_eq!(vec!["API_KEY"], missing_required(None, &doc));
+/// ```
+pub fn missing_required(schema: Option<&Schema>, doc: &Document) -> Vec<String> {
+    let from_schema = schema
+        .into_iter()
+        .flat_map(|schema| &schema.keys)
+        .filter(|(_, rule)| rule.required)
+        .map(|(key, _)| key.as_str());
+    let from_profile = doc.lines().iter().flat_map(|line| match line.directive() {
+        Some((REQUIRED_DIRECTIVE, keys)) => keys
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|key| !key.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    });
+
+    let missing: BTreeSet<&str> = from_schema
+        .chain(from_profile)
+        .filter(|key| doc.get(key).is_none_or(str::is_empty))
+        .collect();
+    missing.into_iter().map(String::from).collect()
+}
+
+impl KeyRule {
+    /// Checks a non-empty value against the rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to check.
+    /// * `shown` - How the value appears in the description, e.g. masked
+    ///   for secrets.
+    ///
+    /// # Returns
+    ///
+    /// A description of the problem, or `None` if the value is valid.
+    pub fn check(&self, value: &str, shown: &str) -> Option<String> {
+        let valid_type = match self.value_type {
+            ValueType::String => true,
+            ValueType::Int => value.parse::<i64>().is_ok(),
+            ValueType::Bool => parse_bool(value).is_some(),
+            ValueType::Url => is_url(value),
+            ValueType::Port => value.parse::<u16>().is_ok_and(|port| port != 0),
+            ValueType::Enum => self.values.iter().any(|v| v == value),
+        };
+
+        if !valid_type {
+            return Some(match self.value_type {
+                ValueType::Enum => format!("`{}` is not one of: {}", shown, self.values.join(", ")),
+                other => format!("`{}` is not a valid {}", shown, other),
+            });
+        }
+
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(value)
+        {
+            return Some(format!(
+                "`{}` does not match `{}`",
+                shown,
+                pattern_source(pattern)
+            ));
+        }
+
+        None
+    }
+}
+
+/// Parses the common spellings of a boolean.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::schema::parse_bool;
+///
+/// assert_eq!(Some(true), parse_bool("Yes"));
+/// assert_eq!(Some(false), parse_bool("0"));
+/// assert_eq!(None, parse_bool("maybe"));
+/// ```
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Checks that a value looks like an absolute URL: a scheme, `://`, and a
+/// non-empty remainder without whitespace.
+fn is_url(value: &str) -> bool {
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return false;
+    };
+
+    let mut chars = scheme.chars();
+    let valid_scheme = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    valid_scheme && !rest.is_empty() && !rest.chars().any(char::is_whitespace)
+}
+
+/// Compiles a pattern so it only matches whole values, as `a|ab` would
+/// otherwise stop at `a` when checking `ab`.
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)?;
+    Regex::new(&format!("^(?:{})$", pattern))
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Gets a pattern as written in the schema, without the anchors added when
+/// it was compiled.
+fn pattern_source(pattern: &Regex) -> &str {
+    let source = pattern.as_str();
+    source
+        .strip_prefix("^(?:")
+        .and_then(|source| source.strip_suffix(")$"))
+        .unwrap_or(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redact::MASK;
+
+    fn schema(input: &str) -> Schema {
+        toml::from_str(input).unwrap()
+    }
+
+    fn validate(schema: &Schema, profile: &str) -> Vec<String> {
+        let doc = Document::parse(profile).unwrap();
+        schema
+            .validate(&doc, &Redactor::new(vec!["*TOKEN*".into()], true))
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_missing_required() {
+        let schema = schema("[keys.A]\nrequired = true\n[keys.B]\n");
+        let doc = Document::parse("# envoke:required C D\nB=\nD=1\n").unwrap();
+        assert_eq!(vec!["A", "C"], missing_required(Some(&schema), &doc));
+
+        let doc = Document::parse("A=1\nC=2\n").unwrap();
+        assert!(missing_required(Some(&schema), &doc).is_empty());
+    }
+
+    #[test]
+    fn test_required() {
+        let schema = schema("[keys.A]\nrequired = true\n[keys.B]\n");
+
+        assert!(validate(&schema, "A=1\n").is_empty());
+        assert_eq!(
+            vec!["A: is required but missing or empty"],
+            validate(&schema, "B=1\n")
+        );
+        assert_eq!(
+            vec!["A: is required but missing or empty"],
+            validate(&schema, "A=\n")
+        );
+    }
+
+    #[test]
+    fn test_types() {
+        let schema = schema(
+            r#"
+            [keys.INT]
+            type = "int"
+            [keys.BOOL]
+            type = "bool"
+            [keys.URL]
+            type = "url"
+            [keys.PORT]
+            type = "port"
+            [keys.LEVEL]
+            type = "enum"
+            values = ["debug", "info"]
+            "#,
+        );
+
+        assert!(
+            validate(
+                &schema,
+                "INT=-4\nBOOL=off\nURL=postgres://db/app\nPORT=5432\nLEVEL=info\n"
+            )
+            .is_empty()
+        );
+
+        let violations = validate(
+            &schema,
+            "INT=4.5\nBOOL=maybe\nURL=localhost\nPORT=70000\nLEVEL=trace\n",
+        );
+        assert_eq!(
+            vec![
+                "BOOL: `maybe` is not a valid bool",
+                "INT: `4.5` is not a valid int",
+                "LEVEL: `trace` is not one of: debug, info",
+                "PORT: `70000` is not a valid port",
+                "URL: `localhost` is not a valid url",
+            ],
+            violations
+        );
+        assert_eq!(1, validate(&schema, "PORT=0\n").len());
+    }
+
+    #[test]
+    fn test_pattern_matches_whole_value() {
+        let schema = schema("[keys.KEY]\npattern = \"sk_[a-z]+\"\n");
+
+        assert!(validate(&schema, "KEY=sk_live\n").is_empty());
+        assert_eq!(1, validate(&schema, "KEY=xsk_live\n").len());
+        assert_eq!(
+            vec!["KEY: `sk_live1` does not match `sk_[a-z]+`"],
+            validate(&schema, "KEY=sk_live1\n")
+        );
+
+        // Alternatives are tried against the whole value.
+        let schema = self::schema("[keys.KEY]\npattern = \"a|ab\"\n");
+        assert!(validate(&schema, "KEY=ab\n").is_empty());
+        assert_eq!(1, validate(&schema, "KEY=abc\n").len());
+    }
+
+    #[test]
+    fn test_secret_values_masked() {
+        let schema = schema("[keys.API_TOKEN]\ntype = \"int\"\npattern = \"[0-9]+\"\n");
+
+        assert_eq!(
+            vec![format!("API_TOKEN: `{}` is not a valid int", MASK)],
+            validate(&schema, "API_TOKEN=hunter2\n")
+        );
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(toml::from_str::<Schema>("[keys.A]\ntype = \"float\"\n").is_err());
+        assert!(toml::from_str::<Schema>("[keys.A]\npattern = \"(\"\n").is_err());
+        assert!(toml::from_str::<Schema>("[keys.A]\nrequird = true\n").is_err());
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com"));
+        assert!(is_url("redis+tls://cache:6379"));
+        assert!(!is_url("example.com"));
+        assert!(!is_url("https://"));
+        assert!(!is_url("1http://example.com"));
+        assert!(!is_url("https://exa mple.com"));
+    }
+}
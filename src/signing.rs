@@ -0,0 +1,423 @@
+//! Signing module for the envoke CLI tool.
+//!
+//! Profiles can be signed with `ssh-keygen -Y` or `minisign`, and the
+//! signature kept next to the profile, e.g. `.envoke/prod.env.sig`. When
+//! `[signing] required` is set, `switch` and `run` refuse a profile whose
+//! signature, or the signature of a file it includes, is missing or not made
+//! by a trusted key, so that only reviewed profiles are activated in
+//! high-trust environments.
+//!
+//! Both tools are run as external programs: envoke never handles the keys.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use crate::config::CONFIG_FILE;
+use crate::error::{Error, ErrorKind, Result};
+use crate::fs::FileSystem;
+use crate::profile::ProfileManager;
+
+/// The namespace of envoke's SSH signatures, so a signature made for another
+/// purpose, such as a git commit, is never accepted for a profile.
+pub const SIGNATURE_NAMESPACE: &str = "envoke";
+
+/// The tool profiles are signed and verified with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFormat {
+    /// `ssh-keygen -Y`, trusting the keys of an allowed signers file.
+    #[default]
+    Ssh,
+
+    /// `minisign`, trusting a single public key.
+    Minisign,
+}
+
+impl SignatureFormat {
+    /// Returns the extension added to the profile file name for its signature.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SignatureFormat::Ssh => "sig",
+            SignatureFormat::Minisign => "minisig",
+        }
+    }
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Gets the path of a profile's signature, e.g. `.envoke/dev.env.sig`.
+    pub fn signature_path<S: AsRef<str>>(&self, profile: S) -> PathBuf {
+        self.file_signature_path(&self.profile_path(profile))
+    }
+
+    /// Gets the path of the signature of any file, such as one a profile
+    /// includes.
+    pub fn file_signature_path(&self, path: &Path) -> PathBuf {
+        let mut path = path.as_os_str().to_os_string();
+        path.push(".");
+        path.push(self.config.signing.format.extension());
+        PathBuf::from(path)
+    }
+
+    /// Signs a profile with the configured key, replacing any signature.
+    ///
+    /// The signing tool may ask for the passphrase of the key. Files the
+    /// profile includes are not signed, see [`ProfileManager::sign_file`].
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The path of the signature, or an `Error` if no key is configured or
+    /// signing fails.
+    pub fn sign_profile<S: AsRef<str>>(&self, profile: S) -> Result<PathBuf> {
+        let name = self.profile_name(&profile);
+        let path = self.profile_path(&name);
+        if !self.fs.path_exists(&path) {
+            return Err(ErrorKind::ProfileNotFound { profile: name }.into());
+        }
+
+        self.sign_file(&path)
+    }
+
+    /// Signs a file with the configured key, replacing any signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to sign.
+    ///
+    /// # Returns
+    ///
+    /// The path of the signature, or an `Error` if no key is configured or
+    /// signing fails.
+    pub fn sign_file(&self, path: &Path) -> Result<PathBuf> {
+        let key = self
+            .config
+            .signing
+            .key
+            .as_deref()
+            .map(expand_home)
+            .ok_or_else(|| ErrorKind::SigningNotConfigured {
+                setting: "key",
+                file: self.config.envoke_dir.join(CONFIG_FILE),
+            })?;
+
+        let signature = self.file_signature_path(path);
+        if self.fs.path_exists(&signature) {
+            self.fs.remove_file(&signature)?;
+        }
+
+        info!(file = %path.display(), key = %key.display(), "signing file");
+        let mut command = match self.config.signing.format {
+            SignatureFormat::Ssh => {
+                let mut command = Command::new("ssh-keygen");
+                command
+                    .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+                    .arg(&key)
+                    .arg(path);
+                command
+            }
+            SignatureFormat::Minisign => {
+                let mut command = Command::new("minisign");
+                command
+                    .args(["-S", "-s"])
+                    .arg(&key)
+                    .arg("-m")
+                    .arg(path)
+                    .arg("-x")
+                    .arg(&signature);
+                command
+            }
+        };
+
+        // The terminal is left to the tool, which may ask for a passphrase.
+        let output = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| spawn_error(&command, e))?;
+        if !output.status.success() {
+            return Err(ErrorKind::SigningFailed {
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+
+        Ok(signature)
+    }
+
+    /// Verifies a profile's signature against the trusted keys.
+    ///
+    /// Files the profile includes are not verified, see
+    /// [`ProfileManager::verify_includes`].
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// Who signed the profile - the principal for SSH signatures, or the
+    /// trusted comment for minisign - or an `Error` if the signature is
+    /// missing or invalid.
+    pub fn verify_signature<S: AsRef<str>>(&self, profile: S) -> Result<String> {
+        let name = self.profile_name(&profile);
+        let path = self.profile_path(&name);
+        if !self.fs.path_exists(&self.signature_path(&name)) {
+            return Err(ErrorKind::SignatureMissing { profile: name }.into());
+        }
+
+        self.verify_file(&path, &name)
+    }
+
+    /// Verifies the signatures of every file a profile includes.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every included file is signed by a trusted key, or an
+    /// `Error` describing the first that is not.
+    pub fn verify_includes<S: AsRef<str>>(&self, profile: S) -> Result<()> {
+        let name = self.profile_name(&profile);
+        for file in self.included_files(&self.profile_path(&name))? {
+            if !self.fs.path_exists(&self.file_signature_path(&file)) {
+                return Err(ErrorKind::IncludeUnsigned {
+                    file,
+                    profile: name,
+                }
+                .into());
+            }
+            self.verify_file(&file, &file.to_string_lossy())?;
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the signature of a file, naming it `label` in errors.
+    fn verify_file(&self, path: &Path, label: &str) -> Result<String> {
+        let signature = self.file_signature_path(path);
+        let trusted = self
+            .config
+            .signing
+            .trusted
+            .as_deref()
+            .map(expand_home)
+            .ok_or_else(|| ErrorKind::SigningNotConfigured {
+                setting: "trusted",
+                file: self
+                    .config
+                    .global_dir
+                    .as_deref()
+                    .unwrap_or(Path::new("~/.config/envoke"))
+                    .join(CONFIG_FILE),
+            })?;
+        let invalid = |reason: String| {
+            Error::new(ErrorKind::SignatureInvalid {
+                profile: label.to_string(),
+                reason,
+            })
+        };
+
+        debug!(file = %path.display(), trusted = %trusted.display(), "verifying signature");
+        match self.config.signing.format {
+            SignatureFormat::Ssh => {
+                let mut command = Command::new("ssh-keygen");
+                command
+                    .args(["-Y", "find-principals", "-f"])
+                    .arg(&trusted)
+                    .arg("-s")
+                    .arg(&signature);
+                let principal = run(&mut command)
+                    .map_err(invalid)?
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+
+                let mut command = Command::new("ssh-keygen");
+                command
+                    .args(["-Y", "verify", "-n", SIGNATURE_NAMESPACE, "-f"])
+                    .arg(&trusted)
+                    .args(["-I", &principal, "-s"])
+                    .arg(&signature)
+                    .stdin(std::fs::File::open(path).map_err(|e| {
+                        Error::new(ErrorKind::ReadFile {
+                            file: path.to_path_buf(),
+                            source: e,
+                        })
+                    })?);
+                run(&mut command).map_err(invalid)?;
+
+                Ok(principal)
+            }
+            SignatureFormat::Minisign => {
+                let mut command = Command::new("minisign");
+                command
+                    .args(["-V", "-p"])
+                    .arg(&trusted)
+                    .arg("-m")
+                    .arg(path)
+                    .arg("-x")
+                    .arg(&signature);
+                let output = run(&mut command).map_err(invalid)?;
+
+                Ok(output
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Trusted comment: "))
+                    .unwrap_or_default()
+                    .to_string())
+            }
+        }
+    }
+
+    /// Fails if signatures are required and a layer of the profile, or a
+    /// file it includes, is not signed by a trusted key.
+    ///
+    /// User overlays and `local.env` are layers like any other, so they must
+    /// be signed too. Missing layers are left for resolving to report.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile or stack.
+    /// * `local` - Whether `local.env` is overlaid.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the profile may be used, or an `Error` describing the first
+    /// file whose signature is missing or invalid.
+    pub fn ensure_signed<S: AsRef<str>>(&self, profile: S, local: bool) -> Result<()> {
+        if !self.config.signing.required {
+            return Ok(());
+        }
+
+        for layer in self.env_layers(profile, local) {
+            if self.fs.path_exists(&self.profile_path(&layer)) {
+                self.verify_signature(&layer)?;
+                self.verify_includes(&layer)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a signing tool, returning its standard output, or its error output
+/// if it fails.
+fn run(command: &mut Command) -> std::result::Result<String, String> {
+    let output = command
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .output()
+        .map_err(|e| format!("cannot run `{}`: {}", command.get_program().display(), e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn spawn_error(command: &Command, source: std::io::Error) -> Error {
+    Error::new(ErrorKind::Spawn {
+        command: command.get_program().to_string_lossy().into_owned(),
+        source,
+    })
+}
+
+/// Replaces a leading `~` with the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_signature_path() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        assert_eq!(
+            PathBuf::from(".envoke/dev.env.sig"),
+            manager.signature_path("dev")
+        );
+
+        config.signing.format = SignatureFormat::Minisign;
+        let manager = ProfileManager::new(config, MockFileSystem::new());
+        assert_eq!(
+            PathBuf::from(".envoke/dev.env.minisig"),
+            manager.signature_path("dev")
+        );
+    }
+
+    #[test]
+    fn test_ensure_signed() {
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.signing.trusted = Some(PathBuf::from("allowed_signers"));
+        let fs = MockFileSystem::new();
+        fs.add_file(".envoke/dev.env", "PORT=8080\n");
+        let manager = ProfileManager::new(config, fs);
+
+        // Nothing is checked unless signatures are required.
+        assert!(manager.ensure_signed("dev", true).is_ok());
+
+        let mut config = Config::new(PathBuf::from(".envoke"));
+        config.signing.required = true;
+        let manager = ProfileManager::new(config, manager.fs);
+        let err = manager.ensure_signed("dev", true).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::SignatureMissing { .. }));
+
+        // A missing profile is reported when it is resolved instead.
+        assert!(manager.ensure_signed("prod", true).is_ok());
+    }
+
+    #[test]
+    fn test_verify_includes() {
+        let fs = MockFileSystem::new();
+        fs.add_file(
+            ".envoke/dev.env",
+            "# envoke:include shared/db.env\nPORT=8080\n",
+        );
+        fs.add_file(".envoke/shared/db.env", "DB_HOST=localhost\n");
+        let manager = ProfileManager::new(Config::new(PathBuf::from(".envoke")), fs);
+
+        // A signed profile cannot pull in unsigned content.
+        let err = manager.verify_includes("dev").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::IncludeUnsigned { ref file, .. } if file == Path::new(".envoke/shared/db.env")
+        ));
+
+        assert_eq!(
+            PathBuf::from(".envoke/shared/db.env.sig"),
+            manager.file_signature_path(Path::new(".envoke/shared/db.env"))
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        assert_eq!(PathBuf::from("keys/id"), expand_home(Path::new("keys/id")));
+        if let Some(home) = env::var_os("HOME") {
+            assert_eq!(
+                Path::new(&home).join(".ssh/id"),
+                expand_home(Path::new("~/.ssh/id"))
+            );
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Profile template module for the envoke CLI tool.
+//!
+//! Templates are profiles containing `{{prompt:NAME}}` placeholders. When a
+//! profile is created from a template, each placeholder is replaced by a value
+//! supplied by the user.
+//!
+//! Profiles may also contain `{{env:NAME}}` references, which are replaced by
+//! the value of an environment variable when running in CI.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::dotenv::{Document, Line};
+
+/// Directory, within the envoke directory, that holds project templates.
+pub const TEMPLATES_DIR: &str = "templates";
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*prompt:([A-Za-z_][A-Za-z0-9_]*)\s*\}\}")
+        .expect("placeholder regex is valid")
+});
+
+static ENV_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*env:([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").expect("reference regex is valid")
+});
+
+/// Lists the placeholders in a template.
+///
+/// # Arguments
+///
+/// * `template` - The template contents.
+///
+/// # Returns
+///
+/// The name of each placeholder, in order of first appearance and without
+/// duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::template::placeholders;
+///
+/// let template = "DB_URL=postgres://{{prompt:DB_USER}}:{{prompt:DB_PASSWORD}}@db\nUSER={{prompt:DB_USER}}\n";
+/// assert_eq!(vec!["DB_USER", "DB_PASSWORD"], placeholders(template));
+/// ```
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for captures in PLACEHOLDER.captures_iter(template) {
+        let name = &captures[1];
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+}
+
+/// Replaces every placeholder in a template with its value.
+///
+/// Values with placeholders are quoted as needed once rendered, so answers with
+/// spaces, `#` or line breaks stay intact. Placeholders without a value are
+/// left untouched.
+///
+/// # Arguments
+///
+/// * `template` - The template contents.
+/// * `values` - The value of each placeholder, keyed by name.
+///
+/// # Returns
+///
+/// The rendered contents.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::template::render;
+/// use std::collections::HashMap;
+///
+/// let values = HashMap::from([("NAME".to_string(), "my app".to_string())]);
+/// assert_eq!("NAME=\"my app\"\n", render("NAME={{prompt:NAME}}\n", &values));
+/// ```
+pub fn render(template: &str, values: &HashMap<String, String>) -> String {
+    let substitute = |text: &str| {
+        PLACEHOLDER
+            .replace_all(text, |captures: &Captures| match values.get(&captures[1]) {
+                Some(value) => value.clone(),
+                None => captures[0].to_string(),
+            })
+            .into_owned()
+    };
+
+    // A template that is not a valid profile has no values to quote.
+    let Ok(doc) = Document::parse(template) else {
+        return substitute(template);
+    };
+
+    let lines = doc
+        .into_lines()
+        .into_iter()
+        .map(|line| match line {
+            Line::Entry(entry) => match substitute(&entry.value) {
+                value if value == entry.value => Line::Entry(entry),
+                value => Line::Entry(entry.with_value(value)),
+            },
+            Line::Comment(raw) => Line::Comment(substitute(&raw).replace('\n', " ")),
+            other => other,
+        })
+        .collect();
+
+    Document::from_lines(lines).to_string()
+}
+
+/// Replaces every `{{env:NAME}}` reference with a value.
+///
+/// # Arguments
+///
+/// * `input` - The text containing references.
+/// * `lookup` - Returns the value of a referenced variable, if it is set.
+///
+/// # Returns
+///
+/// The resolved text, or the names of the references that could not be
+/// resolved.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::template::resolve_references;
+///
+/// let lookup = |name: &str| (name == "TOKEN").then(|| "abc".to_string());
+/// assert_eq!(Ok("Bearer abc".to_string()), resolve_references("Bearer {{env:TOKEN}}", lookup));
+/// assert_eq!(Err(vec!["OTHER".to_string()]), resolve_references("{{env:OTHER}}", lookup));
+/// ```
+pub fn resolve_references<F>(input: &str, lookup: F) -> Result<String, Vec<String>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut missing = Vec::new();
+
+    let resolved = ENV_REFERENCE.replace_all(input, |captures: &Captures| {
+        lookup(&captures[1]).unwrap_or_else(|| {
+            missing.push(captures[1].to_string());
+            String::new()
+        })
+    });
+
+    if missing.is_empty() {
+        Ok(resolved.into_owned())
+    } else {
+        Err(missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders() {
+        assert!(placeholders("A=1\n").is_empty());
+        assert_eq!(
+            vec!["A", "B"],
+            placeholders("X={{ prompt:A }}\nY={{prompt:B}}{{prompt:A}}\n")
+        );
+        assert!(placeholders("X={{prompt:}}\nY={{other:A}}\n").is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let values = HashMap::from([("A".to_string(), "one".to_string())]);
+
+        assert_eq!(
+            "X=one\nY=one\n",
+            render("X={{prompt:A}}\nY={{ prompt:A }}\n", &values)
+        );
+        assert_eq!("X={{prompt:B}}\n", render("X={{prompt:B}}\n", &values));
+    }
+
+    #[test]
+    fn test_render_quotes_values() {
+        let values = HashMap::from([
+            ("A".to_string(), "a #b".to_string()),
+            ("B".to_string(), "one\ntwo".to_string()),
+        ]);
+
+        let rendered = render(
+            "# {{prompt:A}}\nX={{prompt:A}}\nY=\"{{prompt:B}}\" # note\nZ='raw $v'\n",
+            &values,
+        );
+        assert_eq!(
+            "# a #b\nX=\"a #b\"\nY=\"one\\ntwo\" # note\nZ='raw $v'\n",
+            rendered
+        );
+
+        let doc = Document::parse(&rendered).unwrap();
+        assert_eq!(Some("a #b"), doc.get("X"));
+        assert_eq!(Some("one\ntwo"), doc.get("Y"));
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let lookup = |name: &str| match name {
+            "A" => Some("one".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(Ok("x".to_string()), resolve_references("x", lookup));
+        assert_eq!(
+            Ok("one-one".to_string()),
+            resolve_references("{{env:A}}-{{ env:A }}", lookup)
+        );
+        assert_eq!(
+            Err(vec!["B".to_string(), "C".to_string()]),
+            resolve_references("{{env:B}}{{env:A}}{{env:C}}", lookup)
+        );
+        assert_eq!(
+            Ok("{{prompt:A}}".to_string()),
+            resolve_references("{{prompt:A}}", lookup)
+        );
+    }
+}
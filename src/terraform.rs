@@ -0,0 +1,493 @@
+//! Terraform module for the envoke CLI tool.
+//!
+//! This module reads variables from `.tfvars` files, in HCL or JSON syntax,
+//! and outputs from `terraform output -json`, so values such as database
+//! endpoints or bucket names can flow from infrastructure into profiles.
+//! Strings, numbers and booleans become plain values, lists and objects are
+//! encoded as JSON, and `null` values are left out.
+
+use std::path::Path;
+
+use serde_json::{Map, Number, Value};
+
+use crate::error::{ErrorKind, Result};
+
+/// Reads the variables assigned in a `.tfvars` or `.tfvars.json` file.
+///
+/// # Arguments
+///
+/// * `file` - The path of the file, used in errors.
+/// * `input` - The contents of the file.
+///
+/// # Returns
+///
+/// The names and values of the variables in the order they are assigned, or
+/// an `Error` if the file cannot be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use envoke::terraform::tfvars;
+///
+/// let input = "region = \"eu-west-1\"\nzones  = [\"a\", \"b\"]\n";
+/// let vars = tfvars(Path::new("prod.tfvars"), input).unwrap();
+/// assert_eq!(("region".to_string(), "eu-west-1".to_string()), vars[0]);
+/// assert_eq!(("zones".to_string(), "[\"a\",\"b\"]".to_string()), vars[1]);
+/// ```
+pub fn tfvars(file: &Path, input: &str) -> Result<Vec<(String, String)>> {
+    let invalid = |reason: String| ErrorKind::ParseTerraform {
+        file: file.to_path_buf(),
+        reason,
+    };
+
+    let vars = if input.trim_start().starts_with('{') {
+        let object: Map<String, Value> =
+            serde_json::from_str(input).map_err(|e| invalid(e.to_string()))?;
+        object.into_iter().collect()
+    } else {
+        Parser::new(input).body().map_err(invalid)?
+    };
+
+    Ok(values(vars))
+}
+
+/// Reads the outputs printed by `terraform output -json`.
+///
+/// # Arguments
+///
+/// * `file` - The path of the file, used in errors.
+/// * `input` - The JSON printed by terraform.
+///
+/// # Returns
+///
+/// The names and values of the outputs, or an `Error` if the input is not
+/// the output of `terraform output -json`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use envoke::terraform::outputs;
+///
+/// let input = r#"{ "db_host": { "sensitive": false, "type": "string", "value": "db.internal" } }"#;
+/// let vars = outputs(Path::new("-"), input).unwrap();
+/// assert_eq!(vec![("db_host".to_string(), "db.internal".to_string())], vars);
+/// ```
+pub fn outputs(file: &Path, input: &str) -> Result<Vec<(String, String)>> {
+    let invalid = |reason: String| ErrorKind::ParseTerraform {
+        file: file.to_path_buf(),
+        reason,
+    };
+
+    let object: Map<String, Value> =
+        serde_json::from_str(input).map_err(|e| invalid(e.to_string()))?;
+
+    let mut vars = Vec::new();
+    for (name, output) in object {
+        let Some(value) = output.get("value") else {
+            return Err(invalid(format!(
+                "`{}` has no value - pass the output of `terraform output -json`",
+                name
+            ))
+            .into());
+        };
+        vars.push((name, value.clone()));
+    }
+
+    Ok(values(vars))
+}
+
+/// Converts terraform values to profile values, leaving out nulls.
+fn values(vars: Vec<(String, Value)>) -> Vec<(String, String)> {
+    vars.into_iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::Null => return None,
+                Value::String(s) => s,
+                Value::Bool(b) => b.to_string(),
+                Value::Number(n) => n.to_string(),
+                value => value.to_string(),
+            };
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// A parser for the subset of HCL used in `.tfvars` files: attributes whose
+/// values are literals, lists, objects or heredocs.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser { input, pos: 0 }
+    }
+
+    fn body(&mut self) -> ParseResult<Vec<(String, Value)>> {
+        let mut vars = Vec::new();
+        loop {
+            self.skip(true)?;
+            if self.peek().is_none() {
+                return Ok(vars);
+            }
+
+            let name = self.identifier()?;
+            self.skip(false)?;
+            self.expect('=')?;
+            self.skip(false)?;
+            let value = self.value()?;
+            vars.push((name, value));
+
+            self.skip(false)?;
+            match self.peek() {
+                None | Some('\n') => {}
+                Some(_) => return Err(self.error("expected a new line after the value")),
+            }
+        }
+    }
+
+    fn value(&mut self) -> ParseResult<Value> {
+        match self.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some('<') if self.rest().starts_with("<<") => self.heredoc().map(Value::String),
+            Some('[') => self.list(),
+            Some('{') => self.object(),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_alphabetic() => match self.identifier()?.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                other => Err(self.error(format!(
+                    "`{}` is not a literal value - expressions are not supported",
+                    other
+                ))),
+            },
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn list(&mut self) -> ParseResult<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip(true)?;
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+
+            items.push(self.value()?);
+            self.skip(true)?;
+            if !self.eat(',') && self.peek() != Some(']') {
+                return Err(self.error("expected `,` or `]`"));
+            }
+        }
+    }
+
+    fn object(&mut self) -> ParseResult<Value> {
+        self.expect('{')?;
+        let mut object = Map::new();
+        loop {
+            self.skip(true)?;
+            if self.eat('}') {
+                return Ok(Value::Object(object));
+            }
+
+            let key = match self.peek() {
+                Some('"') => self.string()?,
+                _ => self.identifier()?,
+            };
+            self.skip(false)?;
+            if !self.eat('=') && !self.eat(':') {
+                return Err(self.error("expected `=` or `:`"));
+            }
+            self.skip(false)?;
+            object.insert(key, self.value()?);
+
+            // Attributes are separated by commas or new lines.
+            self.skip(false)?;
+            if !self.eat(',') && !matches!(self.peek(), Some('\n' | '}')) {
+                return Err(self.error("expected `,`, a new line or `}`"));
+            }
+        }
+    }
+
+    fn string(&mut self) -> ParseResult<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.next() else {
+                return Err(self.error("unterminated string"));
+            };
+            match c {
+                '"' => return Ok(value),
+                '\n' => return Err(self.error("unterminated string")),
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('u') => self.unicode()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn unicode(&mut self) -> ParseResult<char> {
+        let digits = self.rest().get(..4).unwrap_or_default();
+        let c = u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(c)
+    }
+
+    fn heredoc(&mut self) -> ParseResult<String> {
+        self.pos += 2;
+        let indented = self.eat('-');
+        let marker = self.identifier()?;
+        match self.rest().find('\n') {
+            Some(end) if self.rest()[..end].trim().is_empty() => self.pos += end + 1,
+            _ => return Err(self.error("expected a new line after the heredoc marker")),
+        }
+
+        let mut lines = Vec::new();
+        loop {
+            if self.peek().is_none() {
+                return Err(self.error(format!("heredoc is not closed by `{}`", marker)));
+            }
+            let rest = self.rest();
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let line = &rest[..end];
+            if line.trim() == marker {
+                self.pos += line.len();
+                break;
+            }
+            lines.push(line);
+            self.pos += (end + 1).min(rest.len());
+        }
+
+        // `<<-` heredocs are dedented by the least indented line.
+        let indent = if indented {
+            lines
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.len() - line.trim_start().len())
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        Ok(lines
+            .iter()
+            .map(|line| format!("{}\n", line.get(indent..).unwrap_or_default()))
+            .collect())
+    }
+
+    fn number(&mut self) -> ParseResult<Value> {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.')))
+            .unwrap_or(self.rest().len());
+        let literal = &self.rest()[..len];
+        let number = if let Ok(n) = literal.parse::<i64>() {
+            Number::from(n)
+        } else {
+            literal
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .ok_or_else(|| self.error(format!("`{}` is not a number", literal)))?
+        };
+        self.pos += len;
+        Ok(Value::Number(number))
+    }
+
+    fn identifier(&mut self) -> ParseResult<String> {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    /// Skips whitespace and comments, and new lines if `newlines` is set.
+    fn skip(&mut self, newlines: bool) -> ParseResult<()> {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with('#') || rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                let Some(end) = rest.find("*/") else {
+                    return Err(self.error("unterminated comment"));
+                };
+                self.pos += end + 2;
+            } else {
+                match self.peek() {
+                    Some(' ' | '\t' | '\r') => self.pos += 1,
+                    Some('\n') if newlines => self.pos += 1,
+                    _ => return Ok(()),
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> ParseResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error<D: std::fmt::Display>(&self, reason: D) -> String {
+        let line = self.input[..self.pos].matches('\n').count() + 1;
+        format!("line {}: {}", line, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<(String, String)> {
+        tfvars(Path::new("test.tfvars"), input).unwrap()
+    }
+
+    fn var(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_tfvars() {
+        let input = r#"
+# Networking
+region   = "eu-west-1" // primary
+replicas = 3
+ratio    = 0.5
+public   = false
+unset    = null
+greeting = "say \"hi\"\n"
+zones = [
+  "a",
+  "b", # trailing comma
+]
+tags = {
+  team = "web"
+  "cost-center": 42, owner = "ops"
+}
+/* multi-line
+   comment */
+policy = <<-EOT
+    {
+      "Version": "2012"
+    }
+    EOT
+"#;
+
+        assert_eq!(
+            vec![
+                var("region", "eu-west-1"),
+                var("replicas", "3"),
+                var("ratio", "0.5"),
+                var("public", "false"),
+                var("greeting", "say \"hi\"\n"),
+                var("zones", r#"["a","b"]"#),
+                var("tags", r#"{"cost-center":42,"owner":"ops","team":"web"}"#),
+                var("policy", "{\n  \"Version\": \"2012\"\n}\n"),
+            ],
+            parse(input)
+        );
+    }
+
+    #[test]
+    fn test_tfvars_json() {
+        let input = r#"{ "region": "eu-west-1", "replicas": 3, "zones": ["a"] }"#;
+        assert_eq!(
+            vec![
+                var("region", "eu-west-1"),
+                var("replicas", "3"),
+                var("zones", r#"["a"]"#)
+            ],
+            parse(input)
+        );
+    }
+
+    #[test]
+    fn test_tfvars_errors() {
+        for input in [
+            "region = var.region\n",
+            "region = \"eu\" zone = \"a\"\n",
+            "zones = [\"a\"\n",
+            "region \"eu\"\n",
+        ] {
+            let err = tfvars(Path::new("test.tfvars"), input).unwrap_err();
+            assert!(
+                matches!(err.kind, ErrorKind::ParseTerraform { .. }),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_outputs() {
+        let input = r#"{
+            "bucket": { "sensitive": false, "type": "string", "value": "assets" },
+            "db_port": { "sensitive": true, "type": "number", "value": 5432 },
+            "hosts": { "sensitive": false, "type": ["list", "string"], "value": ["a", "b"] }
+        }"#;
+        assert_eq!(
+            vec![
+                var("bucket", "assets"),
+                var("db_port", "5432"),
+                var("hosts", r#"["a","b"]"#),
+            ],
+            outputs(Path::new("-"), input).unwrap()
+        );
+
+        let err = outputs(Path::new("-"), r#"{ "bucket": "assets" }"#).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseTerraform { .. }));
+    }
+}
@@ -0,0 +1,314 @@
+//! Test harness module for the envoke CLI tool.
+//!
+//! `TestEnv` runs the envoke binary in a temporary directory with isolated
+//! global and state directories, so tests never see the profiles or state of
+//! the machine they run on. `TestEnvBuilder` prepares the directory beforehand: initialized or not,
+//! with given profiles and global profiles, an active profile, or a broken
+//! state such as a dangling `.env` link.
+//!
+//! The module is only compiled with the `test-util` feature.
+//!
+//! ```no_run
+//! use envoke::test_util::TestEnv;
+//!
+//! let env = TestEnv::builder()
+//!     .profile("dev", "PORT=8080\n")
+//!     .active("dev")
+//!     .build();
+//!
+//! let output = env.run_command(&["current"]);
+//! assert_eq!("dev\n", String::from_utf8_lossy(&output.stdout));
+//! ```
+
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+use crate::profile::{ENV_FILE, PROFILES_DIR};
+
+/// Environment variable naming the envoke binary to run, overriding the
+/// binary found next to the test executable.
+pub const BIN_VAR: &str = "ENVOKE_BIN";
+
+/// A temporary directory in which the envoke binary is run.
+pub struct TestEnv {
+    /// The temporary directory for this test.
+    pub temp_dir: TempDir,
+
+    /// Path to the .envoke directory.
+    pub envoke_dir: PathBuf,
+
+    /// Path used as `XDG_CONFIG_HOME`, isolating the global envoke directory.
+    pub config_home: PathBuf,
+
+    /// Path used as `XDG_STATE_HOME`, isolating the envoke state directory.
+    pub state_home: PathBuf,
+
+    /// Path to the executable being tested (for integration tests).
+    pub binary_path: PathBuf,
+}
+
+/// Prepares the directory of a `TestEnv`.
+#[derive(Debug, Default)]
+pub struct TestEnvBuilder {
+    initialized: bool,
+    profiles: Vec<(String, String)>,
+    global_profiles: Vec<(String, String)>,
+    files: Vec<(PathBuf, String)>,
+    env: Option<EnvState>,
+    binary: Option<PathBuf>,
+}
+
+/// What `.env` is when the environment is built.
+#[derive(Debug)]
+enum EnvState {
+    Active(String),
+    Dangling(String),
+    Unmanaged(String),
+}
+
+impl TestEnv {
+    /// Creates a new test environment with an empty, uninitialized directory.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Starts building a test environment.
+    pub fn builder() -> TestEnvBuilder {
+        TestEnvBuilder::default()
+    }
+
+    /// Path to the root of the test directory.
+    pub fn temp_path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Path to a profile in the envoke directory.
+    pub fn envoke_path(&self, name: &str) -> PathBuf {
+        self.envoke_dir.join(format!("{}.env", name))
+    }
+
+    /// Path to the global envoke directory.
+    pub fn global_path(&self) -> PathBuf {
+        self.config_home.join("envoke")
+    }
+
+    /// Path to the envoke state directory.
+    pub fn state_path(&self) -> PathBuf {
+        self.state_home.join("envoke")
+    }
+
+    /// Run a command in the test directory and return its output.
+    pub fn run_command(&self, args: &[&str]) -> Output {
+        self.run_command_with_env(args, &[])
+    }
+
+    /// Run a command in the test directory with extra environment variables.
+    pub fn run_command_with_env(&self, args: &[&str], envs: &[(&str, &str)]) -> Output {
+        self.command(args)
+            .envs(envs.iter().copied())
+            .output()
+            .unwrap()
+    }
+
+    /// Run a command in the test directory, piping `input` to its stdin.
+    pub fn run_command_with_stdin(&self, args: &[&str], input: &str) -> Output {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+
+        child.wait_with_output().unwrap()
+    }
+
+    /// Creates a command running envoke in the test directory.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.binary_path);
+        command
+            .args(args)
+            .env("XDG_CONFIG_HOME", &self.config_home)
+            .env("XDG_STATE_HOME", &self.state_home)
+            .current_dir(self.temp_path());
+
+        command
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestEnvBuilder {
+    /// Creates the `.envoke` directory.
+    pub fn initialized(mut self) -> Self {
+        self.initialized = true;
+        self
+    }
+
+    /// Adds a profile, initializing the directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the profile.
+    /// * `contents` - The contents of the profile.
+    pub fn profile<S: Into<String>, C: Into<String>>(mut self, name: S, contents: C) -> Self {
+        self.initialized = true;
+        self.profiles.push((name.into(), contents.into()));
+        self
+    }
+
+    /// Adds a profile to the global directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the profile.
+    /// * `contents` - The contents of the profile.
+    pub fn global_profile<S: Into<String>, C: Into<String>>(
+        mut self,
+        name: S,
+        contents: C,
+    ) -> Self {
+        self.global_profiles.push((name.into(), contents.into()));
+        self
+    }
+
+    /// Adds a file, relative to the test directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `contents` - The contents of the file.
+    pub fn file<P: Into<PathBuf>, C: Into<String>>(mut self, path: P, contents: C) -> Self {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// Links `.env` to a profile, as `envoke switch` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile to activate.
+    pub fn active<S: Into<String>>(mut self, profile: S) -> Self {
+        self.env = Some(EnvState::Active(profile.into()));
+        self
+    }
+
+    /// Links `.env` to a profile that does not exist, as happens when an
+    /// active profile is deleted by hand.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The missing profile.
+    pub fn dangling_env<S: Into<String>>(mut self, profile: S) -> Self {
+        self.env = Some(EnvState::Dangling(profile.into()));
+        self
+    }
+
+    /// Writes a `.env` that envoke did not create.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents of the file.
+    pub fn unmanaged_env<C: Into<String>>(mut self, contents: C) -> Self {
+        self.env = Some(EnvState::Unmanaged(contents.into()));
+        self
+    }
+
+    /// Runs a specific envoke binary.
+    ///
+    /// By default, the binary named by `ENVOKE_BIN` is used, or else the
+    /// `envoke` binary in the target directory of the test executable.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the binary.
+    pub fn binary<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.binary = Some(path.into());
+        self
+    }
+
+    /// Creates the temporary directory and its contents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory cannot be prepared.
+    pub fn build(self) -> TestEnv {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let envoke_dir = root.join(".envoke");
+        let config_home = root.join(".config");
+        let state_home = root.join(".state");
+
+        if self.initialized {
+            std::fs::create_dir_all(&envoke_dir).unwrap();
+        }
+
+        for (name, contents) in &self.profiles {
+            std::fs::write(envoke_dir.join(format!("{}.env", name)), contents).unwrap();
+        }
+
+        let global_dir = config_home.join("envoke").join(PROFILES_DIR);
+        for (name, contents) in &self.global_profiles {
+            std::fs::create_dir_all(&global_dir).unwrap();
+            std::fs::write(global_dir.join(format!("{}.env", name)), contents).unwrap();
+        }
+
+        for (path, contents) in &self.files {
+            let path = root.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).unwrap();
+            }
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let env_path = root.join(ENV_FILE);
+        match &self.env {
+            Some(EnvState::Active(profile)) | Some(EnvState::Dangling(profile)) => {
+                let target = Path::new(".envoke").join(format!("{}.env", profile));
+                std::os::unix::fs::symlink(target, env_path).unwrap();
+            }
+            Some(EnvState::Unmanaged(contents)) => std::fs::write(env_path, contents).unwrap(),
+            None => {}
+        }
+
+        TestEnv {
+            binary_path: self.binary.unwrap_or_else(default_binary),
+            temp_dir,
+            envoke_dir,
+            config_home,
+            state_home,
+        }
+    }
+}
+
+/// Locates the envoke binary built alongside the running test.
+fn default_binary() -> PathBuf {
+    if let Some(path) = env::var_os(BIN_VAR) {
+        return PathBuf::from(path);
+    }
+
+    let mut path = env::current_exe().unwrap();
+    path.pop(); // Remove the test binary name
+
+    // Integration tests run from target/debug/deps.
+    if path.ends_with("deps") {
+        path.pop();
+    }
+
+    path.join(format!("envoke{}", env::consts::EXE_SUFFIX))
+}
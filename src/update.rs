@@ -0,0 +1,338 @@
+//! Self-update module for the envoke CLI tool.
+//!
+//! Releases are published on GitHub with a build for each platform, named
+//! `envoke-<arch>-<os>`, next to a `.minisig` file holding its minisign
+//! signature. This module finds the latest release, downloads the build for
+//! the running platform over HTTPS, verifies its signature against the public
+//! key built into envoke and swaps it in place of the running binary.
+//!
+//! The module is only compiled with the `self-update` feature, so packagers
+//! who ship envoke through a package manager can leave it out.
+
+use std::env;
+use std::io::Write;
+use std::path::Path;
+
+use minisign_verify::{PublicKey, Signature};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::http::{download, download_https};
+
+/// Where the latest release is described.
+pub const RELEASES_URL: &str = "https://api.github.com/repos/TSxo/envoke/releases/latest";
+
+/// The minisign public key release builds are signed with.
+pub const RELEASE_PUBLIC_KEY: &str = "RWRwiyeL2GfehKuZvkAA+9Ol158minSabHO4kX+Ljh2GkW3EX2msisoM";
+
+/// The version of the running binary.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A published release.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    /// The git tag of the release, e.g. `v0.2.0`.
+    pub tag_name: String,
+
+    /// The files attached to the release.
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Asset {
+    /// The file name.
+    pub name: String,
+
+    /// Where the file can be downloaded from.
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Gets the version of the release, without the `v` prefix of its tag.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Finds an attached file by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name.
+    ///
+    /// # Returns
+    ///
+    /// The file, or an `Error` if the release does not include it.
+    pub fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| {
+                ErrorKind::ReleaseAssetMissing {
+                    asset: name.to_string(),
+                }
+                .into()
+            })
+    }
+}
+
+/// Fetches the description of the latest release.
+///
+/// # Returns
+///
+/// The latest release, or an `Error` if it cannot be downloaded or read.
+pub fn latest_release() -> Result<Release> {
+    let body = fetch(&test_override("ENVOKE_RELEASES_URL").unwrap_or(RELEASES_URL.to_string()))?;
+
+    serde_json::from_slice(&body).map_err(|e| {
+        ErrorKind::ReleaseInvalid {
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+/// Gets the name of the release build for the running platform.
+///
+/// # Returns
+///
+/// The asset name, e.g. `envoke-x86_64-linux`.
+pub fn asset_name() -> String {
+    format!(
+        "envoke-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// Checks whether a version is newer than another.
+///
+/// Versions are compared by their numeric `major.minor.patch` components;
+/// anything after a `-` or `+` is ignored.
+///
+/// # Arguments
+///
+/// * `candidate` - The version that may be newer.
+/// * `current` - The version to compare against.
+///
+/// # Returns
+///
+/// `true` if `candidate` is newer than `current`.
+///
+/// # Examples
+///
+/// ```
+/// use envoke::update::is_newer;
+///
+/// assert!(is_newer("0.10.0", "0.9.3"));
+/// assert!(!is_newer("0.9.3", "0.9.3"));
+/// ```
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    parse_version(candidate) > parse_version(current)
+}
+
+/// Downloads a release build and verifies its signature.
+///
+/// # Arguments
+///
+/// * `release` - The release to download from.
+/// * `name` - The name of the build.
+///
+/// # Returns
+///
+/// The verified build, or an `Error` if it cannot be downloaded or is not
+/// signed with [`RELEASE_PUBLIC_KEY`].
+pub fn download_verified(release: &Release, name: &str) -> Result<Vec<u8>> {
+    let binary = fetch(&release.asset(name)?.browser_download_url)?;
+    let signature = fetch(
+        &release
+            .asset(&format!("{}.minisig", name))?
+            .browser_download_url,
+    )?;
+
+    let public_key =
+        test_override("ENVOKE_RELEASE_PUBLIC_KEY").unwrap_or(RELEASE_PUBLIC_KEY.to_string());
+    verify_signature(
+        name,
+        &binary,
+        &String::from_utf8_lossy(&signature),
+        &public_key,
+    )?;
+
+    Ok(binary)
+}
+
+/// Checks data against a minisign signature.
+///
+/// Only prehashed signatures, the default since minisign 0.8, are accepted.
+///
+/// # Arguments
+///
+/// * `name` - The name of the checked file, used in errors.
+/// * `data` - The downloaded data.
+/// * `signature` - The contents of the `.minisig` file.
+/// * `public_key` - The base64-encoded minisign public key.
+///
+/// # Returns
+///
+/// `Ok(())` if the signature is valid and made with the key, or an `Error`
+/// otherwise.
+pub fn verify_signature(name: &str, data: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    let invalid = |e: minisign_verify::Error| {
+        Error::new(ErrorKind::ReleaseSignatureInvalid {
+            asset: name.to_string(),
+            reason: e.to_string(),
+        })
+    };
+
+    let public_key = PublicKey::from_base64(public_key).map_err(invalid)?;
+    let signature = Signature::decode(signature).map_err(invalid)?;
+    public_key.verify(data, &signature, false).map_err(invalid)
+}
+
+/// Replaces an executable with a new build.
+///
+/// The build is written next to the executable and renamed over it, so the
+/// executable is never left half-written.
+///
+/// # Arguments
+///
+/// * `exe` - The executable to replace.
+/// * `binary` - The new build.
+///
+/// # Returns
+///
+/// `Ok(())` on success, or an `Error` if the executable cannot be replaced.
+pub fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let dir = exe.parent().unwrap_or(Path::new("."));
+    let write_error = |e| {
+        Error::new(ErrorKind::WriteFile {
+            file: exe.to_path_buf(),
+            source: e,
+        })
+    };
+
+    let mut file = tempfile::Builder::new()
+        .prefix(".envoke-update")
+        .tempfile_in(dir)
+        .map_err(write_error)?;
+    file.write_all(binary).map_err(write_error)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(exe)
+            .map(|metadata| metadata.permissions().mode())
+            .unwrap_or(0o755);
+        file.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(mode))
+            .map_err(write_error)?;
+    }
+
+    // A running executable cannot be overwritten on Windows, but it can be
+    // moved out of the way.
+    #[cfg(windows)]
+    std::fs::rename(exe, exe.with_extension("old.exe")).map_err(write_error)?;
+
+    debug!(path = %exe.display(), "replacing executable");
+    file.persist(exe).map_err(|e| write_error(e.error))?;
+
+    Ok(())
+}
+
+/// Downloads a release file, refusing anything but HTTPS.
+///
+/// Builds with the `test-util` feature also read `file://` URLs, so tests can
+/// publish a fake release from disk.
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    if cfg!(feature = "test-util") && url.starts_with("file://") {
+        return download(url);
+    }
+
+    download_https(url)
+}
+
+/// Reads a setting the integration tests override, in builds with the
+/// `test-util` feature only.
+fn test_override(var: &str) -> Option<String> {
+    if cfg!(feature = "test-util") {
+        env::var(var).ok()
+    } else {
+        None
+    }
+}
+
+/// Parses the numeric components of a version.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("1.0.0", "0.99.0"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta.1", "0.1.0"));
+        assert!(!is_newer("0.0.9", "0.1.0"));
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let public_key = "RWRFxOmcMN6dAr9jLH0yOp/N46STGJSh8Ps+/pzJQmIJ/48zByYLm25d";
+        let signature = "untrusted comment: signature from minisign secret key
+RURFxOmcMN6dAgd5EtkwaAObm+ylTLNFlH5P0CDODxcfT9Kp+KG64ELgNuBPzWmpGTZo29C9kSMiDDyIbyj/+sKSf3gyeNiDVww=
+trusted comment: envoke test build
+2pArnIAEDBK5syB2Zey5+PE4G6Z9eOWHBMfjfxtvBi9cRZ3QorEM4Zxcf5oH/JaeSvUD4FekXS7ixCw+e0pFAg==
+";
+
+        assert!(verify_signature("envoke", b"binary", signature, public_key).is_ok());
+        assert!(verify_signature("envoke", b"tampered", signature, public_key).is_err());
+        assert!(verify_signature("envoke", b"binary", "", public_key).is_err());
+
+        // A signature made with another key is rejected.
+        let err = verify_signature("envoke", b"binary", signature, RELEASE_PUBLIC_KEY).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::ReleaseSignatureInvalid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_release_asset() {
+        let release: Release = serde_json::from_str(
+            r#"{"tag_name": "v0.2.0", "assets": [{"name": "envoke-x86_64-linux", "browser_download_url": "https://example.com/envoke"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!("0.2.0", release.version());
+        assert!(release.asset("envoke-x86_64-linux").is_ok());
+        assert!(release.asset("envoke-aarch64-macos").is_err());
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("envoke");
+        std::fs::write(&exe, "old").unwrap();
+
+        replace_executable(&exe, b"new").unwrap();
+
+        assert_eq!("new", std::fs::read_to_string(&exe).unwrap());
+        assert_eq!(1, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+}
@@ -0,0 +1,124 @@
+//! Profile usage module for the envoke CLI tool.
+//!
+//! Every time a profile is switched to or a command is run with it, the time
+//! is recorded in `.envoke/usage.json`, so `envoke list --recent` and the
+//! profile picker can show the profiles in use first. How a person uses
+//! profiles is their own business, so the file is ignored by git.
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::fs::FileSystem;
+use crate::metadata::unix_seconds;
+use crate::profile::ProfileManager;
+
+/// Name of the usage file within the envoke directory.
+pub const USAGE_FILE: &str = "usage.json";
+
+/// When each profile was last used.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    /// Unix timestamp (in seconds) of the last use of each profile.
+    pub profiles: BTreeMap<String, u64>,
+}
+
+impl Usage {
+    /// Records that a profile was used.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    /// * `now` - The current time.
+    pub fn touch(&mut self, profile: &str, now: SystemTime) {
+        self.profiles.insert(profile.to_string(), unix_seconds(now));
+    }
+
+    /// Gets when a profile was last used, as a Unix timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile.
+    ///
+    /// # Returns
+    ///
+    /// The timestamp, or `None` if the profile has not been used.
+    pub fn last_used(&self, profile: &str) -> Option<u64> {
+        self.profiles.get(profile).copied()
+    }
+}
+
+impl<F: FileSystem> ProfileManager<F> {
+    /// Loads when each profile was last used.
+    ///
+    /// Usage only orders listings, so a missing or unreadable file is treated
+    /// as empty.
+    pub fn load_usage(&self) -> Usage {
+        let path = self.config.envoke_dir.join(USAGE_FILE);
+        if !self.fs.path_exists(&path) {
+            return Usage::default();
+        }
+
+        self.read_file(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Records that the layers of a profile were used now.
+    ///
+    /// Failing to record usage is logged rather than returned, and nothing is
+    /// recorded on a dry run.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the profile or stack.
+    pub fn record_use<S: AsRef<str>>(&self, profile: S) {
+        if self.config.dry_run {
+            return;
+        }
+
+        let mut usage = self.load_usage();
+        let now = SystemTime::now();
+        for layer in self.profile_layers(profile) {
+            usage.touch(&layer, now);
+        }
+
+        let contents = serde_json::to_string(&usage).expect("usage is serializable");
+        if let Err(e) = self.write_file(&self.config.envoke_dir.join(USAGE_FILE), contents + "\n") {
+            debug!(error = %e, "failed to record profile usage");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::config::Config;
+    use crate::fs::MockFileSystem;
+
+    #[test]
+    fn test_record_use() {
+        let manager =
+            ProfileManager::new(Config::new(PathBuf::from(".envoke")), MockFileSystem::new());
+        manager.fs.add_dir(".envoke");
+        assert_eq!(Usage::default(), manager.load_usage());
+
+        manager.record_use("dev+ci");
+        let usage = manager.load_usage();
+        assert!(usage.last_used("dev").is_some());
+        assert!(usage.last_used("ci").is_some());
+        assert_eq!(None, usage.last_used("prod"));
+
+        let mut usage = Usage::default();
+        usage.touch("dev", UNIX_EPOCH + Duration::from_secs(60));
+        assert_eq!(Some(60), usage.last_used("dev"));
+    }
+}